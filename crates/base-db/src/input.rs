@@ -22,7 +22,7 @@ use triomphe::Arc;
 use vfs::{AbsPathBuf, AnchoredPath, FileId, VfsPath, file_set::FileSet};
 
 use crate::{
-    CrateWorkspaceData, EditionedFileId, FxIndexSet, SourceDatabase, all_crates,
+    CrateWorkspaceData, EditionedFileId, FxIndexSet, SourceDatabase, all_crates, set_ad_hoc_crates,
     set_all_crates_with_durability,
 };
 
@@ -127,6 +127,8 @@ impl SourceRoot {
 #[derive(Default, Clone)]
 pub struct CrateGraphBuilder {
     arena: Arena<CrateBuilder>,
+    /// Crates whose inputs are set with low durability, see [`CrateGraphBuilder::mark_ad_hoc`].
+    ad_hoc: FxHashSet<CrateBuilderId>,
 }
 
 pub type CrateBuilderId = Idx<CrateBuilder>;
@@ -567,6 +569,12 @@ impl CrateGraphBuilder {
         })
     }
 
+    /// Marks `krate` as an ad hoc crate, like a file opened by the user that doesn't belong to any
+    /// workspace. As it comes and goes with the file, its inputs are set with low durability.
+    pub fn mark_ad_hoc(&mut self, krate: CrateBuilderId) {
+        self.ad_hoc.insert(krate);
+    }
+
     pub fn add_dep(
         &mut self,
         from: CrateBuilderId,
@@ -600,7 +608,11 @@ impl CrateGraphBuilder {
 
         let crates_map = db.crates_map();
         // salsa doesn't compare new input to old input to see if they are the same, so here we are doing all the work ourselves.
-        for krate in self.iter() {
+        // Ad hoc crates, and the crates only they depend on, are kept out of `all_crates`, so that
+        // adding or removing them doesn't invalidate the queries that depend on all crates.
+        let (ad_hoc, proper): (Vec<_>, Vec<_>) =
+            self.iter().partition(|it| self.ad_hoc.contains(it));
+        for krate in proper {
             go(
                 &self,
                 db,
@@ -611,12 +623,27 @@ impl CrateGraphBuilder {
                 krate,
             );
         }
+        let mut ad_hoc_crates = FxIndexSet::default();
+        for krate in ad_hoc {
+            go(
+                &self,
+                db,
+                &crates_map,
+                &mut visited,
+                &mut visited_root_files,
+                &mut ad_hoc_crates,
+                krate,
+            );
+        }
 
         if old_all_crates.len() != all_crates.len()
             || old_all_crates.iter().any(|&krate| !all_crates.contains(&krate))
         {
             set_all_crates_with_durability(db, all_crates, Durability::MEDIUM);
         }
+        if !ad_hoc_crates.iter().eq(crate::ad_hoc_crates(db).iter()) {
+            set_ad_hoc_crates(db, ad_hoc_crates);
+        }
 
         return visited;
 
@@ -633,6 +660,8 @@ impl CrateGraphBuilder {
                 return crate_id;
             }
             let krate = &graph[source];
+            let durability =
+                if graph.ad_hoc.contains(&source) { Durability::LOW } else { Durability::MEDIUM };
             let dependencies = krate
                 .basic
                 .dependencies
@@ -673,30 +702,27 @@ impl CrateGraphBuilder {
                 Entry::Occupied(entry) => {
                     let old_crate = *entry.get();
                     if crate_data != *old_crate.data(db) {
-                        old_crate.set_data(db).with_durability(Durability::MEDIUM).to(crate_data);
+                        old_crate.set_data(db).with_durability(durability).to(crate_data);
                     }
                     if krate.extra != *old_crate.extra_data(db) {
                         old_crate
                             .set_extra_data(db)
-                            .with_durability(Durability::MEDIUM)
+                            .with_durability(durability)
                             .to(krate.extra.clone());
                     }
                     if krate.cfg_options != *old_crate.cfg_options(db) {
                         old_crate
                             .set_cfg_options(db)
-                            .with_durability(Durability::MEDIUM)
+                            .with_durability(durability)
                             .to(krate.cfg_options.clone());
                     }
                     if krate.env != *old_crate.env(db) {
-                        old_crate
-                            .set_env(db)
-                            .with_durability(Durability::MEDIUM)
-                            .to(krate.env.clone());
+                        old_crate.set_env(db).with_durability(durability).to(krate.env.clone());
                     }
                     if krate.ws_data != *old_crate.workspace_data(db) {
                         old_crate
                             .set_workspace_data(db)
-                            .with_durability(Durability::MEDIUM)
+                            .with_durability(durability)
                             .to(krate.ws_data.clone());
                     }
                     old_crate
@@ -709,7 +735,7 @@ impl CrateGraphBuilder {
                         krate.cfg_options.clone(),
                         krate.env.clone(),
                     )
-                    .durability(Durability::MEDIUM)
+                    .durability(durability)
                     .new(db);
                     entry.insert(input);
                     input
@@ -805,6 +831,7 @@ impl CrateGraphBuilder {
             let new_id = find.unwrap_or_else(|| self.arena.alloc(crate_data.clone()));
             id_map.insert(topo, new_id);
         }
+        self.ad_hoc.extend(other.ad_hoc.iter().map(|id| id_map[id]));
 
         *proc_macros =
             mem::take(proc_macros).into_iter().map(|(id, macros)| (id_map[&id], macros)).collect();
@@ -859,11 +886,16 @@ impl CrateGraphBuilder {
                     id_map[dep.crate_id.into_raw().into_u32() as usize].expect("crate was filtered")
             });
         }
+        self.ad_hoc = mem::take(&mut self.ad_hoc)
+            .into_iter()
+            .filter_map(|id| id_map[id.into_raw().into_u32() as usize])
+            .collect();
         id_map
     }
 
     pub fn shrink_to_fit(&mut self) {
         self.arena.shrink_to_fit();
+        self.ad_hoc.shrink_to_fit();
     }
 }
 
@@ -333,6 +333,16 @@ pub fn toolchain_channel(db: &dyn salsa::Database, krate: Crate) -> Option<Relea
 struct AllCrates {
     #[returns(clone)]
     crates: std::sync::Arc<[Crate]>,
+    #[returns(clone)]
+    ad_hoc_crates: std::sync::Arc<[Crate]>,
+}
+
+impl AllCrates {
+    fn get_or_create(db: &mut dyn salsa::Database) -> AllCrates {
+        AllCrates::try_get(db).unwrap_or_else(|| {
+            AllCrates::new(db, std::sync::Arc::default(), std::sync::Arc::default())
+        })
+    }
 }
 
 pub fn set_all_crates_with_durability(
@@ -340,8 +350,7 @@ pub fn set_all_crates_with_durability(
     crates: impl IntoIterator<Item = Crate>,
     durability: Durability,
 ) {
-    AllCrates::try_get(db)
-        .unwrap_or_else(|| AllCrates::new(db, std::sync::Arc::default()))
+    AllCrates::get_or_create(db)
         .set_crates(db)
         .with_durability(durability)
         .to(crates.into_iter().collect());
@@ -349,11 +358,30 @@ pub fn set_all_crates_with_durability(
 
 /// Returns the crates in topological order.
 ///
+/// This doesn't include the [`ad_hoc_crates`].
+///
 /// **Warning**: do not use this query in `hir-*` crates! It kills incrementality across crate metadata modifications.
 pub fn all_crates(db: &dyn salsa::Database) -> std::sync::Arc<[Crate]> {
     AllCrates::try_get(db).map_or(std::sync::Arc::default(), |all_crates| all_crates.crates(db))
 }
 
+pub fn set_ad_hoc_crates(db: &mut dyn salsa::Database, crates: impl IntoIterator<Item = Crate>) {
+    AllCrates::get_or_create(db)
+        .set_ad_hoc_crates(db)
+        .with_durability(Durability::LOW)
+        .to(crates.into_iter().collect());
+}
+
+/// Returns the crates of files that don't belong to any workspace, and of the dependencies only
+/// they have, in topological order.
+///
+/// They come and go with the files, so they are kept apart from [`all_crates`], and only the
+/// crates of their source roots are looked up among them.
+pub fn ad_hoc_crates(db: &dyn salsa::Database) -> std::sync::Arc<[Crate]> {
+    AllCrates::try_get(db)
+        .map_or(std::sync::Arc::default(), |all_crates| all_crates.ad_hoc_crates(db))
+}
+
 // FIXME: VFS rewrite should allow us to get rid of this wrapper
 #[doc(hidden)]
 #[salsa::interned]
@@ -369,10 +397,12 @@ pub fn source_root_crates(db: &dyn SourceDatabase, id: SourceRootId) -> &[Crate]
         db: &'db dyn SourceDatabase,
         id: InternedSourceRootId<'db>,
     ) -> Box<[Crate]> {
-        let crates = AllCrates::get(db).crates(db);
+        let all_crates = AllCrates::get(db);
+        let (crates, ad_hoc_crates) = (all_crates.crates(db), all_crates.ad_hoc_crates(db));
         let id = id.id(db);
         crates
             .iter()
+            .chain(ad_hoc_crates.iter())
             .copied()
             .filter(|&krate| {
                 let root_file = krate.data(db).root_file_id;
@@ -350,8 +350,14 @@ impl CustomProcMacroExpander {
                                 err: Some(ExpandError::other(call_site, text)),
                             }
                         }
-                        ProcMacroExpansionError::System(text)
-                        | ProcMacroExpansionError::Panic(text) => ExpandResult::new(
+                        ProcMacroExpansionError::System(text) => ExpandResult::new(
+                            tt::TopSubtree::empty(tt::DelimSpan {
+                                open: call_site,
+                                close: call_site,
+                            }),
+                            ExpandError::other(call_site, text),
+                        ),
+                        ProcMacroExpansionError::Panic(text) => ExpandResult::new(
                             tt::TopSubtree::empty(tt::DelimSpan {
                                 open: call_site,
                                 close: call_site,
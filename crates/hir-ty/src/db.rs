@@ -33,7 +33,7 @@ use crate::{
         ConstParamTypes, FieldTypes, GenericDefaults, TrackedStructToken, TypeAliasBounds,
         WithDefinedOpaques,
     },
-    mir::{MirBody, MirLowerError},
+    mir::{BorrowckResult, MirBody, MirLowerError},
     next_solver::{
         Allocation, Clause, EarlyBinder, GenericArgs, ParamEnv, PolyFnSig, StoredClauses,
         StoredEarlyBinder, StoredGenericArgs, StoredPolyFnSig, StoredTraitRef, StoredTy, TraitRef,
@@ -70,6 +70,14 @@ pub trait HirDatabase: SourceDatabase + 'static {
         crate::mir::mir_body_for_closure_query(db, def).map_err(|err| err.clone())
     }
 
+    fn borrowck<'db>(
+        &'db self,
+        def: InferBodyId<'db>,
+    ) -> Result<&'db [BorrowckResult], MirLowerError<'db>> {
+        let db = self.as_dyn();
+        crate::mir::borrowck_query(db, def).as_deref().map_err(|err| err.clone())
+    }
+
     fn monomorphized_mir_body<'db>(
         &'db self,
         def: InferBodyId<'db>,
@@ -472,7 +472,7 @@ impl<'db> InferenceTable<'db> {
         diagnostics.extend(std::mem::take(&mut self.trait_errors).into_iter().filter_map(
             |error| {
                 let error = error.into_fulfillment_error(&self.infer_ctxt);
-                SolverDiagnostic::from_fulfillment_error(&error)
+                SolverDiagnostic::from_fulfillment_error(self.interner(), &error)
                     .map(InferenceDiagnostic::SolverDiagnostic)
             },
         ));
@@ -4,8 +4,10 @@ use std::{fmt::Display, iter};
 
 use hir_def::{
     FieldId, LocalFieldId, StaticId, UnionId, VariantId,
+    expr_store::{Body, ExpressionStore},
     hir::{BindingId, Expr, ExprId, Ordering, PatId},
 };
+use hir_expand::InFile;
 use intern::{InternedSlice, InternedSliceRef, impl_slice_internable};
 use la_arena::{Arena, ArenaMap, Idx, RawIdx};
 use macros::{TypeFoldable, TypeVisitable};
@@ -18,11 +20,12 @@ use rustc_type_ir::{
 use salsa::SalsaValue;
 use smallvec::{SmallVec, smallvec};
 use stdx::impl_from;
+use syntax::SyntaxNodePtr;
 
 use crate::{
     CallableDefId, InferBodyId, InferenceResult, MemoryMap,
     db::{HirDatabase, InternedClosureId},
-    infer::PointerCast,
+    infer::{AllowTwoPhase, AutoBorrowMutability, PointerCast},
     next_solver::{
         Allocation, AllocationData, DbInterner, ErrorGuaranteed, GenericArgs, ParamEnv,
         StoredAllocation, StoredConst, StoredGenericArgs, StoredTy, Ty, TyKind,
@@ -32,14 +35,19 @@ use crate::{
     },
 };
 
+mod borrowck;
 mod eval;
 mod lower;
 mod monomorphization;
 mod pretty;
 
+pub use borrowck::{
+    BorrowckResult, ConflictingBorrow, MovedOutOfRef, MutabilityReason, UseOfMovedValue,
+    borrowck_query,
+};
 pub use eval::{
-    Evaluator, IsSigned, MirEvalError, VTableMap, interpret_mir, pad16,
-    render_const_using_debug_impl,
+    Evaluator, IsSigned, MirEvalError, MirEvalObserver, MirOutput, PausedFrame, PausedLocal,
+    VTableMap, interpret_mir, interpret_mir_with_observer, pad16, render_const_using_debug_impl,
 };
 pub use lower::{
     MirLowerError, lower_body_to_mir, lower_to_mir_with_store, mir_body_for_closure_query,
@@ -706,6 +714,18 @@ impl BorrowKind {
         }
     }
 
+    fn from_auto_borrow_mutability(m: AutoBorrowMutability) -> Self {
+        match m {
+            AutoBorrowMutability::Not => BorrowKind::Shared,
+            AutoBorrowMutability::Mut { allow_two_phase_borrow: AllowTwoPhase::Yes } => {
+                BorrowKind::Mut { kind: MutBorrowKind::TwoPhasedBorrow }
+            }
+            AutoBorrowMutability::Mut { allow_two_phase_borrow: AllowTwoPhase::No } => {
+                BorrowKind::Mut { kind: MutBorrowKind::Default }
+            }
+        }
+    }
+
     fn from_hir(bk: crate::infer::closure::analysis::BorrowKind) -> Self {
         match bk {
             crate::closure_analysis::BorrowKind::Immutable => Self::Shared,
@@ -1179,7 +1199,7 @@ impl MirBody<'_> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, SalsaValue)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, SalsaValue)]
 pub enum MirSpan {
     ExprId(ExprId),
     PatId(PatId),
@@ -1195,6 +1215,39 @@ impl From<&ExprId> for MirSpan {
     }
 }
 
+impl MirSpan {
+    /// Returns the syntax this span of a body of `owner` points to.
+    pub fn syntax(
+        self,
+        db: &dyn HirDatabase,
+        owner: InferBodyId<'_>,
+    ) -> Option<InFile<SyntaxNodePtr>> {
+        let (source_map, self_param_syntax) = match owner {
+            InferBodyId::DefWithBodyId(def) => {
+                let body = &Body::with_source_map(db, def).1;
+                (&**body, body.self_param_syntax())
+            }
+            InferBodyId::AnonConstId(def) => {
+                let store = ExpressionStore::with_source_map(db, def.loc(db).owner).1;
+                (store, None)
+            }
+        };
+        match self {
+            MirSpan::ExprId(e) => source_map.expr_syntax(e).ok().map(|s| s.map(|it| it.into())),
+            MirSpan::PatId(p) => {
+                source_map.pat_syntax(p).ok().map(|s| s.map(|it| it.syntax_node_ptr()))
+            }
+            MirSpan::BindingId(b) => source_map
+                .patterns_for_binding(b)
+                .iter()
+                .find_map(|p| source_map.pat_syntax(*p).ok())
+                .map(|s| s.map(|it| it.syntax_node_ptr())),
+            MirSpan::SelfParam => self_param_syntax.map(|s| s.map(|it| it.syntax_node_ptr())),
+            MirSpan::Unknown => None,
+        }
+    }
+}
+
 impl<'tcx> PlaceRef<'tcx> {
     /// If this place represents a local variable like `_X` with no
     /// projections, return `Some(_X)`.
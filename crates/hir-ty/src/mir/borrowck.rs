@@ -0,0 +1,1742 @@
+//! MIR borrow checker, which is used in diagnostics like use-after-move, conflicting borrows,
+//! need-mut and unused-variables.
+//!
+//! This is not rustc's borrow checker: there is no region inference here. Instead, a loan is
+//! considered alive for as long as some live local may hold it, which agrees with NLL in the
+//! common cases. Whenever we are unsure, we prefer to not report anything.
+
+use hir_def::{
+    AdtId, CallableDefId, HasModule, VariantId,
+    attrs::AttrFlags,
+    expr_store::ExpressionStore,
+    hir::{BindingId, Expr, ExprId, PatId},
+    signatures::FunctionSignature,
+};
+use la_arena::ArenaMap;
+use rustc_ast_ir::Mutability;
+use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_index::bit_set::DenseBitSet;
+use rustc_type_ir::{
+    BoundVar, BoundVarIndexKind, ClosureKind, DebruijnIndex, RegionKind, TypeFlags,
+    TypeSuperVisitable, TypeVisitable, TypeVisitableExt, TypeVisitor,
+    inherent::{GenericArgs as _, IntoKind, Ty as _},
+};
+use salsa::SalsaValue;
+use smallvec::{SmallVec, smallvec};
+use stdx::never;
+
+use crate::{
+    InferBodyId,
+    db::{HirDatabase, InternedClosureId},
+    drop::destructor,
+    next_solver::{
+        Binder, DbInterner, ParamEnv, Region, StoredTy, Ty, TyKind, TypingMode,
+        infer::{DbInternerInferExt, InferCtxt},
+    },
+};
+
+use super::{
+    AggregateKind, BasicBlockId, BorrowKind, FieldIndex, LocalId, MirBody, MirLowerError, MirSpan,
+    MutBorrowKind, Operand, OperandKind, PlaceElem, PlaceRef, PlaceTy, ProjectionElem, Rvalue,
+    StatementKind, TerminatorKind, return_slot,
+};
+
+/// A value of a non-`Copy` type that is moved out from behind a reference.
+#[derive(Debug, Clone, PartialEq, Eq, SalsaValue)]
+pub struct MovedOutOfRef {
+    pub ty: StoredTy,
+    pub span: MirSpan,
+}
+
+/// A use of a binding (or of a part of it) which may have been moved out before.
+#[derive(Debug, Clone, PartialEq, Eq, SalsaValue)]
+pub struct UseOfMovedValue {
+    pub binding: BindingId,
+    /// The type of the moved place.
+    pub ty: StoredTy,
+    pub span: MirSpan,
+    pub move_span: MirSpan,
+    /// Whether the value is borrowed rather than used by value.
+    pub is_borrow: bool,
+    /// Whether only a part of the used place was moved out.
+    pub is_partial: bool,
+}
+
+/// A borrow of a binding that overlaps with an earlier borrow which is still alive, where at
+/// least one of them is mutable.
+#[derive(Debug, Clone, PartialEq, Eq, SalsaValue)]
+pub struct ConflictingBorrow {
+    pub binding: BindingId,
+    pub span: MirSpan,
+    pub mutable: bool,
+    pub previous_span: MirSpan,
+    pub previous_mutable: bool,
+}
+
+/// Whether a binding needs to be declared `mut`, and whether it is used at all.
+#[derive(Debug, Clone, PartialEq, Eq, SalsaValue)]
+pub enum MutabilityReason {
+    /// The binding is never read, borrowed or moved out.
+    Unused,
+    /// The binding is used, but never mutated.
+    Not,
+    /// The binding is mutated at these spans.
+    Mut { spans: Vec<MirSpan> },
+}
+
+impl MutabilityReason {
+    fn push_mut_span(&mut self, span: MirSpan) {
+        match self {
+            MutabilityReason::Mut { spans } => spans.push(span),
+            MutabilityReason::Not | MutabilityReason::Unused => {
+                *self = MutabilityReason::Mut { spans: vec![span] }
+            }
+        }
+    }
+
+    fn record_usage(&mut self) {
+        if let MutabilityReason::Unused = self {
+            *self = MutabilityReason::Not;
+        }
+    }
+
+    /// Combines the reasons found for the same binding in different bodies.
+    pub fn merge(&mut self, other: &MutabilityReason) {
+        match other {
+            MutabilityReason::Mut { spans } => {
+                spans.iter().for_each(|&span| self.push_mut_span(span))
+            }
+            MutabilityReason::Not => self.record_usage(),
+            MutabilityReason::Unused => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, SalsaValue)]
+pub struct BorrowckResult {
+    pub moved_out_of_ref: Vec<MovedOutOfRef>,
+    pub used_after_move: Vec<UseOfMovedValue>,
+    pub conflicting_borrows: Vec<ConflictingBorrow>,
+    /// The mutability of the bindings owned by this body. Bindings of enclosing bodies captured by
+    /// value and mutated in this closure are included as well, but only with their mutations.
+    pub mutability_of_bindings: ArenaMap<BindingId, MutabilityReason>,
+}
+
+fn all_mir_bodies<'db>(
+    db: &'db dyn HirDatabase,
+    def: InferBodyId<'db>,
+    mut cb: impl FnMut(&'db MirBody<'db>, Option<InternedClosureId<'db>>),
+) -> Result<(), MirLowerError<'db>> {
+    fn for_closure<'db>(
+        db: &'db dyn HirDatabase,
+        c: InternedClosureId<'db>,
+        cb: &mut impl FnMut(&'db MirBody<'db>, Option<InternedClosureId<'db>>),
+    ) -> Result<(), MirLowerError<'db>> {
+        let body = db.mir_body_for_closure(c)?;
+        cb(body, Some(c));
+        body.closures.iter().try_for_each(|&it| for_closure(db, it, cb))
+    }
+    let body = db.mir_body(def)?;
+    cb(body, None);
+    body.closures.iter().try_for_each(|&it| for_closure(db, it, &mut cb))
+}
+
+#[salsa::tracked(returns(ref))]
+pub fn borrowck_query<'db>(
+    db: &'db dyn HirDatabase,
+    def: InferBodyId<'db>,
+) -> Result<Box<[BorrowckResult]>, MirLowerError<'db>> {
+    let _p = tracing::info_span!("borrowck_query").entered();
+    let mut res = vec![];
+    all_mir_bodies(db, def, |body, closure| {
+        db.unwind_if_revision_cancelled();
+        res.push(BorrowckCtx::new(db, body, closure).check());
+    })?;
+    Ok(res.into_boxed_slice())
+}
+
+/// Upper bound on the size of the loan dataflow state, to keep huge bodies from blowing up.
+const MAX_LOAN_STATE_BITS: usize = 1 << 26;
+
+/// Flags of the regions a value holding a loan has in its type. Loans made in the body have
+/// inferred lifetimes, which are erased (or errors, for elided lifetimes in the signature), so
+/// they can't be held through `'static` or the lifetime parameters of the body.
+const LOAN_REGION_FLAGS: TypeFlags = TypeFlags::HAS_RE_ERASED.union(TypeFlags::HAS_RE_ERROR);
+
+/// The effect of a statement or a terminator on a single place, in execution order.
+#[derive(Debug, Clone, Copy)]
+enum Event<'db> {
+    /// The place is read without moving out of it.
+    Read {
+        place: PlaceRef<'db>,
+        span: MirSpan,
+    },
+    /// The discriminant of the place is read, which is fine even if some fields were moved out.
+    Discriminant {
+        place: PlaceRef<'db>,
+        span: MirSpan,
+    },
+    /// The value is moved out of the place. `site` indexes into the tracked move sites.
+    Move {
+        place: PlaceRef<'db>,
+        span: MirSpan,
+        site: Option<usize>,
+    },
+    Borrow {
+        place: PlaceRef<'db>,
+        span: MirSpan,
+    },
+    /// The place is (re)initialized.
+    Write {
+        place: PlaceRef<'db>,
+    },
+    /// The storage of the local starts or ends, which resets its state.
+    Storage(LocalId),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MoveSite<'db> {
+    place: PlaceRef<'db>,
+    span: MirSpan,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Loan<'db> {
+    place: PlaceRef<'db>,
+    kind: BorrowKind,
+    span: MirSpan,
+    /// The local the reference is directly assigned to.
+    dest: LocalId,
+    /// The location of the borrow, as block and statement index.
+    location: (BasicBlockId, usize),
+}
+
+impl Loan<'_> {
+    fn is_mutable(&self) -> bool {
+        matches!(self.kind, BorrowKind::Mut { .. })
+    }
+
+    fn is_two_phase(&self) -> bool {
+        matches!(self.kind, BorrowKind::Mut { kind: MutBorrowKind::TwoPhasedBorrow })
+    }
+}
+
+struct BorrowckCtx<'a, 'db> {
+    db: &'db dyn HirDatabase,
+    body: &'a MirBody<'db>,
+    infcx: InferCtxt<'db>,
+    env: ParamEnv<'db>,
+    store: &'db ExpressionStore,
+    /// The closure expression this body was lowered from, if any. Bodies of closures have locals
+    /// for all bindings of the store, but only own those declared inside the closure.
+    closure_expr: Option<ExprId>,
+    local_to_binding: ArenaMap<LocalId, BindingId>,
+    /// Patterns of match arms with a guard. Bindings in these are lowered as moves before the
+    /// guard is checked, while they are only borrowed until the guard succeeds.
+    guarded_arm_pats: FxHashSet<PatId>,
+}
+
+impl<'a, 'db> BorrowckCtx<'a, 'db> {
+    fn new(
+        db: &'db dyn HirDatabase,
+        body: &'a MirBody<'db>,
+        closure: Option<InternedClosureId<'db>>,
+    ) -> Self {
+        let owner = body.owner;
+        let interner = DbInterner::new_with(db, owner.krate(db));
+        let infcx = interner.infer_ctxt().build(TypingMode::non_body_analysis());
+        let env = db.trait_environment(owner.generic_def(db));
+        let store = ExpressionStore::of(db, owner.expression_store_owner(db));
+        let mut guarded_arm_pats = FxHashSet::default();
+        for (_, expr) in store.exprs() {
+            if let Expr::Match { arms, .. } = expr {
+                for arm in arms.iter().filter(|arm| arm.guard.is_some()) {
+                    store.walk_pats(arm.pat, &mut |pat| {
+                        guarded_arm_pats.insert(pat);
+                    });
+                }
+            }
+        }
+        BorrowckCtx {
+            db,
+            body,
+            infcx,
+            env,
+            store,
+            closure_expr: closure.map(|it| it.loc(db).expr),
+            local_to_binding: body.local_to_binding_map(),
+            guarded_arm_pats,
+        }
+    }
+
+    fn check(&self) -> BorrowckResult {
+        let (events, move_sites) = self.collect_events();
+        let mut result = BorrowckResult {
+            moved_out_of_ref: self.moved_out_of_ref(&events),
+            used_after_move: self.used_after_move(&events, &move_sites),
+            conflicting_borrows: self.conflicting_borrows(&move_sites),
+            mutability_of_bindings: self.mutability_of_bindings(),
+        };
+        result.moved_out_of_ref.shrink_to_fit();
+        result.used_after_move.shrink_to_fit();
+        result.conflicting_borrows.shrink_to_fit();
+        result
+    }
+
+    fn place_ty(&self, place: PlaceRef<'db>) -> Ty<'db> {
+        place.ty(self.body, &self.infcx, self.env).ty
+    }
+
+    fn is_copy(&self, ty: Ty<'db>) -> bool {
+        // Don't report anything on types we failed to infer, or when we can't tell which types
+        // are `Copy` because `core` is missing.
+        ty.has_type_flags(TypeFlags::HAS_NON_REGION_ERROR)
+            || self.infcx.interner.lang_items().Copy.is_none()
+            || self.infcx.type_is_copy_modulo_regions(self.env, ty)
+    }
+
+    /// Returns the types each projection of the place is applied to.
+    fn projection_base_tys(&self, place: PlaceRef<'db>) -> SmallVec<[Ty<'db>; 4]> {
+        let mut place_ty = PlaceTy::from_ty(self.body.locals[place.local].ty.as_ref());
+        let mut result = SmallVec::new();
+        for elem in place.projection.iter() {
+            result.push(place_ty.ty);
+            place_ty = place_ty.projection_ty(&self.infcx, elem, self.env);
+        }
+        result
+    }
+
+    /// Whether moving out of this place is something we can track, i.e. the place is stored
+    /// directly in the local, not behind a pointer.
+    fn is_move_path(place: PlaceRef<'db>) -> bool {
+        place.projection.iter().all(|elem| {
+            matches!(
+                elem,
+                ProjectionElem::Field(_)
+                    | ProjectionElem::Downcast(_)
+                    | ProjectionElem::ConstantIndex { .. }
+                    | ProjectionElem::Subslice { .. }
+            )
+        })
+    }
+
+    fn operand_event(
+        &self,
+        op: &Operand,
+        span: MirSpan,
+        sites: &mut Vec<MoveSite<'db>>,
+    ) -> Option<Event<'db>> {
+        let (OperandKind::Copy(place) | OperandKind::Move(place)) = &op.kind else {
+            return None;
+        };
+        let place = place.as_ref();
+        let span = op.span.unwrap_or(span);
+        let ty = self.place_ty(place);
+        if self.is_copy(ty) {
+            return Some(Event::Read { place, span });
+        }
+        // Moving a mutable reference around is usually an implicit reborrow in the source, which
+        // we don't always lower faithfully, so don't track these moves.
+        let tracked = Self::is_move_path(place)
+            && !matches!(ty.kind(), TyKind::Ref(..))
+            && !matches!(span, MirSpan::PatId(pat) if self.guarded_arm_pats.contains(&pat));
+        let site = tracked.then(|| {
+            sites.push(MoveSite { place, span });
+            sites.len() - 1
+        });
+        Some(Event::Move { place, span, site })
+    }
+
+    /// Computes the events of every statement and terminator of the body, with terminators coming
+    /// after the statements of their block.
+    fn collect_events(
+        &self,
+    ) -> (ArenaMap<BasicBlockId, Vec<SmallVec<[Event<'db>; 4]>>>, Vec<MoveSite<'db>>) {
+        let mut sites = vec![];
+        let mut result = ArenaMap::default();
+        for (block_id, block) in self.body.basic_blocks.iter() {
+            let mut block_events = Vec::with_capacity(block.statements.len() + 1);
+            for statement in &block.statements {
+                let mut events = SmallVec::new();
+                let span = statement.span;
+                match &statement.kind {
+                    StatementKind::Assign(dest, rvalue) => {
+                        match rvalue {
+                            Rvalue::Use(o)
+                            | Rvalue::Repeat(o, _)
+                            | Rvalue::Cast(_, o, _)
+                            | Rvalue::UnaryOp(_, o) => {
+                                events.extend(self.operand_event(o, span, &mut sites));
+                            }
+                            Rvalue::CheckedBinaryOp(_, o1, o2) => {
+                                // Comparisons against constant patterns are lowered as binary
+                                // operators on non-primitive types, which don't move their operands.
+                                for o in [o1, o2] {
+                                    if let OperandKind::Copy(place) | OperandKind::Move(place) =
+                                        &o.kind
+                                    {
+                                        let span = o.span.unwrap_or(span);
+                                        events.push(Event::Read { place: place.as_ref(), span });
+                                    }
+                                }
+                            }
+                            Rvalue::Aggregate(_, ops) => {
+                                for o in ops.iter() {
+                                    events.extend(self.operand_event(o, span, &mut sites));
+                                }
+                            }
+                            Rvalue::Ref(_, place) => {
+                                events.push(Event::Borrow { place: place.as_ref(), span });
+                            }
+                            Rvalue::Discriminant(place) => {
+                                events.push(Event::Discriminant { place: place.as_ref(), span });
+                            }
+                            Rvalue::Len(place) | Rvalue::CopyForDeref(place) => {
+                                events.push(Event::Read { place: place.as_ref(), span });
+                            }
+                            Rvalue::ThreadLocalRef(n)
+                            | Rvalue::AddressOf(n)
+                            | Rvalue::BinaryOp(n)
+                            | Rvalue::NullaryOp(n) => match *n {},
+                        }
+                        events.push(Event::Write { place: dest.as_ref() });
+                    }
+                    StatementKind::Deinit(place) => {
+                        events.push(Event::Write { place: place.as_ref() });
+                    }
+                    &StatementKind::StorageLive(local) | &StatementKind::StorageDead(local) => {
+                        events.push(Event::Storage(local));
+                    }
+                    StatementKind::FakeRead(_) | StatementKind::Nop => {}
+                }
+                block_events.push(events);
+            }
+            let mut events = SmallVec::new();
+            if let Some(terminator) = &block.terminator {
+                let span = terminator.span;
+                match &terminator.kind {
+                    TerminatorKind::SwitchInt { discr: o, .. }
+                    | TerminatorKind::Assert { cond: o, .. } => {
+                        events.extend(self.operand_event(o, span, &mut sites));
+                    }
+                    TerminatorKind::Call { func, args, destination, .. } => {
+                        events.extend(self.operand_event(func, span, &mut sites));
+                        for arg in args.iter() {
+                            events.extend(self.operand_event(arg, span, &mut sites));
+                        }
+                        events.push(Event::Write { place: destination.as_ref() });
+                    }
+                    TerminatorKind::DropAndReplace { place, value, .. } => {
+                        events.extend(self.operand_event(value, span, &mut sites));
+                        events.push(Event::Write { place: place.as_ref() });
+                    }
+                    TerminatorKind::Yield { value, resume_arg, .. } => {
+                        events.extend(self.operand_event(value, span, &mut sites));
+                        events.push(Event::Write { place: resume_arg.as_ref() });
+                    }
+                    // Dropping a moved out place is a no-op, so this is not a use.
+                    TerminatorKind::Drop { .. }
+                    | TerminatorKind::Goto { .. }
+                    | TerminatorKind::UnwindResume
+                    | TerminatorKind::Abort
+                    | TerminatorKind::Return
+                    | TerminatorKind::Unreachable
+                    | TerminatorKind::CoroutineDrop
+                    | TerminatorKind::FalseEdge { .. }
+                    | TerminatorKind::FalseUnwind { .. } => {}
+                }
+            }
+            block_events.push(events);
+            result.insert(block_id, block_events);
+        }
+        (result, sites)
+    }
+
+    fn moved_out_of_ref(
+        &self,
+        events: &ArenaMap<BasicBlockId, Vec<SmallVec<[Event<'db>; 4]>>>,
+    ) -> Vec<MovedOutOfRef> {
+        let mut result = vec![];
+        let mut seen = FxHashSet::default();
+        for (_, block_events) in events.iter() {
+            for event in block_events.iter().flatten() {
+                let &Event::Move { place, span, .. } = event else { continue };
+                if !place.projection.contains(&ProjectionElem::Deref) {
+                    continue;
+                }
+                let is_deref_of_ref =
+                    place.projection.iter().zip(self.projection_base_tys(place)).any(
+                        |(elem, base)| {
+                            *elem == ProjectionElem::Deref
+                                && matches!(base.kind(), TyKind::Ref(..) | TyKind::RawPtr(..))
+                        },
+                    );
+                if is_deref_of_ref && seen.insert(span) {
+                    result.push(MovedOutOfRef { ty: self.place_ty(place).store(), span });
+                }
+            }
+        }
+        result
+    }
+
+    fn successors(&self, block: BasicBlockId) -> SmallVec<[BasicBlockId; 2]> {
+        let Some(terminator) = &self.body.basic_blocks[block].terminator else {
+            return SmallVec::new();
+        };
+        match &terminator.kind {
+            TerminatorKind::Goto { target } => smallvec![self.thread_jump(block, *target)],
+            TerminatorKind::SwitchInt { targets, .. } => {
+                targets.all_targets().iter().copied().collect()
+            }
+            TerminatorKind::Drop { target, unwind, .. }
+            | TerminatorKind::DropAndReplace { target, unwind, .. } => {
+                std::iter::once(*target).chain(*unwind).collect()
+            }
+            TerminatorKind::Call { target, cleanup, .. } => {
+                target.iter().chain(cleanup).copied().collect()
+            }
+            TerminatorKind::Assert { target, cleanup, .. } => {
+                std::iter::once(*target).chain(*cleanup).collect()
+            }
+            TerminatorKind::Yield { resume, drop, .. } => {
+                std::iter::once(*resume).chain(*drop).collect()
+            }
+            TerminatorKind::FalseEdge { real_target, imaginary_target } => {
+                smallvec![*real_target, *imaginary_target]
+            }
+            TerminatorKind::FalseUnwind { real_target, unwind } => {
+                std::iter::once(*real_target).chain(*unwind).collect()
+            }
+            TerminatorKind::UnwindResume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::CoroutineDrop => SmallVec::new(),
+        }
+    }
+
+    /// Follows a jump from `block` to `target` past a block that only switches on a flag which
+    /// `block` has set to a constant, like the ones `if let` (and let chains, through a few empty
+    /// blocks) are lowered to. This keeps the analyses from merging the paths where the pattern
+    /// matched and where it didn't.
+    fn thread_jump(&self, block: BasicBlockId, target: BasicBlockId) -> BasicBlockId {
+        const MAX_EMPTY_BLOCKS: usize = 8;
+        let mut switch_block = target;
+        let mut switch = None;
+        for _ in 0..MAX_EMPTY_BLOCKS {
+            let data = &self.body.basic_blocks[switch_block];
+            if !data.statements.is_empty() {
+                break;
+            }
+            match data.terminator.as_ref().map(|it| &it.kind) {
+                Some(TerminatorKind::Goto { target }) => switch_block = *target,
+                Some(TerminatorKind::SwitchInt { discr, targets }) => {
+                    switch = Some((discr, targets));
+                    break;
+                }
+                _ => break,
+            }
+        }
+        let Some((discr, targets)) = switch else { return target };
+        let OperandKind::Copy(flag) = &discr.kind else { return target };
+        if !flag.projection.is_empty() {
+            return target;
+        }
+        let last_assignment =
+            self.body.basic_blocks[block].statements.iter().rev().find_map(|it| match &it.kind {
+                StatementKind::Assign(dest, rvalue) if dest.local == flag.local => {
+                    Some((dest, rvalue))
+                }
+                _ => None,
+            });
+        match last_assignment {
+            Some((
+                dest,
+                Rvalue::Use(Operand { kind: OperandKind::Allocation { allocation }, .. }),
+            )) if dest.projection.is_empty() && allocation.as_ref().memory.len() <= 16 => {
+                let mut bytes = [0; 16];
+                let memory = &allocation.as_ref().memory;
+                bytes[..memory.len()].copy_from_slice(memory);
+                targets.target_for_value(u128::from_le_bytes(bytes))
+            }
+            _ => target,
+        }
+    }
+
+    /// Runs a forward dataflow analysis to a fixpoint and returns the state at the entry of each
+    /// block. States are joined by union.
+    fn forward_dataflow(
+        &self,
+        bottom: &DenseBitSet<usize>,
+        mut transfer: impl FnMut(BasicBlockId, &mut DenseBitSet<usize>),
+    ) -> ArenaMap<BasicBlockId, DenseBitSet<usize>> {
+        let mut entry_states = ArenaMap::default();
+        for (block, _) in self.body.basic_blocks.iter() {
+            entry_states.insert(block, bottom.clone());
+        }
+        // Every block has to be visited at least once, even if its entry state stays at the bottom.
+        let mut worklist: Vec<_> = self.body.basic_blocks.iter().map(|(id, _)| id).rev().collect();
+        let mut in_worklist: FxHashSet<_> = worklist.iter().copied().collect();
+        while let Some(block) = worklist.pop() {
+            in_worklist.remove(&block);
+            let mut state = entry_states[block].clone();
+            transfer(block, &mut state);
+            for succ in self.successors(block) {
+                if entry_states[succ].union(&state) && in_worklist.insert(succ) {
+                    worklist.push(succ);
+                }
+            }
+        }
+        entry_states
+    }
+
+    fn used_after_move(
+        &self,
+        events: &ArenaMap<BasicBlockId, Vec<SmallVec<[Event<'db>; 4]>>>,
+        sites: &[MoveSite<'db>],
+    ) -> Vec<UseOfMovedValue> {
+        if sites.is_empty() {
+            return vec![];
+        }
+        let mut sites_of_local: FxHashMap<LocalId, Vec<usize>> = FxHashMap::default();
+        for (idx, site) in sites.iter().enumerate() {
+            sites_of_local.entry(site.place.local).or_default().push(idx);
+        }
+        let apply = |event: &Event<'db>, state: &mut DenseBitSet<usize>| match *event {
+            Event::Move { site: Some(site), .. } => {
+                state.insert(site);
+            }
+            Event::Write { place } if Self::is_move_path(place) => {
+                for &site in sites_of_local.get(&place.local).into_iter().flatten() {
+                    if sites[site].place.projection.starts_with(&place.projection) {
+                        state.remove(site);
+                    }
+                }
+            }
+            Event::Storage(local) => {
+                for &site in sites_of_local.get(&local).into_iter().flatten() {
+                    state.remove(site);
+                }
+            }
+            _ => {}
+        };
+        let bottom = DenseBitSet::new_empty(sites.len());
+        let entry_states = self.forward_dataflow(&bottom, |block, state| {
+            for event in events[block].iter().flatten() {
+                apply(event, state);
+            }
+        });
+
+        let mut result = vec![];
+        let mut seen = FxHashSet::default();
+        for (block, block_events) in events.iter() {
+            let mut state = entry_states[block].clone();
+            for event in block_events.iter().flatten() {
+                let (place, span, is_borrow, is_discriminant) = match *event {
+                    Event::Read { place, span } | Event::Move { place, span, .. } => {
+                        (place, span, false, false)
+                    }
+                    Event::Discriminant { place, span } => (place, span, false, true),
+                    Event::Borrow { place, span } => (place, span, true, false),
+                    Event::Write { .. } | Event::Storage(_) => {
+                        apply(event, &mut state);
+                        continue;
+                    }
+                };
+                let Some(&binding) = self.local_to_binding.get(place.local) else {
+                    apply(event, &mut state);
+                    continue;
+                };
+                let moved = sites_of_local.get(&place.local).into_iter().flatten().find(|&&site| {
+                    let moved_place = &sites[site].place.projection;
+                    state.contains(site)
+                        && places_conflict(moved_place, &place.projection)
+                        && (!is_discriminant || moved_place.len() <= place.projection.len())
+                });
+                if let Some(&site) = moved
+                    && span != MirSpan::Unknown
+                    && seen.insert(span)
+                {
+                    let site = sites[site];
+                    result.push(UseOfMovedValue {
+                        binding,
+                        ty: self.place_ty(site.place).store(),
+                        span,
+                        move_span: site.span,
+                        is_borrow,
+                        is_partial: site.place.projection.len() > place.projection.len(),
+                    });
+                }
+                apply(event, &mut state);
+            }
+        }
+        result
+    }
+
+    /// Returns the arguments of a call whose loans may be held by the returned value.
+    ///
+    /// For references, we only consider the loan of the reference itself, not the loans held by
+    /// the referenced value: we can't tell in which of its lifetimes these are, and assuming they
+    /// flow into e.g. every `Ty<'db>` returned from a `&mut Ctx<'db, 'a>` is wrong way too often.
+    fn call_loan_sources(
+        &self,
+        func: &Operand,
+        args: &[Operand],
+        destination: PlaceRef<'db>,
+    ) -> SmallVec<[LoanSource; 4]> {
+        if !self.place_ty(destination).has_type_flags(LOAN_REGION_FLAGS) {
+            return SmallVec::new();
+        }
+        let func_ty = match &func.kind {
+            OperandKind::Constant { ty, .. } => Some(ty.as_ref()),
+            OperandKind::Allocation { allocation } => Some(allocation.as_ref().ty),
+            OperandKind::Copy(place) | OperandKind::Move(place) => {
+                Some(self.place_ty(place.as_ref()))
+            }
+            OperandKind::Static(_) => None,
+        };
+        let Some(TyKind::FnDef(def, _)) = func_ty.map(|it| it.kind()) else {
+            return std::iter::once(func)
+                .chain(args)
+                .filter_map(operand_local)
+                .map(|local| LoanSource { local, field: None, shared_only: false })
+                .collect();
+        };
+        // Borrows can only make it into the return value through the generic parameters and
+        // lifetimes it shares with the arguments. This is what makes e.g. `Iterator::next()`
+        // not extend the borrow of the iterator.
+        let sig = self.db.callable_item_signature(def.0).skip_binder().skip_binder();
+        let output = SignatureVars::of(sig.output());
+        if output.is_empty() {
+            return SmallVec::new();
+        }
+        // Elided lifetimes in the return type come from `&self`, or from the only argument
+        // having lifetimes.
+        let elided_source = if output.elided {
+            let has_self = match def.0 {
+                CallableDefId::FunctionId(func) => {
+                    FunctionSignature::of(self.db, func).has_self_param()
+                }
+                CallableDefId::StructId(_) | CallableDefId::EnumVariantId(_) => false,
+            };
+            let mut with_regions = sig
+                .inputs()
+                .iter()
+                .enumerate()
+                .filter(|(_, input)| input.has_type_flags(TypeFlags::HAS_REGIONS));
+            match sig.inputs().first() {
+                Some(input) if has_self && matches!(input.kind(), TyKind::Ref(..)) => Some(0),
+                _ => match (with_regions.next(), with_regions.next()) {
+                    (Some((idx, _)), None) => Some(idx),
+                    // We don't know where the lifetime comes from, so let everything flow.
+                    _ => None,
+                },
+            }
+        } else {
+            None
+        };
+        let unknown_elision = output.elided && elided_source.is_none();
+        // Calling a closure that is not `FnOnce` by value still can't give away its captures.
+        let is_call_once = matches!(
+            def.0,
+            CallableDefId::FunctionId(func)
+                if Some(func) == self.infcx.interner.lang_items().FnOnce_call_once
+        );
+        args.iter()
+            .zip(sig.inputs())
+            .enumerate()
+            .filter_map(|(idx, (arg, input))| {
+                let local = operand_local(arg)?;
+                if is_call_once
+                    && idx == 0
+                    && let TyKind::Closure(_, closure_args) =
+                        self.body.locals[local].ty.as_ref().kind()
+                {
+                    if closure_args.as_closure().kind_ty().to_opt_closure_kind()
+                        != Some(ClosureKind::FnOnce)
+                    {
+                        return None;
+                    }
+                    // We don't know which captures the closure returns, but its mutable loans
+                    // usually come from captures by reference, which can't escape it.
+                    return Some(LoanSource { local, field: None, shared_only: true });
+                }
+                if unknown_elision || elided_source == Some(idx) {
+                    return Some(LoanSource { local, field: None, shared_only: false });
+                }
+                let flows = match input.kind() {
+                    TyKind::Ref(region, ..) => output.mentions_region(region),
+                    // We don't know which of the lifetimes of the argument hold its loans, so
+                    // assume they can only flow if all of them make it into the return value.
+                    _ => {
+                        let input = SignatureVars::of(*input);
+                        input.intersects(&output) && input.lifetimes_in(&output)
+                    }
+                };
+                flows.then_some(LoanSource { local, field: None, shared_only: false })
+            })
+            .collect()
+    }
+
+    fn conflicting_borrows(&self, move_sites: &[MoveSite<'db>]) -> Vec<ConflictingBorrow> {
+        let body = self.body;
+
+        // Collect the loans we check against, and the locals that can hold them.
+        let mut loans = vec![];
+        let mut loan_at = FxHashMap::default();
+        for (block_id, block) in body.basic_blocks.iter() {
+            for (idx, statement) in block.statements.iter().enumerate() {
+                let StatementKind::Assign(dest, Rvalue::Ref(kind, place)) = &statement.kind else {
+                    continue;
+                };
+                let place = place.as_ref();
+                if matches!(kind, BorrowKind::Shallow)
+                    || !dest.projection.is_empty()
+                    || !self.local_to_binding.contains_idx(place.local)
+                    || self.derefs_raw_pointer_or_shared_ref(place)
+                {
+                    continue;
+                }
+                loan_at.insert((block_id, idx), loans.len());
+                loans.push(Loan {
+                    place,
+                    kind: *kind,
+                    span: statement.span,
+                    dest: dest.local,
+                    location: (block_id, idx),
+                });
+            }
+        }
+        if loans.is_empty() {
+            return vec![];
+        }
+        // Overwriting a reference ends the loans taken through it, as the borrowed place is not
+        // reachable anymore.
+        let mut loans_through_deref: FxHashMap<LocalId, Vec<usize>> = FxHashMap::default();
+        for (idx, loan) in loans.iter().enumerate() {
+            if loan.place.projection.contains(&ProjectionElem::Deref) {
+                loans_through_deref.entry(loan.place.local).or_default().push(idx);
+            }
+        }
+        // The locals a loan is directly assigned to, which includes reborrows and copies of the
+        // reference, and the field of the local holding it if it was put into an aggregate. Loans
+        // held this way are tied to the lifetime of the reference itself, so reading through it
+        // only gets at the other ones.
+        let mut reference_copies: FxHashMap<LocalId, Vec<(LocalId, Option<Option<u32>>)>> =
+            FxHashMap::default();
+        for (_, block) in body.basic_blocks.iter() {
+            for statement in &block.statements {
+                let StatementKind::Assign(dest, rvalue) = &statement.kind else { continue };
+                if !dest.projection.is_empty() {
+                    continue;
+                }
+                let whole_local = |o: &Operand| match &o.kind {
+                    OperandKind::Copy(place) | OperandKind::Move(place)
+                        if place.projection.is_empty() =>
+                    {
+                        Some(place.local)
+                    }
+                    _ => None,
+                };
+                let mut add = |src, field| {
+                    reference_copies.entry(src).or_default().push((dest.local, field));
+                };
+                match rvalue {
+                    Rvalue::Ref(_, place)
+                        if place.as_ref().projection.first() == Some(&ProjectionElem::Deref) =>
+                    {
+                        add(place.local, Some(None))
+                    }
+                    Rvalue::Use(o) | Rvalue::Cast(_, o, _) => {
+                        if let Some(src) = whole_local(o) {
+                            add(src, None)
+                        }
+                    }
+                    Rvalue::Aggregate(kind, ops) => {
+                        let has_fields = matches!(
+                            kind,
+                            AggregateKind::Tuple(_)
+                                | AggregateKind::Closure(_)
+                                | AggregateKind::Adt(VariantId::StructId(_), _)
+                        );
+                        for (idx, op) in ops.iter().enumerate() {
+                            if let Some(src) = whole_local(op) {
+                                add(src, Some(has_fields.then_some(idx as u32)))
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(terminator) = &block.terminator
+                && let TerminatorKind::Call { func, args, destination, .. } = &terminator.kind
+                && destination.projection.is_empty()
+            {
+                let sources = self.call_loan_sources(func, args, destination.as_ref());
+                for source in sources {
+                    reference_copies
+                        .entry(source.local)
+                        .or_default()
+                        .push((destination.local, Some(None)));
+                }
+            }
+        }
+        let direct_holders: Vec<FxHashMap<LocalId, SmallVec<[Option<u32>; 1]>>> = loans
+            .iter()
+            .map(|loan| {
+                let mut result: FxHashMap<_, SmallVec<_>> = FxHashMap::default();
+                let mut seen = FxHashSet::default();
+                let mut stack = vec![(loan.dest, None)];
+                while let Some((local, field)) = stack.pop() {
+                    if seen.insert((local, field)) {
+                        result.entry(local).or_default().push(field);
+                        stack.extend(
+                            reference_copies
+                                .get(&local)
+                                .into_iter()
+                                .flatten()
+                                .map(|&(dest, new_field)| (dest, new_field.unwrap_or(field))),
+                        );
+                    }
+                }
+                result
+            })
+            .collect();
+        // Whether the loan flows from the source, which is not the case if the source only holds
+        // it directly, in a part we don't read.
+        let loan_flows = |loan: usize, source: &LoanSource| {
+            if source.shared_only && loans[loan].is_mutable() {
+                return false;
+            }
+            let Some(fields) = direct_holders[loan].get(&source.local) else { return true };
+            match source.field {
+                Some(read) => fields.iter().any(|&field| field.is_none_or(|field| field == read)),
+                None => true,
+            }
+        };
+        let mut holders = ArenaMap::default();
+        let mut holder_count = 0;
+        for (local, data) in body.locals.iter() {
+            if data.ty.as_ref().has_type_flags(LOAN_REGION_FLAGS) {
+                holders.insert(local, holder_count);
+                holder_count += 1;
+            }
+        }
+        let loan_count = loans.len();
+        let state_bits = holder_count * loan_count;
+        if state_bits.saturating_mul(body.basic_blocks.len()) > MAX_LOAN_STATE_BITS {
+            return vec![];
+        }
+
+        let kill_loans_through = |local: LocalId, state: &mut DenseBitSet<usize>| {
+            for &loan in loans_through_deref.get(&local).into_iter().flatten() {
+                for (_, &holder) in holders.iter() {
+                    state.remove(holder * loan_count + loan);
+                }
+            }
+        };
+
+        // Which loans may be held by each local.
+        let loan_flow = |from: &[LoanSource],
+                         new_loan: Option<usize>,
+                         dest: PlaceRef<'db>,
+                         state: &mut DenseBitSet<usize>| {
+            let mut row = DenseBitSet::new_empty(loan_count);
+            for source in from {
+                if let Some(&src_idx) = holders.get(source.local) {
+                    for loan in 0..loan_count {
+                        if state.contains(src_idx * loan_count + loan) && loan_flows(loan, source) {
+                            row.insert(loan);
+                        }
+                    }
+                }
+            }
+            if let Some(loan) = new_loan {
+                row.insert(loan);
+            }
+            if let Some(&dest_idx) = holders.get(dest.local) {
+                if dest.projection.is_empty() {
+                    for bit in dest_idx * loan_count..(dest_idx + 1) * loan_count {
+                        state.remove(bit);
+                    }
+                }
+                for loan in row.iter() {
+                    state.insert(dest_idx * loan_count + loan);
+                }
+            }
+            if dest.projection.is_empty() {
+                kill_loans_through(dest.local, state);
+            }
+        };
+        let clear_local = |local: LocalId, state: &mut DenseBitSet<usize>| {
+            kill_loans_through(local, state);
+            if let Some(&idx) = holders.get(local) {
+                for bit in idx * loan_count..(idx + 1) * loan_count {
+                    state.remove(bit);
+                }
+            }
+        };
+        let statement_flow = |block: BasicBlockId, idx: usize, state: &mut DenseBitSet<usize>| {
+            let statement = &body.basic_blocks[block].statements[idx];
+            match &statement.kind {
+                StatementKind::Assign(dest, rvalue) => {
+                    let sources = rvalue_sources(rvalue);
+                    loan_flow(&sources, loan_at.get(&(block, idx)).copied(), dest.as_ref(), state);
+                }
+                &StatementKind::StorageLive(local) | &StatementKind::StorageDead(local) => {
+                    clear_local(local, state)
+                }
+                StatementKind::Deinit(_) | StatementKind::FakeRead(_) | StatementKind::Nop => {}
+            }
+        };
+        let terminator_flow = |block: BasicBlockId, state: &mut DenseBitSet<usize>| {
+            let Some(terminator) = &body.basic_blocks[block].terminator else { return };
+            match &terminator.kind {
+                TerminatorKind::Call { func, args, destination, .. } => {
+                    let destination = destination.as_ref();
+                    let sources = self.call_loan_sources(func, args, destination);
+                    loan_flow(&sources, None, destination, state);
+                }
+                TerminatorKind::DropAndReplace { place, value, .. } => {
+                    let sources: SmallVec<[_; 1]> = operand_source(value).into_iter().collect();
+                    loan_flow(&sources, None, place.as_ref(), state);
+                }
+                _ => {}
+            }
+        };
+        let bottom = DenseBitSet::new_empty(state_bits);
+        let entry_states = self.forward_dataflow(&bottom, |block, state| {
+            for idx in 0..body.basic_blocks[block].statements.len() {
+                statement_flow(block, idx, state);
+            }
+            terminator_flow(block, state);
+        });
+
+        let liveness = Liveness::compute(self, &holders, move_sites);
+
+        let mut result = vec![];
+        let mut seen = FxHashSet::default();
+        for (block_id, block) in body.basic_blocks.iter() {
+            let live_after = liveness.live_after_statements(self, block_id);
+            let mut state = entry_states[block_id].clone();
+            for (idx, statement) in block.statements.iter().enumerate() {
+                if let StatementKind::Assign(_, Rvalue::Ref(kind, place)) = &statement.kind
+                    && !matches!(kind, BorrowKind::Shallow)
+                    && let Some(&binding) = self.local_to_binding.get(place.local)
+                    && statement.span != MirSpan::Unknown
+                {
+                    let place = place.as_ref();
+                    let mutable = matches!(kind, BorrowKind::Mut { .. });
+                    // A two-phase borrow is only a reservation until the reference is used, and
+                    // coexists with shared borrows until then.
+                    let reserved =
+                        matches!(kind, BorrowKind::Mut { kind: MutBorrowKind::TwoPhasedBorrow });
+                    let is_live = |loan_idx: usize, ignore_dest: Option<LocalId>| {
+                        holders.iter().any(|(local, &holder)| {
+                            Some(local) != ignore_dest
+                                && live_after[idx].contains(holder)
+                                && state.contains(holder * loan_count + loan_idx)
+                        })
+                    };
+                    let conflict = loans.iter().enumerate().find(|(loan_idx, loan)| {
+                        if loan.location == (block_id, idx)
+                            || !((mutable && !reserved) || loan.is_mutable())
+                            || loan.place.local != place.local
+                            || !places_conflict(&loan.place.projection, &place.projection)
+                        {
+                            return false;
+                        }
+                        // A two-phase borrow acts like a shared borrow until it is activated by
+                        // the use of the reference it is assigned to.
+                        if loan.is_two_phase() && !mutable && !is_live(*loan_idx, Some(loan.dest)) {
+                            return false;
+                        }
+                        is_live(*loan_idx, None)
+                    });
+                    if let Some((_, loan)) = conflict
+                        && seen.insert(statement.span)
+                    {
+                        result.push(ConflictingBorrow {
+                            binding,
+                            span: statement.span,
+                            mutable,
+                            previous_span: loan.span,
+                            previous_mutable: loan.is_mutable(),
+                        });
+                    }
+                }
+                statement_flow(block_id, idx, &mut state);
+            }
+        }
+        result
+    }
+
+    fn mutability_of_bindings(&self) -> ArenaMap<BindingId, MutabilityReason> {
+        let mut reasons = MutabilityOfLocals::compute(self);
+        let mut result = ArenaMap::default();
+        for (binding, &local) in self.body.binding_locals.iter() {
+            if self.closure_expr.is_none() || self.store.binding_owner(binding) == self.closure_expr
+            {
+                result
+                    .insert(binding, std::mem::replace(&mut reasons[local], MutabilityReason::Not));
+            }
+        }
+        // Mutating a capture directly (as opposed to through a reference) means it was captured
+        // by value, which requires the captured binding to be mutable.
+        for (&binding, captures) in &self.body.upvar_locals {
+            for &(local, _) in captures {
+                if let reason @ MutabilityReason::Mut { .. } = &reasons[local] {
+                    result.entry(binding).or_insert(MutabilityReason::Not).merge(reason);
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether writing to (or mutably borrowing) the place mutates its local, as opposed to
+    /// something behind a reference or a raw pointer.
+    fn mutates_local(&self, place: PlaceRef<'db>) -> bool {
+        !place.projection.contains(&ProjectionElem::Deref)
+            || place
+                .projection
+                .iter()
+                .zip(self.projection_base_tys(place))
+                .all(|(elem, base)| *elem != ProjectionElem::Deref || base.is_box())
+    }
+
+    /// Loans of places behind raw pointers or shared references can't conflict with anything we
+    /// are able to check.
+    fn derefs_raw_pointer_or_shared_ref(&self, place: PlaceRef<'db>) -> bool {
+        place.projection.contains(&ProjectionElem::Deref)
+            && place.projection.iter().zip(self.projection_base_tys(place)).any(|(elem, base)| {
+                *elem == ProjectionElem::Deref
+                    && matches!(
+                        base.kind(),
+                        TyKind::RawPtr(..) | TyKind::Ref(_, _, Mutability::Not)
+                    )
+            })
+    }
+
+    /// Whether dropping a value of the type may use the loans it holds. Like in rustc, this is
+    /// only the case for `Drop` impls which don't mark their generic parameters `#[may_dangle]`.
+    fn drop_may_use_loans(&self, ty: Ty<'db>, visited: &mut FxHashSet<Ty<'db>>) -> bool {
+        if !visited.insert(ty) {
+            return false;
+        }
+        let interner = self.infcx.interner;
+        let fields_may_use_loans = |this: &Self, variant: VariantId, args, visited: &mut _| {
+            this.db.field_types(variant).iter().any(|(_, field)| {
+                let ty = field.ty().instantiate(interner, args).skip_norm_wip();
+                this.drop_may_use_loans(ty, visited)
+            })
+        };
+        match ty.kind() {
+            TyKind::Adt(adt_def, args) => {
+                let adt_id = adt_def.def_id();
+                if let Some(impl_id) = *destructor(self.db, adt_id) {
+                    let (lifetimes, types) =
+                        AttrFlags::query_generic_params(self.db, impl_id.into());
+                    let may_dangle = lifetimes
+                        .values()
+                        .chain(types.values())
+                        .any(|it| it.contains(AttrFlags::MAY_DANGLE));
+                    if !may_dangle && ty.has_type_flags(LOAN_REGION_FLAGS) {
+                        return true;
+                    }
+                }
+                match adt_id {
+                    AdtId::StructId(id) => fields_may_use_loans(self, id.into(), args, visited),
+                    AdtId::UnionId(_) => false,
+                    AdtId::EnumId(id) => {
+                        id.enum_variants(self.db).variants.values().any(|&(variant, _)| {
+                            fields_may_use_loans(self, variant.into(), args, visited)
+                        })
+                    }
+                }
+            }
+            TyKind::Tuple(tys) => tys.iter().any(|ty| self.drop_may_use_loans(ty, visited)),
+            TyKind::Array(ty, _) | TyKind::Slice(ty) => self.drop_may_use_loans(ty, visited),
+            TyKind::Closure(_, args) => {
+                self.drop_may_use_loans(args.as_closure().tupled_upvars_ty(), visited)
+            }
+            TyKind::Dynamic(..) | TyKind::Param(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Liveness of the locals that may hold a loan.
+struct Liveness {
+    live_out: ArenaMap<BasicBlockId, DenseBitSet<usize>>,
+    /// Locals whose drop counts as a use of the value.
+    drop_used: FxHashSet<LocalId>,
+    holders: ArenaMap<LocalId, usize>,
+}
+
+impl Liveness {
+    fn compute(
+        ctx: &BorrowckCtx<'_, '_>,
+        holders: &ArenaMap<LocalId, usize>,
+        move_sites: &[MoveSite<'_>],
+    ) -> Liveness {
+        let body = ctx.body;
+        // Drops of values which may have been moved out are elided, but we don't track which
+        // ones are, so only consider drops of values that are never moved. Temporaries are
+        // dropped later than they would be in rustc, so their drops are ignored as well.
+        let moved: FxHashSet<LocalId> = move_sites.iter().map(|site| site.place.local).collect();
+        let drop_used = holders
+            .iter()
+            .filter(|&(local, _)| {
+                ctx.local_to_binding.contains_idx(local)
+                    && !moved.contains(&local)
+                    && ctx.drop_may_use_loans(
+                        body.locals[local].ty.as_ref(),
+                        &mut FxHashSet::default(),
+                    )
+            })
+            .map(|(local, _)| local)
+            .collect();
+        let mut this =
+            Liveness { live_out: ArenaMap::default(), drop_used, holders: holders.clone() };
+        let bottom = DenseBitSet::new_empty(holders.values().count());
+        let mut live_in: ArenaMap<BasicBlockId, DenseBitSet<usize>> = ArenaMap::default();
+        for (block, _) in body.basic_blocks.iter() {
+            live_in.insert(block, bottom.clone());
+            this.live_out.insert(block, bottom.clone());
+        }
+        let blocks: Vec<_> = body.basic_blocks.iter().map(|(id, _)| id).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in blocks.iter().rev() {
+                let mut state = bottom.clone();
+                for succ in ctx.successors(block) {
+                    state.union(&live_in[succ]);
+                }
+                this.live_out[block] = state.clone();
+                this.terminator_effect(ctx, block, &mut state);
+                for statement in body.basic_blocks[block].statements.iter().rev() {
+                    this.statement_effect(&statement.kind, &mut state);
+                }
+                if live_in[block] != state {
+                    live_in[block] = state;
+                    changed = true;
+                }
+            }
+        }
+        this
+    }
+
+    /// Returns the set of live locals right after each statement of the block.
+    fn live_after_statements(
+        &self,
+        ctx: &BorrowckCtx<'_, '_>,
+        block: BasicBlockId,
+    ) -> Vec<DenseBitSet<usize>> {
+        let statements = &ctx.body.basic_blocks[block].statements;
+        let mut state = self.live_out[block].clone();
+        self.terminator_effect(ctx, block, &mut state);
+        let mut result = vec![state.clone(); statements.len()];
+        for (idx, statement) in statements.iter().enumerate().rev() {
+            result[idx] = state.clone();
+            self.statement_effect(&statement.kind, &mut state);
+        }
+        result
+    }
+
+    fn use_local(&self, local: LocalId, state: &mut DenseBitSet<usize>) {
+        if let Some(&idx) = self.holders.get(local) {
+            state.insert(idx);
+        }
+    }
+
+    fn def_local(&self, local: LocalId, state: &mut DenseBitSet<usize>) {
+        if let Some(&idx) = self.holders.get(local) {
+            state.remove(idx);
+        }
+    }
+
+    fn use_place(&self, place: PlaceRef<'_>, state: &mut DenseBitSet<usize>) {
+        self.use_local(place.local, state);
+        self.use_index_locals(place, state);
+    }
+
+    fn use_index_locals(&self, place: PlaceRef<'_>, state: &mut DenseBitSet<usize>) {
+        for elem in place.projection.iter() {
+            if let &ProjectionElem::Index(local) = elem {
+                self.use_local(local, state);
+            }
+        }
+    }
+
+    /// Applies a write to the place, going backwards.
+    fn write_place(&self, place: PlaceRef<'_>, state: &mut DenseBitSet<usize>) {
+        if place.projection.is_empty() {
+            self.def_local(place.local, state);
+        } else if place.projection.contains(&ProjectionElem::Deref) {
+            // Writing through a pointer uses the pointer.
+            self.use_local(place.local, state);
+        }
+        self.use_index_locals(place, state);
+    }
+
+    fn use_operand(&self, op: &Operand, state: &mut DenseBitSet<usize>) {
+        if let OperandKind::Copy(place) | OperandKind::Move(place) = &op.kind {
+            self.use_place(place.as_ref(), state);
+        }
+    }
+
+    fn statement_effect(&self, statement: &StatementKind, state: &mut DenseBitSet<usize>) {
+        match statement {
+            StatementKind::Assign(dest, rvalue) => {
+                self.write_place(dest.as_ref(), state);
+                match rvalue {
+                    Rvalue::Use(o)
+                    | Rvalue::Repeat(o, _)
+                    | Rvalue::Cast(_, o, _)
+                    | Rvalue::UnaryOp(_, o) => self.use_operand(o, state),
+                    Rvalue::CheckedBinaryOp(_, o1, o2) => {
+                        self.use_operand(o1, state);
+                        self.use_operand(o2, state);
+                    }
+                    Rvalue::Aggregate(_, ops) => {
+                        ops.iter().for_each(|o| self.use_operand(o, state))
+                    }
+                    Rvalue::Ref(_, place)
+                    | Rvalue::Len(place)
+                    | Rvalue::Discriminant(place)
+                    | Rvalue::CopyForDeref(place) => self.use_place(place.as_ref(), state),
+                    Rvalue::ThreadLocalRef(n)
+                    | Rvalue::AddressOf(n)
+                    | Rvalue::BinaryOp(n)
+                    | Rvalue::NullaryOp(n) => match *n {},
+                }
+            }
+            &StatementKind::StorageLive(local) | &StatementKind::StorageDead(local) => {
+                self.def_local(local, state)
+            }
+            StatementKind::Deinit(place) => self.write_place(place.as_ref(), state),
+            StatementKind::FakeRead(_) | StatementKind::Nop => {}
+        }
+    }
+
+    fn terminator_effect(
+        &self,
+        ctx: &BorrowckCtx<'_, '_>,
+        block: BasicBlockId,
+        state: &mut DenseBitSet<usize>,
+    ) {
+        let Some(terminator) = &ctx.body.basic_blocks[block].terminator else { return };
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr: o, .. } | TerminatorKind::Assert { cond: o, .. } => {
+                self.use_operand(o, state)
+            }
+            TerminatorKind::Call { func, args, destination, .. } => {
+                self.write_place(destination.as_ref(), state);
+                self.use_operand(func, state);
+                args.iter().for_each(|arg| self.use_operand(arg, state));
+            }
+            TerminatorKind::DropAndReplace { place, value, .. } => {
+                self.write_place(place.as_ref(), state);
+                self.use_operand(value, state);
+            }
+            TerminatorKind::Yield { value, resume_arg, .. } => {
+                self.write_place(resume_arg.as_ref(), state);
+                self.use_operand(value, state);
+            }
+            TerminatorKind::Drop { place, .. } => {
+                if self.drop_used.contains(&place.local) {
+                    self.use_place(place.as_ref(), state);
+                }
+            }
+            TerminatorKind::Return => self.use_local(return_slot(), state),
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::UnwindResume
+            | TerminatorKind::Abort
+            | TerminatorKind::Unreachable
+            | TerminatorKind::CoroutineDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. } => {}
+        }
+    }
+}
+
+/// Computes which locals are mutated, which requires their bindings to be `mut`, and which ones
+/// are used at all.
+struct MutabilityOfLocals<'a, 'b, 'db> {
+    ctx: &'a BorrowckCtx<'b, 'db>,
+    /// Locals which may have been initialized before, and so need to be `mut` to be assigned.
+    ever_init: DenseBitSet<usize>,
+    /// Where the results are recorded, `None` while computing the dataflow fixpoint.
+    results: Option<&'a mut MutabilityResults>,
+}
+
+struct MutabilityResults {
+    reasons: ArenaMap<LocalId, MutabilityReason>,
+    /// Locals which are initialized somewhere in the body.
+    initialized: DenseBitSet<usize>,
+}
+
+impl<'db> MutabilityOfLocals<'_, '_, 'db> {
+    fn compute(ctx: &BorrowckCtx<'_, 'db>) -> ArenaMap<LocalId, MutabilityReason> {
+        let body = ctx.body;
+        let mut bottom = DenseBitSet::new_empty(body.locals.len());
+        for &param in &body.param_locals {
+            bottom.insert(local_idx(param));
+        }
+        let entry_states = ctx.forward_dataflow(&bottom, |block, state| {
+            let mut this = MutabilityOfLocals {
+                ctx,
+                ever_init: std::mem::replace(state, DenseBitSet::new_empty(0)),
+                results: None,
+            };
+            this.block_effect(block);
+            *state = this.ever_init;
+        });
+
+        let mut results = MutabilityResults {
+            reasons: body
+                .locals
+                .iter()
+                .map(|(local, _)| (local, MutabilityReason::Unused))
+                .collect(),
+            initialized: bottom,
+        };
+        for (block, _) in body.basic_blocks.iter() {
+            let mut this = MutabilityOfLocals {
+                ctx,
+                ever_init: entry_states[block].clone(),
+                results: Some(&mut results),
+            };
+            this.block_effect(block);
+        }
+        // Code after a diverging expression is not lowered, so a local that is never initialized
+        // may still be used there.
+        for (local, reason) in results.reasons.iter_mut() {
+            if !results.initialized.contains(local_idx(local)) {
+                reason.record_usage();
+            }
+        }
+        results.reasons
+    }
+
+    fn record_usage(&mut self, local: LocalId) {
+        if let Some(results) = &mut self.results {
+            results.reasons[local].record_usage();
+        }
+    }
+
+    fn push_mut_span(&mut self, local: LocalId, span: MirSpan) {
+        if let Some(results) = &mut self.results {
+            results.reasons[local].push_mut_span(span);
+        }
+    }
+
+    fn use_place(&mut self, place: PlaceRef<'_>) {
+        self.record_usage(place.local);
+        self.use_index_locals(place);
+    }
+
+    fn use_index_locals(&mut self, place: PlaceRef<'_>) {
+        for elem in place.projection.iter() {
+            if let &ProjectionElem::Index(local) = elem {
+                self.record_usage(local);
+            }
+        }
+    }
+
+    fn use_operand(&mut self, op: &Operand) {
+        if let OperandKind::Copy(place) | OperandKind::Move(place) = &op.kind {
+            self.use_place(place.as_ref());
+        }
+    }
+
+    fn write_place(&mut self, place: PlaceRef<'db>, span: MirSpan) {
+        self.use_index_locals(place);
+        if !self.ctx.mutates_local(place) {
+            // Writing through a reference only uses the local holding it.
+            self.record_usage(place.local);
+        } else if !place.projection.is_empty() {
+            // Partial initialization is not allowed, so assigning to a part of a local always
+            // mutates it.
+            self.push_mut_span(place.local, span);
+        } else {
+            if let Some(results) = &mut self.results {
+                results.initialized.insert(local_idx(place.local));
+            }
+            if !self.ever_init.insert(local_idx(place.local)) {
+                self.push_mut_span(place.local, span);
+            }
+        }
+    }
+
+    fn block_effect(&mut self, block: BasicBlockId) {
+        let data = &self.ctx.body.basic_blocks[block];
+        for statement in &data.statements {
+            let span = statement.span;
+            match &statement.kind {
+                StatementKind::Assign(dest, rvalue) => {
+                    match rvalue {
+                        Rvalue::Use(o)
+                        | Rvalue::Repeat(o, _)
+                        | Rvalue::Cast(_, o, _)
+                        | Rvalue::UnaryOp(_, o) => self.use_operand(o),
+                        Rvalue::CheckedBinaryOp(_, o1, o2) => {
+                            self.use_operand(o1);
+                            self.use_operand(o2);
+                        }
+                        Rvalue::Aggregate(_, ops) => ops.iter().for_each(|o| self.use_operand(o)),
+                        Rvalue::Ref(kind, place) => {
+                            let place = place.as_ref();
+                            self.use_place(place);
+                            // Unique borrows of closure captures don't need the captured place to
+                            // be mutable.
+                            let is_mut = matches!(
+                                kind,
+                                BorrowKind::Mut {
+                                    kind: MutBorrowKind::Default | MutBorrowKind::TwoPhasedBorrow
+                                }
+                            );
+                            if is_mut && self.ctx.mutates_local(place) {
+                                self.push_mut_span(place.local, span);
+                            }
+                        }
+                        Rvalue::Len(place)
+                        | Rvalue::Discriminant(place)
+                        | Rvalue::CopyForDeref(place) => self.use_place(place.as_ref()),
+                        Rvalue::ThreadLocalRef(n)
+                        | Rvalue::AddressOf(n)
+                        | Rvalue::BinaryOp(n)
+                        | Rvalue::NullaryOp(n) => match *n {},
+                    }
+                    self.write_place(dest.as_ref(), span);
+                }
+                StatementKind::FakeRead(place) => self.use_place(place.as_ref()),
+                &StatementKind::StorageLive(local) | &StatementKind::StorageDead(local) => {
+                    self.ever_init.remove(local_idx(local));
+                }
+                StatementKind::Deinit(_) | StatementKind::Nop => {}
+            }
+        }
+        let Some(terminator) = &data.terminator else { return };
+        let span = terminator.span;
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr: o, .. } | TerminatorKind::Assert { cond: o, .. } => {
+                self.use_operand(o)
+            }
+            TerminatorKind::Call { func, args, destination, .. } => {
+                self.use_operand(func);
+                args.iter().for_each(|arg| self.use_operand(arg));
+                self.write_place(destination.as_ref(), span);
+            }
+            TerminatorKind::DropAndReplace { place, value, .. } => {
+                self.use_operand(value);
+                self.write_place(place.as_ref(), span);
+            }
+            TerminatorKind::Yield { value, resume_arg, .. } => {
+                self.use_operand(value);
+                self.write_place(resume_arg.as_ref(), span);
+            }
+            // Dropping a value is not a use of it.
+            TerminatorKind::Drop { .. }
+            | TerminatorKind::Return
+            | TerminatorKind::Goto { .. }
+            | TerminatorKind::UnwindResume
+            | TerminatorKind::Abort
+            | TerminatorKind::Unreachable
+            | TerminatorKind::CoroutineDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. } => {}
+        }
+    }
+}
+
+fn local_idx(local: LocalId) -> usize {
+    local.into_raw().into_u32() as usize
+}
+
+fn operand_local(op: &Operand) -> Option<LocalId> {
+    match &op.kind {
+        OperandKind::Copy(place) | OperandKind::Move(place) => Some(place.local),
+        OperandKind::Constant { .. } | OperandKind::Allocation { .. } | OperandKind::Static(_) => {
+            None
+        }
+    }
+}
+
+/// A local whose loans flow into a value.
+#[derive(Debug, Clone, Copy)]
+struct LoanSource {
+    local: LocalId,
+    /// The field of the local the value is read from.
+    field: Option<u32>,
+    /// Whether only the shared loans of the local flow.
+    shared_only: bool,
+}
+
+fn operand_source(op: &Operand) -> Option<LoanSource> {
+    match &op.kind {
+        OperandKind::Copy(place) | OperandKind::Move(place) => place_source(place.as_ref()),
+        OperandKind::Constant { .. } | OperandKind::Allocation { .. } | OperandKind::Static(_) => {
+            None
+        }
+    }
+}
+
+/// Values read through a reference are not considered to hold the loans of the local, we don't
+/// track which lifetimes of the reference they come from.
+fn place_source(place: PlaceRef<'_>) -> Option<LoanSource> {
+    if place.projection.contains(&ProjectionElem::Deref) {
+        return None;
+    }
+    let field = match place.projection.first() {
+        Some(&ProjectionElem::Field(FieldIndex(field))) => Some(field),
+        _ => None,
+    };
+    Some(LoanSource { local: place.local, field, shared_only: false })
+}
+
+/// The locals whose value (and therefore loans) flow into the result of the rvalue.
+fn rvalue_sources(rvalue: &Rvalue) -> SmallVec<[LoanSource; 4]> {
+    match rvalue {
+        Rvalue::Use(o) | Rvalue::Repeat(o, _) | Rvalue::Cast(_, o, _) | Rvalue::UnaryOp(_, o) => {
+            operand_source(o).into_iter().collect()
+        }
+        Rvalue::CheckedBinaryOp(..) => SmallVec::new(),
+        Rvalue::Aggregate(_, ops) => ops.iter().filter_map(operand_source).collect(),
+        // A reborrow holds everything the reference does.
+        Rvalue::Ref(_, place) => {
+            smallvec![LoanSource { local: place.local, field: None, shared_only: false }]
+        }
+        Rvalue::CopyForDeref(place) => place_source(place.as_ref()).into_iter().collect(),
+        Rvalue::Len(_) | Rvalue::Discriminant(_) => SmallVec::new(),
+        Rvalue::ThreadLocalRef(n)
+        | Rvalue::AddressOf(n)
+        | Rvalue::BinaryOp(n)
+        | Rvalue::NullaryOp(n) => match *n {},
+    }
+}
+
+/// The generic parameters and late-bound lifetimes of a function signature that a type mentions.
+struct SignatureVars {
+    params: FxHashSet<u32>,
+    lifetime_params: FxHashSet<u32>,
+    late_bound: FxHashSet<BoundVar>,
+    /// Whether the type contains something we can't relate to the signature, like an error.
+    unknown: bool,
+    /// Whether the type contains elided lifetimes, which are lowered as errors.
+    elided: bool,
+    binder: DebruijnIndex,
+}
+
+impl SignatureVars {
+    fn of<'db>(ty: Ty<'db>) -> SignatureVars {
+        let mut vars = SignatureVars {
+            params: FxHashSet::default(),
+            lifetime_params: FxHashSet::default(),
+            late_bound: FxHashSet::default(),
+            unknown: false,
+            elided: false,
+            binder: DebruijnIndex::ZERO,
+        };
+        ty.visit_with(&mut vars);
+        vars
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.unknown && !self.elided && self.params.is_empty() && self.late_bound.is_empty()
+    }
+
+    fn intersects(&self, other: &SignatureVars) -> bool {
+        self.unknown
+            || other.unknown
+            || !self.params.is_disjoint(&other.params)
+            || !self.late_bound.is_disjoint(&other.late_bound)
+    }
+
+    /// Whether all lifetimes of `self` are mentioned in `other` as well.
+    fn lifetimes_in(&self, other: &SignatureVars) -> bool {
+        self.unknown
+            || other.unknown
+            || (self.lifetime_params.is_subset(&other.params)
+                && self.late_bound.is_subset(&other.late_bound)
+                && (!self.elided || other.elided))
+    }
+
+    fn mentions_region(&self, region: Region<'_>) -> bool {
+        match region.kind() {
+            RegionKind::ReEarlyParam(param) => self.params.contains(&param.index),
+            RegionKind::ReBound(BoundVarIndexKind::Bound(debruijn), bound) => {
+                debruijn == DebruijnIndex::ZERO && self.late_bound.contains(&bound.var)
+            }
+            // Elided lifetimes are handled by the caller.
+            RegionKind::ReStatic | RegionKind::ReError(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl<'db> TypeVisitor<DbInterner<'db>> for SignatureVars {
+    type Result = ();
+
+    fn visit_binder<T: TypeVisitable<DbInterner<'db>>>(&mut self, t: &Binder<'db, T>) {
+        self.binder.shift_in(1);
+        t.super_visit_with(self);
+        self.binder.shift_out(1);
+    }
+
+    fn visit_ty(&mut self, ty: Ty<'db>) {
+        match ty.kind() {
+            TyKind::Param(param) => {
+                self.params.insert(param.index);
+            }
+            TyKind::Error(_) => self.unknown = true,
+            _ => {}
+        }
+        ty.super_visit_with(self);
+    }
+
+    fn visit_region(&mut self, region: Region<'db>) {
+        match region.kind() {
+            RegionKind::ReEarlyParam(param) => {
+                self.params.insert(param.index);
+                self.lifetime_params.insert(param.index);
+            }
+            RegionKind::ReBound(BoundVarIndexKind::Bound(debruijn), bound) => {
+                // Lifetimes bound inside the signature, like in `for<'a> fn(&'a u8)`, can't
+                // refer to an argument.
+                if debruijn == self.binder {
+                    self.late_bound.insert(bound.var);
+                }
+            }
+            RegionKind::ReStatic => {}
+            // Elided lifetimes of function pointers and trait objects may be lowered as errors,
+            // but they still can't refer to an argument.
+            RegionKind::ReError(_) if self.binder != DebruijnIndex::ZERO => {}
+            RegionKind::ReError(_) => self.elided = true,
+            _ => self.unknown = true,
+        }
+    }
+}
+
+/// Whether two places based on the same local may overlap.
+fn places_conflict(a: &[PlaceElem], b: &[PlaceElem]) -> bool {
+    for (a, b) in a.iter().zip(b) {
+        match (a, b) {
+            (ProjectionElem::Field(a), ProjectionElem::Field(b)) if a != b => return false,
+            (ProjectionElem::Downcast(a), ProjectionElem::Downcast(b)) if a != b => return false,
+            (
+                ProjectionElem::ConstantIndex { offset: a, from_end: a_from_end },
+                ProjectionElem::ConstantIndex { offset: b, from_end: b_from_end },
+            ) if a_from_end == b_from_end && a != b => return false,
+            (a, b) if a == b => {}
+            (ProjectionElem::Deref, _) | (_, ProjectionElem::Deref) => {
+                never!("places with the same prefix have a different projection kind");
+                return true;
+            }
+            // Indexing and subslices may overlap with anything else.
+            _ => return true,
+        }
+    }
+    true
+}
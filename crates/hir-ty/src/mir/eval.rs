@@ -61,10 +61,13 @@ use super::{
     StatementKind, TerminatorKind, UnOp, return_slot,
 };
 
+mod debug;
 mod shim;
 #[cfg(test)]
 mod tests;
 
+pub use debug::{MirEvalObserver, PausedFrame, PausedLocal, interpret_mir_with_observer};
+
 macro_rules! from_bytes {
     ($ty:tt, $value:expr) => {
         ($ty::from_le_bytes(match ($value).try_into() {
@@ -89,6 +92,13 @@ macro_rules! not_supported {
 }
 use not_supported;
 
+macro_rules! missing_shim {
+    ($name: expr) => {
+        return Err($crate::mir::eval::MirEvalError::MissingShim($name.to_owned()))
+    };
+}
+use missing_shim;
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, GenericTypeVisitable)]
 pub struct VTableMap<'db> {
     ty_to_id: FxHashMap<Ty<'db>, usize>,
@@ -209,6 +219,9 @@ pub struct Evaluator<'a, 'db> {
     /// Maximum count of bytes that heap and stack can grow
     memory_limit: usize,
     infcx: InferCtxt<'db>,
+    /// Number of stack frames currently executing, including those of enclosing `interpret_mir` calls.
+    frame_depth: usize,
+    observer: Option<&'a mut dyn MirEvalObserver<'db>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -363,6 +376,8 @@ pub enum MirEvalError<'db> {
     MirLowerErrorForClosure(InternedClosureId<'db>, MirLowerError<'db>),
     TypeIsUnsized(StoredTy, &'static str),
     NotSupported(String),
+    /// Calls an intrinsic or an extern function that the interpreter has no shim for.
+    MissingShim(String),
     InvalidConst,
     InFunction(
         Box<MirEvalError<'db>>,
@@ -405,41 +420,7 @@ impl MirEvalError<'_> {
                         writeln!(f, "In {closure:?}")?;
                     }
                 }
-                let (source_map, self_param_syntax) = match *def {
-                    InferBodyId::DefWithBodyId(def) => {
-                        let body = &Body::with_source_map(db, def).1;
-                        (&**body, body.self_param_syntax())
-                    }
-                    InferBodyId::AnonConstId(def) => {
-                        let store = ExpressionStore::with_source_map(db, def.loc(db).owner).1;
-                        (store, None)
-                    }
-                };
-                let span: InFile<SyntaxNodePtr> = match *span {
-                    MirSpan::ExprId(e) => match source_map.expr_syntax(e) {
-                        Ok(s) => s.map(|it| it.into()),
-                        Err(_) => continue,
-                    },
-                    MirSpan::PatId(p) => match source_map.pat_syntax(p) {
-                        Ok(s) => s.map(|it| it.syntax_node_ptr()),
-                        Err(_) => continue,
-                    },
-                    MirSpan::BindingId(b) => {
-                        match source_map
-                            .patterns_for_binding(b)
-                            .iter()
-                            .find_map(|p| source_map.pat_syntax(*p).ok())
-                        {
-                            Some(s) => s.map(|it| it.syntax_node_ptr()),
-                            None => continue,
-                        }
-                    }
-                    MirSpan::SelfParam => match self_param_syntax {
-                        Some(s) => s.map(|it| it.syntax_node_ptr()),
-                        None => continue,
-                    },
-                    MirSpan::Unknown => continue,
-                };
+                let Some(span) = span.syntax(db, *def) else { continue };
                 let file_id = span.file_id.original_file(db);
                 let text_range = span.value.text_range();
                 writeln!(f, "{}", span_formatter(file_id.file_id(db), text_range))?;
@@ -498,6 +479,7 @@ impl MirEvalError<'_> {
             | MirEvalError::MirLowerErrorForClosure(_, _)
             | MirEvalError::TypeIsUnsized(_, _)
             | MirEvalError::NotSupported(_)
+            | MirEvalError::MissingShim(_)
             | MirEvalError::InvalidConst
             | MirEvalError::ExecutionLimitExceeded
             | MirEvalError::StackOverflow
@@ -551,6 +533,7 @@ impl std::fmt::Debug for MirEvalError<'_> {
             Self::InternalError(arg0) => f.debug_tuple("InternalError").field(arg0).finish(),
             Self::InvalidVTableId(arg0) => f.debug_tuple("InvalidVTableId").field(arg0).finish(),
             Self::NotSupported(arg0) => f.debug_tuple("NotSupported").field(arg0).finish(),
+            Self::MissingShim(arg0) => f.debug_tuple("MissingShim").field(arg0).finish(),
             Self::InvalidConst => f.write_str("InvalidConst"),
             Self::InFunction(e, stack) => {
                 f.debug_struct("WithStack").field("error", e).field("stack", &stack).finish()
@@ -594,6 +577,8 @@ struct Locals<'a, 'db> {
     ptr: ArenaMap<LocalId, Interval>,
     body: &'db MirBody<'db>,
     drop_flags: DropFlags<'a>,
+    /// The locals that have been written to, only tracked while an observer is attached.
+    assigned: FxHashSet<LocalId>,
 }
 
 pub struct MirOutput {
@@ -621,30 +606,8 @@ pub fn interpret_mir<'db>(
     assert_placeholder_ty_is_unused: bool,
     trait_env: Option<ParamEnvAndCrate<'db>>,
 ) -> Result<'db, (Result<'db, Allocation<'db>>, MirOutput)> {
-    let ty = body.locals[return_slot()].ty.as_ref();
-    let mut evaluator = Evaluator::new(db, body.owner, assert_placeholder_ty_is_unused, trait_env)?;
-    let it: Result<'db, Allocation<'db>> = (|| {
-        if evaluator.ptr_size() != size_of::<usize>() {
-            not_supported!("targets with different pointer size from host");
-        }
-        let interval = evaluator.interpret_mir(body, None.into_iter())?;
-        let bytes = interval.get(&evaluator)?;
-        let mut memory_map = evaluator.create_memory_map(
-            bytes,
-            ty,
-            &Locals { ptr: ArenaMap::new(), body, drop_flags: DropFlags::default() },
-        )?;
-        let bytes = Box::from(bytes);
-        let memory_map = if memory_map.memory.is_empty() && evaluator.vtable_map.is_empty() {
-            MemoryMap::Empty
-        } else {
-            memory_map.vtable = mem::take(&mut evaluator.vtable_map);
-            memory_map.vtable.shrink_to_fit();
-            MemoryMap::Complex(Box::new(memory_map))
-        };
-        Ok(Allocation::new(AllocationData { ty, memory: bytes, memory_map }))
-    })();
-    Ok((it, MirOutput { stdout: evaluator.stdout, stderr: evaluator.stderr }))
+    let evaluator = Evaluator::new(db, body.owner, assert_placeholder_ty_is_unused, trait_env)?;
+    Ok(evaluator.run(body))
 }
 
 #[cfg(test)]
@@ -700,6 +663,8 @@ impl<'a, 'db> Evaluator<'a, 'db> {
             cached_fn_mut_trait_func: lang_items.FnMut_call_mut,
             cached_fn_once_trait_func: lang_items.FnOnce_call_once,
             infcx,
+            frame_depth: 0,
+            observer: None,
         })
     }
 
@@ -941,6 +906,38 @@ impl<'a, 'db> Evaluator<'a, 'db> {
         })
     }
 
+    /// Interprets `body` from its start to completion.
+    fn run(mut self, body: &'db MirBody<'db>) -> (Result<'db, Allocation<'db>>, MirOutput) {
+        let ty = body.locals[return_slot()].ty.as_ref();
+        let it: Result<'db, Allocation<'db>> = (|| {
+            if self.ptr_size() != size_of::<usize>() {
+                not_supported!("targets with different pointer size from host");
+            }
+            let interval = self.interpret_mir(body, None.into_iter())?;
+            let bytes = interval.get(&self)?;
+            let mut memory_map = self.create_memory_map(
+                bytes,
+                ty,
+                &Locals {
+                    ptr: ArenaMap::new(),
+                    body,
+                    drop_flags: DropFlags::default(),
+                    assigned: FxHashSet::default(),
+                },
+            )?;
+            let bytes = Box::from(bytes);
+            let memory_map = if memory_map.memory.is_empty() && self.vtable_map.is_empty() {
+                MemoryMap::Empty
+            } else {
+                memory_map.vtable = mem::take(&mut self.vtable_map);
+                memory_map.vtable.shrink_to_fit();
+                MemoryMap::Complex(Box::new(memory_map))
+            };
+            Ok(Allocation::new(AllocationData { ty, memory: bytes, memory_map }))
+        })();
+        (it, MirOutput { stdout: self.stdout, stderr: self.stderr })
+    }
+
     fn interpret_mir(
         &mut self,
         body: &'db MirBody<'db>,
@@ -955,6 +952,8 @@ impl<'a, 'db> Evaluator<'a, 'db> {
         let (mut locals, prev_stack_ptr) = self.create_locals_for_body(body, None)?;
         self.fill_locals_for_body(body, &mut locals, args)?;
         let prev_code_stack = mem::take(&mut self.code_stack);
+        let prev_frame_depth = self.frame_depth;
+        self.frame_depth += 1;
         let span = (MirSpan::Unknown, body.owner);
         self.code_stack.push(StackFrame { locals, destination: None, prev_stack_ptr, span });
         'stack: loop {
@@ -962,6 +961,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 not_supported!("missing stack frame");
             };
             let e = (|| {
+                let call_span = my_stack_frame.span.0;
                 let locals = &mut my_stack_frame.locals;
                 let body = locals.body.clone();
                 loop {
@@ -972,12 +972,16 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                         return Err(MirEvalError::ExecutionLimitExceeded);
                     }
                     for statement in &current_block.statements {
+                        if self.observer.is_some() {
+                            self.observe_step(statement.span, call_span, locals);
+                        }
                         match &statement.kind {
                             StatementKind::Assign(l, r) => {
                                 let addr = self.place_addr(l, locals)?;
                                 let result = self.eval_rvalue(r, locals)?;
                                 self.copy_from_interval_or_owned(addr, result)?;
                                 locals.drop_flags.add_place(l.as_ref());
+                                self.record_assignment(locals, l.local);
                             }
                             StatementKind::Deinit(_) => not_supported!("de-init statement"),
                             StatementKind::StorageLive(_)
@@ -989,6 +993,9 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                     let Some(terminator) = current_block.terminator.as_ref() else {
                         not_supported!("block without terminator");
                     };
+                    if self.observer.is_some() {
+                        self.observe_step(terminator.span, call_span, locals);
+                    }
                     match &terminator.kind {
                         TerminatorKind::Goto { target } => {
                             current_block_idx = *target;
@@ -1035,8 +1042,10 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                                 self.code_stack.push(my_stack_frame);
                                 current_block_idx = stack_frame.locals.body.start_block;
                                 self.code_stack.push(stack_frame);
+                                self.frame_depth += 1;
                                 return Ok(None);
                             } else {
+                                self.record_assignment(locals, destination.local);
                                 current_block_idx =
                                     target.ok_or(MirEvalError::UndefinedBehavior(
                                         "Diverging function returned".to_owned(),
@@ -1072,6 +1081,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 Ok(Some(x)) => x,
                 Err(e) => {
                     let my_code_stack = mem::replace(&mut self.code_stack, prev_code_stack);
+                    self.frame_depth = prev_frame_depth;
                     let mut error_stack = vec![];
                     for frame in my_code_stack.into_iter().rev() {
                         if let Some(f) = frame.locals.body.owner.as_function() {
@@ -1087,6 +1097,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 .entry(my_stack_frame.locals.body.owner)
                 .or_default()
                 .push(my_stack_frame.locals);
+            self.frame_depth -= 1;
             match my_stack_frame.destination {
                 None => {
                     self.code_stack = prev_code_stack;
@@ -1094,6 +1105,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                     return Ok(return_interval);
                 }
                 Some(bb) => {
+                    self.record_return(return_interval);
                     // We don't support const promotion, so we can't truncate the stack yet.
                     let _ = my_stack_frame.prev_stack_ptr;
                     // self.stack.truncate(my_stack_frame.prev_stack_ptr);
@@ -1112,6 +1124,9 @@ impl<'a, 'db> Evaluator<'a, 'db> {
         let mut remain_args = body.param_locals.len();
         for ((l, interval), value) in locals.ptr.iter().skip(1).zip(args) {
             locals.drop_flags.add_place(l.into());
+            if self.observer.is_some() {
+                locals.assigned.insert(l);
+            }
             match value {
                 IntervalOrOwned::Owned(value) => interval.write_from_bytes(self, &value)?,
                 IntervalOrOwned::Borrowed(value) => interval.write_from_interval(self, value)?,
@@ -1134,9 +1149,15 @@ impl<'a, 'db> Evaluator<'a, 'db> {
     ) -> Result<'db, (Locals<'a, 'db>, usize)> {
         let mut locals =
             match self.unused_locals_store.borrow_mut().entry(body.owner).or_default().pop() {
-                None => Locals { ptr: ArenaMap::new(), body, drop_flags: DropFlags::default() },
+                None => Locals {
+                    ptr: ArenaMap::new(),
+                    body,
+                    drop_flags: DropFlags::default(),
+                    assigned: FxHashSet::default(),
+                },
                 Some(mut l) => {
                     l.drop_flags.clear();
+                    l.assigned.clear();
                     l.body = body;
                     l
                 }
@@ -3229,6 +3250,7 @@ pub fn render_const_using_debug_impl<'db>(
             .mir_body(owner)
             .map_err(|_| MirEvalError::NotSupported("unreachable".to_owned()))?,
         drop_flags: DropFlags::default(),
+        assigned: FxHashSet::default(),
     };
     let data = evaluator.allocate_allocation_in_heap(locals, c)?;
     let lang_items = evaluator.interner().lang_items();
@@ -0,0 +1,136 @@
+//! Hooks to observe the interpreter while it executes, which is what a debugger is built on.
+
+use hir_def::hir::BindingId;
+
+use crate::{
+    InferBodyId, MemoryMap,
+    db::HirDatabase,
+    next_solver::{Allocation, AllocationData, Ty},
+};
+
+use super::{Evaluator, Interval, LocalId, Locals, MirBody, MirOutput, MirSpan, Result};
+
+/// Follows the execution of [`interpret_mir_with_observer`] and decides where to pause it.
+pub trait MirEvalObserver<'db> {
+    /// Called before every statement and terminator is executed, with the number of stack
+    /// frames that are currently executing and the body and span of the innermost one.
+    /// Returning `true` pauses the execution and calls [`MirEvalObserver::paused`].
+    fn should_pause(&mut self, depth: usize, owner: InferBodyId<'db>, span: MirSpan) -> bool;
+
+    /// Called with the stack, innermost frame first, when the execution is paused. The
+    /// execution resumes once this returns.
+    fn paused(&mut self, frames: Vec<PausedFrame<'db>>);
+}
+
+#[derive(Debug)]
+pub struct PausedFrame<'db> {
+    pub owner: InferBodyId<'db>,
+    /// The span being executed in this frame, which is a call for all but the innermost frame.
+    pub span: MirSpan,
+    pub locals: Vec<PausedLocal<'db>>,
+}
+
+#[derive(Debug)]
+pub struct PausedLocal<'db> {
+    pub binding: BindingId,
+    pub ty: Ty<'db>,
+    /// The value of the binding, `None` if it isn't initialized yet or was moved out of.
+    pub value: Option<Allocation<'db>>,
+}
+
+/// Like [`super::interpret_mir`], but reports every step to `observer`.
+///
+/// Only the frames of the innermost function call made by the interpreter itself are reported
+/// when paused, e.g. a closure called by a shim won't show its caller's frames.
+pub fn interpret_mir_with_observer<'db>(
+    db: &'db dyn HirDatabase,
+    body: &'db MirBody<'db>,
+    observer: &mut dyn MirEvalObserver<'db>,
+) -> Result<'db, (Result<'db, Allocation<'db>>, MirOutput)> {
+    let mut evaluator = Evaluator::new(db, body.owner, false, None)?;
+    evaluator.observer = Some(observer);
+    Ok(evaluator.run(body))
+}
+
+impl<'a, 'db> Evaluator<'a, 'db> {
+    /// Reports the step at `span` to the observer. `call_span` is the span of the call that
+    /// created the innermost frame, whose `locals` are not on `code_stack` while it executes.
+    pub(super) fn observe_step(
+        &mut self,
+        span: MirSpan,
+        call_span: MirSpan,
+        locals: &Locals<'a, 'db>,
+    ) {
+        let Some(observer) = self.observer.take() else { return };
+        if observer.should_pause(self.frame_depth, locals.body.owner, span) {
+            let mut frames = vec![PausedFrame {
+                owner: locals.body.owner,
+                span,
+                locals: self.paused_locals(locals),
+            }];
+            let mut call_span = call_span;
+            for frame in self.code_stack.iter().rev() {
+                frames.push(PausedFrame {
+                    owner: frame.locals.body.owner,
+                    span: call_span,
+                    locals: self.paused_locals(&frame.locals),
+                });
+                call_span = frame.span.0;
+            }
+            observer.paused(frames);
+        }
+        self.observer = Some(observer);
+    }
+
+    pub(super) fn record_assignment(&self, locals: &mut Locals<'a, 'db>, local: LocalId) {
+        if self.observer.is_some() {
+            locals.assigned.insert(local);
+        }
+    }
+
+    /// Records the assignment of a call's return value once the callee's frame returned into
+    /// the frame on top of `code_stack`.
+    pub(super) fn record_return(&mut self, return_interval: Interval) {
+        if self.observer.is_none() {
+            return;
+        }
+        if let Some(caller) = self.code_stack.last_mut()
+            && let Some((local, _)) =
+                caller.locals.ptr.iter().find(|(_, it)| it.addr == return_interval.addr)
+        {
+            caller.locals.assigned.insert(local);
+        }
+    }
+
+    fn paused_locals(&self, locals: &Locals<'a, 'db>) -> Vec<PausedLocal<'db>> {
+        locals
+            .body
+            .binding_locals
+            .iter()
+            .map(|(binding, &local)| {
+                let ty = locals.body.locals[local].ty.as_ref();
+                let value = if locals.assigned.contains(&local) {
+                    locals.ptr.get(local).and_then(|&interval| {
+                        let bytes = interval.get(self).ok()?;
+                        let mut memory_map = self.create_memory_map(bytes, ty, locals).ok()?;
+                        let memory_map =
+                            if memory_map.memory.is_empty() && self.vtable_map.is_empty() {
+                                MemoryMap::Empty
+                            } else {
+                                memory_map.vtable = self.vtable_map.clone();
+                                MemoryMap::Complex(Box::new(memory_map))
+                            };
+                        Some(Allocation::new(AllocationData {
+                            ty,
+                            memory: Box::from(bytes),
+                            memory_map,
+                        }))
+                    })
+                } else {
+                    None
+                };
+                PausedLocal { binding, ty, value }
+            })
+            .collect()
+    }
+}
@@ -14,7 +14,8 @@ use crate::{
     mir::eval::{
         Address, AdtId, Arc, Evaluator, FunctionId, GenericArgs, HasModule, HirDisplay, Interval,
         IntervalAndTy, IntervalOrOwned, IsSigned, ItemContainerId, Layout, Locals, Lookup,
-        MirEvalError, MirSpan, Mutability, Result, Ty, TyKind, from_bytes, not_supported, pad16,
+        MirEvalError, MirSpan, Mutability, Result, Ty, TyKind, from_bytes, missing_shim,
+        not_supported, pad16,
     },
     next_solver::Region,
 };
@@ -554,7 +555,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 }
                 Ok(())
             }
-            _ => not_supported!("unknown external function {as_str}"),
+            _ => missing_shim!(as_str),
         }
     }
 
@@ -578,6 +579,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 .exec_simd_intrinsic(name, args, generic_args, destination, locals, span)
                 .map(|()| true);
         }
+        let intrinsic = name;
         // FIXME(#17451): Add `f16` and `f128` intrinsics.
         if let Some(name) = name.strip_suffix("f64") {
             let result = match name {
@@ -647,7 +649,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                     let arg3 = from_bytes!(f64, arg3.get(self)?);
                     arg1.mul_add(arg2, arg3)
                 }
-                _ => not_supported!("unknown f64 intrinsic {name}"),
+                _ => missing_shim!(intrinsic),
             };
             return destination.write_from_bytes(self, &result.to_le_bytes()).map(|()| true);
         }
@@ -719,7 +721,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                     let arg3 = from_bytes!(f32, arg3.get(self)?);
                     arg1.mul_add(arg2, arg3)
                 }
-                _ => not_supported!("unknown f32 intrinsic {name}"),
+                _ => missing_shim!(intrinsic),
             };
             return destination.write_from_bytes(self, &result.to_le_bytes()).map(|()| true);
         }
@@ -1457,7 +1459,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 self.copy_from_interval(addr, args[0].interval)?;
                 destination.write_from_bytes(self, &addr.to_bytes()[..self.ptr_size()])
             }
-            _ if needs_override => not_supported!("intrinsic {name} is not implemented"),
+            _ if needs_override => missing_shim!(name),
             _ => return Ok(false),
         }
         .map(|()| true)
@@ -1635,6 +1637,6 @@ impl<'a, 'db> Evaluator<'a, 'db> {
             )?;
             return destination.write_from_bytes(self, &result);
         }
-        not_supported!("unknown atomic intrinsic {name}");
+        missing_shim!(format!("atomic_{name}"));
     }
 }
@@ -167,7 +167,7 @@ impl<'a, 'db> Evaluator<'a, 'db> {
                 }
                 destination.write_from_bytes(self, &result)
             }
-            _ => not_supported!("unknown simd intrinsic {name}"),
+            _ => missing_shim!(format!("simd_{name}")),
         }
     }
 }
@@ -1,53 +1,61 @@
-use hir_def::{HasModule, signatures::FunctionSignature};
+use hir_def::{HasModule, expr_store::ExpressionStore, signatures::FunctionSignature};
 use hir_expand::EditionedFileId;
 use span::Edition;
 use syntax::{TextRange, TextSize};
 use test_fixture::WithFixture;
 
 use crate::{
+    InferBodyId,
     db::HirDatabase,
-    display::DisplayTarget,
-    mir::MirLowerError,
+    display::{DisplayTarget, HirDisplay},
+    mir::{MirBody, MirLowerError, MirSpan},
     next_solver::{DbInterner, GenericArgs},
     setup_tracing,
     test_db::TestDB,
 };
 
-use super::{MirEvalError, interpret_mir};
+use super::{
+    MirEvalError, MirEvalObserver, PausedFrame, interpret_mir, interpret_mir_with_observer,
+};
 
-fn eval_main(db: &TestDB, file_id: EditionedFileId) -> Result<(String, String), MirEvalError<'_>> {
-    crate::attach_db(db, || {
-        let interner = DbInterner::new_no_crate(db);
-        let module_id = db.module_for_file(file_id.file_id(db));
-        let def_map = module_id.def_map(db);
-        let scope = &def_map[module_id].scope;
-        let func_id = scope
-            .declarations()
-            .find_map(|x| match x {
-                hir_def::ModuleDefId::FunctionId(x) => {
-                    if FunctionSignature::of(db, x).name.display(db, Edition::CURRENT).to_string()
-                        == "main"
-                    {
-                        Some(x)
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            })
-            .expect("no main function found");
-        let body = db
-            .monomorphized_mir_body(
-                func_id.into(),
-                GenericArgs::empty(interner).store(),
-                crate::ParamEnvAndCrate {
-                    param_env: db.trait_environment(func_id.into()),
-                    krate: func_id.krate(db),
+fn main_body<'db>(
+    db: &'db TestDB,
+    file_id: EditionedFileId,
+) -> Result<&'db MirBody<'db>, MirEvalError<'db>> {
+    let interner = DbInterner::new_no_crate(db);
+    let module_id = db.module_for_file(file_id.file_id(db));
+    let def_map = module_id.def_map(db);
+    let scope = &def_map[module_id].scope;
+    let func_id = scope
+        .declarations()
+        .find_map(|x| match x {
+            hir_def::ModuleDefId::FunctionId(x) => {
+                if FunctionSignature::of(db, x).name.display(db, Edition::CURRENT).to_string()
+                    == "main"
+                {
+                    Some(x)
+                } else {
+                    None
                 }
-                .store(),
-            )
-            .map_err(|e| MirEvalError::MirLowerError(func_id, e))?;
+            }
+            _ => None,
+        })
+        .expect("no main function found");
+    db.monomorphized_mir_body(
+        func_id.into(),
+        GenericArgs::empty(interner).store(),
+        crate::ParamEnvAndCrate {
+            param_env: db.trait_environment(func_id.into()),
+            krate: func_id.krate(db),
+        }
+        .store(),
+    )
+    .map_err(|e| MirEvalError::MirLowerError(func_id, e))
+}
 
+fn eval_main(db: &TestDB, file_id: EditionedFileId) -> Result<(String, String), MirEvalError<'_>> {
+    crate::attach_db(db, || {
+        let body = main_body(db, file_id)?;
         let (result, output) = interpret_mir(db, body, false, None)?;
         result?;
         Ok((output.stdout().into_owned(), output.stderr().into_owned()))
@@ -1279,3 +1287,138 @@ fn main() {
 "#,
     );
 }
+
+#[test]
+fn unknown_extern_function_is_missing_shim() {
+    check_error_with(
+        r#"
+extern "C" {
+    fn frobnicate(x: i32) -> i32;
+}
+
+fn main() {
+    let _ = unsafe { frobnicate(2) };
+}
+"#,
+        |e| {
+            let mut err = &e;
+            while let MirEvalError::InFunction(inner, _) = err {
+                err = inner;
+            }
+            matches!(err, MirEvalError::MissingShim(name) if name == "frobnicate")
+        },
+    );
+}
+
+#[test]
+fn unknown_intrinsic_is_missing_shim_with_its_full_name() {
+    let is_missing_shim = |name: &'static str| {
+        move |e: MirEvalError<'_>| {
+            let mut err = &e;
+            while let MirEvalError::InFunction(inner, _) = err {
+                err = inner;
+            }
+            matches!(err, MirEvalError::MissingShim(it) if it == name)
+        }
+    };
+    check_error_with(
+        r#"
+#[rustc_intrinsic]
+fn log1pf64(x: f64) -> f64;
+
+fn main() {
+    let _ = log1pf64(2.0);
+}
+"#,
+        is_missing_shim("log1pf64"),
+    );
+    check_error_with(
+        r#"
+#[rustc_intrinsic]
+fn simd_frobnicate(x: i32) -> i32;
+
+fn main() {
+    let _ = simd_frobnicate(2);
+}
+"#,
+        is_missing_shim("simd_frobnicate"),
+    );
+    check_error_with(
+        r#"
+//- minicore: copy
+#[rustc_intrinsic]
+unsafe fn atomic_frobnicate<T: Copy>(dst: *mut T, a: T, b: T) -> T;
+
+fn main() {
+    let mut x = 0;
+    let _ = unsafe { atomic_frobnicate(&mut x, 1, 2) };
+}
+"#,
+        is_missing_shim("atomic_frobnicate"),
+    );
+}
+
+#[test]
+fn observer_pauses_with_stack_and_locals() {
+    struct PauseInCallee<'db> {
+        db: &'db TestDB,
+        display_target: DisplayTarget,
+        stack: Vec<String>,
+    }
+
+    impl<'db> MirEvalObserver<'db> for PauseInCallee<'db> {
+        fn should_pause(&mut self, depth: usize, _: InferBodyId<'db>, _: MirSpan) -> bool {
+            depth == 2 && self.stack.is_empty()
+        }
+
+        fn paused(&mut self, frames: Vec<PausedFrame<'db>>) {
+            let db = self.db;
+            for frame in frames {
+                let store = ExpressionStore::of(db, frame.owner.expression_store_owner(db));
+                let locals = frame.locals.iter().map(|local| {
+                    let value = match &local.value {
+                        Some(value) => value.display(db, self.display_target).to_string(),
+                        None => "<uninitialized>".to_owned(),
+                    };
+                    format!(
+                        "{}: {} = {value}",
+                        store[local.binding].name.display(db, Edition::CURRENT),
+                        local.ty.display(db, self.display_target),
+                    )
+                });
+                self.stack.push(locals.collect::<Vec<_>>().join(", "));
+            }
+        }
+    }
+
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+fn add(a: i32, b: i32) -> i32 {
+    let c = a + b;
+    c
+}
+fn main() {
+    let x = 2;
+    let y = add(x, 3);
+}
+"#,
+    );
+    crate::attach_db(&db, || {
+        let krate = db.module_for_file(file_id.file_id(&db)).krate(&db);
+        let mut observer = PauseInCallee {
+            db: &db,
+            display_target: DisplayTarget::from_crate(&db, krate),
+            stack: vec![],
+        };
+        let body = main_body(&db, file_id).unwrap();
+        let (result, _) = interpret_mir_with_observer(&db, body, &mut observer).unwrap();
+        result.unwrap();
+        assert_eq!(
+            observer.stack,
+            [
+                "a: i32 = 2, b: i32 = 3, c: i32 = <uninitialized>",
+                "x: i32 = 2, y: i32 = <uninitialized>"
+            ]
+        );
+    });
+}
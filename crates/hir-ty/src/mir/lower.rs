@@ -408,11 +408,16 @@ impl<'a, 'db> MirLowerCtx<'a, 'db> {
                     place,
                     current,
                     rest,
-                    (*m).into(),
+                    BorrowKind::from_auto_borrow_mutability(*m),
                 ),
-                Adjust::Borrow(AutoBorrow::RawPtr(m)) => {
-                    self.lower_expr_to_place_with_borrow_adjust(expr_id, place, current, rest, *m)
-                }
+                Adjust::Borrow(AutoBorrow::RawPtr(m)) => self
+                    .lower_expr_to_place_with_borrow_adjust(
+                        expr_id,
+                        place,
+                        current,
+                        rest,
+                        BorrowKind::from_rustc_mutability(*m),
+                    ),
                 Adjust::Pointer(cast) => {
                     let Some((p, current)) =
                         self.lower_expr_as_place_with_adjust(current, expr_id, true, rest)?
@@ -442,14 +447,13 @@ impl<'a, 'db> MirLowerCtx<'a, 'db> {
         place: PlaceRef<'db>,
         current: BasicBlockId,
         rest: &[Adjustment],
-        m: Mutability,
+        bk: BorrowKind,
     ) -> Result<'db, Option<BasicBlockId>> {
         let Some((p, current)) =
             self.lower_expr_as_place_with_adjust(current, expr_id, true, rest)?
         else {
             return Ok(None);
         };
-        let bk = BorrowKind::from_rustc_mutability(m);
         self.push_assignment(current, place, Rvalue::Ref(bk, p.store()), expr_id.into());
         Ok(Some(current))
     }
@@ -1118,12 +1122,15 @@ impl<'a, 'db> MirLowerCtx<'a, 'db> {
                         syntax::ast::LogicOp::Or => 1,
                     };
                     let start_of_then = self.new_basic_block();
-                    self.push_assignment(
+                    // We know the value of `lhs` here, writing it as a constant lets borrowck see
+                    // through let chains.
+                    self.write_bytes_to_place(
                         start_of_then,
                         place,
-                        lhs_op.clone().into(),
+                        Box::new([value_to_short as u8]),
+                        Ty::new_bool(self.interner()),
                         expr_id.into(),
-                    );
+                    )?;
                     let end_of_then = Some(start_of_then);
                     let start_of_else = self.new_basic_block();
                     let end_of_else = self.lower_expr_to_place(*rhs, place, start_of_else)?;
@@ -1534,16 +1541,36 @@ impl<'a, 'db> MirLowerCtx<'a, 'db> {
         is_uninhabited: bool,
         span: MirSpan,
     ) -> Result<'db, Option<BasicBlockId>> {
-        let Some(args) = args
-            .map(|arg| {
-                if let Some((temp, c)) = self.lower_expr_to_some_operand(arg, current)? {
-                    current = c;
-                    Ok(Some(temp))
-                } else {
-                    Ok(None)
+        let mut args = args.peekable();
+        let Some(args) = iter::from_fn(|| {
+            let arg = args.next()?;
+            let is_last = args.peek().is_none();
+            Some((arg, is_last))
+        })
+        .map(|(arg, is_last)| {
+            let Some((operand, c)) = self.lower_expr_to_some_operand(arg, current)? else {
+                return Ok(None);
+            };
+            current = c;
+            // Like rustc, evaluate the argument into a temporary right away, so that a later
+            // argument moving out of (or mutating) the same place doesn't affect it.
+            if !is_last && let OperandKind::Copy(place) = &operand.kind {
+                let place = place.as_ref();
+                if !place.projection.is_empty()
+                    || self.result.binding_locals.values().any(|&it| it == place.local)
+                {
+                    let temp =
+                        self.temp(self.expr_ty_after_adjustments(arg), current, arg.into())?;
+                    self.push_assignment(current, temp.into(), operand.into(), arg.into());
+                    return Ok(Some(Operand {
+                        kind: OperandKind::Copy(PlaceRef::from(temp).store()),
+                        span: Some(arg.into()),
+                    }));
                 }
-            })
-            .collect::<Result<'_, Option<Vec<_>>>>()?
+            }
+            Ok(Some(operand))
+        })
+        .collect::<Result<'_, Option<Vec<_>>>>()?
         else {
             return Ok(None);
         };
@@ -1,6 +1,7 @@
 use std::{fmt, ops::ControlFlow};
 
 use hir_def::attrs::AttrFlags;
+use macros::{TypeFoldable, TypeVisitable};
 use rustc_next_trait_solver::solve::{GoalEvaluation, SolverDelegateEvalExt};
 use rustc_type_ir::{
     AliasRelationDirection, AliasTermKind, PredicatePolarity,
@@ -32,7 +33,17 @@ use crate::{
 pub struct FulfillmentError<'db> {
     pub obligation: PredicateObligation<'db>,
     pub code: FulfillmentErrorCode<'db>,
-    pub parent_trait_obligations: Vec<Predicate<'db>>,
+    pub parent_trait_obligations: Vec<ParentTraitObligation<'db>>,
+}
+
+/// A trait obligation on the path from the root obligation to the leaf of a [`FulfillmentError`].
+#[derive(Debug, Clone, Copy, TypeVisitable, TypeFoldable)]
+pub struct ParentTraitObligation<'db> {
+    pub predicate: Predicate<'db>,
+    /// The candidate that required the next obligation in the chain, if it was a trait candidate.
+    #[type_foldable(identity)]
+    #[type_visitable(ignore)]
+    pub source: Option<CandidateSource<DbInterner<'db>>>,
 }
 
 impl FulfillmentError<'_> {
@@ -256,7 +267,7 @@ fn find_best_leaf_obligation<'db>(
     infcx: &InferCtxt<'db>,
     obligation: &PredicateObligation<'db>,
     consider_ambiguities: bool,
-) -> (PredicateObligation<'db>, Vec<Predicate<'db>>) {
+) -> (PredicateObligation<'db>, Vec<ParentTraitObligation<'db>>) {
     let obligation = infcx.resolve_vars_if_possible(obligation.clone());
     // FIXME: we use a probe here as the `BestObligation` visitor does not
     // check whether it uses candidates which get shadowed by where-bounds.
@@ -293,20 +304,23 @@ fn find_best_leaf_obligation<'db>(
 struct BestObligation<'db> {
     obligation: PredicateObligation<'db>,
     consider_ambiguities: bool,
-    parent_trait_obligations: Vec<Predicate<'db>>,
+    parent_trait_obligations: Vec<ParentTraitObligation<'db>>,
 }
 
 impl<'db> BestObligation<'db> {
     fn with_derived_obligation(
         &mut self,
         derived_obligation: PredicateObligation<'db>,
+        source: Option<CandidateSource<DbInterner<'db>>>,
         and_then: impl FnOnce(&mut Self) -> <Self as ProofTreeVisitor<'db>>::Result,
     ) -> <Self as ProofTreeVisitor<'db>>::Result {
         let parent_predicate = self.obligation.predicate;
         let should_push = parent_predicate.as_trait_clause().is_some()
-            && self.parent_trait_obligations.last() != Some(&parent_predicate);
+            && self.parent_trait_obligations.last().map(|parent| parent.predicate)
+                != Some(parent_predicate);
         if should_push {
-            self.parent_trait_obligations.push(parent_predicate);
+            self.parent_trait_obligations
+                .push(ParentTraitObligation { predicate: parent_predicate, source });
         }
         let old_obligation = std::mem::replace(&mut self.obligation, derived_obligation);
         let result = and_then(self);
@@ -399,7 +413,7 @@ impl<'db> BestObligation<'db> {
         //         _ => continue,
         //     }
 
-        //     self.with_derived_obligation(obligation, |this| nested_goal.visit_with(this))?;
+        //     self.with_derived_obligation(obligation, None, |this| nested_goal.visit_with(this))?;
         // }
 
         self.break_with_current_obligation()
@@ -424,7 +438,7 @@ impl<'db> BestObligation<'db> {
             );
             let obligation =
                 Obligation::new(interner, self.obligation.cause, goal.goal().param_env, pred);
-            self.with_derived_obligation(obligation, |this| {
+            self.with_derived_obligation(obligation, None, |this| {
                 goal.infcx().visit_proof_tree_at_depth(
                     goal.goal().with(interner, pred),
                     goal.depth() + 1,
@@ -458,7 +472,7 @@ impl<'db> BestObligation<'db> {
                 goal.goal().param_env,
                 deeply_normalize_for_diagnostics(goal.infcx(), goal.goal().param_env, pred),
             );
-            self.with_derived_obligation(obligation, |this| {
+            self.with_derived_obligation(obligation, None, |this| {
                 goal.infcx().visit_proof_tree_at_depth(
                     goal.goal().with(interner, pred),
                     goal.depth() + 1,
@@ -488,7 +502,7 @@ impl<'db> BestObligation<'db> {
             goal.goal().param_env,
             alias.trait_ref(interner),
         );
-        self.with_derived_obligation(obligation, |this| {
+        self.with_derived_obligation(obligation, None, |this| {
             goal.infcx().visit_proof_tree_at_depth(
                 goal.goal().with(interner, alias.trait_ref(interner)),
                 goal.depth() + 1,
@@ -525,7 +539,7 @@ impl<'db> BestObligation<'db> {
 }
 
 impl<'db> ProofTreeVisitor<'db> for BestObligation<'db> {
-    type Result = ControlFlow<(PredicateObligation<'db>, Vec<Predicate<'db>>)>;
+    type Result = ControlFlow<(PredicateObligation<'db>, Vec<ParentTraitObligation<'db>>)>;
 
     fn span(&self) -> Span {
         self.obligation.cause.span()
@@ -657,7 +671,11 @@ impl<'db> ProofTreeVisitor<'db> for BestObligation<'db> {
                 }
             }
 
-            self.with_derived_obligation(obligation, |this| nested_goal.visit_with(this))?;
+            let source = match candidate.kind() {
+                inspect::ProbeKind::TraitCandidate { source, result: _ } => Some(source),
+                _ => None,
+            };
+            self.with_derived_obligation(obligation, source, |this| nested_goal.visit_with(this))?;
         }
 
         // alias-relate may fail because the lhs or rhs can't be normalized,
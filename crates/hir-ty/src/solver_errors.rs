@@ -5,13 +5,14 @@
 //!
 //! [`NextSolverError`]: crate::next_solver::fulfill::NextSolverError
 
+use hir_def::{AdtId, FieldId, VariantId};
 use macros::{TypeFoldable, TypeVisitable};
-use rustc_type_ir::{PredicatePolarity, inherent::IntoKind};
+use rustc_type_ir::{PredicatePolarity, inherent::IntoKind, solve::CandidateSource};
 
 use crate::{
     Span,
     next_solver::{
-        ClauseKind, DbInterner, PredicateKind, StoredTraitRef, TraitPredicate,
+        ClauseKind, DbInterner, PredicateKind, StoredTraitRef, TraitPredicate, Ty, TyKind,
         infer::{
             errors::{FulfillmentError, FulfillmentErrorCode},
             select::SelectionError,
@@ -29,10 +30,21 @@ pub struct SolverDiagnostic {
 pub enum SolverDiagnosticKind {
     TraitUnimplemented {
         trait_predicate: StoredTraitPredicate,
-        parent_trait_predicates: Vec<StoredTraitPredicate>,
+        /// The predicates that required `trait_predicate`, innermost first.
+        parent_trait_predicates: Vec<ParentTraitPredicate>,
     },
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, TypeVisitable, TypeFoldable)]
+pub struct ParentTraitPredicate {
+    pub trait_predicate: StoredTraitPredicate,
+    /// The field whose type is the self type of the next predicate in the chain, if this
+    /// predicate only required it structurally (e.g. `Foo: Send` requires `Rc<i32>: Send`
+    /// because of a field `bar: Rc<i32>`).
+    #[type_visitable(ignore)]
+    pub field: Option<FieldId>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, TypeVisitable, TypeFoldable)]
 pub struct StoredTraitPredicate {
     pub trait_ref: StoredTraitRef,
@@ -47,29 +59,42 @@ impl StoredTraitPredicate {
 }
 
 impl SolverDiagnostic {
-    pub fn from_fulfillment_error(error: &FulfillmentError<'_>) -> Option<Self> {
+    pub fn from_fulfillment_error<'db>(
+        interner: DbInterner<'db>,
+        error: &FulfillmentError<'db>,
+    ) -> Option<Self> {
         let span = error.obligation.cause.span();
         if span.is_dummy() {
             return None;
         }
 
+        let kind = SolverDiagnosticKind::from_fulfillment_error(interner, error)?;
+        Some(SolverDiagnostic { span, kind })
+    }
+}
+
+impl SolverDiagnosticKind {
+    pub fn from_fulfillment_error<'db>(
+        interner: DbInterner<'db>,
+        error: &FulfillmentError<'db>,
+    ) -> Option<Self> {
         // FIXME: Handle more error kinds.
-        let kind = match &error.code {
+        match &error.code {
             FulfillmentErrorCode::Select(SelectionError::Unimplemented) => {
                 match error.obligation.predicate.kind().skip_binder() {
                     PredicateKind::Clause(ClauseKind::Trait(trait_pred)) => {
-                        handle_trait_unimplemented(error, trait_pred)?
+                        handle_trait_unimplemented(interner, error, trait_pred)
                     }
-                    _ => return None,
+                    _ => None,
                 }
             }
-            _ => return None,
-        };
-        Some(SolverDiagnostic { span, kind })
+            _ => None,
+        }
     }
 }
 
 fn handle_trait_unimplemented<'db>(
+    interner: DbInterner<'db>,
     error: &FulfillmentError<'db>,
     trait_pred: TraitPredicate<'db>,
 ) -> Option<SolverDiagnosticKind> {
@@ -78,19 +103,54 @@ fn handle_trait_unimplemented<'db>(
         polarity: trait_pred.polarity,
     };
 
-    let mut parent_trait_predicates = error
+    let mut child_self_ty = trait_pred.self_ty();
+    let parent_trait_predicates = error
         .parent_trait_obligations
         .iter()
-        .filter_map(|predicate| predicate.as_trait_clause())
-        .map(|trait_predicate| {
+        .rev()
+        .filter_map(|parent| Some((parent.predicate.as_trait_clause()?, parent.source)))
+        .map(|(trait_predicate, source)| {
             let trait_predicate = trait_predicate.skip_binder();
-            StoredTraitPredicate {
-                trait_ref: StoredTraitRef::new(trait_predicate.trait_ref),
-                polarity: trait_predicate.polarity,
+            let field = match source {
+                Some(CandidateSource::BuiltinImpl(_)) => {
+                    structural_field(interner, trait_predicate.self_ty(), child_self_ty)
+                }
+                _ => None,
+            };
+            child_self_ty = trait_predicate.self_ty();
+            ParentTraitPredicate {
+                trait_predicate: StoredTraitPredicate {
+                    trait_ref: StoredTraitRef::new(trait_predicate.trait_ref),
+                    polarity: trait_predicate.polarity,
+                },
+                field,
             }
         })
         .collect::<Vec<_>>();
-    parent_trait_predicates.reverse();
 
     Some(SolverDiagnosticKind::TraitUnimplemented { trait_predicate, parent_trait_predicates })
 }
+
+/// Finds the field of the ADT `self_ty` whose type is `field_ty`, which is how builtin impls
+/// (e.g. of auto traits) derive their nested obligations.
+fn structural_field<'db>(
+    interner: DbInterner<'db>,
+    self_ty: Ty<'db>,
+    field_ty: Ty<'db>,
+) -> Option<FieldId> {
+    let TyKind::Adt(adt_def, args) = self_ty.kind() else { return None };
+    let db = interner.db;
+    let variants: Vec<VariantId> = match adt_def.def_id() {
+        AdtId::StructId(id) => vec![id.into()],
+        AdtId::UnionId(id) => vec![id.into()],
+        AdtId::EnumId(id) => {
+            id.enum_variants(db).variants.values().map(|&(variant, _)| variant.into()).collect()
+        }
+    };
+    variants.into_iter().find_map(|variant| {
+        db.field_types(variant).iter().find_map(|(local_id, field)| {
+            (field.ty().instantiate(interner, args).skip_norm_wip() == field_ty)
+                .then_some(FieldId { parent: variant, local_id })
+        })
+    })
+}
@@ -37,6 +37,7 @@ use crate::{
         },
         obligation_ctxt::ObligationCtxt,
     },
+    solver_errors::SolverDiagnosticKind,
 };
 
 /// Type for `hir`, because commonly we want both param env and a crate in an exported API.
@@ -162,10 +163,11 @@ pub fn implements_trait_unique_with_infcx<'db>(
     infcx.predicate_must_hold_modulo_regions(&obligation)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WherePredicateEvaluation {
     Holds,
-    NotProven,
+    /// The predicate does not hold; carries the unsatisfied obligation chain if the solver found one.
+    NotProven(Option<SolverDiagnosticKind>),
     HasErrors,
     NoObligations,
 }
@@ -227,23 +229,29 @@ pub fn where_predicate_must_hold<'db>(
         let mut result = EvaluationResult::EvaluatedToOk;
         for error in ocx.evaluate_obligations_error_on_ambiguity() {
             if error.is_true_error() {
-                return EvaluationResult::EvaluatedToErr;
+                return (
+                    EvaluationResult::EvaluatedToErr,
+                    SolverDiagnosticKind::from_fulfillment_error(
+                        interner,
+                        &error.into_fulfillment_error(&infcx),
+                    ),
+                );
             }
             result = result.max(EvaluationResult::EvaluatedToAmbig);
         }
-        if infcx.opaque_types_added_in_snapshot(snapshot) {
+        let result = if infcx.opaque_types_added_in_snapshot(snapshot) {
             result.max(EvaluationResult::EvaluatedToOkModuloOpaqueTypes)
         } else if infcx.region_constraints_added_in_snapshot(snapshot) {
             result.max(EvaluationResult::EvaluatedToOkModuloRegions)
         } else {
             result
-        }
+        };
+        (result, None)
     });
 
-    if result.must_apply_modulo_regions() {
-        WherePredicateEvaluation::Holds
-    } else {
-        WherePredicateEvaluation::NotProven
+    match result {
+        (result, _) if result.must_apply_modulo_regions() => WherePredicateEvaluation::Holds,
+        (_, error) => WherePredicateEvaluation::NotProven(error),
     }
 }
 
@@ -0,0 +1,123 @@
+//! Pausing and inspecting functions interpreted by [`Function::debug`].
+
+use hir_def::expr_store::ExpressionStore;
+use hir_expand::{FileRange, name::Name};
+use hir_ty::{
+    InferBodyId,
+    db::HirDatabase,
+    display::{DisplayTarget, HirDisplay},
+    mir::{MirBody, MirEvalObserver, MirSpan, PausedFrame},
+};
+use rustc_hash::FxHashMap;
+
+use crate::Function;
+
+/// Decides where to pause a function interpreted by [`Function::debug`] and inspects it.
+pub trait Debugger {
+    /// Called before every step of the interpreter with the number of frames currently
+    /// executing and the source range of the step, if it has one. Returning `true` pauses the
+    /// execution and calls [`Debugger::paused`].
+    fn should_pause(&mut self, depth: usize, range: Option<FileRange>) -> bool;
+
+    /// Called with the stack, innermost frame first, when the execution is paused. The
+    /// execution resumes once this returns.
+    fn paused(&mut self, frames: Vec<DebugFrame>);
+}
+
+#[derive(Debug)]
+pub struct DebugFrame {
+    /// The function this frame executes, or whose closure it executes.
+    pub function: Option<Function>,
+    /// The range being executed in this frame, which is a call for all but the innermost frame.
+    pub range: Option<FileRange>,
+    pub locals: Vec<DebugLocal>,
+}
+
+#[derive(Debug)]
+pub struct DebugLocal {
+    pub name: Name,
+    pub ty: String,
+    /// The rendered value, `None` if the local isn't initialized yet.
+    pub value: Option<String>,
+}
+
+pub(crate) struct Observer<'a, 'db> {
+    db: &'db dyn HirDatabase,
+    debugger: &'a mut dyn Debugger,
+    display_target: DisplayTarget,
+    ranges: FxHashMap<(InferBodyId<'db>, MirSpan), Option<FileRange>>,
+}
+
+impl<'a, 'db> Observer<'a, 'db> {
+    pub(crate) fn new(
+        db: &'db dyn HirDatabase,
+        debugger: &'a mut dyn Debugger,
+        display_target: DisplayTarget,
+    ) -> Self {
+        Observer { db, debugger, display_target, ranges: FxHashMap::default() }
+    }
+
+    fn range(&mut self, owner: InferBodyId<'db>, span: MirSpan) -> Option<FileRange> {
+        let db = self.db;
+        *self.ranges.entry((owner, span)).or_insert_with(|| span_range(db, owner, span))
+    }
+}
+
+fn span_range(db: &dyn HirDatabase, owner: InferBodyId<'_>, span: MirSpan) -> Option<FileRange> {
+    let ptr = span.syntax(db, owner)?;
+    Some(ptr.map(|it| it.text_range()).original_node_file_range_rooted(db))
+}
+
+/// The ranges of the steps of `body` and of the closures it defines, see
+/// [`Function::debug_step_ranges`].
+pub(crate) fn step_ranges<'db>(
+    db: &'db dyn HirDatabase,
+    body: &'db MirBody<'db>,
+    acc: &mut Vec<FileRange>,
+) {
+    for (_, block) in body.basic_blocks.iter() {
+        let spans = block.statements.iter().map(|it| it.span);
+        for span in spans.chain(block.terminator.as_ref().map(|it| it.span)) {
+            acc.extend(span_range(db, body.owner, span));
+        }
+    }
+    for &closure in &body.closures {
+        if let Ok(body) = db.mir_body_for_closure(closure) {
+            step_ranges(db, body, acc);
+        }
+    }
+}
+
+impl<'db> MirEvalObserver<'db> for Observer<'_, 'db> {
+    fn should_pause(&mut self, depth: usize, owner: InferBodyId<'db>, span: MirSpan) -> bool {
+        let range = self.range(owner, span);
+        self.debugger.should_pause(depth, range)
+    }
+
+    fn paused(&mut self, frames: Vec<PausedFrame<'db>>) {
+        let db = self.db;
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                let store = ExpressionStore::of(db, frame.owner.expression_store_owner(db));
+                let locals = frame
+                    .locals
+                    .into_iter()
+                    .map(|local| DebugLocal {
+                        name: store[local.binding].name.clone(),
+                        ty: local.ty.display(db, self.display_target).to_string(),
+                        value: local
+                            .value
+                            .map(|value| value.display(db, self.display_target).to_string()),
+                    })
+                    .collect();
+                DebugFrame {
+                    function: frame.owner.as_function().map(Into::into),
+                    range: self.range(frame.owner, frame.span),
+                    locals,
+                }
+            })
+            .collect();
+        self.debugger.paused(frames);
+    }
+}
@@ -32,7 +32,7 @@ use syntax::{
 };
 use triomphe::Arc;
 
-use crate::{AssocItem, Field, Function, GenericDef, Trait, Type, TypeOwnerId, Variant};
+use crate::{AssocItem, Field, Function, GenericDef, Local, Trait, Type, TypeOwnerId, Variant};
 
 pub use hir_def::VariantId;
 pub use hir_ty::{
@@ -109,6 +109,7 @@ diagnostics![AnyDiagnostic<'db> ->
     CannotImplicitlyDerefTraitObject<'db>,
     CannotIndexInto<'db>,
     CastToUnsized<'db>,
+    ConflictingBorrow<'db>,
     ExpectedArrayOrSlicePat<'db>,
     ExpectedFunction<'db>,
     ExplicitDropMethodUse,
@@ -135,7 +136,9 @@ diagnostics![AnyDiagnostic<'db> ->
     MissingFields,
     MissingMatchArms,
     MissingUnsafe,
+    MovedOutOfRef<'db>,
     MutRefInImmRefPat,
+    NeedMut<'db>,
     MutableRefBinding,
     NonExhaustiveLet,
     NonExhaustiveRecordExpr,
@@ -149,6 +152,9 @@ diagnostics![AnyDiagnostic<'db> ->
     RemoveTrailingReturn,
     RemoveUnnecessaryElse,
     UnusedMustUse<'db>,
+    UnusedMut<'db>,
+    UnusedVariable<'db>,
+    UseOfMovedValue<'db>,
     ReplaceFilterMapNextWithFindMap,
     TraitImplIncorrectSafety,
     TraitImplMissingAssocItems,
@@ -662,7 +668,48 @@ pub struct PatternArgInExternFn {
 pub struct UnimplementedTrait<'db> {
     pub span: SpanSyntax,
     pub trait_predicate: crate::TraitPredicate<'db>,
-    pub parent_trait_predicates: Vec<crate::TraitPredicate<'db>>,
+    /// The predicates that required `trait_predicate`, innermost first.
+    pub parent_trait_predicates: Vec<crate::ParentTraitPredicate<'db>>,
+}
+
+#[derive(Debug)]
+pub struct MovedOutOfRef<'db> {
+    pub ty: Type<'db>,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct UseOfMovedValue<'db> {
+    pub local: Local<'db>,
+    /// The type of the value that was moved out.
+    pub ty: Type<'db>,
+    pub span: InFile<SyntaxNodePtr>,
+    pub is_borrow: bool,
+    pub is_partial: bool,
+}
+
+#[derive(Debug)]
+pub struct NeedMut<'db> {
+    pub local: Local<'db>,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct UnusedMut<'db> {
+    pub local: Local<'db>,
+}
+
+#[derive(Debug)]
+pub struct UnusedVariable<'db> {
+    pub local: Local<'db>,
+}
+
+#[derive(Debug)]
+pub struct ConflictingBorrow<'db> {
+    pub local: Local<'db>,
+    pub span: InFile<SyntaxNodePtr>,
+    pub mutable: bool,
+    pub previous_mutable: bool,
 }
 
 #[derive(Debug)]
@@ -1200,17 +1247,12 @@ impl<'db> AnyDiagnostic<'db> {
                 trait_predicate,
                 parent_trait_predicates,
             } => {
-                let trait_predicate = crate::TraitPredicate {
-                    inner: trait_predicate.get(interner),
-                    owner: type_owner,
-                };
-                let parent_trait_predicates = parent_trait_predicates
-                    .iter()
-                    .map(|trait_predicate| crate::TraitPredicate {
-                        inner: trait_predicate.get(interner),
-                        owner: type_owner,
-                    })
-                    .collect();
+                let (trait_predicate, parent_trait_predicates) = crate::unsatisfied_trait_chain(
+                    interner,
+                    trait_predicate,
+                    parent_trait_predicates,
+                    type_owner,
+                );
                 UnimplementedTrait { span, trait_predicate, parent_trait_predicates }.into()
             }
         })
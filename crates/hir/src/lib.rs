@@ -23,6 +23,7 @@
 extern crate ra_ap_rustc_type_ir as rustc_type_ir;
 
 mod attrs;
+mod debugger;
 mod from_id;
 mod has_source;
 mod semantics;
@@ -95,15 +96,19 @@ use hir_ty::{
     direct_super_traits, known_const_to_ast,
     layout::{Layout as TyLayout, RustcEnumVariantIdx, RustcFieldIdx, TagEncoding},
     method_resolution::{self, InherentImpls, MethodResolutionContext},
-    mir::interpret_mir,
+    mir::{
+        MirBody, MirOutput, MirSpan, MutabilityReason, interpret_mir, interpret_mir_with_observer,
+    },
     next_solver::{
         AliasTy, AnyImplId, ClauseKind, DbInterner, EarlyBinder, ErrorGuaranteed, FnSig,
         GenericArg, GenericArgs, ParamEnv, PolyFnSig, Region, SolverDefId, Ty, TyKind, TypingMode,
-        infer::{DbInternerInferExt, InferCtxt},
+        infer::{DbInternerInferExt, InferCtxt, traits::ObligationCause},
     },
+    solver_errors::{self, StoredTraitPredicate},
     traits::{self, is_inherent_impl_coherent, structurally_normalize_ty},
 };
 use itertools::Itertools;
+use la_arena::ArenaMap;
 use rustc_hash::{FxHashMap, FxHashSet};
 use rustc_type_ir::{
     AliasTyKind, TypeSuperVisitable, TypeVisitable, TypeVisitableExt, TypeVisitor, fast_reject,
@@ -154,6 +159,7 @@ impl PredicateEvaluationResult {
 
 pub use crate::{
     attrs::{AttrsWithOwner, HasAttrs, resolve_doc_path_on},
+    debugger::{DebugFrame, DebugLocal, Debugger},
     diagnostics::*,
     has_source::HasSource,
     semantics::{
@@ -2105,6 +2111,118 @@ impl DefWithBody {
         for diag in hir_ty::diagnostics::incorrect_case(db, id.into()) {
             acc.push(diag.into())
         }
+
+        if let Ok(borrowck_results) = db.borrowck(id.into()) {
+            // Code coming from macro expansions is not something the user can fix, and our
+            // lowering of it is less reliable, so don't report anything there.
+            let mir_span_syntax = |span: MirSpan| -> Option<InFile<SyntaxNodePtr>> {
+                let ptr = match span {
+                    MirSpan::ExprId(expr) => {
+                        source_map.expr_syntax(expr).ok().map(|it| it.map(Into::into))
+                    }
+                    MirSpan::PatId(pat) => {
+                        source_map.pat_syntax(pat).ok().map(|it| it.map(Into::into))
+                    }
+                    MirSpan::BindingId(binding) => source_map
+                        .patterns_for_binding(binding)
+                        .iter()
+                        .find_map(|&pat| source_map.pat_syntax(pat).ok())
+                        .map(|it| it.map(Into::into)),
+                    MirSpan::SelfParam => {
+                        source_map.self_param_syntax().map(|it| it.map(Into::into))
+                    }
+                    MirSpan::Unknown => None,
+                }?;
+                (!ptr.file_id.is_macro()).then_some(ptr)
+            };
+            let generic_def = id.generic_def(db);
+            let local =
+                |binding_id| Local { parent: id.into(), parent_infer: id.into(), binding_id };
+            let mut mutability_of_bindings = ArenaMap::<_, MutabilityReason>::default();
+            for result in borrowck_results {
+                for (binding, reason) in result.mutability_of_bindings.iter() {
+                    match mutability_of_bindings.get_mut(binding) {
+                        Some(existing) => existing.merge(reason),
+                        None => {
+                            mutability_of_bindings.insert(binding, reason.clone());
+                        }
+                    }
+                }
+                for moved in &result.moved_out_of_ref {
+                    let Some(span) = mir_span_syntax(moved.span) else { continue };
+                    acc.push(
+                        MovedOutOfRef { ty: Type::new(generic_def, moved.ty.as_ref()), span }
+                            .into(),
+                    );
+                }
+                for used in &result.used_after_move {
+                    let Some(span) = mir_span_syntax(used.span) else { continue };
+                    acc.push(
+                        UseOfMovedValue {
+                            local: local(used.binding),
+                            ty: Type::new(generic_def, used.ty.as_ref()),
+                            span,
+                            is_borrow: used.is_borrow,
+                            is_partial: used.is_partial,
+                        }
+                        .into(),
+                    );
+                }
+                for borrow in &result.conflicting_borrows {
+                    let Some(span) = mir_span_syntax(borrow.span) else { continue };
+                    acc.push(
+                        ConflictingBorrow {
+                            local: local(borrow.binding),
+                            span,
+                            mutable: borrow.mutable,
+                            previous_mutable: borrow.previous_mutable,
+                        }
+                        .into(),
+                    );
+                }
+            }
+            for (binding_id, reason) in mutability_of_bindings.iter() {
+                let binding = &body[binding_id];
+                if binding.problems.is_some() {
+                    // We should report specific diagnostics for these problems, not `need-mut`
+                    // and `unused-mut`.
+                    continue;
+                }
+                if source_map
+                    .patterns_for_binding(binding_id)
+                    .iter()
+                    .any(|&pat| source_map.pat_syntax(pat).is_err())
+                {
+                    // Skip synthetic bindings.
+                    continue;
+                }
+                let should_ignore = binding.name.as_str().starts_with('_');
+                let is_mut = binding.mode == BindingAnnotation::Mutable;
+                let reason = match reason {
+                    MutabilityReason::Unused if binding.name == sym::self_ => {
+                        &MutabilityReason::Not
+                    }
+                    reason => reason,
+                };
+                match reason {
+                    MutabilityReason::Unused => {
+                        if !should_ignore {
+                            acc.push(UnusedVariable { local: local(binding_id) }.into());
+                        }
+                    }
+                    MutabilityReason::Mut { spans } if !is_mut => {
+                        for &span in spans {
+                            let Some(span) = mir_span_syntax(span) else { continue };
+                            acc.push(NeedMut { local: local(binding_id), span }.into());
+                        }
+                    }
+                    MutabilityReason::Not if is_mut && !should_ignore => {
+                        acc.push(UnusedMut { local: local(binding_id) }.into());
+                    }
+                    MutabilityReason::Mut { .. } | MutabilityReason::Not => {}
+                }
+            }
+        }
     }
 
     /// Returns an iterator over the inferred types of all expressions in this body.
@@ -2530,6 +2648,47 @@ impl Function {
         db: &dyn HirDatabase,
         span_formatter: impl Fn(FileId, TextRange) -> String,
     ) -> Result<String, ConstEvalError<'_>> {
+        self.eval_with(db, span_formatter, |body| interpret_mir(db, body, false, None))
+    }
+
+    /// Like [`Function::eval`], but reports every step of the interpreter to `debugger`, which
+    /// can pause it to inspect the stack.
+    pub fn debug<'db>(
+        self,
+        db: &'db dyn HirDatabase,
+        debugger: &mut dyn Debugger,
+        span_formatter: impl Fn(FileId, TextRange) -> String,
+    ) -> Result<String, ConstEvalError<'db>> {
+        let mut observer =
+            debugger::Observer::new(db, debugger, self.krate(db).to_display_target(db));
+        self.eval_with(db, span_formatter, |body| {
+            interpret_mir_with_observer(db, body, &mut observer)
+        })
+    }
+
+    /// The ranges at which [`Function::debug`] can pause while it executes the body of this
+    /// function or of one of the closures it defines.
+    pub fn debug_step_ranges(self, db: &dyn HirDatabase) -> Vec<FileRange> {
+        let mut acc = Vec::new();
+        if let AnyFunctionId::FunctionId(id) = self.id
+            && let Ok(body) = db.mir_body(id.into())
+        {
+            debugger::step_ranges(db, body, &mut acc);
+        }
+        acc
+    }
+
+    fn eval_with<'db>(
+        self,
+        db: &'db dyn HirDatabase,
+        span_formatter: impl Fn(FileId, TextRange) -> String,
+        interpret: impl FnOnce(
+            &'db MirBody<'db>,
+        ) -> Result<
+            (Result<hir_ty::next_solver::Allocation<'db>, MirEvalError<'db>>, MirOutput),
+            MirEvalError<'db>,
+        >,
+    ) -> Result<String, ConstEvalError<'db>> {
         let AnyFunctionId::FunctionId(id) = self.id else {
             return Err(ConstEvalError::MirEvalError(MirEvalError::NotSupported(
                 "evaluation of builtin derive impl methods is not supported".to_owned(),
@@ -2545,7 +2704,7 @@ impl Function {
             }
             .store(),
         )?;
-        let (result, output) = interpret_mir(db, body, false, None)?;
+        let (result, output) = interpret(body)?;
         let mut text = match result {
             Ok(_) => "pass".to_owned(),
             Err(e) => {
@@ -5830,6 +5989,18 @@ impl<'db> Type<'db> {
         if ty.is_ty_error() { None } else { Some(Type { owner, ty: EarlyBinder::bind(ty) }) }
     }
 
+    /// Deeply normalizes the associated type projections in this type, as far as the trait solver
+    /// can resolve them in the environment the type comes from.
+    pub fn normalize(&self, db: &'db dyn HirDatabase) -> Type<'db> {
+        let env = self.param_env(db);
+        let interner = DbInterner::new_with(db, env.krate);
+        let infcx = interner.infer_ctxt().build(TypingMode::PostAnalysis);
+        let ty = self.ty.instantiate_identity().skip_norm_wip();
+        let ty =
+            infcx.at(&ObligationCause::dummy(), env.param_env).deeply_normalize(ty).unwrap_or(ty);
+        self.derived(ty)
+    }
+
     pub fn is_copy(&self, db: &'db dyn HirDatabase) -> bool {
         let env = self.param_env(db);
         let lang_items = hir_def::lang_item::lang_items(db, env.krate);
@@ -5937,6 +6108,32 @@ impl<'db> Type<'db> {
         self.ty.skip_binder().references_non_lt_error()
     }
 
+    /// The fields of `variant` of this enum type.
+    pub fn variant_fields(
+        &self,
+        db: &'db dyn HirDatabase,
+        variant: EnumVariant,
+    ) -> Vec<(Field, Self)> {
+        let interner = DbInterner::new_no_crate(db);
+        let substs = match self.ty.skip_binder().kind() {
+            TyKind::Adt(adt_def, substs)
+                if adt_def.def_id() == variant.id.lookup(db).parent.into() =>
+            {
+                substs
+            }
+            _ => return Vec::new(),
+        };
+        let variant_id = VariantId::from(variant.id);
+        db.field_types(variant_id)
+            .iter()
+            .map(|(local_id, field)| {
+                let def = Field { parent: variant_id.into(), id: local_id };
+                let ty = field.ty().instantiate(interner, substs).skip_norm_wip();
+                (def, self.derived(ty))
+            })
+            .collect()
+    }
+
     pub fn fields(&self, db: &'db dyn HirDatabase) -> Vec<(Field, Self)> {
         let interner = DbInterner::new_no_crate(db);
         let (variant_id, substs) = match self.ty.skip_binder().kind() {
@@ -6804,6 +7001,55 @@ impl<'db> Layout<'db> {
         }
     }
 
+    /// The tag storing the variant of an enum with multiple variants.
+    pub fn enum_tag(&self) -> Option<EnumTagLayout> {
+        let layout::Variants::Multiple { tag, tag_encoding, tag_field, .. } = &self.0.variants
+        else {
+            return None;
+        };
+        let offset = match &self.0.fields {
+            layout::FieldsShape::Arbitrary { offsets, .. } => offsets.get(*tag_field)?.bytes(),
+            _ => return None,
+        };
+        let valid_range = tag.valid_range(self.1);
+        Some(EnumTagLayout {
+            offset,
+            size: tag.size(self.1).bytes(),
+            valid_range: (valid_range.start, valid_range.end),
+            niche: matches!(tag_encoding, TagEncoding::Niche { .. }),
+        })
+    }
+
+    /// The layouts of the variants of an enum with multiple variants, in declaration order.
+    pub fn enum_variants(&self) -> Option<Vec<Layout<'db>>> {
+        let layout::Variants::Multiple { variants, .. } = &self.0.variants else {
+            return None;
+        };
+        Some(variants.iter().map(|it| Layout(Arc::new(it.clone()), self.1)).collect())
+    }
+
+    /// The value the tag of an enum with multiple variants holds for `variant`, `None` for the
+    /// variant whose field the tag is a niche of.
+    pub fn enum_tag_value(&self, db: &dyn HirDatabase, variant: EnumVariant) -> Option<u128> {
+        let layout::Variants::Multiple { tag, tag_encoding, .. } = &self.0.variants else {
+            return None;
+        };
+        let value = match tag_encoding {
+            TagEncoding::Direct => variant.eval(db).ok()? as u128,
+            TagEncoding::Niche { untagged_variant, niche_variants, niche_start } => {
+                let idx = variant.id.lookup(db).index(db);
+                if idx == untagged_variant.0
+                    || !(niche_variants.start().0..=niche_variants.end().0).contains(&idx)
+                {
+                    return None;
+                }
+                ((idx - niche_variants.start().0) as u128).wrapping_add(*niche_start)
+            }
+        };
+        let bits = tag.size(self.1).bits();
+        Some(if bits >= 128 { value } else { value & ((1 << bits) - 1) })
+    }
+
     pub fn enum_tag_size(&self) -> Option<usize> {
         let tag_size =
             if let layout::Variants::Multiple { tag, tag_encoding, .. } = &self.0.variants {
@@ -6818,6 +7064,17 @@ impl<'db> Layout<'db> {
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnumTagLayout {
+    /// Offset of the tag relative to the start of the enum.
+    pub offset: u64,
+    pub size: u64,
+    /// The values the tag can hold, wrapping around if the start is greater than the end.
+    pub valid_range: (u128, u128),
+    /// Whether the tag is a niche of a field of a variant rather than a field of its own.
+    pub niche: bool,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum BindingMode {
     Move,
@@ -6963,6 +7220,34 @@ impl<'db> TraitPredicate<'db> {
     }
 }
 
+/// A trait predicate that required the next, more nested predicate of an unsatisfied bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParentTraitPredicate<'db> {
+    pub predicate: TraitPredicate<'db>,
+    /// The field whose type had to satisfy the next predicate, if this predicate only required
+    /// it structurally (as auto traits do).
+    pub field: Option<Field>,
+}
+
+/// Converts an unsatisfied trait predicate and the chain of predicates that required it,
+/// innermost first.
+pub(crate) fn unsatisfied_trait_chain<'db>(
+    interner: DbInterner<'db>,
+    trait_predicate: &'db StoredTraitPredicate,
+    parent_trait_predicates: &'db [solver_errors::ParentTraitPredicate],
+    owner: TypeOwnerId<'db>,
+) -> (TraitPredicate<'db>, Vec<ParentTraitPredicate<'db>>) {
+    let trait_predicate = TraitPredicate { inner: trait_predicate.get(interner), owner };
+    let parent_trait_predicates = parent_trait_predicates
+        .iter()
+        .map(|parent| ParentTraitPredicate {
+            predicate: TraitPredicate { inner: parent.trait_predicate.get(interner), owner },
+            field: parent.field.map(Into::into),
+        })
+        .collect();
+    (trait_predicate, parent_trait_predicates)
+}
+
 /// Trait for obtaining the defining crate of an item.
 pub trait HasCrate {
     fn krate(&self, db: &dyn HirDatabase) -> Crate;
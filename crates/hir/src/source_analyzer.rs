@@ -40,12 +40,14 @@ use hir_ty::{
         InsideUnsafeBlock, record_literal_missing_fields, record_pattern_missing_fields,
         unsafe_operations,
     },
+    display::{DisplayTarget, HirDisplay},
     lang_items::lang_items_for_bin_op,
     method_resolution::{self, CandidateId},
     next_solver::{
         AliasTy, DbInterner, DefaultAny, EarlyBinder, ErrorGuaranteed, GenericArgs, ParamEnv,
         Region, Ty, TyKind, TypingMode, infer::DbInternerInferExt,
     },
+    solver_errors::{ParentTraitPredicate, SolverDiagnosticKind, StoredTraitPredicate},
     traits::{WherePredicateEvaluation, structurally_normalize_ty, where_predicate_must_hold},
 };
 use intern::sym;
@@ -56,7 +58,7 @@ use rustc_type_ir::{
     inherent::{IntoKind, Ty as _},
 };
 use smallvec::SmallVec;
-use stdx::never;
+use stdx::{format_to, never};
 use syntax::{
     SyntaxKind, SyntaxNode, TextRange, TextSize,
     ast::{self, AstNode, RangeItem, RangeOp},
@@ -411,8 +413,21 @@ impl<'db> SourceAnalyzer<'db> {
                         "predicate contains unresolved names or invalid type syntax",
                     );
                 }
-                WherePredicateEvaluation::NotProven => {
-                    return PredicateEvaluationResult::not_proven("predicate is not known to hold");
+                WherePredicateEvaluation::NotProven(error) => {
+                    let mut message = String::from("predicate is not known to hold");
+                    if let Some(SolverDiagnosticKind::TraitUnimplemented {
+                        trait_predicate,
+                        parent_trait_predicates,
+                    }) = &error
+                    {
+                        self.explain_unsatisfied_trait_chain(
+                            db,
+                            trait_predicate,
+                            parent_trait_predicates,
+                            &mut message,
+                        );
+                    }
+                    return PredicateEvaluationResult::not_proven(message);
                 }
             }
         }
@@ -420,6 +435,35 @@ impl<'db> SourceAnalyzer<'db> {
         PredicateEvaluationResult::holds("predicate holds")
     }
 
+    /// Appends a compact explanation like "`Rc<i32>: Send` is not satisfied, required by
+    /// `Foo: Send` (field `bar`)" to `message`.
+    fn explain_unsatisfied_trait_chain(
+        &self,
+        db: &'db dyn HirDatabase,
+        trait_predicate: &StoredTraitPredicate,
+        parent_trait_predicates: &[ParentTraitPredicate],
+        message: &mut String,
+    ) {
+        let display_target = DisplayTarget::from_crate(db, self.resolver.krate());
+        let (trait_predicate, parent_trait_predicates) = crate::unsatisfied_trait_chain(
+            DbInterner::new_no_crate(db),
+            trait_predicate,
+            parent_trait_predicates,
+            self.type_owner,
+        );
+        format_to!(message, ": `{}` is not satisfied", trait_predicate.display(db, display_target));
+        for parent in &parent_trait_predicates {
+            format_to!(message, ", required by `{}`", parent.predicate.display(db, display_target));
+            if let Some(field) = parent.field {
+                format_to!(
+                    message,
+                    " (field `{}`)",
+                    field.name(db).display(db, display_target.edition)
+                );
+            }
+        }
+    }
+
     pub(crate) fn expr_id(&self, expr: ast::Expr) -> Option<ExprOrPatId> {
         let src = InFile { file_id: self.file_id, value: expr };
         self.store_sm()?.node_expr(src.as_ref())
@@ -162,3 +162,72 @@ impl RootDatabase {
         acc
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use base_db::{
+        CrateGraphBuilder, CrateOrigin, CrateWorkspaceData, Env, FileSet, SourceRoot, VfsPath,
+        ad_hoc_crates, all_crates, relevant_crates,
+    };
+    use span::{Edition, FileId};
+    use triomphe::Arc;
+    use vfs::AbsPathBuf;
+
+    use crate::{ChangeWithProcMacros, RootDatabase};
+
+    fn crate_graph(ad_hoc_file: Option<FileId>) -> CrateGraphBuilder {
+        let mut graph = CrateGraphBuilder::default();
+        let mut add_crate = |file_id: FileId| {
+            graph.add_crate_root(
+                file_id,
+                Edition::CURRENT,
+                None,
+                None,
+                Default::default(),
+                Default::default(),
+                Env::default(),
+                CrateOrigin::Local { repo: None, name: None },
+                Vec::new(),
+                false,
+                Arc::new(AbsPathBuf::assert_utf8(std::env::current_dir().unwrap())),
+                Arc::new(CrateWorkspaceData { target: Err("".into()), toolchain: None }),
+            )
+        };
+        add_crate(FileId::from_raw(0));
+        if let Some(file_id) = ad_hoc_file {
+            let krate = add_crate(file_id);
+            graph.mark_ad_hoc(krate);
+        }
+        graph
+    }
+
+    #[test]
+    fn ad_hoc_crates_are_kept_out_of_all_crates() {
+        let mut db = RootDatabase::default();
+        let (lib, ad_hoc) = (FileId::from_raw(0), FileId::from_raw(1));
+        let mut change = ChangeWithProcMacros::default();
+        let mut file_set = FileSet::default();
+        file_set.insert(lib, VfsPath::new_virtual_path("/lib.rs".to_owned()));
+        file_set.insert(ad_hoc, VfsPath::new_virtual_path("/scratch.rs".to_owned()));
+        change.set_roots(vec![SourceRoot::new_local(file_set)]);
+        change.change_file(lib, Some(String::new()));
+        change.change_file(ad_hoc, Some(String::new()));
+        change.set_crate_graph(crate_graph(None));
+        db.apply_change(change);
+        let crates = all_crates(&db);
+        assert_eq!(crates.len(), 1);
+
+        let mut change = ChangeWithProcMacros::default();
+        change.set_crate_graph(crate_graph(Some(ad_hoc)));
+        db.apply_change(change);
+        assert!(std::sync::Arc::ptr_eq(&crates, &all_crates(&db)), "all crates were set again");
+        assert_eq!(ad_hoc_crates(&db).len(), 1);
+        assert_eq!(relevant_crates(&db, ad_hoc), &[crates[0], ad_hoc_crates(&db)[0]]);
+
+        let mut change = ChangeWithProcMacros::default();
+        change.set_crate_graph(crate_graph(None));
+        db.apply_change(change);
+        assert!(std::sync::Arc::ptr_eq(&crates, &all_crates(&db)), "all crates were set again");
+        assert!(ad_hoc_crates(&db).is_empty());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: conflicting-borrow
+//
+// This diagnostic is triggered when a value is borrowed while an earlier borrow of it is still
+// in use, and at least one of the borrows is mutable.
+pub(crate) fn conflicting_borrow<'db>(
+    ctx: &DiagnosticsContext<'_, 'db>,
+    d: &hir::ConflictingBorrow<'db>,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    let name = name.display(ctx.sema.db, ctx.edition);
+    let (code, message) = match (d.mutable, d.previous_mutable) {
+        (true, true) => {
+            ("E0499", format!("cannot borrow `{name}` as mutable more than once at a time"))
+        }
+        (true, false) => (
+            "E0502",
+            format!("cannot borrow `{name}` as mutable because it is also borrowed as immutable"),
+        ),
+        (false, _) => (
+            "E0502",
+            format!("cannot borrow `{name}` as immutable because it is also borrowed as mutable"),
+        ),
+    };
+    Diagnostic::new_with_syntax_node_ptr(ctx, DiagnosticCode::RustcHardError(code), message, d.span)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics_with_disabled;
+
+    #[test]
+    fn two_mutable_borrows() {
+        check_diagnostics_with_disabled(
+            r#"
+fn main() {
+    let mut x = 5;
+    let a = &mut x;
+    let b = &mut x;
+          //^^^^^^ error: cannot borrow `x` as mutable more than once at a time
+    *a = 2;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn mutable_and_shared_borrows() {
+        check_diagnostics_with_disabled(
+            r#"
+fn main() {
+    let mut x = 5;
+    let a = &x;
+    let b = &mut x;
+          //^^^^^^ error: cannot borrow `x` as mutable because it is also borrowed as immutable
+    let c = *a;
+    let d = &mut x;
+    let e = &x;
+          //^^ error: cannot borrow `x` as immutable because it is also borrowed as mutable
+    *d = 2;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn borrow_through_call() {
+        check_diagnostics_with_disabled(
+            r#"
+struct S { v: i32 }
+fn get(s: &mut S) -> &mut i32 { &mut s.v }
+
+fn main() {
+    let mut s = S { v: 0 };
+    let a = get(&mut s);
+    let b = &s;
+          //^^ error: cannot borrow `s` as immutable because it is also borrowed as mutable
+    *a = 2;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_when_borrow_is_dead() {
+        check_diagnostics_with_disabled(
+            r#"
+fn len(_: &i32) -> usize { 0 }
+
+fn main() {
+    let mut x = 5;
+    let a = &mut x;
+    *a = 2;
+    let b = &mut x;
+    *b = 3;
+    let c = &x;
+    let d = &x;
+    let e = *c + *d;
+    let mut r = &mut x;
+    let mut y = 5;
+    r = &mut y;
+    let f = &mut x;
+    *f = 3;
+    *r = 4;
+    let l = len(&x);
+    let g = &mut x;
+    *g = l as i32;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_disjoint_fields() {
+        check_diagnostics_with_disabled(
+            r#"
+struct S { a: i32, b: i32 }
+
+fn main() {
+    let mut s = S { a: 0, b: 0 };
+    let a = &mut s.a;
+    let b = &mut s.b;
+    *a = 1;
+    *b = 2;
+    let mut t = (1, 2);
+    let c = &mut t.0;
+    let d = &t.1;
+    *c = *d;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_two_phase_borrows() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: sized
+struct V;
+impl V {
+    fn push(&mut self, _: usize) {}
+    fn len(&self) -> usize { 0 }
+}
+
+fn main() {
+    let mut v = V;
+    v.push(v.len());
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_values_not_borrowing_arguments() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option, iterator, fn
+struct Peek<I: Iterator> { it: I, peeked: Option<I::Item> }
+impl<I: Iterator> Peek<I> {
+    fn next(&mut self) -> Option<I::Item> { self.it.next() }
+    fn peek(&mut self) -> Option<&I::Item> { loop {} }
+}
+struct Queue { handler: Option<fn(&mut Queue)> }
+impl Queue {
+    fn take(&mut self) -> Option<fn(&mut Queue)> { self.handler }
+}
+
+fn f<I: Iterator<Item = u32>>(mut p: Peek<I>, mut q: Queue) {
+    while let Some(x) = p.next() {
+        if let Some(&y) = p.peek() {
+            p.next();
+        }
+    }
+    if let Some(h) = q.take() {
+        h(&mut q);
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_overwritten_reborrow() {
+        check_diagnostics_with_disabled(
+            r#"
+struct Node { v: i32 }
+impl Node {
+    fn child(&mut self) -> &mut Node { self }
+    fn val(&self) -> i32 { self.v }
+}
+
+fn f(n: &mut Node) {
+    let mut cur = n;
+    loop {
+        cur = cur.child();
+        if cur.val() == 0 {
+            break;
+        }
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_closures() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option, fn, coerce_unsized, dispatch_from_dyn
+fn key(buf: &mut u32, x: &u32) -> u32 { loop {} }
+fn find<F: FnMut(u32) -> Option<u32>>(f: F) -> Option<u32> { loop {} }
+
+fn f(a: &u32, b: &u32) {
+    let mut buf = 0;
+    let mut k = |x| key(&mut buf, x);
+    let ordering = k(a) == k(b);
+    let mut stack = 0;
+    let found = find(|it| {
+        stack = it;
+        None
+    });
+    stack += 1;
+}
+
+enum Event { Enter(u32), Leave(u32) }
+fn preorder(check: &'static dyn Fn(&u32) -> bool, f: &mut dyn FnMut(Event)) {}
+struct Sema;
+impl Sema {
+    fn expand(&self, x: &u32) -> Option<u32> { loop {} }
+}
+struct Walk<'a> { sema: &'a Sema, depth: u32, check: &'static dyn Fn(&u32) -> bool }
+impl Walk<'_> {
+    fn walk(&mut self, cb: &mut dyn FnMut(u32, u32)) {
+        preorder(self.check, &mut |ev| match ev {
+            Event::Enter(x) => {
+                cb(self.depth, x);
+                if x == 0 {
+                    self.depth += 1;
+                }
+                if let Some(expanded) = self.sema.expand(&x) {
+                    self.walk(cb);
+                }
+            }
+            Event::Leave(x) if x == 0 => {
+                self.depth -= 1;
+            }
+            _ => {}
+        });
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_values_read_through_references() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option, iterator
+enum Chunk<'a> { Insert(&'a u32), Delete(&'a u32) }
+struct Peek<'a> { it: Option<Chunk<'a>> }
+impl<'a> Peek<'a> {
+    fn next(&mut self) -> Option<Chunk<'a>> { loop {} }
+    fn peek(&mut self) -> Option<&Chunk<'a>> { loop {} }
+}
+
+fn f(mut chunks: Peek<'_>) {
+    while let Some(chunk) = chunks.next() {
+        if let (Chunk::Delete(deleted), Some(&Chunk::Insert(inserted))) = (chunk, chunks.peek()) {
+            chunks.next();
+            let _ = (deleted, inserted);
+        }
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_loans_held_by_referenced_values() {
+        check_diagnostics_with_disabled(
+            r#"
+struct Infcx;
+struct Ctxt<'a> { infcx: &'a Infcx, errors: u32 }
+struct Lowerer<'a, 'db> { infcx: &'a Infcx, ty: &'db u32 }
+impl Infcx {
+    fn eq(&self) -> u32 { 0 }
+}
+impl<'a, 'db> Lowerer<'a, 'db> {
+    fn lower(&mut self) -> &'db u32 { self.ty }
+}
+
+impl Ctxt<'_> {
+    fn register(&mut self, x: u32) { self.errors += x; }
+    fn eq(&mut self) {
+        let x = self.infcx.eq();
+        self.register(x);
+    }
+}
+
+fn f<'db>(l: &mut Lowerer<'_, 'db>) {
+    let ty = l.lower();
+    let ty2 = l.lower();
+    let _ = (ty, ty2);
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_values_not_holding_loans_after_call() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: drop
+struct Table { v: u32 }
+struct At<'a, 'db> { table: &'a Table, db: &'db u32 }
+impl Table {
+    fn at<'db>(&self, db: &'db u32) -> At<'_, 'db> { loop {} }
+    fn register(&mut self, _: &u32) {}
+}
+impl<'a, 'db> At<'a, 'db> {
+    fn eq(self) -> &'db u32 { self.db }
+}
+
+struct Obligations;
+impl Drop for Obligations {
+    fn drop(&mut self) {}
+}
+struct Autoderef<'a> { table: &'a mut Table, obligations: Obligations }
+impl Autoderef<'_> {
+    fn take(&mut self) -> u32 { 0 }
+}
+
+fn f(table: &mut Table, db: &u32) {
+    let result = table.at(db).eq();
+    table.register(result);
+    let mut autoderef = Autoderef { table: &mut *table, obligations: Obligations };
+    autoderef.take();
+    table.register(db);
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_results_of_closures_and_copied() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: fn, copy
+struct Frame<'a>(&'a u32);
+enum Opt<T> { Some(T), None }
+impl<T: Copy> Opt<&T> {
+    fn copied(self) -> Opt<T> { loop {} }
+}
+struct Ctx { frame: u32 }
+impl Ctx {
+    fn frame(&mut self) -> Opt<&u32> { Opt::Some(&self.frame) }
+    fn push(&mut self) {}
+}
+
+fn f(ctx: &mut Ctx) {
+    let y = 0;
+    let frame = Frame(&y);
+    let frame = (|| {
+        ctx.push();
+        frame
+    })();
+    let m = &mut ctx.frame;
+    let frame2 = frame;
+    let first = ctx.frame().copied();
+    let m = &mut ctx.frame;
+    let first2 = first;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn drop_uses_borrow() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: drop
+struct Guard<'a>(&'a mut u32);
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {}
+}
+
+fn f() {
+    let mut x = 0;
+    let guard = Guard(&mut x);
+    let r = &x;
+          //^^ error: cannot borrow `x` as immutable because it is also borrowed as mutable
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_temporaries_and_reservations() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option, fn, drop, try
+struct Guard<F>(F);
+impl<F> Drop for Guard<F> {
+    fn drop(&mut self) {}
+}
+impl<F: FnMut(u32)> Guard<F> {
+    fn find(&mut self) -> Option<u32> { loop {} }
+}
+
+fn push(stack: &mut u32, x: u32) {}
+
+fn f() -> Option<u32> {
+    let mut stack = 0;
+    let found = Guard(|x| push(&mut stack, x)).find()?;
+    stack += 1;
+    None
+}
+
+struct Vfs;
+impl Vfs {
+    fn path(&self, id: u32) -> &u32 { loop {} }
+}
+struct Lsif { vfs: Vfs }
+impl Lsif {
+    fn add(&mut self, v: u32) -> u32 { loop {} }
+    fn get(&mut self, id: u32) -> u32 {
+        let path = self.vfs.path(id);
+        self.add(*path)
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+}
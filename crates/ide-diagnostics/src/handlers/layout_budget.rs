@@ -0,0 +1,407 @@
+use hir::{Adt, Enum, Layout, Semantics};
+use ide_db::{
+    EditionedFileId, FileRange, RootDatabase, assists::Assist, source_change::SourceChange,
+    text_edit::TextEdit,
+};
+use itertools::Itertools;
+use syntax::{
+    AstNode, SyntaxKind, SyntaxNode, T,
+    ast::{self, HasAttrs as _, HasName},
+};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsConfig, Severity, fix};
+
+/// The largest size and padding, in bytes, a type is allowed to have.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutBudget {
+    pub size: Option<u64>,
+    pub padding: Option<u64>,
+}
+
+// Diagnostic: layout-budget-exceeded
+//
+// This diagnostic is triggered if the layout of a type is larger or has more padding than its
+// budget, declared with the attribute configured by `rust-analyzer.diagnostics.layoutBudget.attribute`
+// (like `#[layout_budget(size = 16, padding = 0)]`) or in `rust-analyzer.diagnostics.layoutBudget.types`.
+// The padding of an enum is the bytes that hold neither its tag nor a field of any of its variants.
+pub(crate) fn layout_budget(
+    sema: &Semantics<'_, RootDatabase>,
+    acc: &mut Vec<Diagnostic>,
+    file_id: EditionedFileId,
+    node: &SyntaxNode,
+    config: &DiagnosticsConfig,
+) {
+    (|| {
+        if config.layout_budget_attribute.is_none() && config.layout_budgets.is_empty() {
+            return None;
+        }
+        let adt = ast::Adt::cast(node.clone())?;
+        let def = sema.to_def(&adt).or_else(|| {
+            // An item under an attribute macro, like a no-op `#[layout_budget]`, is defined by
+            // its expansion.
+            let expanded = sema.expand_attr_macro(&ast::Item::from(adt.clone()))?.value.value;
+            sema.to_def(&expanded.descendants().find_map(ast::Adt::cast)?)
+        })?;
+        let db = sema.db;
+        let budget = match budget_from_attr(&adt, config) {
+            Some(it) => it,
+            None => *config.layout_budgets.get(&qualified_path(db, def)?)?,
+        };
+        let layout = def.layout(db).ok()?;
+        let name = adt.name()?;
+        let range = FileRange { file_id: file_id.file_id(db), range: name.syntax().text_range() };
+
+        if let Some(max_size) = budget.size
+            && layout.size() > max_size
+        {
+            acc.push(
+                Diagnostic::new(
+                    DiagnosticCode::Ra("layout-budget-exceeded", Severity::Warning),
+                    format!(
+                        "`{name}` is {} bytes, more than its budget of {max_size} bytes",
+                        layout.size()
+                    ),
+                    range,
+                )
+                .stable(),
+            );
+        }
+
+        let max_padding = budget.padding?;
+        let (padding, fixes) = match def {
+            Adt::Struct(strukt) => {
+                let fields = strukt
+                    .fields(db)
+                    .into_iter()
+                    .map(|field| field.layout(db).ok().map(|it| (it.size(), it.align())))
+                    .collect::<Option<Vec<_>>>()?;
+                let padding = layout.size() - fields.iter().map(|(size, _)| size).sum::<u64>();
+                let fixes = (padding > max_padding
+                    && strukt.repr(db).is_some_and(|it| it.inhibit_struct_field_reordering()))
+                .then(|| reorder_fields_fix(range, &adt, &fields, layout.align(), padding))
+                .flatten()
+                .map(|it| vec![it]);
+                (padding, fixes)
+            }
+            Adt::Union(union) => {
+                let largest = union
+                    .fields(db)
+                    .into_iter()
+                    .map(|field| field.layout(db).ok().map(|it| it.size()))
+                    .collect::<Option<Vec<_>>>()?
+                    .into_iter()
+                    .max()
+                    .unwrap_or(0);
+                (layout.size() - largest, None)
+            }
+            Adt::Enum(enum_) => (enum_padding(db, enum_, &layout)?, None),
+        };
+        if padding <= max_padding {
+            return Some(());
+        }
+        acc.push(
+            Diagnostic::new(
+                DiagnosticCode::Ra("layout-budget-exceeded", Severity::Warning),
+                format!("`{name}` has {padding} bytes of padding, more than its budget of {max_padding} bytes"),
+                range,
+            )
+            .stable()
+            .with_fixes(fixes),
+        );
+        Some(())
+    })();
+}
+
+/// The budget declared by the configured attribute on `adt`.
+fn budget_from_attr(adt: &ast::Adt, config: &DiagnosticsConfig) -> Option<LayoutBudget> {
+    let attr_name = config.layout_budget_attribute.as_deref()?;
+    let token_tree = adt.attrs().find_map(|attr| match attr.meta()? {
+        ast::Meta::TokenTreeMeta(meta) if meta.path()?.syntax().text() == attr_name => {
+            meta.token_tree()
+        }
+        _ => None,
+    })?;
+    let mut budget = LayoutBudget::default();
+    let tokens = token_tree
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !matches!(it.kind(), SyntaxKind::WHITESPACE | T!['('] | T![')']))
+        .collect::<Vec<_>>();
+    for arg in tokens.split(|it| it.kind() == T![,]) {
+        let [key, eq, value] = arg else { continue };
+        if eq.kind() != T![=] {
+            continue;
+        }
+        let Ok(value) = value.text().replace('_', "").parse() else { continue };
+        match key.text() {
+            "size" => budget.size = Some(value),
+            "padding" => budget.padding = Some(value),
+            _ => {}
+        }
+    }
+    Some(budget)
+}
+
+/// The bytes of the layout of `enum_` that hold neither its tag nor a field of any of its
+/// variants.
+fn enum_padding(db: &RootDatabase, enum_: Enum, layout: &Layout<'_>) -> Option<u64> {
+    let mut occupied =
+        layout.enum_tag().map(|tag| (tag.offset, tag.size)).into_iter().collect::<Vec<_>>();
+    // Enums with a single variant are laid out like structs.
+    let variant_layouts = layout.enum_variants().unwrap_or_else(|| vec![layout.clone()]);
+    for (variant, variant_layout) in enum_.variants(db).into_iter().zip(&variant_layouts) {
+        for field in variant.fields(db) {
+            let size = field.layout(db).ok()?.size();
+            occupied.push((variant_layout.field_offset(field)?, size));
+        }
+    }
+    occupied.sort_unstable();
+    let (mut covered, mut end) = (0, 0);
+    for (offset, size) in occupied {
+        let (start, range_end) = (offset.max(end), (offset + size).min(layout.size()));
+        if range_end > start {
+            covered += range_end - start;
+            end = range_end;
+        }
+    }
+    Some(layout.size() - covered)
+}
+
+/// The path of `adt` starting with its crate, like `my_crate::net::Packet`.
+fn qualified_path(db: &RootDatabase, adt: Adt) -> Option<String> {
+    let module = adt.module(db);
+    let mut segments = vec![module.krate(db).display_name(db)?.crate_name().to_string()];
+    segments.extend(
+        module
+            .path_to_root(db)
+            .into_iter()
+            .rev()
+            .filter_map(|it| Some(it.name(db)?.as_str().to_owned())),
+    );
+    segments.push(adt.name(db).as_str().to_owned());
+    Some(segments.join("::"))
+}
+
+/// Orders the fields of a record struct, whose fields are laid out in declaration order, by
+/// decreasing alignment if that reduces its padding.
+fn reorder_fields_fix(
+    target: FileRange,
+    adt: &ast::Adt,
+    fields: &[(u64, u64)],
+    align: u64,
+    padding: u64,
+) -> Option<Assist> {
+    let ast::Adt::Struct(strukt) = adt else { return None };
+    let Some(ast::FieldList::RecordFieldList(field_list)) = strukt.field_list() else {
+        return None;
+    };
+    let ast_fields = field_list.fields().collect::<Vec<_>>();
+    if ast_fields.len() != fields.len() || ast_fields.len() < 2 {
+        return None;
+    }
+    let order = (0..fields.len()).sorted_by_key(|&i| std::cmp::Reverse(fields[i].1)).collect_vec();
+    let reordered_padding = c_layout_padding(order.iter().map(|&i| fields[i]), align);
+    if reordered_padding >= padding {
+        return None;
+    }
+
+    // Keep the formatting by separating the fields like the first two.
+    let list_start = field_list.syntax().text_range().start();
+    let list_text = field_list.syntax().text().to_string();
+    let first = ast_fields[0].syntax().text_range();
+    let second = ast_fields[1].syntax().text_range();
+    let separator =
+        &list_text[usize::from(first.end() - list_start)..usize::from(second.start() - list_start)];
+    let text = order.iter().map(|&i| ast_fields[i].syntax().to_string()).join(separator);
+    let range = first.cover(ast_fields[ast_fields.len() - 1].syntax().text_range());
+    Some(fix(
+        "reorder_fields_by_alignment",
+        "Reorder fields by alignment",
+        SourceChange::from_text_edit(target.file_id, TextEdit::replace(range, text)),
+        target.range,
+    ))
+}
+
+/// The padding of a struct with `fields`, as `(size, align)`, laid out in this order.
+fn c_layout_padding(fields: impl Iterator<Item = (u64, u64)>, align: u64) -> u64 {
+    let (mut offset, mut fields_size) = (0u64, 0);
+    for (size, field_align) in fields {
+        offset = offset.next_multiple_of(field_align) + size;
+        fields_size += size;
+    }
+    offset.next_multiple_of(align) - fields_size
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::{RootDatabase, assists::AssistResolveStrategy, base_db::SourceDatabase};
+    use test_fixture::WithFixture;
+
+    use crate::{
+        DiagnosticsConfig, LayoutBudget,
+        tests::{check_diagnostics_with_config, check_nth_fix_with_config},
+    };
+
+    fn config() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            layout_budget_attribute: Some("layout_budget".to_owned()),
+            // The attribute is usually a no-op attribute macro, which most tests leave unresolved.
+            disabled: std::iter::once("unresolved-macro-call".to_owned()).collect(),
+            ..DiagnosticsConfig::test_sample()
+        }
+    }
+
+    #[test]
+    fn size_over_budget() {
+        check_diagnostics_with_config(
+            config(),
+            r#"
+#[layout_budget(size = 8)]
+struct Small(u64, u8);
+     //^^^^^ warn: `Small` is 16 bytes, more than its budget of 8 bytes
+#[layout_budget(size = 16)]
+struct Fits(u64, u8);
+"#,
+        );
+    }
+
+    #[test]
+    fn padding_over_budget() {
+        check_diagnostics_with_config(
+            config(),
+            r#"
+#[layout_budget(padding = 0)]
+struct Padded {
+     //^^^^^^ warn: `Padded` has 7 bytes of padding, more than its budget of 0 bytes
+    a: u8,
+    b: u64,
+}
+#[layout_budget(padding = 7)]
+struct Allowed {
+    a: u8,
+    b: u64,
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn padding_of_enums_and_unions() {
+        check_diagnostics_with_config(
+            config(),
+            r#"
+#[layout_budget(padding = 0)]
+enum Padded {
+   //^^^^^^ warn: `Padded` has 6 bytes of padding, more than its budget of 0 bytes
+    A(u8),
+    B(u64),
+}
+#[layout_budget(padding = 0)]
+enum Niche {
+    A(&'static u8),
+    B,
+}
+#[layout_budget(padding = 2)]
+union Union {
+    //^^^^^ warn: `Union` has 3 bytes of padding, more than its budget of 2 bytes
+    a: [u8; 5],
+    b: u32,
+}
+"#,
+        );
+    }
+
+    /// Returns the ranges and messages of the diagnostics of `ra_fixture`, with attribute macros
+    /// expanded.
+    fn diagnostics_with_attr_macros(
+        config: DiagnosticsConfig,
+        #[rust_analyzer::rust_fixture] ra_fixture: &str,
+    ) -> Vec<(String, String)> {
+        let (mut db, file_id) = RootDatabase::with_single_file(ra_fixture);
+        db.enable_proc_attr_macros();
+        let file_id = file_id.file_id(&db);
+        let diagnostics = hir::attach_db(&db, || {
+            crate::full_diagnostics(&db, &config, &AssistResolveStrategy::All, file_id)
+        });
+        let text = db.file_text(file_id).text(&db);
+        diagnostics.into_iter().map(|d| (text[d.range.range].to_owned(), d.message)).collect()
+    }
+
+    #[test]
+    fn budget_on_attribute_macro() {
+        let diagnostics = diagnostics_with_attr_macros(
+            DiagnosticsConfig { disabled: Default::default(), ..config() },
+            r#"
+//- proc_macros: identity
+use proc_macros::identity as layout_budget;
+
+#[layout_budget(size = 8)]
+struct Small(u64, u8);
+"#,
+        );
+        assert_eq!(
+            diagnostics,
+            [(
+                "Small".to_owned(),
+                "`Small` is 16 bytes, more than its budget of 8 bytes".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn budget_from_config() {
+        let mut config = DiagnosticsConfig::test_sample();
+        config.layout_budgets.insert(
+            "ra_test_fixture::net::Packet".to_owned(),
+            LayoutBudget { size: Some(4), padding: None },
+        );
+        check_diagnostics_with_config(
+            config,
+            r#"
+mod net {
+    struct Packet([u8; 8]);
+         //^^^^^^ warn: `Packet` is 8 bytes, more than its budget of 4 bytes
+}
+struct Packet([u8; 8]);
+"#,
+        );
+    }
+
+    #[test]
+    fn no_budget_without_config() {
+        check_diagnostics_with_config(
+            DiagnosticsConfig { layout_budget_attribute: None, ..config() },
+            r#"
+#[layout_budget(size = 1)]
+struct Large(u64);
+"#,
+        );
+    }
+
+    #[test]
+    fn reorder_fields_of_repr_c() {
+        check_nth_fix_with_config(
+            config(),
+            0,
+            r#"
+#[repr(C)]
+#[layout_budget(padding = 0)]
+struct Padded$0 {
+    a: u8,
+    b: u32,
+    c: u16,
+}
+"#,
+            r#"
+#[repr(C)]
+#[layout_budget(padding = 0)]
+struct Padded {
+    b: u32,
+    c: u16,
+    a: u8,
+}
+"#,
+        );
+    }
+}
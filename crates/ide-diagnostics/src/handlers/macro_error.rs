@@ -44,6 +44,10 @@ pub(crate) fn macro_def_error(
 
 #[cfg(test)]
 mod tests {
+    use ide_db::{RootDatabase, assists::AssistResolveStrategy, base_db::SourceDatabase};
+    use test_fixture::ChangeFixture;
+    use test_utils::MiniCore;
+
     use crate::{
         DiagnosticsConfig,
         tests::{check_diagnostics, check_diagnostics_with_config},
@@ -71,6 +75,33 @@ macro_rules! compile_error { () => {} }
         );
     }
 
+    #[test]
+    fn proc_macro_server_error() {
+        let mut db = RootDatabase::default();
+        let fixture = ChangeFixture::parse_with_proc_macros(
+            r#"
+//- proc_macros: failing
+proc_macros::failing!(struct S;);
+"#,
+            MiniCore::RAW_SOURCE,
+            vec![test_fixture::failing_proc_macro("failing", "the proc-macro server failed")],
+        );
+        fixture.change.apply(&mut db);
+        let file_id = fixture.files[0].file_id();
+        let diagnostics = hir::attach_db(&db, || {
+            crate::full_diagnostics(
+                &db,
+                &DiagnosticsConfig::test_sample(),
+                &AssistResolveStrategy::All,
+                file_id,
+            )
+        });
+        let text = db.file_text(file_id).text(&db);
+        let diagnostics: Vec<_> =
+            diagnostics.iter().map(|d| (&text[d.range.range], &*d.message)).collect();
+        assert_eq!(diagnostics, [("failing", "the proc-macro server failed")]);
+    }
+
     #[test]
     fn eager_macro_concat() {
         check_diagnostics(
@@ -0,0 +1,102 @@
+use hir::HirDisplay;
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: moved-out-of-ref
+//
+// This diagnostic is triggered on moving non copy things out of references.
+pub(crate) fn moved_out_of_ref<'db>(
+    ctx: &DiagnosticsContext<'_, 'db>,
+    d: &hir::MovedOutOfRef<'db>,
+) -> Diagnostic {
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0507"),
+        format!("cannot move `{}` out of reference", d.ty.display(ctx.sema.db, ctx.display_target)),
+        d.span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics_with_disabled;
+
+    #[test]
+    fn move_out_of_field() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+struct Y(X, i32);
+
+fn main() {
+    let a = &Y(X, 5);
+    let b = a.0;
+      //^ error: cannot move `X` out of reference
+    let y = a.1;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn move_out_of_deref() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy, deref
+struct X;
+
+fn f(x: &X, y: &mut String, z: &&i32) {
+    let a = *x;
+      //^ error: cannot move `X` out of reference
+    let b = *y;
+      //^ error: cannot move `String` out of reference
+    let c = **z;
+}
+struct String;
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_box_and_copy() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy, derive, clone
+#[derive(Clone, Copy)]
+struct C;
+struct X;
+
+fn f(c: &C, r: &X) {
+    let b = *c;
+    let d = &*r;
+    let e = &r;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_ref_patterns() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy, option
+struct X;
+
+fn f(x: &Option<X>) {
+    if let Some(y) = x {
+        let _: &X = y;
+    }
+    match *x {
+        Some(ref y) => {}
+        None => {}
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+}
@@ -0,0 +1,412 @@
+use ide_db::source_change::SourceChange;
+use ide_db::text_edit::TextEdit;
+use syntax::{AstNode, SyntaxKind, SyntaxNode, SyntaxNodePtr, SyntaxToken, T, ast};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext, fix};
+
+// Diagnostic: need-mut
+//
+// This diagnostic is triggered on mutating an immutable variable.
+pub(crate) fn need_mut(
+    ctx: &DiagnosticsContext<'_, '_>,
+    d: &hir::NeedMut<'_>,
+) -> Option<Diagnostic> {
+    let root = d.span.file_id.parse_or_expand(ctx.sema.db);
+    let node = d.span.value.to_node(&root);
+    let mut span = d.span;
+    if let Some(parent) = node.parent()
+        && ast::BinExpr::cast(parent.clone()).is_some()
+    {
+        // In case of an assignment, the diagnostic is provided on the variable name.
+        // We want to expand it to include the whole assignment, but only when this
+        // is an ordinary assignment, not a destructuring assignment. So, the direct
+        // parent is an assignment expression.
+        span = d.span.with_value(SyntaxNodePtr::new(&parent));
+    };
+
+    let fixes = (|| {
+        if d.local.is_ref(ctx.sema.db) {
+            // There is no simple way to add `mut` to `ref x` and `ref mut x`
+            return None;
+        }
+        let file_id = span.file_id.file_id()?;
+        let mut edit_builder = TextEdit::builder();
+        let use_range = span.value.text_range();
+        for source in d.local.sources(ctx.sema.db) {
+            let Some(ast) = source.name() else { continue };
+            // FIXME: macros
+            edit_builder.insert(ast.value.syntax().text_range().start(), "mut ".to_owned());
+        }
+        let edit = edit_builder.finish();
+        Some(vec![fix(
+            "add_mut",
+            "Change it to be mutable",
+            SourceChange::from_text_edit(file_id.file_id(ctx.sema.db), edit),
+            use_range,
+        )])
+    })();
+
+    Some(
+        Diagnostic::new_with_syntax_node_ptr(
+            ctx,
+            // FIXME: `E0384` is not the only error that this diagnostic handles
+            DiagnosticCode::RustcHardError("E0384"),
+            format!(
+                "cannot mutate immutable variable `{}`",
+                d.local.name(ctx.sema.db).display(ctx.sema.db, ctx.edition)
+            ),
+            span,
+        )
+        .with_fixes(fixes),
+    )
+}
+
+// Diagnostic: unused-mut
+//
+// This diagnostic is triggered when a mutable variable isn't actually mutated.
+pub(crate) fn unused_mut(
+    ctx: &DiagnosticsContext<'_, '_>,
+    d: &hir::UnusedMut<'_>,
+) -> Option<Diagnostic> {
+    let ast = d.local.primary_source(ctx.sema.db).syntax_ptr();
+    let fixes = (|| {
+        let file_id = ast.file_id.file_id()?;
+        let mut edit_builder = TextEdit::builder();
+        let use_range = ast.value.text_range();
+        for source in d.local.sources(ctx.sema.db) {
+            let ast = source.syntax();
+            let Some(mut_token) = token(ast, T![mut]) else { continue };
+            edit_builder.delete(mut_token.text_range());
+            if let Some(token) = mut_token.next_token()
+                && token.kind() == SyntaxKind::WHITESPACE
+            {
+                edit_builder.delete(token.text_range());
+            }
+        }
+        let edit = edit_builder.finish();
+        Some(vec![fix(
+            "remove_mut",
+            "Remove unnecessary `mut`",
+            SourceChange::from_text_edit(file_id.file_id(ctx.sema.db), edit),
+            use_range,
+        )])
+    })();
+    Some(
+        Diagnostic::new_with_syntax_node_ptr(
+            ctx,
+            DiagnosticCode::RustcLint("unused_mut"),
+            "variable does not need to be mutable",
+            ast,
+        )
+        .with_fixes(fixes),
+    )
+}
+
+pub(super) fn token(parent: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    parent.children_with_tokens().filter_map(|it| it.into_token()).find(|it| it.kind() == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_diagnostics_with_disabled, check_fix};
+
+    #[test]
+    fn unused_mut_simple() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x = 2;
+      //^^^^^ 💡 warn: variable does not need to be mutable
+    f(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_false_positive_simple() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let x = 2;
+    f(x);
+}
+"#,
+        );
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x = 2;
+    x = 5;
+    f(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn multiple_errors_for_single_variable() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let x = 2;
+    x = 10;
+  //^^^^^^ 💡 error: cannot mutate immutable variable `x`
+    x = 5;
+  //^^^^^ 💡 error: cannot mutate immutable variable `x`
+    &mut x;
+  //^^^^^^ 💡 error: cannot mutate immutable variable `x`
+    f(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unused_mut_fix() {
+        check_fix(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mu$0t x = 2;
+    f(x);
+}
+"#,
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let x = 2;
+    f(x);
+}
+"#,
+        );
+        check_fix(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let ((mu$0t x, _) | (_, mut x)) = (2, 3);
+    f(x);
+}
+"#,
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let ((x, _) | (_, x)) = (2, 3);
+    f(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn need_mut_fix() {
+        check_fix(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let x = 2;
+    x$0 = 5;
+    f(x);
+}
+"#,
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x = 2;
+    x = 5;
+    f(x);
+}
+"#,
+        );
+        check_fix(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let ((x, _) | (_, x)) = (2, 3);
+    x =$0 4;
+    f(x);
+}
+"#,
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let ((mut x, _) | (_, mut x)) = (2, 3);
+    x = 4;
+    f(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn need_mut_for_method_call_autoref() {
+        check_diagnostics(
+            r#"
+struct Foo;
+impl Foo {
+    fn mutate(&mut self) {}
+    fn read(&self) {}
+}
+fn main() {
+    let foo = Foo;
+    foo.mutate();
+  //^^^ 💡 error: cannot mutate immutable variable `foo`
+    foo.read();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_false_positive_through_references() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct Foo { a: i32 }
+fn f(x: &mut i32, foo: &mut Foo, r: &mut &mut i32) {
+    *x = 5;
+    foo.a = 2;
+    **r = 3;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn deferred_initialization() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main(b: bool) {
+    let x;
+    if b {
+        x = 1;
+    } else {
+        x = 2;
+    }
+    f(x);
+    let y;
+    loop {
+        y = 3;
+        break;
+    }
+    f(y);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn assignment_in_loop() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let x;
+    loop {
+        x = 3;
+      //^^^^^ 💡 error: cannot mutate immutable variable `x`
+        f(x);
+    }
+}
+"#,
+        );
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    loop {
+        let x;
+        x = 3;
+        f(x);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn field_assignment() {
+        check_diagnostics(
+            r#"
+struct Foo { a: i32 }
+fn main() {
+    let foo = Foo { a: 1 };
+    foo.a = 2;
+  //^^^^^^^^^ 💡 error: cannot mutate immutable variable `foo`
+    let mut bar = Foo { a: 1 };
+    bar.a = 2;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn closures() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, fn
+fn main() {
+    let x = 5;
+    let closure1 = || { x = 2; };
+                      //^^^^^ 💡 error: cannot mutate immutable variable `x`
+    let _ = closure1();
+          //^^^^^^^^ 💡 error: cannot mutate immutable variable `closure1`
+    let mut y = 5;
+    let mut closure2 = move || { y = 2; };
+    closure2();
+    let z = 5;
+    let closure3 = move || { let w = z; w };
+    closure3();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn mutable_param() {
+        check_diagnostics(
+            r#"
+fn f(x: i32, mut y: i32, mut z: i32) -> i32 {
+           //^^^^^ 💡 warn: variable does not need to be mutable
+    x = 3;
+  //^^^^^ 💡 error: cannot mutate immutable variable `x`
+    z = 2;
+    x + y + z
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn allow_unused_mut_for_identifiers_starting_with_underline() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut _x = 2;
+    f(_x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn respect_allow_unused_mut() {
+        check_diagnostics_with_disabled(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    #[allow(unused_mut)]
+    let mut x = 2;
+    f(x);
+}
+"#,
+            &[],
+        );
+    }
+}
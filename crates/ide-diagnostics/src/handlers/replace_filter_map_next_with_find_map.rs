@@ -59,7 +59,7 @@ fn fixes(
 mod tests {
     use crate::{
         DiagnosticsConfig,
-        tests::{check_diagnostics_with_config, check_fix},
+        tests::{check_diagnostics_with_config, check_fix_with_disabled},
     };
 
     #[track_caller]
@@ -142,7 +142,7 @@ fn foo() {
 
     #[test]
     fn replace_with_find_map() {
-        check_fix(
+        check_fix_with_disabled(
             r#"
 //- minicore: iterators
 fn foo() {
@@ -154,6 +154,7 @@ fn foo() {
     let m = core::iter::repeat(()).find_map(|()| Some(92));
 }
 "#,
+            &["unused_variables"],
         )
     }
 
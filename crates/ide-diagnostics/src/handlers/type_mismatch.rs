@@ -732,7 +732,7 @@ fn test(_arg: &Bar) {}
 
     #[test]
     fn add_reference_needs_parentheses() {
-        check_fix(
+        check_fix_with_disabled(
             r#"
 //- minicore: add
 struct Foo;
@@ -758,6 +758,7 @@ fn main() {
 }
 fn test(_arg: &Foo) {}
             "#,
+            &["unused_variables"],
         );
     }
 
@@ -13,11 +13,22 @@ pub(crate) fn unimplemented_trait<'db>(
         "the trait bound `{}` is not satisfied",
         d.trait_predicate.display(ctx.db(), ctx.display_target),
     );
-    for parent_predicate in &d.parent_trait_predicates {
+    let mut required_predicate = &d.trait_predicate;
+    for parent in &d.parent_trait_predicates {
         message.push_str(&format!(
             "\nrequired by the bound `{}`",
-            parent_predicate.display(ctx.db(), ctx.display_target),
+            parent.predicate.display(ctx.db(), ctx.display_target),
         ));
+        if let Some(field) = parent.field {
+            let edition = ctx.display_target.edition;
+            message.push_str(&format!(
+                " because field `{}: {}` is not `{}`",
+                field.name(ctx.db()).display(ctx.db(), edition),
+                required_predicate.trait_ref().self_ty().display(ctx.db(), ctx.display_target),
+                parent.predicate.trait_ref().trait_().name(ctx.db()).display(ctx.db(), edition),
+            ));
+        }
+        required_predicate = &parent.predicate;
     }
     if !d.parent_trait_predicates.is_empty() {
         message.push('\n');
@@ -90,6 +101,38 @@ fn foo() {
         );
     }
 
+    #[test]
+    fn explains_auto_trait_through_fields() {
+        check_diagnostics(
+            r#"
+auto trait Send {}
+
+struct Rc<T>(T);
+impl<T> !Send for Rc<T> {}
+
+struct Foo {
+    bar: Rc<i32>,
+}
+enum Outer {
+    Empty,
+    Foo(Foo),
+}
+
+fn require_send<T: Send>(_: T) {}
+
+fn baz(foo: Foo, outer: Outer) {
+    require_send(foo);
+ // ^^^^^^^^^^^^ error: the trait bound `Rc<i32>: Send` is not satisfied
+            // | required by the bound `Foo: Send` because field `bar: Rc<i32>` is not `Send`
+    require_send(outer);
+ // ^^^^^^^^^^^^ error: the trait bound `Rc<i32>: Send` is not satisfied
+            // | required by the bound `Foo: Send` because field `bar: Rc<i32>` is not `Send`
+            // | required by the bound `Outer: Send` because field `0: Foo` is not `Send`
+}
+        "#,
+        );
+    }
+
     #[test]
     fn coroutine_non_held_types_with_auto_traits() {
         check_diagnostics(
@@ -0,0 +1,251 @@
+use hir::Name;
+use ide_db::{
+    FileRange, RootDatabase,
+    assists::{Assist, AssistId},
+    label::Label,
+    source_change::SourceChange,
+    text_edit::TextEdit,
+};
+use syntax::{AstNode, Edition, SyntaxNodePtr, TextRange, ast};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unused-variables
+//
+// This diagnostic is triggered when a local variable is not used.
+pub(crate) fn unused_variables(
+    ctx: &DiagnosticsContext<'_, '_>,
+    d: &hir::UnusedVariable<'_>,
+) -> Option<Diagnostic> {
+    let source = d.local.primary_source(ctx.sema.db);
+    let ast = source.source.as_ref().map(|it| SyntaxNodePtr::new(it.syntax()));
+    if ast.file_id.macro_file().is_some() {
+        // FIXME: Our infra can't handle allow from within macro expansions rn
+        return None;
+    }
+    let diagnostic_range = ctx.sema.diagnostics_display_range(ast);
+    // The range for the actual name. We don't want to replace the entire declaration, which
+    // would e.g. remove the `mut` or the subpattern.
+    let name_range =
+        source.name().map(|v| v.syntax().original_file_range_rooted(ctx.sema.db)).filter(|it| {
+            Some(it.file_id) == ast.file_id.file_id()
+                && diagnostic_range.range.contains_range(it.range)
+        });
+    // In a record pattern shorthand like `Foo { x }`, we have to keep the field name.
+    let is_shorthand_field = source
+        .as_ident_pat()
+        .and_then(|it| it.syntax().parent())
+        .and_then(ast::RecordPatField::cast)
+        .is_some_and(|it| it.field_name().is_some() && it.name_ref().is_none());
+    let var_name = d.local.name(ctx.sema.db);
+    Some(
+        Diagnostic::new_with_syntax_node_ptr(
+            ctx,
+            DiagnosticCode::RustcLint("unused_variables"),
+            "unused variable",
+            ast,
+        )
+        .with_fixes(name_range.and_then(|it| {
+            fixes(
+                ctx.sema.db,
+                var_name,
+                it.range,
+                diagnostic_range,
+                is_shorthand_field,
+                ctx.edition,
+            )
+        }))
+        .with_unused(true),
+    )
+}
+
+fn fixes(
+    db: &RootDatabase,
+    var_name: Name,
+    name_range: TextRange,
+    diagnostic_range: FileRange,
+    is_shorthand_field: bool,
+    edition: Edition,
+) -> Option<Vec<Assist>> {
+    let var_name = var_name.display(db, edition);
+    let replacement = if is_shorthand_field {
+        format!("{var_name}: _{var_name}")
+    } else {
+        format!("_{var_name}")
+    };
+    Some(vec![Assist {
+        id: AssistId::quick_fix("unscore_unused_variable_name"),
+        label: Label::new(format!("Rename unused {var_name} to _{var_name}")),
+        group: None,
+        target: diagnostic_range.range,
+        source_change: Some(SourceChange::from_text_edit(
+            diagnostic_range.file_id,
+            TextEdit::replace(name_range, replacement),
+        )),
+        command: None,
+    }])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unused_variables_simple() {
+        check_diagnostics(
+            r#"
+//- minicore: fn
+struct Foo { f1: i32, f2: i64 }
+
+fn f(kkk: i32) {}
+   //^^^ 💡 warn: unused variable
+fn main() {
+    let a = 2;
+      //^ 💡 warn: unused variable
+    let b = 5;
+    // note: `unused variable` implies `unused mut`, so we should not emit both at the same time.
+    let mut c = f(b);
+      //^^^^^ 💡 warn: unused variable
+    let (d, e) = (3, 5);
+       //^ 💡 warn: unused variable
+    let _ = e;
+    let f1 = 2;
+    let f2 = 5;
+    let f = Foo { f1, f2 };
+    match f {
+        Foo { f1, f2 } => {
+            //^^ 💡 warn: unused variable
+            _ = f2;
+        }
+    }
+    let g = false;
+    if g {}
+    let h: fn() -> i32 = || 2;
+    let i = h();
+      //^ 💡 warn: unused variable
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unused_self() {
+        check_diagnostics(
+            r#"
+struct S {
+}
+impl S {
+    fn owned_self(self, u: i32) {}
+                      //^ 💡 warn: unused variable
+    fn ref_self(&self, u: i32) {}
+                     //^ 💡 warn: unused variable
+    fn ref_mut_self(&mut self, u: i32) {}
+                             //^ 💡 warn: unused variable
+    fn owned_mut_self(mut self) {}
+                    //^^^^^^^^ 💡 warn: variable does not need to be mutable
+
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn allow_unused_variables_for_identifiers_starting_with_underline() {
+        check_diagnostics(
+            r#"
+fn main() {
+    let _x = 2;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn respect_lint_attributes_for_unused_variables() {
+        check_diagnostics(
+            r#"
+fn main() {
+    #[allow(unused_variables)]
+    let x = 2;
+}
+
+#[deny(unused)]
+fn main2() {
+    let x = 2;
+      //^ 💡 error: unused variable
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_unused_variable() {
+        check_fix(
+            r#"
+fn main() {
+    let x$0 = 2;
+}
+"#,
+            r#"
+fn main() {
+    let _x = 2;
+}
+"#,
+        );
+
+        check_fix(
+            r#"
+fn main() {
+    let ($0d, _e) = (3, 5);
+}
+"#,
+            r#"
+fn main() {
+    let (_d, _e) = (3, 5);
+}
+"#,
+        );
+
+        check_fix(
+            r#"
+struct Foo { f1: i32, f2: i64 }
+fn main() {
+    let f = Foo { f1: 0, f2: 0 };
+    match f {
+        Foo { f1$0, f2 } => {
+            _ = f2;
+        }
+    }
+}
+"#,
+            r#"
+struct Foo { f1: i32, f2: i64 }
+fn main() {
+    let f = Foo { f1: 0, f2: 0 };
+    match f {
+        Foo { f1: _f1, f2 } => {
+            _ = f2;
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_diagnostic_in_macro() {
+        check_diagnostics(
+            r#"
+macro_rules! my_macro {
+    () => {
+        let x = 3;
+    };
+}
+
+fn main() {
+    my_macro!();
+}
+"#,
+        );
+    }
+}
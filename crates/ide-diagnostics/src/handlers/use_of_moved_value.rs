@@ -0,0 +1,280 @@
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: use-of-moved-value
+//
+// This diagnostic is triggered when a value is used after it has been moved out.
+pub(crate) fn use_of_moved_value<'db>(
+    ctx: &DiagnosticsContext<'_, 'db>,
+    d: &hir::UseOfMovedValue<'db>,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    let name = name.display(ctx.sema.db, ctx.edition);
+    let message = if d.is_partial {
+        format!("use of partially moved value: `{name}`")
+    } else if d.is_borrow {
+        format!("borrow of moved value: `{name}`")
+    } else {
+        format!("use of moved value: `{name}`")
+    };
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0382"),
+        message,
+        d.span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics_with_disabled;
+
+    #[test]
+    fn use_after_move() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+
+fn consume(_: X) {}
+
+fn main() {
+    let x = X;
+    consume(x);
+    consume(x);
+          //^ error: use of moved value: `x`
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn borrow_after_move() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+
+fn main() {
+    let x = X;
+    let y = x;
+    let z = &x;
+          //^^ error: borrow of moved value: `x`
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn partial_move() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+struct Y(X, X);
+
+fn main() {
+    let y = Y(X, X);
+    let a = y.0;
+    let b = y.1;
+    let c = y;
+      //^ error: use of partially moved value: `y`
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn move_in_one_branch() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+
+fn consume(_: X) {}
+
+fn f(c: bool) {
+    let x = X;
+    if c {
+        consume(x);
+    }
+    consume(x);
+          //^ error: use of moved value: `x`
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn move_in_loop() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+
+fn consume(_: X) {}
+
+fn f() {
+    let x = X;
+    loop {
+        consume(x);
+              //^ error: use of moved value: `x`
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn reassign_after_move() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy
+struct X;
+
+fn consume(_: X) {}
+
+fn f(c: bool) {
+    let mut x = X;
+    consume(x);
+    x = X;
+    consume(x);
+    let mut y = (X, X);
+    consume(y.0);
+    y.0 = X;
+    let z = y;
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_copy_and_disjoint_fields() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy, derive, clone
+#[derive(Clone, Copy)]
+struct C;
+struct X;
+
+fn consume<T>(_: T) {}
+
+fn f(c: bool) {
+    let a = C;
+    consume(a);
+    consume(a);
+    let b = (X, X);
+    consume(b.0);
+    consume(b.1);
+    let r = &mut X;
+    consume(r);
+    let d = X;
+    if c {
+        consume(d);
+    } else {
+        consume(d);
+    }
+    let e = X;
+    let f = move || consume(e);
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_match_guards_and_constants() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: copy, derive, eq
+struct X;
+enum E { A(X), B(X), C }
+
+#[derive(PartialEq, Eq)]
+struct K(X);
+const K0: K = K(X);
+
+fn consume<T>(_: T) {}
+
+fn f(e: E, k: K, c: bool) {
+    match e {
+        E::A(x) if c => consume(x),
+        E::B(x) => consume(x),
+        _ => consume(e),
+    }
+    match k {
+        K0 => {}
+        _ => {}
+    }
+    consume(k);
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_move_in_diverging_branch() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option
+struct X;
+fn consume<T>(_: T) {}
+
+fn f(x: Option<X>, y: Option<u32>, c: bool) -> Option<u32> {
+    if c {
+        if let Some(x) = x {
+            consume(x);
+            return None;
+        }
+    }
+    let zip = (y, x);
+    None
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_argument_moved_by_later_argument() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option, fn
+struct S { o: Option<u32>, x: X }
+struct X;
+fn apply<F: FnOnce() -> S>(o: Option<u32>, f: F) {}
+
+fn f(s: S) {
+    apply(s.o, move || s);
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+
+    #[test]
+    fn no_false_positive_on_let_chains() {
+        check_diagnostics_with_disabled(
+            r#"
+//- minicore: option
+struct X;
+fn consume<T>(_: T) {}
+
+fn f(x: Option<X>, c: bool) {
+    if c && let Some(x) = x {
+        consume(x);
+    } else if let Some(x) = x {
+        consume(x);
+    }
+}
+"#,
+            &["unused_variables"],
+        );
+    }
+}
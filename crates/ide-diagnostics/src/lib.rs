@@ -36,6 +36,7 @@ mod handlers {
     pub(crate) mod cannot_be_dereferenced;
     pub(crate) mod cannot_implicitly_deref_trait_object;
     pub(crate) mod cannot_index_into;
+    pub(crate) mod conflicting_borrow;
     pub(crate) mod duplicate_field;
     pub(crate) mod elided_lifetimes_in_path;
     pub(crate) mod expected_array_or_slice_pat;
@@ -64,8 +65,10 @@ mod handlers {
     pub(crate) mod missing_lifetime;
     pub(crate) mod missing_match_arms;
     pub(crate) mod missing_unsafe;
+    pub(crate) mod moved_out_of_ref;
     pub(crate) mod mut_ref_in_imm_ref_pat;
     pub(crate) mod mutable_ref;
+    pub(crate) mod need_mut;
     pub(crate) mod no_such_field;
     pub(crate) mod non_exhaustive_let;
     pub(crate) mod non_exhaustive_record_expr;
@@ -102,11 +105,14 @@ mod handlers {
     pub(crate) mod unresolved_method;
     pub(crate) mod unresolved_module;
     pub(crate) mod unused_must_use;
+    pub(crate) mod unused_variables;
+    pub(crate) mod use_of_moved_value;
     pub(crate) mod yield_outside_coroutine;
 
     // The handlers below are unusual, the implement the diagnostics as well.
     pub(crate) mod field_shorthand;
     pub(crate) mod json_is_not_rust;
+    pub(crate) mod layout_budget;
     pub(crate) mod unlinked_file;
     pub(crate) mod useless_braces;
 }
@@ -114,6 +120,8 @@ mod handlers {
 #[cfg(test)]
 mod tests;
 
+pub use handlers::layout_budget::LayoutBudget;
+
 use std::sync::LazyLock;
 
 use hir::{Crate, DisplayTarget, InFile, MacroCallIdExt, Semantics, diagnostics::AnyDiagnostic};
@@ -285,6 +293,12 @@ pub struct DiagnosticsConfig {
     pub prefer_absolute: bool,
     pub term_search_fuel: u64,
     pub show_rename_conflicts: bool,
+    /// The attribute declaring the [`LayoutBudget`] of a type, like `layout_budget` for
+    /// `#[layout_budget(size = 16, padding = 0)]`.
+    pub layout_budget_attribute: Option<String>,
+    /// The [`LayoutBudget`]s of types by their path starting with their crate, like
+    /// `my_crate::net::Packet`.
+    pub layout_budgets: FxHashMap<String, LayoutBudget>,
 }
 
 impl DiagnosticsConfig {
@@ -313,6 +327,8 @@ impl DiagnosticsConfig {
             prefer_absolute: false,
             term_search_fuel: 400,
             show_rename_conflicts: true,
+            layout_budget_attribute: None,
+            layout_budgets: FxHashMap::default(),
         }
     }
 
@@ -400,6 +416,7 @@ pub fn semantic_diagnostics(
             config,
             edition,
         );
+        handlers::layout_budget::layout_budget(&sema, &mut res, editioned_file_id, &node, config);
     }
 
     let module = sema.file_to_module_def(file_id);
@@ -442,6 +459,7 @@ pub fn semantic_diagnostics(
             AnyDiagnostic::CannotImplicitlyDerefTraitObject(d) => handlers::cannot_implicitly_deref_trait_object::cannot_implicitly_deref_trait_object(&ctx, &d),
             AnyDiagnostic::CannotIndexInto(d) => handlers::cannot_index_into::cannot_index_into(&ctx, &d),
             AnyDiagnostic::CastToUnsized(d) => handlers::invalid_cast::cast_to_unsized(&ctx, &d),
+            AnyDiagnostic::ConflictingBorrow(d) => handlers::conflicting_borrow::conflicting_borrow(&ctx, &d),
             AnyDiagnostic::InferVarsNotAllowed(d) => handlers::infer_vars_not_allowed::infer_vars_not_allowed(&ctx, &d),
             AnyDiagnostic::ArrayPatternWithoutFixedLength(d) => {
                 handlers::array_pattern_without_fixed_length::array_pattern_without_fixed_length(
@@ -479,8 +497,13 @@ pub fn semantic_diagnostics(
             AnyDiagnostic::MissingFields(d) => handlers::missing_fields::missing_fields(&ctx, &d),
             AnyDiagnostic::MissingMatchArms(d) => handlers::missing_match_arms::missing_match_arms(&ctx, &d),
             AnyDiagnostic::MissingUnsafe(d) => handlers::missing_unsafe::missing_unsafe(&ctx, &d),
+            AnyDiagnostic::MovedOutOfRef(d) => handlers::moved_out_of_ref::moved_out_of_ref(&ctx, &d),
             AnyDiagnostic::MutRefInImmRefPat(d) => handlers::mut_ref_in_imm_ref_pat::mut_ref_in_imm_ref_pat(&ctx, &d),
             AnyDiagnostic::MutableRefBinding(d) => handlers::mutable_ref::mutable_ref_binding(&ctx, &d),
+            AnyDiagnostic::NeedMut(d) => match handlers::need_mut::need_mut(&ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
             AnyDiagnostic::NonExhaustiveLet(d) => handlers::non_exhaustive_let::non_exhaustive_let(&ctx, &d),
             AnyDiagnostic::NonExhaustiveRecordExpr(d) => {
                 handlers::non_exhaustive_record_expr::non_exhaustive_record_expr(&ctx, &d)
@@ -514,6 +537,15 @@ pub fn semantic_diagnostics(
             AnyDiagnostic::UnresolvedMethodCall(d) => handlers::unresolved_method::unresolved_method(&ctx, &d),
             AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(&ctx, &d),
             AnyDiagnostic::UnusedMustUse(d) => handlers::unused_must_use::unused_must_use(&ctx, &d),
+            AnyDiagnostic::UnusedMut(d) => match handlers::need_mut::unused_mut(&ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::UnusedVariable(d) => match handlers::unused_variables::unused_variables(&ctx, &d) {
+                Some(it) => it,
+                None => continue,
+            },
+            AnyDiagnostic::UseOfMovedValue(d) => handlers::use_of_moved_value::use_of_moved_value(&ctx, &d),
             AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(&ctx, &d),
             AnyDiagnostic::MismatchedTupleStructPatArgCount(d) => handlers::mismatched_arg_count::mismatched_tuple_struct_pat_arg_count(&ctx, &d),
             AnyDiagnostic::RemoveTrailingReturn(d) => match handlers::remove_trailing_return::remove_trailing_return(&ctx, &d) {
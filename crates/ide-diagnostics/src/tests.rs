@@ -66,7 +66,7 @@ pub(crate) fn check_fix_with_disabled(
 }
 
 #[track_caller]
-fn check_nth_fix_with_config(
+pub(crate) fn check_nth_fix_with_config(
     config: DiagnosticsConfig,
     nth: usize,
     #[rust_analyzer::rust_fixture] ra_fixture_before: &str,
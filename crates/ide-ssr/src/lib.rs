@@ -36,8 +36,18 @@
 // | Constraint    | Restricts placeholder |
 // |---------------|------------------------|
 // | kind(literal) | Is a literal (e.g. `42` or `"forty two"`) |
+// | type(T)       | Has the type `T`, where `_` matches any type (e.g. `Vec<_>`) |
 // | not(a)        | Negates the constraint `a` |
 //
+// A rule may end with a scope to only match code in part of the workspace:
+//
+// | Scope                      | Restricts matches to |
+// |----------------------------|----------------------|
+// | in crate <name>            | Code in the crate `<name>` |
+// | in mod <path>              | Code in the module `<path>` or its submodules |
+// | in impl <Trait> for <Type> | Code in impls of `<Trait>` for `<Type>`, or for any type with `*` |
+// | in impl <Type>             | Code in inherent impls of `<Type>` |
+//
 // Available via the command `rust-analyzer.ssr`.
 //
 // ```rust
@@ -4,7 +4,7 @@
 use crate::{
     SsrMatches,
     parsing::{Constraint, NodeKind, Placeholder, Var},
-    resolving::{ResolvedPattern, ResolvedRule, UfcsCallInfo},
+    resolving::{ResolvedPattern, ResolvedRule, TypePattern, UfcsCallInfo},
 };
 use hir::{FileRange, FindPathConfig, Semantics};
 use ide_db::{FxHashMap, base_db::all_crates};
@@ -325,6 +325,24 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
             Constraint::Kind(kind) => {
                 kind.matches(code)?;
             }
+            Constraint::Type(text) => {
+                let Some(pattern) = self.rule.pattern.constraint_types.get(text) else {
+                    fail_match!("Unresolved type constraint `{}`", text);
+                };
+                let ty = if let Some(expr) = ast::Expr::cast(code.clone()) {
+                    self.sema.type_of_expr(&expr).map(|it| it.original)
+                } else if let Some(pat) = ast::Pat::cast(code.clone()) {
+                    self.sema.type_of_pat(&pat).map(|it| it.original)
+                } else {
+                    None
+                };
+                let Some(ty) = ty else {
+                    fail_match!("Couldn't determine type of '{}'", code.text());
+                };
+                if !pattern.matches(self.sema.db, &ty) {
+                    fail_match!("Type of '{}' didn't match `{}`", code.text(), text);
+                }
+            }
             Constraint::Not(sub) => {
                 if self.check_constraint(sub, code).is_ok() {
                     fail_match!("Constraint {:?} failed for '{}'", constraint, code.text());
@@ -738,6 +756,37 @@ impl PlaceholderMatch {
     }
 }
 
+impl TypePattern {
+    fn matches(&self, db: &ide_db::RootDatabase, ty: &hir::Type<'_>) -> bool {
+        match self {
+            TypePattern::Any => true,
+            TypePattern::Adt(adt, args) => {
+                ty.as_adt_with_args().is_some_and(|(ty_adt, ty_args)| {
+                    // Trailing arguments may be left out to match their defaults too, e.g. `Vec<_>`
+                    // matches `Vec<T, A>` with any allocator.
+                    ty_adt == *adt
+                        && args.len() <= ty_args.len()
+                        && args.iter().zip(ty_args).all(|(arg, ty_arg)| match ty_arg {
+                            Some(ty_arg) => arg.matches(db, &ty_arg),
+                            None => matches!(arg, TypePattern::Any),
+                        })
+                })
+            }
+            TypePattern::Builtin(builtin) => ty.as_builtin() == Some(*builtin),
+            TypePattern::Ref(inner, mutability) => {
+                ty.as_reference().is_some_and(|(ty, m)| m == *mutability && inner.matches(db, &ty))
+            }
+            TypePattern::Slice(inner) => ty.as_slice().is_some_and(|ty| inner.matches(db, &ty)),
+            TypePattern::Tuple(fields) => {
+                let ty_fields = ty.tuple_fields(db);
+                ty.is_tuple()
+                    && fields.len() == ty_fields.len()
+                    && fields.iter().zip(&ty_fields).all(|(field, ty)| field.matches(db, ty))
+            }
+        }
+    }
+}
+
 impl NodeKind {
     fn matches(&self, node: &SyntaxNode) -> Result<(), MatchFailed> {
         let ok = match self {
@@ -16,6 +16,20 @@ pub(crate) struct ParsedRule {
     pub(crate) placeholders_by_stand_in: FxHashMap<SmolStr, Placeholder>,
     pub(crate) pattern: SyntaxNode,
     pub(crate) template: Option<SyntaxNode>,
+    pub(crate) scope: Option<RuleScope>,
+}
+
+/// Restricts where a rule applies, written as a trailing `in ...` clause. e.g. for
+/// `foo($a) ==>> bar($a) in mod a::b`, this would be `Module("a::b")`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RuleScope {
+    /// `in crate foo`
+    Crate(String),
+    /// `in mod a::b`
+    Module(String),
+    /// `in impl Trait for Type`, `in impl Trait for *` or `in impl Type`. A `self_ty` of `None`
+    /// stands for `*`.
+    Impl { trait_: Option<String>, self_ty: Option<String> },
 }
 
 #[derive(Debug)]
@@ -46,6 +60,8 @@ pub(crate) struct Var(pub(crate) String);
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum Constraint {
     Kind(NodeKind),
+    /// The type that the placeholder must have, as written in the rule.
+    Type(String),
     Not(Box<Constraint>),
 }
 
@@ -64,12 +80,14 @@ impl ParsedRule {
     fn new(
         pattern: &RawPattern,
         template: Option<&RawPattern>,
+        scope: Option<RuleScope>,
     ) -> Result<Vec<ParsedRule>, SsrError> {
         let raw_pattern = pattern.as_rust_code();
         let raw_template = template.map(|t| t.as_rust_code());
         let raw_template = raw_template.as_deref();
         let mut builder = RuleBuilder {
             placeholders_by_stand_in: pattern.placeholders_by_stand_in(),
+            scope,
             rules: Vec::new(),
         };
 
@@ -89,6 +107,7 @@ impl ParsedRule {
 
 struct RuleBuilder {
     placeholders_by_stand_in: FxHashMap<SmolStr, Placeholder>,
+    scope: Option<RuleScope>,
     rules: Vec<ParsedRule>,
 }
 
@@ -103,11 +122,13 @@ impl RuleBuilder {
                 placeholders_by_stand_in: self.placeholders_by_stand_in.clone(),
                 pattern,
                 template: Some(template),
+                scope: self.scope.clone(),
             }),
             (Ok(pattern), None) => self.rules.push(ParsedRule {
                 placeholders_by_stand_in: self.placeholders_by_stand_in.clone(),
                 pattern,
                 template: None,
+                scope: self.scope.clone(),
             }),
             _ => {}
         }
@@ -156,9 +177,10 @@ impl FromStr for SsrRule {
         if it.next().is_some() {
             return Err(SsrError("More than one delimiter found".into()));
         }
+        let (template, scope) = split_scope(&template)?;
         let raw_pattern = pattern.parse()?;
         let raw_template = template.parse()?;
-        let parsed_rules = ParsedRule::new(&raw_pattern, Some(&raw_template))?;
+        let parsed_rules = ParsedRule::new(&raw_pattern, Some(&raw_template), scope)?;
         let rule = SsrRule { pattern: raw_pattern, template: raw_template, parsed_rules };
         validate_rule(&rule)?;
         Ok(rule)
@@ -201,8 +223,9 @@ impl FromStr for SsrPattern {
     type Err = SsrError;
 
     fn from_str(pattern_str: &str) -> Result<SsrPattern, SsrError> {
+        let (pattern_str, scope) = split_scope(pattern_str)?;
         let raw_pattern = pattern_str.parse()?;
-        let parsed_rules = ParsedRule::new(&raw_pattern, None)?;
+        let parsed_rules = ParsedRule::new(&raw_pattern, None, scope)?;
         Ok(SsrPattern { parsed_rules })
     }
 }
@@ -228,6 +251,63 @@ fn parse_pattern(pattern_str: &str) -> Result<Vec<PatternElement>, SsrError> {
     Ok(res)
 }
 
+/// Splits a trailing scope clause like `in crate foo` off `text`. Neither `mod` nor `impl` can
+/// follow `in` in Rust code, whereas `in crate::foo` is an ordinary path, so only `in crate foo`
+/// without `::` is taken as a scope.
+fn split_scope(text: &str) -> Result<(String, Option<RuleScope>), SsrError> {
+    let tokens = tokenize(text)?;
+    let significant = |from: usize| {
+        tokens[from..].iter().filter(|t| t.kind != SyntaxKind::WHITESPACE).collect::<Vec<_>>()
+    };
+    let Some(start) = (0..tokens.len()).rev().find(|&i| {
+        tokens[i].kind == T![in]
+            && match significant(i + 1).as_slice() {
+                [first, ..] if matches!(first.kind, T![mod] | T![impl]) => true,
+                [first, rest @ ..] if first.kind == T![crate] => {
+                    !rest.is_empty()
+                        && rest.iter().all(|t| {
+                            t.text.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                        })
+                }
+                _ => false,
+            }
+    }) else {
+        return Ok((text.to_owned(), None));
+    };
+    let rest = significant(start + 1);
+    let join = |tokens: &[&Token]| tokens.iter().map(|t| t.text.as_str()).collect::<String>();
+    let scope = match rest.as_slice() {
+        [kw, name @ ..] if kw.kind == T![crate] => RuleScope::Crate(join(name)),
+        [kw, path @ ..] if kw.kind == T![mod] => {
+            if path.is_empty() {
+                bail!("Expected a module path after `in mod`");
+            }
+            RuleScope::Module(join(path))
+        }
+        [_impl, rest @ ..] => {
+            let (trait_, self_ty) = match rest.iter().position(|t| t.kind == T![for]) {
+                Some(idx) => (Some(join(&rest[..idx])), &rest[idx + 1..]),
+                None => (None, rest),
+            };
+            let self_ty = match self_ty {
+                [star] if star.kind == T![*] => None,
+                _ => Some(join(self_ty)),
+            };
+            match (&trait_, &self_ty) {
+                (Some(trait_), _) if trait_.is_empty() => bail!("Expected a trait after `in impl`"),
+                (None, None) => bail!("`in impl *` needs a trait, e.g. `in impl Trait for *`"),
+                (_, Some(self_ty)) if self_ty.is_empty() => {
+                    bail!("Expected a type in the `in impl` scope")
+                }
+                _ => RuleScope::Impl { trait_, self_ty },
+            }
+        }
+        [] => unreachable!(),
+    };
+    let text = tokens[..start].iter().map(|t| t.text.as_str()).collect::<String>();
+    Ok((text.trim().to_owned(), Some(scope)))
+}
+
 /// Checks for errors in a rule. e.g. the replace pattern referencing placeholders that the search
 /// pattern didn't define.
 fn validate_rule(rule: &SsrRule) -> Result<(), SsrError> {
@@ -326,6 +406,27 @@ fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint
             expect_token(tokens, ")")?;
             Ok(Constraint::Not(Box::new(sub)))
         }
+        "type" => {
+            expect_token(tokens, "(")?;
+            let mut ty = String::new();
+            let mut depth = 0;
+            loop {
+                let t = tokens.next().ok_or_else(|| {
+                    SsrError::new("Unexpected end of constraint while looking for a type")
+                })?;
+                match t.kind {
+                    T!['('] => depth += 1,
+                    T![')'] if depth == 0 => break,
+                    T![')'] => depth -= 1,
+                    _ => {}
+                }
+                ty.push_str(&t.text);
+            }
+            if ty.trim().is_empty() {
+                bail!("Expected a type in type constraint");
+            }
+            Ok(Constraint::Type(ty.trim().to_owned()))
+        }
         x => bail!("Unsupported constraint type '{}'", x),
     }
 }
@@ -403,4 +504,27 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn parse_scopes() {
+        fn scope(rule: &str) -> Option<RuleScope> {
+            split_scope(rule).unwrap().1
+        }
+        assert_eq!(scope("bar() in crate my-crate"), Some(RuleScope::Crate("my-crate".to_owned())));
+        assert_eq!(scope("bar() in mod a::b"), Some(RuleScope::Module("a::b".to_owned())));
+        assert_eq!(
+            scope("bar() in impl a::Trait for *"),
+            Some(RuleScope::Impl { trait_: Some("a::Trait".to_owned()), self_ty: None })
+        );
+        assert_eq!(
+            scope("bar() in impl Foo"),
+            Some(RuleScope::Impl { trait_: None, self_ty: Some("Foo".to_owned()) })
+        );
+        // Not scopes, but ordinary Rust code.
+        assert_eq!(scope("for x in crate::items() {}"), None);
+        assert_eq!(scope("for x in y {}"), None);
+        assert_eq!(split_scope("for x in y {} in mod m").unwrap().0, "for x in y {}");
+        assert!(split_scope("bar() in mod").is_err());
+        assert!(split_scope("bar() in impl *").is_err());
+    }
 }
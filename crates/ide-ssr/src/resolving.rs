@@ -8,7 +8,12 @@ use syntax::{
     ast::{self, HasGenericArgs},
 };
 
-use crate::{SsrError, errors::error, parsing};
+use crate::{
+    SsrError,
+    errors::{bail, error},
+    fragments,
+    parsing::{self, Constraint, RuleScope},
+};
 
 pub(crate) struct ResolutionScope<'db> {
     scope: hir::SemanticsScope<'db>,
@@ -18,9 +23,30 @@ pub(crate) struct ResolutionScope<'db> {
 pub(crate) struct ResolvedRule<'db> {
     pub(crate) pattern: ResolvedPattern<'db>,
     pub(crate) template: Option<ResolvedPattern<'db>>,
+    pub(crate) scope: Option<ResolvedScope>,
     pub(crate) index: usize,
 }
 
+/// Where a rule is permitted to match.
+pub(crate) enum ResolvedScope {
+    /// All crates with the given name.
+    Crates(Vec<hir::Crate>),
+    /// The module and all of its descendants.
+    Module(hir::Module),
+    /// Impls of `trait_`, or inherent impls if `None`, for `self_ty`, or any type if `None`.
+    Impl { trait_: Option<hir::Trait>, self_ty: Option<hir::Adt> },
+}
+
+/// The type required by a `type(...)` constraint, where `_` matches any type.
+pub(crate) enum TypePattern {
+    Any,
+    Adt(hir::Adt, Vec<TypePattern>),
+    Builtin(hir::BuiltinType),
+    Ref(Box<TypePattern>, hir::Mutability),
+    Slice(Box<TypePattern>),
+    Tuple(Vec<TypePattern>),
+}
+
 pub(crate) struct ResolvedPattern<'db> {
     pub(crate) placeholders_by_stand_in: FxHashMap<SmolStr, parsing::Placeholder>,
     pub(crate) node: SyntaxNode,
@@ -28,6 +54,8 @@ pub(crate) struct ResolvedPattern<'db> {
     pub(crate) resolved_paths: FxHashMap<SyntaxNode, ResolvedPath<'db>>,
     pub(crate) ufcs_function_calls: FxHashMap<SyntaxNode, UfcsCallInfo<'db>>,
     pub(crate) contains_self: bool,
+    // Types of `type(...)` constraints on placeholders, by their text.
+    pub(crate) constraint_types: FxHashMap<String, TypePattern>,
 }
 
 pub(crate) struct ResolvedPath<'db> {
@@ -57,9 +85,14 @@ impl<'db> ResolvedRule<'db> {
                 Some(template) => Some(resolver.resolve_pattern_tree(template)?),
                 None => None,
             };
+            let scope = match &rule.scope {
+                Some(scope) => Some(resolver.resolve_scope(scope)?),
+                None => None,
+            };
             Ok(ResolvedRule {
                 pattern: resolver.resolve_pattern_tree(rule.pattern)?,
                 template: resolved_template,
+                scope,
                 index,
             })
         })
@@ -107,15 +140,156 @@ impl<'db> Resolver<'_, 'db> {
                 SyntaxElement::Token(t) => t.kind() == T![self],
                 _ => false,
             });
+        let mut constraint_types = FxHashMap::default();
+        for placeholder in self.placeholders_by_stand_in.values() {
+            for constraint in &placeholder.constraints {
+                self.resolve_constraint_types(constraint, &mut constraint_types)?;
+            }
+        }
         Ok(ResolvedPattern {
             node: pattern,
             resolved_paths,
             placeholders_by_stand_in: self.placeholders_by_stand_in.clone(),
             ufcs_function_calls,
             contains_self,
+            constraint_types,
+        })
+    }
+
+    fn resolve_constraint_types(
+        &self,
+        constraint: &Constraint,
+        out: &mut FxHashMap<String, TypePattern>,
+    ) -> Result<(), SsrError> {
+        use syntax::ast::AstNode;
+        match constraint {
+            Constraint::Kind(_) => {}
+            Constraint::Type(text) => {
+                if !out.contains_key(text) {
+                    let ty = fragments::ty(text)
+                        .ok()
+                        .and_then(ast::Type::cast)
+                        .ok_or_else(|| error!("Invalid type in constraint: `{}`", text))?;
+                    out.insert(text.clone(), self.resolve_type_pattern(&ty)?);
+                }
+            }
+            Constraint::Not(inner) => self.resolve_constraint_types(inner, out)?,
+        }
+        Ok(())
+    }
+
+    fn resolve_type_pattern(&self, ty: &ast::Type) -> Result<TypePattern, SsrError> {
+        use syntax::ast::AstNode;
+        let resolve_all = |tys: &mut dyn Iterator<Item = ast::Type>| {
+            tys.map(|ty| self.resolve_type_pattern(&ty)).collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match ty {
+            ast::Type::InferType(_) => TypePattern::Any,
+            ast::Type::ParenType(it) => {
+                self.resolve_type_pattern(&it.ty().ok_or_else(|| {
+                    error!("Invalid type in constraint: `{}`", ty.syntax().text())
+                })?)?
+            }
+            ast::Type::RefType(it) => {
+                let inner = it.ty().ok_or_else(|| error!("Missing type after `&`"))?;
+                let mutability = hir::Mutability::from_mutable(it.mut_token().is_some());
+                TypePattern::Ref(Box::new(self.resolve_type_pattern(&inner)?), mutability)
+            }
+            ast::Type::SliceType(it) => {
+                let inner = it.ty().ok_or_else(|| error!("Missing slice element type"))?;
+                TypePattern::Slice(Box::new(self.resolve_type_pattern(&inner)?))
+            }
+            ast::Type::TupleType(it) => TypePattern::Tuple(resolve_all(&mut it.fields())?),
+            ast::Type::PathType(it) => {
+                let path = it.path().ok_or_else(|| error!("Missing path in type"))?;
+                let mut plain_path = path.clone();
+                let generic_args = path.segment().and_then(|it| it.generic_arg_list());
+                if let Some(args) = &generic_args {
+                    // Resolve the path without its generic arguments.
+                    let text = path.syntax().text().to_string();
+                    let args_start =
+                        args.syntax().text_range().start() - path.syntax().text_range().start();
+                    plain_path = fragments::ty(&text[..usize::from(args_start)])
+                        .ok()
+                        .and_then(ast::PathType::cast)
+                        .and_then(|it| it.path())
+                        .ok_or_else(|| error!("Invalid type in constraint: `{}`", text))?;
+                }
+                let args = generic_args
+                    .into_iter()
+                    .flat_map(|args| args.generic_args())
+                    .filter_map(|arg| match arg {
+                        ast::GenericArg::TypeArg(arg) => arg.ty(),
+                        _ => None,
+                    });
+                match self.resolution_scope.resolve_path(&plain_path) {
+                    Some(hir::PathResolution::Def(hir::ModuleDef::Adt(adt))) => {
+                        TypePattern::Adt(adt, resolve_all(&mut args.into_iter())?)
+                    }
+                    Some(hir::PathResolution::Def(hir::ModuleDef::BuiltinType(builtin))) => {
+                        TypePattern::Builtin(builtin)
+                    }
+                    _ => bail!("Failed to resolve type `{}`", path.syntax().text()),
+                }
+            }
+            _ => bail!("Unsupported type in constraint: `{}`", ty.syntax().text()),
         })
     }
 
+    fn resolve_scope(&self, scope: &RuleScope) -> Result<ResolvedScope, SsrError> {
+        let db = self.resolution_scope.scope.db;
+        Ok(match scope {
+            RuleScope::Crate(name) => {
+                let crates = hir::Crate::all(db)
+                    .into_iter()
+                    .filter(|krate| {
+                        krate.display_name(db).is_some_and(|it| {
+                            it.canonical_name().as_str() == name
+                                || it.crate_name().symbol().as_str() == name
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if crates.is_empty() {
+                    bail!("Unknown crate `{}`", name);
+                }
+                ResolvedScope::Crates(crates)
+            }
+            RuleScope::Module(path) => match self.resolve_scope_path(path)? {
+                hir::PathResolution::Def(hir::ModuleDef::Module(module)) => {
+                    ResolvedScope::Module(module)
+                }
+                _ => bail!("`{}` is not a module", path),
+            },
+            RuleScope::Impl { trait_, self_ty } => {
+                let trait_ = match trait_ {
+                    Some(path) => match self.resolve_scope_path(path)? {
+                        hir::PathResolution::Def(hir::ModuleDef::Trait(it)) => Some(it),
+                        _ => bail!("`{}` is not a trait", path),
+                    },
+                    None => None,
+                };
+                let self_ty = match self_ty {
+                    Some(path) => match self.resolve_scope_path(path)? {
+                        hir::PathResolution::Def(hir::ModuleDef::Adt(it)) => Some(it),
+                        _ => bail!("`{}` is not a struct, enum or union", path),
+                    },
+                    None => None,
+                };
+                ResolvedScope::Impl { trait_, self_ty }
+            }
+        })
+    }
+
+    fn resolve_scope_path(&self, path: &str) -> Result<hir::PathResolution<'db>, SsrError> {
+        use syntax::ast::AstNode;
+        fragments::ty(path)
+            .ok()
+            .and_then(ast::PathType::cast)
+            .and_then(|it| it.path())
+            .and_then(|it| self.resolution_scope.resolve_path(&it))
+            .ok_or_else(|| error!("Failed to resolve path `{}`", path))
+    }
+
     fn resolve(
         &self,
         node: SyntaxNode,
@@ -2,7 +2,7 @@
 
 use crate::{
     Match, MatchFinder, matching,
-    resolving::{ResolvedPath, ResolvedPattern, ResolvedRule},
+    resolving::{ResolvedPath, ResolvedPattern, ResolvedRule, ResolvedScope},
 };
 use hir::FileRange;
 use ide_db::{
@@ -209,11 +209,39 @@ impl<'db> MatchFinder<'db> {
             cov_mark::hit!(replace_nonpath_within_selection);
             return;
         }
+        if !self.within_rule_scope(rule, code) {
+            return;
+        }
         if let Ok(m) = matching::get_match(false, rule, code, restrict_range, &self.sema) {
             matches_out.push(m);
         }
     }
 
+    /// Returns whether `code` is within the scope the rule is restricted to, if any.
+    fn within_rule_scope(&self, rule: &ResolvedRule<'db>, code: &SyntaxNode) -> bool {
+        let Some(scope) = &rule.scope else { return true };
+        let db = self.sema.db;
+        match scope {
+            ResolvedScope::Crates(crates) => self
+                .sema
+                .scope(code)
+                .is_some_and(|scope| crates.contains(&scope.module().krate(db))),
+            ResolvedScope::Module(module) => self
+                .sema
+                .scope(code)
+                .is_some_and(|scope| scope.module().path_to_root(db).contains(module)),
+            ResolvedScope::Impl { trait_, self_ty } => self
+                .sema
+                .ancestors_with_macros(code.clone())
+                .filter_map(ast::Impl::cast)
+                .filter_map(|impl_| self.sema.to_def(&impl_))
+                .any(|impl_| {
+                    impl_.trait_(db) == *trait_
+                        && self_ty.is_none_or(|adt| impl_.self_ty(db).as_adt() == Some(adt))
+                }),
+        }
+    }
+
     /// Returns whether `code` is within one of our range restrictions if we have any. No range
     /// restrictions is considered unrestricted and always returns true.
     fn within_range_restrictions(&self, code: &SyntaxNode) -> bool {
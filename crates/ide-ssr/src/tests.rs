@@ -591,6 +591,103 @@ fn literal_constraint() {
     assert_matches("Some(${a:not(kind(literal))})", code, &["Some(x1)", "Some(40 + 2)"]);
 }
 
+#[test]
+fn match_type_constraint() {
+    let code = r#"
+        struct Vec<T>(T);
+        struct Wrapper;
+        fn g<T>(_: T) {}
+        fn f(v: Vec<i32>, w: Vec<bool>, n: i32, r: &mut Wrapper, t: (i32, Wrapper)) {
+            g(v); g(w); g(n); g(r); g(t);
+        }
+        "#;
+    assert_matches("g(${a:type(Vec<_>)})", code, &["g(v)", "g(w)"]);
+    assert_matches("g(${a:type(Vec<i32>)})", code, &["g(v)"]);
+    assert_matches("g(${a:type(i32)})", code, &["g(n)"]);
+    assert_matches("g(${a:type(&mut _)})", code, &["g(r)"]);
+    assert_no_match("g(${a:type(&Wrapper)})", code);
+    assert_matches("g(${a:type((_, Wrapper))})", code, &["g(t)"]);
+    assert_matches("g(${a:not(type(Vec<_>))})", code, &["g(n)", "g(r)", "g(t)"]);
+}
+
+#[test]
+fn parser_invalid_type_constraint() {
+    let (db, position, _) = single_file("fn f() {}");
+    hir::attach_db(&db, || {
+        let position = ide_db::FilePosition {
+            file_id: position.file_id.file_id(&db),
+            offset: position.offset,
+        };
+        let mut match_finder = MatchFinder::in_context(&db, position, vec![]).unwrap();
+        let err =
+            match_finder.add_search_pattern("f(${a:type(Missing)})".parse().unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "Parse error: Failed to resolve type `Missing`");
+    });
+}
+
+#[test]
+fn match_in_module_scope() {
+    let code = r#"
+        fn foo(_: i32) {}
+        mod a {
+            pub mod b { fn f() { super::super::foo(1); } }
+            fn f() { super::foo(2); }
+        }
+        fn f() { foo(3); }
+        "#;
+    assert_matches("foo($x) in mod a::b", code, &["super::super::foo(1)"]);
+    assert_matches("foo($x) in mod a", code, &["super::super::foo(1)", "super::foo(2)"]);
+}
+
+#[test]
+fn match_in_impl_scope() {
+    let code = r#"
+        fn foo(_: i32) {}
+        trait T { fn m(&self); }
+        struct S;
+        struct U;
+        impl T for S { fn m(&self) { foo(1); } }
+        impl T for U { fn m(&self) { foo(2); } }
+        impl S { fn n(&self) { foo(3); } }
+        "#;
+    assert_matches("foo($x) in impl T for *", code, &["foo(1)", "foo(2)"]);
+    assert_matches("foo($x) in impl T for U", code, &["foo(2)"]);
+    assert_matches("foo($x) in impl S", code, &["foo(3)"]);
+}
+
+#[test]
+fn match_in_crate_scope() {
+    let code = r#"
+//- /lib.rs crate:dep
+pub fn foo(_: i32) {}
+fn f() { foo(1); }
+//- /main.rs crate:main deps:dep
+$0use dep::foo;
+fn g() { foo(2); }
+"#;
+    assert_matches("dep::foo($x) in crate main", code, &["foo(2)"]);
+    assert_matches("dep::foo($x) in crate dep", code, &["foo(1)"]);
+}
+
+#[test]
+fn replace_in_module_scope() {
+    assert_ssr_transform(
+        "foo($x) ==>> bar($x) in mod a",
+        r#"
+        fn foo(_: i32) {}
+        fn bar(_: i32) {}
+        mod a { fn f() { super::foo(1); } }
+        fn f() { foo(2); }
+        "#,
+        expect![[r#"
+            fn foo(_: i32) {}
+            fn bar(_: i32) {}
+            mod a { fn f() { crate::bar(1); } }
+            fn f() { foo(2); }
+        "#]],
+    );
+}
+
 #[test]
 fn match_reordered_struct_instantiation() {
     assert_matches(
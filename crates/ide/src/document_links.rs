@@ -0,0 +1,258 @@
+//! Computes clickable links to files and URLs in a document.
+
+use hir::Semantics;
+use ide_db::{
+    FileId, RootDatabase,
+    base_db::{AnchoredPath, SourceDatabase},
+};
+use syntax::{
+    AstNode, AstToken, NodeOrToken, TextRange, TextSize,
+    ast::{self, HasAttrs, IsString},
+    match_ast,
+};
+
+/// A link from a range in a document to another file or a web page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentLink {
+    pub range: TextRange,
+    pub target: DocumentLinkTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentLinkTarget {
+    File(FileId),
+    Url(String),
+}
+
+const INCLUDE_MACROS: &[&str] = &["include", "include_str", "include_bytes"];
+
+// Feature: Document Links
+//
+// Makes the paths in `#[path = "..."]` attributes and in the arguments of `include!`,
+// `include_str!` and `include_bytes!` clickable, as well as URLs in doc comments and
+// `#[doc = "..."]` attributes.
+pub(crate) fn document_links(db: &RootDatabase, file_id: FileId) -> Vec<DocumentLink> {
+    let sema = Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+
+    let mut res = Vec::new();
+    for element in file.syntax().descendants_with_tokens() {
+        let node = match element {
+            NodeOrToken::Token(token) => {
+                if let Some(comment) = ast::Comment::cast(token)
+                    && let Some((text, offset)) = comment.doc_comment()
+                {
+                    let start = comment.syntax().text_range().start() + offset;
+                    res.extend(urls(text, start));
+                }
+                continue;
+            }
+            NodeOrToken::Node(node) => node,
+        };
+        match_ast! {
+            match node {
+                ast::Module(module) => {
+                    if let Some(link) = module_path_link(&sema, file_id, &module) {
+                        res.push(link);
+                    }
+                },
+                ast::MacroCall(call) => {
+                    if let Some(link) = include_link(db, file_id, &call) {
+                        res.push(link);
+                    }
+                },
+                ast::Attr(attr) => {
+                    if let Some(doc) = key_value_string(&attr, "doc")
+                        && let Some(range) = doc.text_range_between_quotes()
+                    {
+                        let text = &doc.text()[range - doc.syntax().text_range().start()];
+                        res.extend(urls(text, range.start()));
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    res
+}
+
+fn module_path_link(
+    sema: &Semantics<'_, RootDatabase>,
+    file_id: FileId,
+    module: &ast::Module,
+) -> Option<DocumentLink> {
+    if module.item_list().is_some() {
+        return None;
+    }
+    let path = module.attrs().find_map(|attr| key_value_string(&attr, "path"))?;
+    let range = path.text_range_between_quotes()?;
+    let target = match sema.to_def(module).and_then(|it| it.as_source_file_id(sema.db)) {
+        Some(it) => it.file_id(sema.db),
+        // The module is not part of the crate graph, fall back to resolving the path manually.
+        None => {
+            sema.db.resolve_path(AnchoredPath { anchor: file_id, path: &path.value().ok()? })?
+        }
+    };
+    Some(DocumentLink { range, target: DocumentLinkTarget::File(target) })
+}
+
+fn include_link(db: &RootDatabase, file_id: FileId, call: &ast::MacroCall) -> Option<DocumentLink> {
+    let name = call.path()?.segment()?.name_ref()?;
+    if !INCLUDE_MACROS.iter().any(|it| name.text() == *it) {
+        return None;
+    }
+    let arg = call
+        .token_tree()?
+        .token_trees_and_tokens()
+        .filter_map(NodeOrToken::into_token)
+        .find_map(ast::String::cast)?;
+    let range = arg.text_range_between_quotes()?;
+    let target = db.resolve_path(AnchoredPath { anchor: file_id, path: &arg.value().ok()? })?;
+    Some(DocumentLink { range, target: DocumentLinkTarget::File(target) })
+}
+
+/// Returns the string literal of an attribute like `#[key = "..."]`.
+fn key_value_string(attr: &ast::Attr, key: &str) -> Option<ast::String> {
+    let ast::Meta::KeyValueMeta(meta) = attr.meta()? else { return None };
+    if meta.path()?.as_single_name_ref()?.text() != key {
+        return None;
+    }
+    match meta.expr()? {
+        ast::Expr::Literal(lit) => ast::String::cast(lit.token()),
+        _ => None,
+    }
+}
+
+/// Finds the `http://` and `https://` URLs in `text`, which starts at `offset` in the file.
+fn urls(text: &str, offset: TextSize) -> impl Iterator<Item = DocumentLink> + '_ {
+    let mut rest = 0;
+    std::iter::from_fn(move || {
+        loop {
+            let start = rest
+                + ["https://", "http://"]
+                    .iter()
+                    .filter_map(|scheme| text[rest..].find(scheme))
+                    .min()?;
+            let len = text[start..]
+                .find(|c: char| c.is_whitespace() || "<>()[]{}\"'`".contains(c))
+                .unwrap_or(text.len() - start);
+            let url = text[start..start + len].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            rest = start + len;
+            if url.ends_with("://") {
+                continue;
+            }
+            let range = TextRange::at(offset + TextSize::of(&text[..start]), TextSize::of(url));
+            return Some(DocumentLink { range, target: DocumentLinkTarget::Url(url.to_owned()) });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::FileRange;
+
+    use crate::{DocumentLinkTarget, fixture};
+
+    #[track_caller]
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        let (analysis, position, expected) = fixture::annotations(ra_fixture);
+        let links = analysis.document_links(position.file_id).unwrap();
+        let actual = links
+            .into_iter()
+            .map(|link| {
+                let target = match link.target {
+                    DocumentLinkTarget::File(file_id) => {
+                        format!("file {}", analysis.file_text(file_id).unwrap().trim())
+                    }
+                    DocumentLinkTarget::Url(url) => format!("url {url}"),
+                };
+                (FileRange { file_id: position.file_id, range: link.range }, target)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn path_attribute() {
+        check(
+            r#"
+//- /main.rs
+$0#[path = "other/foo.rs"]
+        //^^^^^^^^^^^^ file // foo
+mod foo;
+//- /other/foo.rs
+// foo
+"#,
+        );
+    }
+
+    #[test]
+    fn include_macros() {
+        check(
+            r#"
+//- /main.rs
+$0
+const A: &str = include_str!("a.txt");
+                            //^^^^^ file a
+const B: &[u8] = include_bytes!("b.bin");
+                               //^^^^^ file b
+include!("gen.rs");
+        //^^^^^^ file // gen
+const C: &str = include_str!("missing.txt");
+//- /a.txt
+a
+//- /b.bin
+b
+//- /gen.rs
+// gen
+"#,
+        );
+    }
+
+    #[test]
+    fn doc_include() {
+        check(
+            r#"
+//- /lib.rs
+$0#![doc = include_str!("README.md")]
+                     //^^^^^^^^^ file readme
+//- /README.md
+readme
+"#,
+        );
+    }
+
+    #[test]
+    fn urls_in_docs() {
+        let (analysis, file_id) = fixture::file(
+            r#"
+/// See https://example.com/docs, and <http://example.org/a(b)>.
+#[doc = "More at https://rust-lang.org."]
+fn f() {
+    // https://not-a-doc-comment.com
+}
+/** Block https://block.example */
+struct S;
+"#,
+        );
+        let text = analysis.file_text(file_id).unwrap();
+        let links = analysis.document_links(file_id).unwrap();
+        let actual = links
+            .into_iter()
+            .map(|link| {
+                let DocumentLinkTarget::Url(url) = link.target else { panic!("expected a url") };
+                assert_eq!(&text[link.range], url);
+                url
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            [
+                "https://example.com/docs",
+                "http://example.org/a",
+                "https://rust-lang.org",
+                "https://block.example",
+            ]
+        );
+    }
+}
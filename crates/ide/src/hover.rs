@@ -36,6 +36,7 @@ use crate::{
 pub struct HoverConfig<'a> {
     pub links_in_hover: bool,
     pub memory_layout: Option<MemoryLayoutHoverConfig>,
+    pub normalized_type: Option<NormalizedTypeHoverConfig>,
     pub documentation: bool,
     pub keywords: bool,
     pub format: HoverDocFormat,
@@ -54,6 +55,12 @@ pub enum SubstTyLen {
     Hide,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NormalizedTypeHoverConfig {
+    /// How many levels of type aliases to expand in the type as written.
+    pub alias_expansion_depth: usize,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct MemoryLayoutHoverConfig {
     pub size: Option<MemoryLayoutHoverRenderKind>,
@@ -480,6 +487,7 @@ pub(crate) fn hover_for_definition(
         _ => None,
     };
     let notable_traits = def_ty.map(|ty| notable_traits(db, &ty)).unwrap_or_default();
+    let normalized_type = render::normalized_type(sema, config, def, display_target);
     let subst_types = subst.map(|subst| subst.types(db));
     let render_private_fields = sema.scope(scope_node).is_some_and(|scope| {
         def.krate(db)
@@ -491,6 +499,7 @@ pub(crate) fn hover_for_definition(
         def,
         famous_defs.as_ref(),
         &notable_traits,
+        normalized_type,
         macro_arm,
         render_extras,
         render_private_fields,
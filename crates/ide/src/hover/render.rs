@@ -8,7 +8,7 @@ use hir::{
     MethodViolationCode, Name, Semantics, Symbol, Trait, Type, TypeInfo, Variant,
 };
 use ide_db::{
-    RootDatabase,
+    FxHashMap, FxHashSet, RootDatabase,
     defs::{Definition, find_std_module},
     documentation::{Documentation, HasDocs},
     famous_defs::FamousDefs,
@@ -22,11 +22,15 @@ use rustc_apfloat::{
 };
 use span::{Edition, TextSize};
 use stdx::format_to;
-use syntax::{AstNode, AstToken, Direction, SyntaxToken, T, algo, ast, match_ast};
+use syntax::{
+    AstNode, AstToken, Direction, NodeOrToken, SyntaxNode, SyntaxToken, T, algo,
+    ast::{self, HasGenericArgs},
+    match_ast,
+};
 
 use crate::{
     HoverAction, HoverConfig, HoverResult, Markup, MemoryLayoutHoverConfig,
-    MemoryLayoutHoverRenderKind,
+    MemoryLayoutHoverRenderKind, NormalizedTypeHoverConfig,
     doc_links::{remove_links, rewrite_links},
     hover::{SubstTyLen, notable_traits, walk_and_push_ty},
     interpret::render_const_eval_error,
@@ -448,6 +452,7 @@ pub(super) fn definition(
     def: Definition<'_>,
     famous_defs: Option<&FamousDefs<'_, '_>>,
     notable_traits: &[(Trait, Vec<(Option<Type<'_>>, Name)>)],
+    normalized_type: Option<String>,
     macro_arm: Option<u32>,
     render_extras: bool,
     render_private_fields: bool,
@@ -768,6 +773,10 @@ pub(super) fn definition(
             extra.push_str("\n___\n");
             extra.push_str(&notable_traits);
         }
+        if let Some(normalized_type) = normalized_type {
+            extra.push_str("\n___\n");
+            extra.push_str(&normalized_type);
+        }
         if let Some(variance_info) = variance_info() {
             extra.push_str("\n___\n");
             extra.push_str(&variance_info);
@@ -999,6 +1008,13 @@ fn type_info(
             desc.push_str("\n---\n");
             desc.push_str(&extra);
         };
+        if config.normalized_type.is_some() {
+            let normalized = original.normalize(db);
+            if normalized != original {
+                desc.push_str("\n---\n");
+                format_to!(desc, "Normalized type: `{}`", normalized.display(db, display_target));
+            }
+        }
         desc.into()
     };
     if let Some(actions) = HoverAction::goto_type_from_targets(sema, targets, edition) {
@@ -1142,6 +1158,223 @@ fn markup(
     }
 }
 
+/// Renders the type of definitions whose type is written out in the source, or the signature of
+/// functions, with its type aliases expanded and its associated types normalized, if that differs
+/// from the written type.
+pub(super) fn normalized_type(
+    sema: &Semantics<'_, RootDatabase>,
+    config: &HoverConfig<'_>,
+    def: Definition<'_>,
+    display_target: DisplayTarget,
+) -> Option<String> {
+    let config = config.normalized_type?;
+    let db = sema.db;
+    let (written, ty) = match def {
+        Definition::Function(it) => {
+            return normalized_signature(sema, config, it, display_target);
+        }
+        // Locals are shown with their inferred type, which has its aliases expanded already.
+        Definition::Local(it) => {
+            let ty = it.ty(db);
+            let normalized = ty.normalize(db);
+            return (normalized != ty)
+                .then(|| format!("Normalized type: `{}`", normalized.display(db, display_target)));
+        }
+        Definition::TypeAlias(it) => (sema.source(it)?.value.ty()?, it.ty(db)),
+        Definition::Field(it) => {
+            let written = match sema.source(it)?.value {
+                hir::FieldSource::Named(it) => it.ty()?,
+                hir::FieldSource::Pos(it) => it.ty()?,
+            };
+            (written, it.ty(db))
+        }
+        Definition::Const(it) => (sema.source(it)?.value.ty()?, it.ty(db)),
+        Definition::Static(it) => (sema.source(it)?.value.ty()?, it.ty(db)),
+        _ => return None,
+    };
+
+    let normalized = ty.normalize(db);
+    render_normalized(
+        config,
+        "type",
+        &written.syntax().text().to_string(),
+        |depth, mentions_alias| {
+            expand_aliases(
+                sema,
+                written.syntax(),
+                depth,
+                &FxHashMap::default(),
+                &mut FxHashSet::default(),
+                mentions_alias,
+            )
+        },
+        normalized.display(db, display_target).to_string(),
+        normalized != ty,
+    )
+}
+
+/// Renders the signature of `func` with the type aliases of its parameter and return types
+/// expanded and their associated types normalized.
+fn normalized_signature(
+    sema: &Semantics<'_, RootDatabase>,
+    config: NormalizedTypeHoverConfig,
+    func: hir::Function,
+    display_target: DisplayTarget,
+) -> Option<String> {
+    let db = sema.db;
+    let source = sema.source(func)?.value;
+    let param_list = source.param_list()?;
+    let ret_type = source.ret_type().and_then(|it| it.ty());
+    let name = func.name(db);
+    let name = name.display(db, display_target.edition);
+
+    let written = match &ret_type {
+        Some(ret) => format!("fn {name}{param_list} -> {ret}"),
+        None => format!("fn {name}{param_list}"),
+    };
+    let expand = |depth, mentions_alias: &mut bool| {
+        let mut expand = |node: &SyntaxNode| {
+            expand_aliases(
+                sema,
+                node,
+                depth,
+                &FxHashMap::default(),
+                &mut FxHashSet::default(),
+                mentions_alias,
+            )
+        };
+        let params = expand(param_list.syntax());
+        match &ret_type {
+            Some(ret) => format!("fn {name}{params} -> {}", expand(ret.syntax())),
+            None => format!("fn {name}{params}"),
+        }
+    };
+
+    let mut normalized_changed = false;
+    let mut normalize = |ty: &Type<'_>| {
+        let normalized = ty.normalize(db);
+        normalized_changed |= normalized != *ty;
+        normalized.display(db, display_target).to_string()
+    };
+    let self_param = param_list.self_param().map(|it| it.to_string());
+    let params = param_list.params().zip(func.params_without_self(db)).map(|(param, ty)| {
+        let ty = normalize(ty.ty());
+        match param.pat() {
+            Some(pat) => format!("{pat}: {ty}"),
+            None => ty,
+        }
+    });
+    let params = self_param.into_iter().chain(params).collect::<Vec<_>>().join(", ");
+    let normalized = match &ret_type {
+        Some(_) => format!("fn {name}({params}) -> {}", normalize(&func.ret_type(db))),
+        None => format!("fn {name}({params})"),
+    };
+
+    render_normalized(config, "signature", &written, expand, normalized, normalized_changed)
+}
+
+/// Renders the "Expanded" and "Normalized" lines for the `written` text, where `expand` renders
+/// it with aliases expanded the given number of levels deep, recording whether it mentions any.
+fn render_normalized(
+    config: NormalizedTypeHoverConfig,
+    what: &str,
+    written: &str,
+    expand: impl Fn(usize, &mut bool) -> String,
+    normalized: String,
+    normalized_changed: bool,
+) -> Option<String> {
+    let mut mentions_alias = false;
+    let expanded_fully = expand(usize::MAX, &mut mentions_alias);
+    let expanded = (config.alias_expansion_depth > 0 && mentions_alias)
+        .then(|| expand(config.alias_expansion_depth, &mut false))
+        .filter(|it| it != written && *it != expanded_fully);
+    let normalized =
+        (mentions_alias || normalized_changed).then_some(normalized).filter(|it| it != written);
+
+    let lines = expanded
+        .map(|it| format!("Expanded {what}: `{it}`"))
+        .into_iter()
+        .chain(normalized.map(|it| format!("Normalized {what}: `{it}`")))
+        .collect::<Vec<_>>();
+    (!lines.is_empty()).then(|| lines.join("\n\n"))
+}
+
+/// Renders `node` with the type aliases its path types refer to expanded `depth` levels deep,
+/// substituting the type parameters of the aliases being expanded with `substs`. Aliases in
+/// `expanding` are already being expanded further up and are left as written to break cycles.
+fn expand_aliases(
+    sema: &Semantics<'_, RootDatabase>,
+    node: &SyntaxNode,
+    depth: usize,
+    substs: &FxHashMap<hir::TypeParam, String>,
+    expanding: &mut FxHashSet<hir::TypeAlias>,
+    mentions_alias: &mut bool,
+) -> String {
+    let expanded = ast::PathType::cast(node.clone()).and_then(|it| {
+        expand_alias_path(sema, &it.path()?, depth, substs, expanding, mentions_alias)
+    });
+    if let Some(expanded) = expanded {
+        return expanded;
+    }
+    let mut acc = String::new();
+    for child in node.children_with_tokens() {
+        match child {
+            NodeOrToken::Token(it) => acc.push_str(it.text()),
+            NodeOrToken::Node(it) => {
+                acc.push_str(&expand_aliases(sema, &it, depth, substs, expanding, mentions_alias))
+            }
+        }
+    }
+    acc
+}
+
+fn expand_alias_path(
+    sema: &Semantics<'_, RootDatabase>,
+    path: &ast::Path,
+    depth: usize,
+    substs: &FxHashMap<hir::TypeParam, String>,
+    expanding: &mut FxHashSet<hir::TypeAlias>,
+    mentions_alias: &mut bool,
+) -> Option<String> {
+    let db = sema.db;
+    let alias = match sema.resolve_path(path)? {
+        hir::PathResolution::TypeParam(it) => return substs.get(&it).cloned(),
+        hir::PathResolution::Def(hir::ModuleDef::TypeAlias(it))
+            if it.as_assoc_item(db).is_none() =>
+        {
+            it
+        }
+        _ => return None,
+    };
+    *mentions_alias = true;
+    if depth == 0 || expanding.contains(&alias) {
+        return None;
+    }
+    let aliased = sema.source(alias)?.value.ty()?;
+    let args = path
+        .segment()
+        .and_then(|it| it.generic_arg_list())
+        .into_iter()
+        .flat_map(|it| it.generic_args())
+        .filter_map(|it| match it {
+            ast::GenericArg::TypeArg(it) => {
+                Some(expand_aliases(sema, it.syntax(), depth, substs, expanding, mentions_alias))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let params = hir::GenericDef::TypeAlias(alias)
+        .type_or_const_params(db)
+        .into_iter()
+        .filter_map(|it| it.as_type_param(db));
+    let substs = params.zip(args).collect();
+    expanding.insert(alias);
+    let expanded =
+        expand_aliases(sema, aliased.syntax(), depth - 1, &substs, expanding, &mut false);
+    expanding.remove(&alias);
+    Some(expanded)
+}
+
 fn render_memory_layout<'db>(
     config: Option<MemoryLayoutHoverConfig>,
     layout: impl FnOnce() -> Result<Layout<'db>, LayoutError>,
@@ -5,7 +5,7 @@ use syntax::TextRange;
 
 use crate::{
     HoverAction, HoverConfig, HoverDocFormat, MemoryLayoutHoverConfig, MemoryLayoutHoverRenderKind,
-    fixture,
+    NormalizedTypeHoverConfig, fixture,
 };
 
 const HOVER_BASE_CONFIG: HoverConfig<'_> = HoverConfig {
@@ -17,6 +17,7 @@ const HOVER_BASE_CONFIG: HoverConfig<'_> = HoverConfig {
         padding: Some(MemoryLayoutHoverRenderKind::Both),
         niches: true,
     }),
+    normalized_type: None,
     documentation: true,
     format: HoverDocFormat::Markdown,
     keywords: true,
@@ -11948,3 +11949,290 @@ fn main() {}
         "#]],
     );
 }
+
+#[test]
+fn hover_normalized_type_of_alias_chain() {
+    check_with_config(
+        r#"
+//- minicore: option
+type Inner<T> = Option<T>;
+type Middle<T> = Vec<Inner<T>>;
+struct Vec<T>(T);
+type Outer$0 = Middle<u8>;
+"#,
+        expect![[r#"
+            *Outer*
+
+            ```rust
+            ra_test_fixture
+            ```
+
+            ```rust
+            type Outer = Middle<u8>
+            ```
+
+            ---
+
+            Expanded type: `Vec<Inner<u8>>`
+
+            Normalized type: `Vec<Option<u8>>`
+
+            ---
+
+            no Drop
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 1 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_type_of_projection() {
+    check_with_config(
+        r#"
+trait Tr { type Assoc; }
+struct S;
+impl Tr for S { type Assoc = u32; }
+struct W<T: Iterator<Item = u8>> {
+    f$0: (<S as Tr>::Assoc, T::Item),
+}
+trait Iterator { type Item; }
+"#,
+        expect![[r#"
+            *f*
+
+            ```rust
+            ra_test_fixture::W
+            ```
+
+            ```rust
+            f: (<S as Tr>::Assoc, <T as Iterator>::Item)
+            ```
+
+            ---
+
+            Normalized type: `(u32, u8)`
+
+            ---
+
+            no Drop
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 1 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_type_without_alias_expansion() {
+    check_with_config(
+        r#"
+type Inner = u8;
+const C$0: (Inner, u16) = (0, 0);
+"#,
+        expect![[r#"
+            *C*
+
+            ```rust
+            ra_test_fixture
+            ```
+
+            ```rust
+            const C: (Inner, u16) = (0, 0)
+            ```
+
+            ---
+
+            Normalized type: `(u8, u16)`
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 0 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_type_skips_written_types() {
+    check_with_config(
+        r#"
+struct S;
+static S$0: (S, u16) = (S, 0);
+"#,
+        expect![[r#"
+            *S*
+
+            ```rust
+            ra_test_fixture
+            ```
+
+            ```rust
+            static S: (S, u16) = (S, 0)
+            ```
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 1 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_type_of_cyclic_alias() {
+    check_with_config(
+        r#"
+type A = B;
+type B = A;
+const C$0: A = 0;
+"#,
+        expect![[r#"
+            *C*
+
+            ```rust
+            ra_test_fixture
+            ```
+
+            ```rust
+            const C: A = 0
+            ```
+
+            ---
+
+            Normalized type: `{unknown}`
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 4 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+    check_with_config(
+        r#"
+//- minicore: sized
+struct Vec<T>(T);
+type A = Vec<A>;
+static S$0: (A, u8) = loop {};
+"#,
+        expect![[r#"
+            *S*
+
+            ```rust
+            ra_test_fixture
+            ```
+
+            ```rust
+            static S: (A, u8) = loop {}
+            ```
+
+            ---
+
+            Normalized type: `({unknown}, u8)`
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 4 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_signature() {
+    check_with_config(
+        r#"
+trait Tr { type Assoc; }
+struct S;
+impl Tr for S { type Assoc = u32; }
+type Inner = u8;
+type Pair<T> = (T, Inner);
+impl S {
+    fn f$0(&self, (a, b): Pair<u16>, c: <S as Tr>::Assoc) -> Pair<Inner> { loop {} }
+}
+"#,
+        expect![[r#"
+            *f*
+
+            ```rust
+            ra_test_fixture::S
+            ```
+
+            ```rust
+            fn f(&self, (a, b): Pair<u16>, c: <S as Tr>::Assoc) -> Pair<Inner>
+            ```
+
+            ---
+
+            Expanded signature: `fn f(&self, (a, b): (u16, Inner), c: <S as Tr>::Assoc) -> (u8, Inner)`
+
+            Normalized signature: `fn f(&self, (a, b): (u16, u8), c: u32) -> (u8, u8)`
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 1 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_type_of_local() {
+    check_with_config(
+        r#"
+trait Tr { type Assoc; }
+struct S;
+impl Tr for S { type Assoc = u32; }
+type Inner = u8;
+fn f(p: <S as Tr>::Assoc) {
+    let l$0: (Inner, <S as Tr>::Assoc) = (0, p);
+}
+"#,
+        expect![[r#"
+            *l*
+
+            ```rust
+            let l: (u8, u32)
+            ```
+
+            ---
+
+            no Drop
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 1 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
+
+#[test]
+fn hover_normalized_type_of_expression() {
+    check_with_config(
+        r#"
+trait Tr { type Assoc; }
+struct S;
+impl Tr for S { type Assoc = u32; }
+fn f(p: <S as Tr>::Assoc) {
+    let _$0 = p;
+}
+"#,
+        expect![[r#"
+            *_*
+            ```rust
+            u32
+            ```
+        "#]],
+        &HoverConfig {
+            memory_layout: None,
+            normalized_type: Some(NormalizedTypeHoverConfig { alias_expansion_depth: 1 }),
+            ..HOVER_BASE_CONFIG
+        },
+    );
+}
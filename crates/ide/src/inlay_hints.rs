@@ -33,6 +33,7 @@ mod chaining;
 mod closing_brace;
 mod closure_captures;
 mod closure_ret;
+mod const_value;
 mod discriminant;
 mod extern_block;
 mod generic_param;
@@ -61,7 +62,9 @@ mod range_exclusive;
 //
 // * return types of closure expressions
 // * elided lifetimes
+// * lifetimes of reference arguments at call sites and of closures passed to `Fn(&T)` bounds
 // * compiler inserted reborrows
+// * evaluated values of constants, array lengths and enum discriminants
 // * names of generic type and lifetime parameters
 //
 // Note: inlay hints for function argument names are heuristically omitted to reduce noise and will not appear if
@@ -230,11 +233,12 @@ fn hints(
                 chaining::hints(hints, famous_defs, config, display_target, &expr);
                 adjustment::hints(hints, famous_defs, config, display_target, &expr);
                 match expr {
-                    ast::Expr::CallExpr(it) => param_name::hints(hints, famous_defs, config, file_id, ast::Expr::from(it)),
-                    ast::Expr::MethodCallExpr(it) => {
-                        param_name::hints(hints, famous_defs, config, file_id, ast::Expr::from(it))
+                    ast::Expr::CallExpr(_) | ast::Expr::MethodCallExpr(_) => {
+                        lifetime::call_hints(hints, famous_defs, config, file_id, &expr);
+                        param_name::hints(hints, famous_defs, config, file_id, expr)
                     }
                     ast::Expr::ClosureExpr(it) => {
+                        lifetime::closure_hints(hints, ctx, famous_defs, config, &it);
                         closure_captures::hints(
                             hints,
                             famous_defs,
@@ -252,6 +256,8 @@ fn hints(
                         file_id.edition(sema.db),
                     ),
                     ast::Expr::RangeExpr(it) => range_exclusive::hints(hints, famous_defs, config, it),
+                    ast::Expr::PathExpr(it) => const_value::path_hints(hints, famous_defs, config, display_target, &it),
+                    ast::Expr::ArrayExpr(it) => const_value::array_expr_hints(hints, famous_defs, config, &it),
                     ast::Expr::Literal(it) => ra_fixture::hints(hints, famous_defs.0, file_id, config, it),
                     _ => Some(()),
                 }
@@ -281,10 +287,17 @@ fn hints(
                     if let Some(extern_block) = &ctx.extern_block_parent {
                         extern_block::static_hints(hints, famous_defs, config, &it, extern_block);
                     }
+                    const_value::item_hints(hints, famous_defs, config, display_target, Either::Right(it.clone()));
                     implicit_static::hints(hints, famous_defs, config,  Either::Left(it))
                 },
-                ast::Item::Const(it) => implicit_static::hints(hints, famous_defs, config, Either::Right(it)),
-                ast::Item::Enum(it) => discriminant::enum_hints(hints, famous_defs, config, it),
+                ast::Item::Const(it) => {
+                    const_value::item_hints(hints, famous_defs, config, display_target, Either::Left(it.clone()));
+                    implicit_static::hints(hints, famous_defs, config, Either::Right(it))
+                },
+                ast::Item::Enum(it) => {
+                    const_value::enum_hints(hints, famous_defs, config, &it);
+                    discriminant::enum_hints(hints, famous_defs, config, it)
+                },
                 ast::Item::ExternBlock(it) => extern_block::extern_block_hints(hints, famous_defs, config, it),
                 _ => None,
             },
@@ -300,6 +313,10 @@ fn hints(
                     implied_dyn_trait::hints(hints, famous_defs, config, Either::Right(dyn_));
                     Some(())
                 },
+                ast::Type::ArrayType(array) => {
+                    const_value::array_type_hints(hints, famous_defs, config, &array);
+                    Some(())
+                },
                 ast::Type::InferType(placeholder) => {
                     placeholders::type_hints(hints, famous_defs, config, display_target, placeholder);
                     Some(())
@@ -318,6 +335,7 @@ pub struct InlayHintsConfig<'a> {
     pub type_hints: bool,
     pub type_hints_placement: TypeHintsPlacement,
     pub sized_bound: bool,
+    pub const_value_hints: bool,
     pub discriminant_hints: DiscriminantHints,
     pub parameter_hints: bool,
     pub parameter_hints_for_missing_arguments: bool,
@@ -333,6 +351,7 @@ pub struct InlayHintsConfig<'a> {
     pub implicit_drop_hints: bool,
     pub implied_dyn_trait_hints: bool,
     pub lifetime_elision_hints: LifetimeElisionHints,
+    pub lifetime_parameter_hints: bool,
     pub param_names_for_lifetime_elision_hints: bool,
     pub hide_inferred_type_hints: bool,
     pub hide_named_constructor_hints: bool,
@@ -477,6 +496,7 @@ pub enum InlayKind {
     Chaining,
     ClosingBrace,
     ClosureCapture,
+    ConstValue,
     Discriminant,
     GenericParamList,
     Lifetime,
@@ -947,6 +967,7 @@ mod tests {
         },
         chaining_hints: false,
         lifetime_elision_hints: LifetimeElisionHints::Never,
+        lifetime_parameter_hints: false,
         closure_return_type_hints: ClosureReturnTypeHints::Never,
         closure_capture_hints: false,
         adjustment_hints: AdjustmentHints::Never,
@@ -966,6 +987,7 @@ mod tests {
         implicit_drop_hints: false,
         implied_dyn_trait_hints: false,
         range_exclusive_hints: false,
+        const_value_hints: false,
         ra_fixture: RaFixtureConfig::default(),
     };
     pub(super) const TEST_CONFIG: InlayHintsConfig<'_> = InlayHintsConfig {
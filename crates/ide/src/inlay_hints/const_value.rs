@@ -0,0 +1,266 @@
+//! Implementation of "const value" inlay hints:
+//! ```no_run
+//! const FLAGS: u32 = 1 << 4 | 1/* = 17 (0x11) */;
+//! static TABLE: [u8; 2 * LEN/* = 8 */] = [0; 2 * LEN/* = 8 */];
+//! enum E { A = 1 << 3/* = 8 */ }
+//! fn f() -> u32 { FLAGS & Foo::MASK/* = 240 (0xF0) */ }
+//! ```
+use either::Either;
+use hir::{AsAssocItem, DisplayTarget, EvaluatedConst, PathResolution, Semantics};
+use ide_db::{RootDatabase, famous_defs::FamousDefs};
+use syntax::ast::{self, AstNode};
+
+use crate::{InlayHint, InlayHintLabel, InlayHintPosition, InlayHintsConfig, InlayKind};
+
+/// Hints the value of `const` and `static` items after their initializer.
+pub(super) fn item_hints(
+    acc: &mut Vec<InlayHint>,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    display_target: DisplayTarget,
+    item: Either<ast::Const, ast::Static>,
+) -> Option<()> {
+    if !config.const_value_hints {
+        return None;
+    }
+    let (body, value) = match item {
+        Either::Left(it) => (it.body()?, sema.to_def(&it)?.eval(sema.db).ok()?),
+        Either::Right(it) => (it.body()?, sema.to_def(&it)?.eval(sema.db).ok()?),
+    };
+    if is_literal(&body) {
+        return None;
+    }
+    acc.push(hint(config, &body, render(sema, &value, display_target)));
+    Some(())
+}
+
+/// Hints the value of associated consts where they are used, as their value can depend on the
+/// implementation they come from.
+pub(super) fn path_hints(
+    acc: &mut Vec<InlayHint>,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    display_target: DisplayTarget,
+    path_expr: &ast::PathExpr,
+) -> Option<()> {
+    if !config.const_value_hints {
+        return None;
+    }
+    let PathResolution::Def(hir::ModuleDef::Const(konst)) =
+        sema.resolve_path(&path_expr.path()?)?
+    else {
+        return None;
+    };
+    konst.as_assoc_item(sema.db)?;
+    let value = konst.eval(sema.db).ok()?;
+    acc.push(hint(config, path_expr, render(sema, &value, display_target)));
+    Some(())
+}
+
+/// Hints the length of array repeat expressions, e.g. `[0; 2 * LEN]`.
+pub(super) fn array_expr_hints(
+    acc: &mut Vec<InlayHint>,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    array: &ast::ArrayExpr,
+) -> Option<()> {
+    if !config.const_value_hints {
+        return None;
+    }
+    // `[x; len]` is the only form with a `;`
+    array.semicolon_token()?;
+    let len = array.exprs().nth(1)?;
+    if is_literal(&len) {
+        return None;
+    }
+    let ty = sema.type_of_expr(&ast::Expr::ArrayExpr(array.clone()))?.original;
+    let (_, len_value) = ty.as_array(sema.db)?;
+    acc.push(hint(config, &len, len_value.to_string()));
+    Some(())
+}
+
+/// Hints the length of array types, e.g. `[u8; 2 * LEN]`.
+pub(super) fn array_type_hints(
+    acc: &mut Vec<InlayHint>,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    array: &ast::ArrayType,
+) -> Option<()> {
+    if !config.const_value_hints {
+        return None;
+    }
+    let len = array.const_arg()?;
+    if len.expr().is_none_or(|it| is_literal(&it)) {
+        return None;
+    }
+    let ty = sema.resolve_type(&ast::Type::ArrayType(array.clone()))?;
+    let (_, len_value) = ty.as_array(sema.db)?;
+    acc.push(hint(config, &len, len_value.to_string()));
+    Some(())
+}
+
+/// Hints the value of explicit enum discriminants that are not literals.
+pub(super) fn enum_hints(
+    acc: &mut Vec<InlayHint>,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    enum_: &ast::Enum,
+) -> Option<()> {
+    if !config.const_value_hints {
+        return None;
+    }
+    for variant in enum_.variant_list()?.variants() {
+        let Some(discriminant) = variant.const_arg().and_then(|it| it.expr()) else { continue };
+        if is_literal(&discriminant) {
+            continue;
+        }
+        let Some(value) = sema.to_def(&variant).and_then(|it| it.eval(sema.db).ok()) else {
+            continue;
+        };
+        let label = if value >= 10 { format!("{value} ({value:#X})") } else { value.to_string() };
+        acc.push(hint(config, &discriminant, label));
+    }
+    Some(())
+}
+
+fn render(
+    sema: &Semantics<'_, RootDatabase>,
+    value: &EvaluatedConst<'_>,
+    display_target: DisplayTarget,
+) -> String {
+    match value.render_debug(sema.db) {
+        Ok(rendered) if !rendered.is_empty() => rendered,
+        _ => value.render(sema.db, display_target),
+    }
+}
+
+fn hint(config: &InlayHintsConfig<'_>, node: &impl AstNode, value: String) -> InlayHint {
+    let mut label = format!("= {value}");
+    if let Some(max_length) = config.max_length
+        && label.chars().count() > max_length
+    {
+        label = label.chars().take(max_length).collect::<String>() + "…";
+    }
+    InlayHint {
+        range: node.syntax().text_range(),
+        position: InlayHintPosition::After,
+        pad_left: true,
+        pad_right: false,
+        kind: InlayKind::ConstValue,
+        label: InlayHintLabel::from(label),
+        text_edit: None,
+        resolve_parent: None,
+    }
+}
+
+/// Whether the value of `expr` is already spelled out in the source.
+fn is_literal(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Literal(_) => true,
+        ast::Expr::PrefixExpr(it) => {
+            it.op_kind() == Some(ast::UnaryOp::Neg) && it.expr().is_some_and(|it| is_literal(&it))
+        }
+        ast::Expr::ParenExpr(it) => it.expr().is_some_and(|it| is_literal(&it)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        InlayHintsConfig,
+        inlay_hints::tests::{DISABLED_CONFIG, check_with_config},
+    };
+
+    const CONFIG: InlayHintsConfig<'_> =
+        InlayHintsConfig { const_value_hints: true, ..DISABLED_CONFIG };
+
+    #[test]
+    fn const_and_static_items() {
+        check_with_config(
+            CONFIG,
+            r#"
+const LITERAL: u32 = 5;
+const NEGATIVE: i32 = -5;
+const FLAGS: u32 = 1 << 4 | 1;
+                 //^^^^^^^^^^ = 17 (0x11)
+const SUM: i32 = LITERAL as i32 + NEGATIVE - 2;
+               //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ = -2 (0xFFFFFFFE)
+static SMALL: u8 = FLAGS as u8 - 10;
+                 //^^^^^^^^^^^^^^^^ = 7
+const PAIR: (u8, bool) = (1 + 1, !false);
+                       //^^^^^^^^^^^^^^^ = (2, true)
+"#,
+        );
+    }
+
+    #[test]
+    fn assoc_consts_at_use_sites() {
+        check_with_config(
+            CONFIG,
+            r#"
+struct Foo;
+impl Foo {
+    const MASK: u32 = 0xF << 4;
+                    //^^^^^^^^ = 240 (0xF0)
+    fn f(x: u32) -> u32 {
+        x & Self::MASK
+          //^^^^^^^^^^ = 240 (0xF0)
+    }
+}
+const TOP: u32 = 1;
+fn g() -> u32 {
+    TOP + Foo::MASK
+        //^^^^^^^^^ = 240 (0xF0)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn array_lengths() {
+        check_with_config(
+            CONFIG,
+            r#"
+const LEN: usize = 4;
+fn f(_: [u8; 2 * LEN]) {
+           //^^^^^^^ = 8
+    let _ = [0u8; LEN + 1];
+                //^^^^^^^ = 5
+    let _ = [0u8; 3];
+    let _: [u8; LEN] = [0; LEN];
+              //^^^ = 4
+                         //^^^ = 4
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn enum_discriminants() {
+        check_with_config(
+            CONFIG,
+            r#"
+#[repr(u8)]
+enum Flags {
+    A = 1 << 3,
+      //^^^^^^ = 8
+    B = 1 << 4 | 1,
+      //^^^^^^^^^^ = 17 (0x11)
+    C = 2,
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn truncates_long_values() {
+        check_with_config(
+            InlayHintsConfig { max_length: Some(10), ..CONFIG },
+            r#"
+const TABLE: [u8; 8] = [1 + 1; 8];
+                     //^^^^^^^^^^ = [2, 2, 2…
+"#,
+        );
+    }
+}
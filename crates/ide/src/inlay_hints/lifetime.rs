@@ -1,20 +1,27 @@
-//! Implementation of "lifetime elision" inlay hints:
+//! Implementation of "lifetime elision" and "lifetime parameter" inlay hints:
 //! ```no_run
 //! fn example/* <'0> */(a: &/* '0 */()) {}
+//! fn first<'a>(a: &'a str, b: &str) -> &'a str { a }
+//! first(/* 'a */&x, /* '0 */&y);
+//! v.sort_by(/* for<'0, '1> */|a, b| a.cmp(b));
 //! ```
+//!
+//! The lifetime parameter hints are read off the callee's signature as written, applying the
+//! lifetime elision rules.
 use std::iter;
 
+use hir::EditionedFileId;
 use ide_db::{FxHashMap, famous_defs::FamousDefs, syntax_helpers::node_ext::walk_ty};
 use itertools::Itertools;
 use syntax::{SmolStr, format_smolstr};
 use syntax::{
     SyntaxKind, SyntaxToken,
-    ast::{self, AstNode, HasGenericParams, HasName},
+    ast::{self, AstNode, HasGenericArgs, HasGenericParams, HasName, HasTypeBounds},
 };
 
 use crate::{
     InlayHint, InlayHintPosition, InlayHintsConfig, InlayKind, LifetimeElisionHints,
-    inlay_hints::InlayHintCtx,
+    inlay_hints::{InlayHintCtx, param_name},
 };
 
 pub(super) fn fn_hints(
@@ -404,11 +411,314 @@ fn hints_(
     Some(())
 }
 
+/// Hints the lifetime parameter of the callee each reference argument is passed to, if the callee
+/// returns a borrow.
+///
+/// Elided lifetimes are named like the elision hints of the callee's signature would name them,
+/// as the type lowering does not resolve elided lifetimes in signatures yet.
+pub(super) fn call_hints(
+    acc: &mut Vec<InlayHint>,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    file_id: EditionedFileId,
+    expr: &ast::Expr,
+) -> Option<()> {
+    if !config.lifetime_parameter_hints {
+        return None;
+    }
+
+    let (callable, arg_list) = param_name::get_callable(sema, expr)?;
+    let hir::CallableKind::Function(func) = callable.kind() else {
+        return None;
+    };
+    let func = sema.source(func)?.value;
+    if !returns_borrow(&func) {
+        return None;
+    }
+
+    let param_list = func.param_list()?;
+    let inputs = param_list
+        .self_param()
+        .map(|it| self_param_refs(&it))
+        .into_iter()
+        .chain(param_list.params().map(|it| (param_name(it.pat()), it.ty().map(refs_of))))
+        .map(|(name, refs)| (name, refs.unwrap_or_default()));
+    let taken = lifetime_names(
+        func.syntax()
+            .ancestors()
+            .filter_map(ast::AnyHasGenericParams::cast)
+            .filter_map(|it| it.generic_param_list()),
+    );
+    let (lifetimes, _) = name_lifetimes(config, &taken, inputs);
+
+    let receiver = match expr {
+        ast::Expr::MethodCallExpr(it) => it.receiver(),
+        _ => None,
+    };
+    let args = receiver.into_iter().map(Some).chain(arg_list.args_maybe_empty());
+    for (lifetime, arg) in iter::zip(lifetimes, args) {
+        let (Some((lifetime, _)), Some(arg)) = (lifetime, arg) else { continue };
+        // Only annotate arguments that exist in the original file
+        let Some(range) = sema.original_range_opt(arg.syntax()) else { continue };
+        if range.file_id != file_id {
+            continue;
+        }
+        acc.push(InlayHint {
+            range: range.range,
+            kind: InlayKind::Lifetime,
+            label: lifetime.to_string().into(),
+            text_edit: None,
+            position: InlayHintPosition::Before,
+            pad_left: false,
+            pad_right: true,
+            resolve_parent: Some(expr.syntax().text_range()),
+        });
+    }
+    Some(())
+}
+
+/// Hints the higher-ranked lifetimes a closure is generic over when it is passed where a
+/// `Fn(&T)`-like bound is expected, as declared by that bound.
+pub(super) fn closure_hints(
+    acc: &mut Vec<InlayHint>,
+    ctx: &mut InlayHintCtx,
+    FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig<'_>,
+    closure: &ast::ClosureExpr,
+) -> Option<()> {
+    if !config.lifetime_parameter_hints || closure.for_binder().is_some() {
+        return None;
+    }
+
+    let arg_list = ast::ArgList::cast(closure.syntax().parent()?)?;
+    let call = ast::Expr::cast(arg_list.syntax().parent()?)?;
+    let (callable, _) = param_name::get_callable(sema, &call)?;
+    let hir::CallableKind::Function(func) = callable.kind() else {
+        return None;
+    };
+    let func = sema.source(func)?.value;
+    let param_list = func.param_list()?;
+    let idx = arg_list.args().position(|it| it.syntax() == closure.syntax())?;
+    let param = match call {
+        ast::Expr::CallExpr(_) if param_list.self_param().is_some() => {
+            param_list.params().nth(idx.checked_sub(1)?)?
+        }
+        _ => param_list.params().nth(idx)?,
+    };
+    let (fn_args, binder) = fn_bound(&func, &param.ty()?)?;
+    let bound_names = lifetime_names(binder.and_then(|it| it.generic_param_list()));
+
+    let closure_params = closure.param_list()?.params().collect::<Vec<_>>();
+    let inputs = fn_args.type_args().enumerate().map(|(idx, arg)| {
+        let name = closure_params.get(idx).and_then(|it| param_name(it.pat()));
+        (name, arg.ty().map(refs_of).unwrap_or_default())
+    });
+    let taken = ctx.lifetime_stacks.iter().flatten().cloned().collect::<Vec<_>>();
+    let (lifetimes, allocated) = name_lifetimes(config, &taken, inputs);
+    if allocated.is_empty() && bound_names.is_empty() {
+        return None;
+    }
+
+    acc.push(InlayHint {
+        range: closure.syntax().first_token()?.text_range(),
+        kind: InlayKind::GenericParamList,
+        label: format!("for<{}>", bound_names.iter().chain(&allocated).format(", ")).into(),
+        text_edit: None,
+        position: InlayHintPosition::Before,
+        pad_left: false,
+        pad_right: true,
+        resolve_parent: None,
+    });
+    for (param, lifetime) in iter::zip(closure_params, lifetimes) {
+        let Some((lifetime, is_elided)) = lifetime else { continue };
+        if !is_elided && !bound_names.contains(&lifetime) {
+            continue;
+        }
+        let Some(ast::Type::RefType(ty)) = param.ty() else { continue };
+        if ty.lifetime().is_some_and(|it| it.text() != "'_") {
+            continue;
+        }
+        let Some(amp) = ty.amp_token() else { continue };
+        acc.push(InlayHint {
+            range: amp.text_range(),
+            kind: InlayKind::Lifetime,
+            label: lifetime.to_string().into(),
+            text_edit: None,
+            position: InlayHintPosition::After,
+            pad_left: false,
+            pad_right: true,
+            resolve_parent: None,
+        });
+    }
+    Some(())
+}
+
+/// The references in a parameter's type, as `(is_outermost, lifetime)` pairs in source order.
+type ParamRefs = Vec<(bool, Option<ast::Lifetime>)>;
+
+fn refs_of(ty: ast::Type) -> ParamRefs {
+    let mut acc = vec![];
+    walk_ty(&ty, &mut |it| match it {
+        ast::Type::RefType(r) => {
+            acc.push((r.syntax() == ty.syntax(), r.lifetime()));
+            false
+        }
+        // References in nested fn signatures are bound by those signatures
+        ast::Type::FnPtrType(_) => true,
+        ast::Type::PathType(t) => t.path().is_some_and(|it| path_as_fn(&it).is_some()),
+        _ => false,
+    });
+    acc
+}
+
+fn self_param_refs(self_param: &ast::SelfParam) -> (Option<SmolStr>, Option<ParamRefs>) {
+    let refs = match self_param.ty() {
+        Some(ty) => Some(refs_of(ty)),
+        None => self_param.amp_token().map(|_| vec![(true, self_param.lifetime())]),
+    };
+    (Some("self".into()), refs)
+}
+
+fn lifetime_names(lists: impl IntoIterator<Item = ast::GenericParamList>) -> Vec<SmolStr> {
+    let mut acc = vec![];
+    for list in lists {
+        acc.extend(list.lifetime_params().filter_map(|it| Some(it.lifetime()?.text().into())));
+    }
+    acc
+}
+
+fn param_name(pat: Option<ast::Pat>) -> Option<SmolStr> {
+    match pat? {
+        ast::Pat::IdentPat(it) => Some(it.name()?.text().into()),
+        _ => None,
+    }
+}
+
+/// Names the elided lifetimes of the given inputs the same way [`hints_`] does, returning the
+/// lifetime of each input's outermost reference along with whether it was elided, and the names
+/// that were allocated.
+fn name_lifetimes(
+    config: &InlayHintsConfig<'_>,
+    taken: &[SmolStr],
+    inputs: impl Iterator<Item = (Option<SmolStr>, ParamRefs)>,
+) -> (Vec<Option<(SmolStr, bool)>>, Vec<SmolStr>) {
+    let mut generic = (0u8..).map(|idx| match idx {
+        idx if idx < 10 => SmolStr::from_iter(['\'', (idx + 48) as char]),
+        idx => format_smolstr!("'{idx}"),
+    });
+    let mut used_names: FxHashMap<SmolStr, usize> = FxHashMap::default();
+    let mut allocated = vec![];
+    let lifetimes = inputs
+        .map(|(name, refs)| {
+            let mut outermost = None;
+            for (is_outermost, lifetime) in refs {
+                let lifetime = match lifetime {
+                    Some(lt) if lt.text() != "'_" => (SmolStr::from(lt.text()), false),
+                    _ => {
+                        let new = match &name {
+                            Some(it) if config.param_names_for_lifetime_elision_hints => {
+                                if let Some(c) = used_names.get_mut(it) {
+                                    *c += 1;
+                                    format_smolstr!("'{it}{c}")
+                                } else {
+                                    used_names.insert(it.clone(), 0);
+                                    format_smolstr!("'{it}")
+                                }
+                            }
+                            _ => {
+                                generic.by_ref().find(|it| !taken.contains(it)).unwrap_or_default()
+                            }
+                        };
+                        allocated.push(new.clone());
+                        (new, true)
+                    }
+                };
+                if is_outermost {
+                    outermost = Some(lifetime);
+                }
+            }
+            outermost
+        })
+        .collect();
+    (lifetimes, allocated)
+}
+
+fn returns_borrow(func: &ast::Fn) -> bool {
+    let Some(ty) = func.ret_type().and_then(|it| it.ty()) else {
+        return false;
+    };
+    let is_borrow = |lt: Option<ast::Lifetime>| lt.is_some_and(|it| it.text() != "'static");
+    let mut borrows = false;
+    walk_ty(&ty, &mut |it| {
+        borrows |= match it {
+            ast::Type::RefType(r) => r.lifetime().is_none_or(|it| it.text() != "'static"),
+            ast::Type::PathType(t) => t
+                .path()
+                .and_then(|it| it.segment())
+                .and_then(|it| it.generic_arg_list())
+                .is_some_and(|it| it.lifetime_args().any(|it| is_borrow(it.lifetime()))),
+            ast::Type::ImplTraitType(t) => {
+                t.type_bound_list().is_some_and(|it| it.bounds().any(|it| is_borrow(it.lifetime())))
+            }
+            ast::Type::DynTraitType(t) => {
+                t.type_bound_list().is_some_and(|it| it.bounds().any(|it| is_borrow(it.lifetime())))
+            }
+            // References in nested fn signatures are bound by those signatures
+            ast::Type::FnPtrType(_) => return true,
+            _ => false,
+        };
+        borrows
+    });
+    borrows
+}
+
+/// Finds the `Fn*(..)` bound the parameter type `ty` of `func` has to satisfy.
+fn fn_bound(
+    func: &ast::Fn,
+    ty: &ast::Type,
+) -> Option<(ast::ParenthesizedArgList, Option<ast::ForBinder>)> {
+    let as_fn = |bound: ast::TypeBound| {
+        let ast::Type::PathType(path) = bound.ty()? else { return None };
+        let (args, _) = path_as_fn(&path.path()?)?;
+        Some((args, bound.for_binder()))
+    };
+    match ty {
+        ast::Type::ImplTraitType(it) => it.type_bound_list()?.bounds().find_map(as_fn),
+        ast::Type::PathType(it) => {
+            let name = it.path()?.as_single_name_ref()?;
+            let param =
+                func.generic_param_list()?.type_or_const_params().find_map(|it| match it {
+                    ast::TypeOrConstParam::Type(it) if it.name()?.text() == name.text() => Some(it),
+                    _ => None,
+                })?;
+            let where_bounds = func
+                .where_clause()
+                .into_iter()
+                .flat_map(|it| it.predicates())
+                .filter(|pred| match pred.ty() {
+                    Some(ast::Type::PathType(ty)) => ty
+                        .path()
+                        .and_then(|it| it.as_single_name_ref())
+                        .is_some_and(|it| it.text() == name.text()),
+                    _ => false,
+                })
+                .filter_map(|pred| pred.type_bound_list());
+            param
+                .type_bound_list()
+                .into_iter()
+                .chain(where_bounds)
+                .flat_map(|it| it.bounds())
+                .find_map(as_fn)
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         InlayHintsConfig, LifetimeElisionHints,
-        inlay_hints::tests::{TEST_CONFIG, check, check_with_config},
+        inlay_hints::tests::{DISABLED_CONFIG, TEST_CONFIG, check, check_with_config},
     };
 
     #[test]
@@ -576,6 +886,101 @@ const _: fn(&()) -> &();
        //^^ for<'0>
           //^'0
                   //^'0
+"#,
+        );
+    }
+
+    #[test]
+    fn hints_lifetimes_at_call_sites() {
+        check_with_config(
+            InlayHintsConfig { lifetime_parameter_hints: true, ..DISABLED_CONFIG },
+            r#"
+struct S;
+impl S {
+    fn get(&self, key: &str) -> &str { key }
+    fn set(&mut self, value: &str) {}
+}
+fn longest<'a>(a: &'a str, b: &'a str) -> &'a str { a }
+fn first<'a>(a: &'a str, b: &str) -> &'a str { a }
+fn only(a: u32, b: &str) -> &str { b }
+fn owned(a: &str) -> u32 { 0 }
+fn leak(a: &str) -> &'static str { "" }
+
+fn main() {
+    let (x, y) = ("", "");
+    longest(x, y);
+          //^'a
+             //^'a
+    first(x, y);
+        //^'a
+           //^'0
+    only(0, y);
+          //^'0
+    owned(x);
+    leak(x);
+    let s = S;
+    s.get(x);
+  //^'0
+        //^'1
+    S::get(&s, x);
+         //^^'0
+             //^'1
+    let mut s = S;
+    s.set(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn hints_lifetimes_at_call_sites_with_param_names() {
+        check_with_config(
+            InlayHintsConfig {
+                lifetime_parameter_hints: true,
+                param_names_for_lifetime_elision_hints: true,
+                ..DISABLED_CONFIG
+            },
+            r#"
+struct S;
+impl S {
+    fn get(&self, key: &str) -> &str { key }
+}
+fn main() {
+    S.get("");
+  //^'self
+        //^^'key
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn hints_higher_ranked_closure_lifetimes() {
+        check_with_config(
+            InlayHintsConfig { lifetime_parameter_hints: true, ..DISABLED_CONFIG },
+            r#"
+struct V;
+impl V {
+    fn sort_by<F: FnMut(&u32, &u32) -> bool>(&mut self, f: F) {}
+    fn map<F>(self, f: F) where F: FnOnce(u32) -> u32 {}
+}
+fn apply(f: impl Fn(&str) -> &str) {}
+fn apply_explicit<F>(f: F) where F: for<'s> Fn(&'s str, &str) {}
+
+fn main<'a>() {
+    let mut v = V;
+    v.sort_by(|a, b| a < b);
+            //^ for<'0, '1>
+    v.sort_by(move |a: &u32, b| a < b);
+            //^^^^ for<'0, '1>
+                     //^'0
+    apply(|s| s);
+        //^ for<'0>
+    apply_explicit(|a, b| ());
+                 //^ for<'s, '0>
+    apply(for<'b> |s: &'b str| s);
+    V.map(|x| x);
+}
 "#,
         );
     }
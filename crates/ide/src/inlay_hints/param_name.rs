@@ -159,7 +159,7 @@ fn missing_arg_hint_position(arg_list: &ast::ArgList) -> Option<syntax::TextRang
         .map(|t| t.text_range())
 }
 
-fn get_callable<'db>(
+pub(super) fn get_callable<'db>(
     sema: &Semantics<'db, RootDatabase>,
     expr: &ast::Expr,
 ) -> Option<(hir::Callable<'db>, ast::ArgList)> {
@@ -0,0 +1,269 @@
+//! Computes the values a debugger can show inline at a stopped location.
+
+use std::collections::hash_map::Entry;
+
+use hir::{PathResolution, ScopeDef, Semantics};
+use ide_db::{FileId, FxHashMap, FxHashSet, RootDatabase};
+use syntax::{
+    AstNode, SyntaxNode, TextRange,
+    ast::{self, HasName},
+    match_ast,
+};
+
+/// A value a debugger can display inline next to the source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InlineValue {
+    /// A local variable, looked up by name in the stopped frame.
+    VariableLookup { range: TextRange, name: String },
+    /// A field-access expression, evaluated by the debugger in the stopped frame.
+    EvaluatableExpression { range: TextRange, expression: String },
+}
+
+// Feature: Inline Values
+//
+// When a debugger is stopped in a function, shows the values of the locals and field accesses
+// that are in scope at the stopped location and appear before it in the function.
+pub(crate) fn inline_values(
+    db: &RootDatabase,
+    file_id: FileId,
+    range: TextRange,
+    stopped_at: TextRange,
+) -> Vec<InlineValue> {
+    let sema = Semantics::new(db);
+    let file = sema.parse_guess_edition(file_id);
+    let stop = stopped_at.end().min(file.syntax().text_range().end());
+    let Some(token) = file.syntax().token_at_offset(stop).left_biased() else {
+        return Vec::new();
+    };
+    let Some(func) = token.parent_ancestors().find_map(ast::Fn::cast) else {
+        return Vec::new();
+    };
+    let Some(range) = range.intersect(TextRange::new(func.syntax().text_range().start(), stop))
+    else {
+        return Vec::new();
+    };
+    let Some(scope) = token.parent().and_then(|parent| sema.scope_at_offset(&parent, stop)) else {
+        return Vec::new();
+    };
+    let mut candidates = Vec::new();
+    scope.process_all_names(&mut |_, def| {
+        if let ScopeDef::Local(local) = def {
+            candidates.push(local);
+        }
+    });
+    // At a statement boundary the scope only covers the bindings up to the previous expression,
+    // so the `let` statements that completed right before the stop need to be added manually.
+    for stmt_list in token.parent_ancestors().take_while(|it| it != func.syntax()) {
+        let Some(stmt_list) = ast::StmtList::cast(stmt_list) else { continue };
+        let lets = stmt_list
+            .statements()
+            .filter_map(|stmt| match stmt {
+                ast::Stmt::LetStmt(it) if it.syntax().text_range().end() <= stop => it.pat(),
+                _ => None,
+            })
+            .flat_map(|pat| pat.syntax().descendants().filter_map(ast::IdentPat::cast));
+        candidates.extend(lets.filter_map(|pat| sema.to_def(&pat)));
+    }
+    // Of several bindings with the same name, the one declared last shadows the others.
+    let mut visible = FxHashMap::default();
+    for local in candidates {
+        let start = local.primary_source(db).syntax().text_range().start();
+        match visible.entry(local.name(db)) {
+            Entry::Occupied(mut entry) => {
+                let (prev_start, _) = *entry.get();
+                if start > prev_start {
+                    entry.insert((start, local));
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((start, local));
+            }
+        }
+    }
+    let locals_in_scope: FxHashSet<_> = visible.into_values().map(|(_, local)| local).collect();
+
+    let is_local_in_scope = |path: &ast::Path| match sema.resolve_path(path) {
+        Some(PathResolution::Local(local)) => locals_in_scope.contains(&local),
+        _ => false,
+    };
+
+    let mut res = Vec::new();
+    for node in func.syntax().descendants() {
+        if !range.contains_range(node.text_range()) {
+            continue;
+        }
+        match_ast! {
+            match node {
+                ast::IdentPat(pat) => {
+                    let Some(name) = pat.name() else { continue };
+                    if sema.to_def(&pat).is_some_and(|local| locals_in_scope.contains(&local)) {
+                        res.push(InlineValue::VariableLookup {
+                            range: name.syntax().text_range(),
+                            name: name.text().trim_start_matches("r#").to_owned(),
+                        });
+                    }
+                },
+                ast::PathExpr(expr) => {
+                    // Receivers of field accesses are covered by the whole expression.
+                    if is_field_receiver(expr.syntax()) {
+                        continue;
+                    }
+                    let Some(path) = expr.path() else { continue };
+                    let Some(segment) = path.as_single_segment() else { continue };
+                    if is_local_in_scope(&path) {
+                        res.push(InlineValue::VariableLookup {
+                            range: segment.syntax().text_range(),
+                            name: segment.syntax().text().to_string().trim_start_matches("r#").to_owned(),
+                        });
+                    }
+                },
+                ast::FieldExpr(expr) => {
+                    if is_field_receiver(expr.syntax()) {
+                        continue;
+                    }
+                    if field_access_base(&expr).is_some_and(|path| is_local_in_scope(&path)) {
+                        res.push(InlineValue::EvaluatableExpression {
+                            range: expr.syntax().text_range(),
+                            expression: expr.syntax().text().to_string(),
+                        });
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    res
+}
+
+fn is_field_receiver(node: &SyntaxNode) -> bool {
+    node.parent()
+        .and_then(ast::FieldExpr::cast)
+        .and_then(|parent| parent.expr())
+        .is_some_and(|receiver| receiver.syntax() == node)
+}
+
+/// Returns the path at the root of a chain of field accesses such as `a.b.0.c`, if there is one.
+fn field_access_base(expr: &ast::FieldExpr) -> Option<ast::Path> {
+    let mut receiver = expr.expr()?;
+    loop {
+        match receiver {
+            ast::Expr::FieldExpr(it) => receiver = it.expr()?,
+            ast::Expr::ParenExpr(it) => receiver = it.expr()?,
+            ast::Expr::PathExpr(it) => {
+                return it.path().filter(|it| it.as_single_segment().is_some());
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::FileRange;
+    use syntax::{TextRange, TextSize};
+
+    use crate::{InlineValue, fixture};
+
+    #[track_caller]
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        let (analysis, position, expected) = fixture::annotations(ra_fixture);
+        let file_range = FileRange {
+            file_id: position.file_id,
+            range: TextRange::up_to(TextSize::of(&*analysis.file_text(position.file_id).unwrap())),
+        };
+        let values = analysis
+            .inline_values(file_range.file_id, file_range.range, TextRange::empty(position.offset))
+            .unwrap();
+        let actual = values
+            .into_iter()
+            .map(|it| match it {
+                InlineValue::VariableLookup { range, name } => (range, format!("var {name}")),
+                InlineValue::EvaluatableExpression { range, expression } => {
+                    (range, format!("expr {expression}"))
+                }
+            })
+            .collect::<Vec<_>>();
+        let expected =
+            expected.into_iter().map(|(it, label)| (it.range, label)).collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn locals_before_stop() {
+        check(
+            r#"
+fn f(a: i32) -> i32 {
+   //^ var a
+    let b = a + 1;
+      //^ var b
+          //^ var a
+    $0let c = b;
+    c
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn locals_out_of_scope_are_skipped() {
+        check(
+            r#"
+fn f() {
+    {
+        let inner = 1;
+    }
+    let outer = 2;
+      //^^^^^ var outer
+    $0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn field_accesses() {
+        check(
+            r#"
+struct Inner { x: i32 }
+struct Outer { inner: Inner, t: (i32, i32) }
+fn f(o: Outer) {
+   //^ var o
+    let y = o.inner.x + o.t.0;
+      //^ var y
+          //^^^^^^^^^ expr o.inner.x
+                      //^^^^^ expr o.t.0
+    $0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn shadowed_locals_are_skipped() {
+        check(
+            r#"
+fn f() {
+    let x = 1;
+    let x = x + 1;
+      //^ var x
+    $0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn nested_functions() {
+        check(
+            r#"
+fn outer() {
+    let x = 1;
+    fn inner(y: i32) {
+           //^ var y
+        $0
+    }
+}
+"#,
+        );
+    }
+}
@@ -22,6 +22,7 @@ mod annotations;
 mod call_hierarchy;
 mod child_modules;
 mod doc_links;
+mod document_links;
 mod expand_macro;
 mod extend_selection;
 mod fetch_crates;
@@ -34,8 +35,10 @@ mod goto_type_definition;
 mod highlight_related;
 mod hover;
 mod inlay_hints;
+mod inline_values;
 mod interpret;
 mod join_lines;
+mod linked_editing_ranges;
 mod markdown_remove;
 mod matching_brace;
 mod moniker;
@@ -51,6 +54,7 @@ mod static_index;
 mod status;
 mod syntax_highlighting;
 mod test_explorer;
+mod type_hierarchy;
 mod typing;
 mod view_crate_graph;
 mod view_hir;
@@ -68,8 +72,8 @@ use hir::{ChangeWithProcMacros, EditionedFileId, crate_def_map, sym};
 use ide_db::{
     FxHashMap, FxIndexSet,
     base_db::{
-        AbsPathBuf, CrateOrigin, CrateWorkspaceData, Env, FileSet, SourceDatabase, VfsPath,
-        relevant_crates,
+        AbsPathBuf, AnchoredPathBuf, CrateOrigin, CrateWorkspaceData, Env, FileSet, SourceDatabase,
+        VfsPath, relevant_crates,
         salsa::{Cancelled, Database, Durability},
     },
     line_index, prime_caches,
@@ -86,6 +90,7 @@ use crate::navigation_target::ToNav;
 pub use crate::{
     annotations::{Annotation, AnnotationConfig, AnnotationKind, AnnotationLocation},
     call_hierarchy::{CallHierarchyConfig, CallItem},
+    document_links::{DocumentLink, DocumentLinkTarget},
     expand_macro::ExpandedMacro,
     file_structure::{FileStructureConfig, StructureNode, StructureNodeKind},
     folding_ranges::{Fold, FoldKind},
@@ -94,7 +99,8 @@ pub use crate::{
     highlight_related::{HighlightRelatedConfig, HighlightedRange},
     hover::{
         HoverAction, HoverConfig, HoverDocFormat, HoverGotoTypeData, HoverResult,
-        MemoryLayoutHoverConfig, MemoryLayoutHoverRenderKind, SubstTyLen,
+        MemoryLayoutHoverConfig, MemoryLayoutHoverRenderKind, NormalizedTypeHoverConfig,
+        SubstTyLen,
     },
     inlay_hints::{
         AdjustmentHints, AdjustmentHintsMode, ClosureReturnTypeHints, DiscriminantHints,
@@ -102,6 +108,7 @@ pub use crate::{
         InlayHintPosition, InlayHintsConfig, InlayKind, InlayTooltip, LazyProperty,
         LifetimeElisionHints, TypeHintsPlacement,
     },
+    inline_values::InlineValue,
     join_lines::JoinLinesConfig,
     markup::Markup,
     moniker::{
@@ -121,9 +128,10 @@ pub use crate::{
         HighlightConfig, HlRange,
         tags::{Highlight, HlMod, HlMods, HlOperator, HlPunct, HlTag},
     },
-    test_explorer::{TestItem, TestItemKind},
+    test_explorer::{DOC_TEST_ID_SUFFIX, TestItem, TestItemKind},
+    view_memory_layout::MemoryLayoutNodeKind,
 };
-pub use hir::{PredicateEvaluationResult, PredicateEvaluationStatus, Semantics};
+pub use hir::{EnumTagLayout, PredicateEvaluationResult, PredicateEvaluationStatus, Semantics};
 pub use ide_assists::{
     Assist, AssistConfig, AssistId, AssistKind, AssistResolveStrategy, SingleResolve,
 };
@@ -146,7 +154,7 @@ pub use ide_db::{
     symbol_index::Query,
     text_edit::{Indel, TextEdit},
 };
-pub use ide_diagnostics::{Diagnostic, DiagnosticCode, DiagnosticsConfig};
+pub use ide_diagnostics::{Diagnostic, DiagnosticCode, DiagnosticsConfig, LayoutBudget};
 pub use ide_ssr::SsrError;
 pub use span::Edition;
 pub use syntax::{TextRange, TextSize};
@@ -525,6 +533,21 @@ impl Analysis {
         })
     }
 
+    /// Returns the values a debugger can show inline in `range` while stopped at `stopped_at`.
+    pub fn inline_values(
+        &self,
+        file_id: FileId,
+        range: TextRange,
+        stopped_at: TextRange,
+    ) -> Cancellable<Vec<InlineValue>> {
+        self.with_db(|db| inline_values::inline_values(db, file_id, range, stopped_at))
+    }
+
+    /// Returns the links to files and URLs in the given file.
+    pub fn document_links(&self, file_id: FileId) -> Cancellable<Vec<DocumentLink>> {
+        self.with_db(|db| document_links::document_links(db, file_id))
+    }
+
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId, collapsed_text: bool) -> Cancellable<Vec<Fold>> {
         self.with_db(|db| {
@@ -662,6 +685,24 @@ impl Analysis {
         self.with_db(|db| call_hierarchy::outgoing_calls(db, config, position))
     }
 
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the supertypes of the trait or type at the given file position.
+    pub fn supertypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the subtypes of the trait at the given file position.
+    pub fn subtypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::subtypes(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancellable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
@@ -736,6 +777,14 @@ impl Analysis {
         })
     }
 
+    /// Computes the ranges that are edited together with the identifier at the given position.
+    pub fn linked_editing_ranges(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<Vec<TextRange>>> {
+        self.with_db(|db| linked_editing_ranges::linked_editing_ranges(db, position))
+    }
+
     /// Computes syntax highlighting for the given file
     pub fn highlight(
         &self,
@@ -890,6 +939,16 @@ impl Analysis {
         self.with_db(|db| rename::will_rename_file(db, file_id, new_name_stem, config))
     }
 
+    /// Computes the `mod` declarations to insert for the given files that are about to be created.
+    pub fn will_create_files(&self, files: &[AnchoredPathBuf]) -> Cancellable<SourceChange> {
+        self.with_db(|db| rename::will_create_files(db, files))
+    }
+
+    /// Computes the `mod` declarations to remove for the given files that are about to be deleted.
+    pub fn will_delete_files(&self, files: &[FileId]) -> Cancellable<SourceChange> {
+        self.with_db(|db| rename::will_delete_files(db, files))
+    }
+
     pub fn structural_search_replace(
         &self,
         query: &str,
@@ -0,0 +1,212 @@
+//! Computes the ranges that should be edited together with the identifier under the cursor.
+
+use hir::Semantics;
+use ide_db::{
+    FilePosition, RootDatabase,
+    defs::{Definition, IdentClass},
+    helpers::pick_best_token,
+};
+use syntax::{
+    AstNode, NodeOrToken, SyntaxElement,
+    SyntaxKind::{IDENT, LIFETIME_IDENT},
+    TextRange, TextSize, ast,
+};
+
+use crate::highlight_related::{self, HighlightRelatedConfig};
+
+// Feature: Linked Editing
+//
+// Editing a loop label, a lifetime parameter or a binding introduced by a `match` arm
+// simultaneously edits all of its occurrences in the surrounding item.
+pub(crate) fn linked_editing_ranges(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let sema = Semantics::new(db);
+    let file_id = sema.attach_first_edition(position.file_id);
+    let file = sema.parse(file_id);
+    let token =
+        pick_best_token(file.syntax().token_at_offset(position.offset), |kind| match kind {
+            IDENT | LIFETIME_IDENT => 1,
+            _ => 0,
+        })?;
+
+    let defs = IdentClass::classify_token(&sema, &token)?.definitions_no_ops();
+    let &[def] = defs.as_slice() else { return None };
+    if !is_linkable(&sema, def) {
+        return None;
+    }
+
+    let config = HighlightRelatedConfig { references: true, ..Default::default() };
+    // Point into the token we picked, as `highlight_related` has its own notion of the best token
+    // and would prefer the punctuation next to a lifetime.
+    let token_range = token.text_range();
+    let offset = if token_range.len() > 1.into() {
+        token_range.start() + TextSize::from(1)
+    } else {
+        position.offset
+    };
+    let position = FilePosition { offset, ..position };
+    let mut ranges = highlight_related::highlight_related(&sema, config, position)?
+        .into_iter()
+        .map(|it| it.range)
+        .collect::<Vec<_>>();
+    // `highlight_related` does not report the declaration of lifetime parameters.
+    if let Some(decl) = def.range_for_rename(&sema)
+        && decl.file_id == file_id
+    {
+        ranges.push(decl.range);
+    }
+    ranges.sort_by_key(|it| it.start());
+    ranges.dedup();
+
+    // Linked ranges must all hold the same text, and changing one of them must not require
+    // touching anything else, which rules out field shorthands.
+    let text = file.syntax().text();
+    let linkable = ranges.iter().all(|&range| {
+        text.slice(range) == token.text() && !is_shorthand(&file.syntax().covering_element(range))
+    });
+    (linkable && ranges.len() > 1).then_some(ranges)
+}
+
+fn is_linkable(sema: &Semantics<'_, RootDatabase>, def: Definition<'_>) -> bool {
+    match def {
+        Definition::Label(_) | Definition::GenericParam(hir::GenericParam::LifetimeParam(_)) => {
+            true
+        }
+        Definition::Local(local) => local.sources(sema.db).iter().all(|source| {
+            let Some(pat) = source.as_ident_pat() else { return false };
+            pat.syntax()
+                .ancestors()
+                .find_map(ast::MatchArm::cast)
+                .and_then(|arm| arm.pat())
+                .is_some_and(|arm_pat| {
+                    arm_pat.syntax().text_range().contains_range(pat.syntax().text_range())
+                })
+        }),
+        _ => false,
+    }
+}
+
+fn is_shorthand(element: &SyntaxElement) -> bool {
+    let node = match element {
+        NodeOrToken::Node(node) => node.clone(),
+        NodeOrToken::Token(token) => match token.parent() {
+            Some(it) => it,
+            None => return false,
+        },
+    };
+    if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+        return ast::RecordExprField::for_field_name(&name_ref)
+            .is_some_and(|field| field.name_ref().is_none());
+    }
+    if let Some(name) = ast::Name::cast(node) {
+        return ast::RecordPatField::for_field_name(&name)
+            .is_some_and(|field| field.name_ref().is_none());
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(#[rust_analyzer::rust_fixture] ra_fixture: &str) {
+        let (analysis, position, expected) = fixture::annotations(ra_fixture);
+        let ranges = analysis.linked_editing_ranges(position).unwrap().unwrap_or_default();
+        let expected = expected.into_iter().map(|(it, _)| it.range).collect::<Vec<_>>();
+        assert_eq!(expected, ranges);
+    }
+
+    #[test]
+    fn loop_label() {
+        check(
+            r#"
+fn f() {
+    'outer$0: loop {
+  //^^^^^^
+        loop {
+            break 'outer;
+                //^^^^^^
+        }
+        continue 'outer;
+               //^^^^^^
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn lifetime_param() {
+        check(
+            r#"
+struct S<'a>(&'a ());
+fn f<'a$0>(x: &'a i32, s: S<'a>) -> &'a i32 {
+   //^^      ^^           ^^       ^^
+    let _: &'a i32 = x;
+          //^^
+    x
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn match_arm_binding() {
+        check(
+            r#"
+fn f(x: Option<i32>) -> i32 {
+    match x {
+        Some(v$0) => v + 1,
+           //^     ^
+        None => 0,
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn or_pattern_binding() {
+        check(
+            r#"
+enum E { A(i32), B(i32) }
+fn f(e: E) -> i32 {
+    match e {
+        E::A(v) | E::B(v$0) => v,
+           //^         ^     ^
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_linked_editing_for_other_locals() {
+        check(
+            r#"
+fn f() -> i32 {
+    let x$0 = 1;
+    x
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_linked_editing_with_field_shorthand() {
+        check(
+            r#"
+struct S { v: i32 }
+fn f(x: Option<i32>) -> Option<S> {
+    match x {
+        Some(v$0) => Some(S { v }),
+        None => None,
+    }
+}
+"#,
+        );
+    }
+}
@@ -88,6 +88,26 @@ fn f<T>() { $0 }
         );
     }
 
+    #[test]
+    fn explains_unsatisfied_auto_trait_bound() {
+        let (analysis, position) = fixture::position(
+            r#"
+auto trait Send {}
+struct Rc<T>(T);
+impl<T> !Send for Rc<T> {}
+struct Foo { bar: Rc<i32> }
+fn f() { $0 }
+"#,
+        );
+        let result = analysis.evaluate_predicate("Foo: Send".to_owned(), position).unwrap();
+        assert_eq!(result.status, PredicateEvaluationStatus::NotProven);
+        assert_eq!(
+            result.message,
+            "predicate is not known to hold: `Rc<i32>: Send` is not satisfied, \
+             required by `Foo: Send` (field `bar`)"
+        );
+    }
+
     #[test]
     fn evaluates_associated_type_binding() {
         check(
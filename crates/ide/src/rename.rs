@@ -4,9 +4,13 @@
 //! tests. This module also implements a couple of magic tricks, like renaming
 //! `self` and to `self` (to switch between associated function and method).
 
-use hir::{AsAssocItem, FindPathConfig, HasContainer, HirDisplay, InFile, Name, Semantics, sym};
+use hir::{
+    AsAssocItem, FindPathConfig, HasContainer, HirDisplay, InFile, ModuleSource, Name, Semantics,
+    sym,
+};
 use ide_db::{
-    FileId, FileRange, RootDatabase,
+    FileId, FileRange, FxHashSet, FxIndexMap, RootDatabase,
+    base_db::{AnchoredPathBuf, SourceDatabase},
     defs::{Definition, NameClass, NameRefClass},
     rename::{IdentifierKind, RenameDefinition, bail, format_err, source_edit_from_references},
     source_change::SourceChangeBuilder,
@@ -15,8 +19,9 @@ use itertools::Itertools;
 use std::fmt::Write;
 use stdx::{always, format_to, never};
 use syntax::{
-    AstNode, SyntaxKind, SyntaxNode, TextRange, TextSize,
-    ast::{self, HasArgList, prec::ExprPrecedence},
+    AstNode, SyntaxElement, SyntaxKind, SyntaxNode, TextRange, TextSize,
+    ast::{self, HasArgList, HasModuleItem, HasName, edit::IndentLevel, prec::ExprPrecedence},
+    utils::{is_identifier, is_raw_identifier},
 };
 
 use ide_db::text_edit::TextEdit;
@@ -242,6 +247,176 @@ pub(crate) fn will_rename_file(
     Some(change)
 }
 
+/// Inserts `mod` declarations for files that are about to be created into the modules that will
+/// own them.
+pub(crate) fn will_create_files(db: &RootDatabase, files: &[AnchoredPathBuf]) -> SourceChange {
+    let sema = Semantics::new(db);
+    let mut new_mods = FxIndexMap::<_, Vec<_>>::default();
+    for file in files {
+        if let Some((module, name)) = owner_of_new_file(&sema, file)
+            && !new_mods.get(&module).is_some_and(|names| names.contains(&name))
+        {
+            new_mods.entry(module).or_default().push(name);
+        }
+    }
+
+    let mut change = SourceChange::default();
+    for (module, names) in new_mods {
+        if let Some((file_id, edit)) = insert_mod_decls(&sema, module, &names) {
+            change.insert_source_edit(file_id, edit);
+        }
+    }
+    change
+}
+
+/// Removes the `mod` declarations of files that are about to be deleted.
+pub(crate) fn will_delete_files(db: &RootDatabase, files: &[FileId]) -> SourceChange {
+    let sema = Semantics::new(db);
+    let deleted = files.iter().copied().collect::<FxHashSet<_>>();
+    let mut removals = FxIndexMap::<_, Vec<TextRange>>::default();
+    for &file_id in files {
+        for module in sema.file_to_module_defs(file_id) {
+            let Some(InFile { file_id: decl_file, value: decl }) = module.declaration_source(db)
+            else {
+                continue;
+            };
+            let Some(decl_file) = decl_file.file_id() else { continue };
+            let decl_file = decl_file.file_id(db);
+            // Declarations in files that are going away as well need no edit.
+            if deleted.contains(&decl_file) {
+                continue;
+            }
+            let mut range = decl.syntax().text_range();
+            // Take the rest of the line with the declaration, or the indentation for the last item
+            // of an inline module.
+            let is_whitespace = |it: &SyntaxElement| it.kind() == SyntaxKind::WHITESPACE;
+            if let Some(next) = decl.syntax().next_sibling_or_token().filter(is_whitespace) {
+                let len = next.as_token().map_or(0, |it| match it.text().find('\n') {
+                    Some(newline) => newline + 1,
+                    None => it.text().len(),
+                });
+                range = range.cover_offset(range.end() + TextSize::new(len as u32));
+            } else if let Some(prev) = decl.syntax().prev_sibling_or_token().filter(is_whitespace) {
+                range = range.cover(prev.text_range());
+            }
+            removals.entry(decl_file).or_default().push(range);
+        }
+    }
+
+    let mut change = SourceChange::default();
+    for (file_id, mut ranges) in removals {
+        ranges.sort_by_key(|it| it.start());
+        let mut builder = TextEdit::builder();
+        // Neighbouring declarations can claim the same whitespace.
+        for range in ranges.into_iter().coalesce(|prev, next| {
+            if prev.end() >= next.start() { Ok(prev.cover(next)) } else { Err((prev, next)) }
+        }) {
+            builder.delete(range);
+        }
+        change.insert_source_edit(file_id, builder.finish());
+    }
+    change
+}
+
+/// Finds the module whose `mod name;` item would resolve to the file at `path`, along with `name`.
+fn owner_of_new_file<'db>(
+    sema: &Semantics<'db, RootDatabase>,
+    path: &AnchoredPathBuf,
+) -> Option<(hir::Module, String)> {
+    let db = sema.db;
+    let source_root = db.file_source_root(path.anchor).source_root_id(db);
+    let source_root = db.source_root(source_root).source_root(db);
+    let new_path = source_root.path_for_file(&path.anchor)?.parent()?.join(&path.path)?;
+    let (stem, ext) = new_path.name_and_extension()?;
+    if ext != Some("rs") {
+        return None;
+    }
+    let mut dir = new_path.parent()?;
+    let name = match stem {
+        "mod" => {
+            let name = dir.name_and_extension()?.0.to_owned();
+            dir = dir.parent()?;
+            name
+        }
+        _ => stem.to_owned(),
+    };
+
+    // Like name resolution does, look for the children of crate roots and `mod.rs` files next to
+    // them and for the children of any other `foo.rs` in `foo/`.
+    let siblings = ["mod.rs", "lib.rs", "main.rs"].into_iter().map(|it| (dir.join(it), true));
+    let outer = dir
+        .name_and_extension()
+        .zip(dir.parent())
+        .map(|((dir_name, _), parent)| (parent.join(&format!("{dir_name}.rs")), false));
+    let module = siblings.chain(outer).find_map(|(candidate, owns_dir)| {
+        let file_id = *source_root.file_for_path(&candidate?)?;
+        sema.file_to_module_defs(file_id).find(|module| {
+            !module.is_inline(db) && (module.is_crate_root(db) || module.is_mod_rs(db)) == owns_dir
+        })
+    })?;
+
+    let edition = module.krate(db).edition(db);
+    let name = if is_identifier(&name, edition) {
+        name
+    } else if is_raw_identifier(&name, edition) {
+        format!("r#{name}")
+    } else {
+        return None;
+    };
+    let unescaped = name.trim_start_matches("r#");
+    let declared =
+        module.children(db).any(|child| child.name(db).is_some_and(|it| it.as_str() == unescaped));
+    (!declared).then_some((module, name))
+}
+
+fn insert_mod_decls(
+    sema: &Semantics<'_, RootDatabase>,
+    module: hir::Module,
+    names: &[String],
+) -> Option<(FileId, TextEdit)> {
+    fn is_outline_mod(item: &ast::Item) -> bool {
+        matches!(item, ast::Item::Module(m) if m.item_list().is_none())
+    }
+
+    let InFile { file_id, value: source } = module.definition_source(sema.db);
+    let file_id = file_id.file_id()?.file_id(sema.db);
+    let ModuleSource::SourceFile(source) = source else { return None };
+    let mut items = source.items();
+    // A `cfg`d out declaration does not show up as a child, but still must not be duplicated.
+    let names = names
+        .iter()
+        .filter(|name| {
+            !items.clone().any(|item| match item {
+                ast::Item::Module(m) if m.item_list().is_none() => {
+                    m.name().is_some_and(|it| it.text() == **name)
+                }
+                _ => false,
+            })
+        })
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        return None;
+    }
+
+    let mut builder = TextEdit::builder();
+    // Append after the first group of `mod m;` items if there is one, like `rustfmt` groups them.
+    match items.clone().skip_while(|item| !is_outline_mod(item)).take_while(is_outline_mod).last() {
+        Some(last) => {
+            let indent = IndentLevel::from_node(last.syntax());
+            let decls = names.iter().map(|name| format!("\n{indent}mod {name};")).join("");
+            builder.insert(last.syntax().text_range().end(), decls);
+        }
+        None => {
+            let decls = names.iter().map(|name| format!("mod {name};\n")).join("");
+            match items.next() {
+                Some(first) => builder.insert(first.syntax().text_range().start(), decls + "\n"),
+                None => builder.insert(source.syntax().text_range().end(), decls),
+            }
+        }
+    }
+    Some((file_id, builder.finish()))
+}
+
 // FIXME: Should support `extern crate`.
 fn alias_fallback(
     syntax: &SyntaxNode,
@@ -853,7 +1028,9 @@ mod tests {
     use stdx::trim_indent;
     use test_utils::assert_eq_text;
 
-    use crate::fixture;
+    use ide_db::{FileId, base_db::AnchoredPathBuf};
+
+    use crate::{Analysis, fixture};
 
     use super::{RangeInfo, RenameConfig, RenameError};
 
@@ -957,6 +1134,44 @@ mod tests {
         expect.assert_eq(&filter_expect(source_change))
     }
 
+    fn check_will_create_files(
+        new_paths: &[&str],
+        #[rust_analyzer::rust_fixture] ra_fixture: &str,
+        expect: Expect,
+    ) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let files = new_paths
+            .iter()
+            .map(|path| AnchoredPathBuf { anchor: position.file_id, path: (*path).to_owned() })
+            .collect::<Vec<_>>();
+        let source_change = analysis.will_create_files(&files).unwrap();
+        expect.assert_eq(&apply_source_change(&analysis, source_change))
+    }
+
+    fn check_will_delete_files(
+        deleted: &[u32],
+        #[rust_analyzer::rust_fixture] ra_fixture: &str,
+        expect: Expect,
+    ) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        let files = deleted.iter().map(|&it| FileId::from_raw(it)).collect::<Vec<_>>();
+        let source_change = analysis.will_delete_files(&files).unwrap();
+        expect.assert_eq(&apply_source_change(&analysis, source_change))
+    }
+
+    fn apply_source_change(analysis: &Analysis, source_change: SourceChange) -> String {
+        source_change
+            .source_file_edits
+            .into_iter()
+            .sorted_by_key(|(file_id, _)| *file_id)
+            .map(|(file_id, (edit, _))| {
+                let mut text = analysis.file_text(file_id).unwrap().to_string();
+                edit.apply(&mut text);
+                format!("//- {file_id:?}\n{text}")
+            })
+            .join("")
+    }
+
     fn check_prepare(#[rust_analyzer::rust_fixture] ra_fixture: &str, expect: Expect) {
         let (analysis, position) = fixture::position(ra_fixture);
         let result = analysis
@@ -1887,6 +2102,114 @@ mod bar$0;
         )
     }
 
+    #[test]
+    fn will_create_file_next_to_crate_root() {
+        check_will_create_files(
+            &["baz.rs", "qux/mod.rs"],
+            r#"
+//- /lib.rs
+$0//! Docs.
+mod foo;
+mod bar;
+
+use foo::S;
+//- /foo.rs
+pub struct S;
+//- /bar.rs
+"#,
+            expect![[r#"
+                //- FileId(0)
+                //! Docs.
+                mod foo;
+                mod bar;
+                mod baz;
+                mod qux;
+
+                use foo::S;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn will_create_file_in_module_directory() {
+        check_will_create_files(
+            &["foo/bar.rs", "foo/mod.rs", "other/baz.rs"],
+            r#"
+//- /lib.rs
+$0mod foo;
+//- /foo.rs
+fn f() {}
+"#,
+            expect![[r#"
+                //- FileId(1)
+                mod bar;
+
+                fn f() {}
+            "#]],
+        );
+    }
+
+    #[test]
+    fn will_create_file_in_mod_rs_directory() {
+        check_will_create_files(
+            &["fn.rs"],
+            r#"
+//- /lib.rs
+mod foo;
+//- /foo/mod.rs
+$0// Empty.
+"#,
+            expect![[r#"
+                //- FileId(1)
+                // Empty.
+                mod r#fn;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn will_create_file_already_declared() {
+        check_will_create_files(
+            &["foo.rs", "bar.rs"],
+            r#"
+//- /lib.rs
+$0mod foo;
+#[cfg(any())]
+mod bar;
+"#,
+            expect![[""]],
+        );
+    }
+
+    #[test]
+    fn will_delete_files() {
+        check_will_delete_files(
+            &[1, 2, 3],
+            r#"
+//- /lib.rs
+mod foo;
+mod bar;
+mod baz;
+pub mod qux;
+
+fn f() {}
+//- /foo.rs
+mod inner;
+//- /foo/inner.rs
+//- /bar.rs
+//- /baz.rs
+//- /qux.rs
+"#,
+            expect![[r#"
+                //- FileId(0)
+                mod baz;
+                pub mod qux;
+
+                fn f() {}
+            "#]],
+        );
+    }
+
     #[test]
     fn test_rename_mod_to_raw_ident() {
         check_expect(
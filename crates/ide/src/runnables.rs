@@ -3,7 +3,7 @@ use std::{fmt, sync::OnceLock};
 use arrayvec::ArrayVec;
 use ast::HasName;
 use cfg::{CfgAtom, CfgExpr};
-use hir::{AsAssocItem, HasAttrs, HasCrate, HasSource, Semantics, Symbol, sym};
+use hir::{AsAssocItem, HasAttrs, HasCrate, HasSource, Module, Semantics, Symbol, sym};
 use ide_assists::utils::{has_test_related_attribute, test_related_attribute_syn};
 use ide_db::base_db::all_crates;
 use ide_db::impl_empty_upmap_from_ra_fixture;
@@ -487,6 +487,14 @@ fn runnable_mod_outline_definition(
     })
 }
 
+/// The doc tests of `def`, including those of the crate docs when `def` is the crate root.
+pub(crate) fn doctest(sema: &Semantics<'_, RootDatabase>, def: Definition<'_>) -> Option<Runnable> {
+    module_def_doctest(sema, def).or_else(|| match def {
+        Definition::Module(module) => crate_doctest(sema, module),
+        _ => None,
+    })
+}
+
 fn module_def_doctest(sema: &Semantics<'_, RootDatabase>, def: Definition<'_>) -> Option<Runnable> {
     let db = sema.db;
     let attrs = match def {
@@ -556,6 +564,27 @@ fn module_def_doctest(sema: &Semantics<'_, RootDatabase>, def: Definition<'_>) -
     Some(res)
 }
 
+/// The doc tests of the crate root's docs. Rustdoc names them `<path> - (line <n>)`, without the
+/// item path the doc tests of other items have, so the filter only matches those.
+fn crate_doctest(sema: &Semantics<'_, RootDatabase>, module: Module) -> Option<Runnable> {
+    let db = sema.db;
+    let attrs = module.attrs(db);
+    if !module.is_crate_root(db) || !has_runnable_doc_test(db, &attrs) {
+        return None;
+    }
+    let mut nav = NavigationTarget::from_module_to_decl(db, module).call_site();
+    nav.focus_range = None;
+    nav.description = None;
+    nav.kind = None;
+    Some(Runnable {
+        use_name_in_title: false,
+        nav,
+        kind: RunnableKind::DocTest { test_id: TestId::Name(SmolStr::new_static(" - (line ")) },
+        cfg: attrs.cfgs(db).cloned(),
+        update_test: UpdateTest::default(),
+    })
+}
+
 fn has_runnable_doc_test(db: &RootDatabase, attrs: &hir::AttrsWithOwner) -> bool {
     const RUSTDOC_FENCES: [&str; 2] = ["```", "~~~"];
     const RUSTDOC_CODE_BLOCK_ATTRIBUTES_RUNNABLE: &[&str] =
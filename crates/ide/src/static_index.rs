@@ -183,6 +183,7 @@ impl<'a> StaticIndex<'a> {
         let hover_config = HoverConfig {
             links_in_hover: true,
             memory_layout: None,
+            normalized_type: None,
             documentation: true,
             keywords: true,
             format: crate::HoverDocFormat::Markdown,
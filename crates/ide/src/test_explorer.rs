@@ -1,17 +1,31 @@
 //! Discovers tests
 
-use hir::{Crate, Module, ModuleDef, Semantics};
+use hir::{Crate, Module, ModuleDef, Semantics, Symbol};
 use ide_db::base_db::{self, all_crates};
-use ide_db::{FileId, RootDatabase};
-use syntax::TextRange;
+use ide_db::{FileId, RootDatabase, SymbolKind, defs::Definition};
+use itertools::Itertools;
+use syntax::{
+    TextRange,
+    ast::{self, AstNode, HasArgList},
+};
 
-use crate::{NavigationTarget, Runnable, TryToNav, runnables::runnable_fn};
+use crate::{
+    NavigationTarget, Runnable, RunnableKind, TryToNav,
+    runnables::{TestId, UpdateTest, doctest, runnable_fn},
+};
+
+/// Appended to the id of the item whose doc tests a [`TestItemKind::DocTest`] item runs, e.g.
+/// `my_crate::module::Item (doctest)`.
+pub const DOC_TEST_ID_SUFFIX: &str = " (doctest)";
 
 #[derive(Debug)]
 pub enum TestItemKind {
     Crate(base_db::Crate),
     Module,
+    /// A `#[test]` or `#[bench]` function, or a test registered with a custom test harness.
     Function,
+    /// The doc tests of an item.
+    DocTest,
 }
 
 #[derive(Debug)]
@@ -63,6 +77,7 @@ fn discover_tests_in_module(
     let sema = Semantics::new(db);
 
     let mut r = vec![];
+    r.extend(doc_test_item(&sema, module.into(), module, &prefix_id));
     for c in module.children(db) {
         let module_name = c
             .name(db)
@@ -88,10 +103,11 @@ fn discover_tests_in_module(
         }
     }
     for def in module.declarations(db) {
+        r.extend(doc_test_item(&sema, def.into(), module, &prefix_id));
         let ModuleDef::Function(f) = def else {
             continue;
         };
-        if !f.is_test(db) {
+        if !f.is_test(db) && !f.is_bench(db) {
             continue;
         }
         let nav = f.try_to_nav(&sema).map(|r| r.call_site);
@@ -106,6 +122,141 @@ fn discover_tests_in_module(
             runnable: runnable_fn(&sema, f),
         });
     }
+    for impl_ in module.impl_defs(db) {
+        for item in impl_.items(db) {
+            r.extend(doc_test_item(&sema, item.into(), module, &prefix_id));
+        }
+    }
+    r
+}
+
+/// Creates a test item for the doc tests of `def`, which is declared in `module`.
+fn doc_test_item(
+    sema: &Semantics<'_, RootDatabase>,
+    def: Definition<'_>,
+    module: Module,
+    module_id: &str,
+) -> Option<TestItem> {
+    let runnable = doctest(sema, def)?;
+    let RunnableKind::DocTest { test_id } = &runnable.kind else { return None };
+    let (id, label) = if let Definition::Module(_) = def {
+        (
+            format!("{module_id}{DOC_TEST_ID_SUFFIX}"),
+            format!("{}{DOC_TEST_ID_SUFFIX}", module_label(sema.db, module)),
+        )
+    } else {
+        // The test id is the path of the item from the crate root, e.g. `module::Type::method`.
+        let module_path = module
+            .path_to_root(sema.db)
+            .into_iter()
+            .rev()
+            .filter_map(|it| it.name(sema.db))
+            .map(|name| name.as_str().to_owned())
+            .join("::");
+        let test_id = test_id.to_string();
+        let item_path = match test_id.strip_prefix(&module_path) {
+            Some(rest) if !module_path.is_empty() => rest.trim_start_matches("::"),
+            _ => &test_id,
+        };
+        (
+            format!("{module_id}::{item_path}{DOC_TEST_ID_SUFFIX}"),
+            format!("{item_path}{DOC_TEST_ID_SUFFIX}"),
+        )
+    };
+    Some(TestItem {
+        id,
+        kind: TestItemKind::DocTest,
+        label,
+        parent: Some(module_id.to_owned()),
+        file: Some(runnable.nav.file_id),
+        text_range: Some(runnable.nav.focus_or_full_range()),
+        runnable: Some(runnable),
+    })
+}
+
+fn module_label(db: &RootDatabase, module: Module) -> String {
+    match module.name(db) {
+        Some(name) => name.as_str().to_owned(),
+        None => module
+            .krate(db)
+            .display_name(db)
+            .map(|it| it.to_string())
+            .unwrap_or_else(|| "[mod without name]".to_owned()),
+    }
+}
+
+/// Returns the name of the test registered by a `Trial::test("name", ...)` call, and whether it
+/// is a benchmark.
+fn trial_name(call: &ast::CallExpr) -> Option<(String, bool)> {
+    let ast::Expr::PathExpr(callee) = call.expr()? else { return None };
+    let path = callee.path()?;
+    let function = path.segment()?.name_ref()?;
+    let ty = path.qualifier()?.segment()?.name_ref()?;
+    if ty.text() != "Trial" || !matches!(function.text().as_ref(), "test" | "bench") {
+        return None;
+    }
+    let ast::Expr::Literal(name) = call.arg_list()?.args().next()? else { return None };
+    let ast::LiteralKind::String(name) = name.kind() else { return None };
+    Some((name.value().ok()?.into_owned(), function.text() == "bench"))
+}
+
+/// Finds tests registered with `libtest-mimic`, the most common library for custom test harnesses
+/// (`harness = false` targets), i.e. calls like `Trial::test("name", ...)` with a literal name.
+fn discover_custom_harness_tests(
+    sema: &Semantics<'_, RootDatabase>,
+    krate: Crate,
+    crate_test_id: &str,
+) -> Vec<TestItem> {
+    let db = sema.db;
+    if !krate.dependencies(db).iter().any(|dep| dep.name.as_str() == "libtest_mimic") {
+        return vec![];
+    }
+    let mut r = vec![];
+    for file_id in krate.modules(db).into_iter().filter_map(|it| it.as_source_file_id(db)) {
+        // Trials are usually collected with `vec![]`, so also look into macro expansions.
+        let mut nodes = vec![sema.parse(file_id).syntax().clone()];
+        while let Some(node) = nodes.pop() {
+            for node in node.descendants() {
+                if let Some(macro_call) = ast::MacroCall::cast(node.clone()) {
+                    nodes.extend(sema.expand_macro_call(&macro_call).map(|it| it.value));
+                    continue;
+                }
+                let Some(call) = ast::CallExpr::cast(node) else { continue };
+                let Some((name, is_bench)) = trial_name(&call) else { continue };
+                let range = sema.original_range(call.syntax());
+                let file_id = range.file_id.file_id(db);
+                let nav = NavigationTarget::from_syntax(
+                    file_id,
+                    Symbol::intern(&name),
+                    None,
+                    range.range,
+                    SymbolKind::Function,
+                );
+                // libtest-mimic filters trials by name like libtest does.
+                let test_id = TestId::Name(name.as_str().into());
+                let kind = if is_bench {
+                    RunnableKind::Bench { test_id }
+                } else {
+                    RunnableKind::Test { test_id }
+                };
+                r.push(TestItem {
+                    id: format!("{crate_test_id}::{name}"),
+                    kind: TestItemKind::Function,
+                    label: name,
+                    parent: Some(crate_test_id.to_owned()),
+                    file: Some(file_id),
+                    text_range: Some(range.range),
+                    runnable: Some(Runnable {
+                        use_name_in_title: false,
+                        nav,
+                        kind,
+                        cfg: None,
+                        update_test: UpdateTest::default(),
+                    }),
+                });
+            }
+        }
+    }
     r
 }
 
@@ -191,6 +342,129 @@ pub(crate) fn discover_tests_in_crate(
         text_range: None,
         runnable: None,
     }];
-    r.extend(discover_tests_in_module(db, module, crate_test_id, false));
+    r.extend(discover_tests_in_module(db, module, crate_test_id.clone(), false));
+    r.extend(discover_custom_harness_tests(&Semantics::new(db), crate_id, &crate_test_id));
     r
 }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{Expect, expect};
+
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, crate_id: &str, expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        let tests = analysis.discover_tests_in_crate_by_test_id(crate_id).unwrap();
+        let actual = tests
+            .iter()
+            .map(|it| {
+                let runnable = it.runnable.as_ref().map(|it| it.label(None));
+                format!("{:?} {} ({:?}) {runnable:?}\n", it.kind, it.id, it.parent)
+            })
+            .collect::<String>();
+        let actual = actual.replace(|c: char| c.is_ascii_digit(), "_");
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn discovers_tests_benches_and_doc_tests() {
+        check(
+            r#"
+//- /lib.rs crate:foo
+//! ```
+//! foo::f();
+//! ```
+
+/// ```
+/// foo::f();
+/// ```
+pub fn f() {}
+
+/// ```ignore
+/// ```
+pub fn ignored() {}
+
+pub mod m {
+    pub struct S;
+    impl S {
+        /// ```
+        /// let _ = foo::m::S::new();
+        /// ```
+        pub fn new() -> S { S }
+    }
+
+    #[test]
+    fn t() {}
+
+    #[bench]
+    fn b() {}
+}
+"#,
+            "foo",
+            expect![[r#"
+                Crate(Crate(Id(___))) foo (None) None
+                DocTest foo (doctest) (Some("foo")) Some("doctest  - (line ")
+                Module foo::m (Some("foo")) None
+                Function foo::m::t (Some("foo::m")) Some("test m::t")
+                Function foo::m::b (Some("foo::m")) Some("bench m::b")
+                DocTest foo::m::S::new (doctest) (Some("foo::m")) Some("doctest m::S::new")
+                DocTest foo::f (doctest) (Some("foo")) Some("doctest f")
+            "#]],
+        );
+    }
+
+    #[test]
+    fn discovers_libtest_mimic_trials() {
+        check(
+            r#"
+//- /main.rs crate:harness deps:libtest_mimic
+use libtest_mimic::Trial;
+
+macro_rules! vec {
+    ($($it:tt)*) => { [$($it)*] };
+}
+
+fn main() {
+    let tests = vec![
+        Trial::test("check_toph", || Ok(())),
+        libtest_mimic::Trial::bench("bench_toph", |_| Ok(())),
+        Trial::test(dynamic_name(), || Ok(())),
+    ];
+}
+//- /lib.rs crate:libtest_mimic
+pub struct Trial;
+"#,
+            "harness",
+            expect![[r#"
+                Crate(Crate(Id(___))) harness (None) None
+                Function harness::check_toph (Some("harness")) Some("test check_toph")
+                Function harness::bench_toph (Some("harness")) Some("bench bench_toph")
+            "#]],
+        );
+    }
+
+    #[test]
+    fn discovers_tests_from_attribute_macros() {
+        check(
+            r#"
+//- proc_macros: async_test
+//- /lib.rs crate:foo
+#[proc_macros::async_test]
+async fn t() {}
+
+mod m {
+    #[proc_macros::async_test]
+    async fn t() {}
+}
+"#,
+            "foo",
+            expect![[r#"
+                Crate(Crate(Id(___))) foo (None) None
+                Module foo::m (Some("foo")) None
+                Function foo::m::t (Some("foo::m")) Some("test m::t")
+                Function foo::t (Some("foo")) Some("test t")
+            "#]],
+        );
+    }
+}
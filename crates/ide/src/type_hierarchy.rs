@@ -0,0 +1,271 @@
+//! Entry point for type-hierarchy
+
+use hir::{Adt, Impl, Semantics, Trait};
+use ide_db::{
+    FxIndexSet, RootDatabase,
+    defs::{Definition, NameClass, NameRefClass},
+    helpers::pick_best_token,
+    search::FileReference,
+};
+use syntax::{AstNode, SyntaxKind::IDENT, T, ast};
+
+use crate::{FilePosition, NavigationTarget, RangeInfo, TryToNav};
+
+/// A node in the type hierarchy: either a trait or a nominal type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum HierarchyItem {
+    Trait(Trait),
+    Adt(Adt),
+}
+
+impl HierarchyItem {
+    fn to_navs(self, sema: &Semantics<'_, RootDatabase>) -> Vec<NavigationTarget> {
+        let navs = match self {
+            HierarchyItem::Trait(it) => it.try_to_nav(sema),
+            HierarchyItem::Adt(it) => it.try_to_nav(sema),
+        };
+        navs.into_iter().flatten().collect()
+    }
+}
+
+// Feature: Type Hierarchy
+//
+// Shows the supertraits and implementing types of a trait, and the traits implemented by a type.
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = Semantics::new(db);
+    let RangeInfo { range, info: items } = hierarchy_items_at(&sema, position)?;
+    let navs = items.into_iter().flat_map(|item| item.to_navs(&sema)).collect();
+    Some(RangeInfo { range, info: navs })
+}
+
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let items = hierarchy_items_at(&sema, position)?.info;
+
+    let mut supertypes = FxIndexSet::default();
+    for item in items {
+        match item {
+            HierarchyItem::Trait(trait_) => {
+                supertypes
+                    .extend(trait_.direct_supertraits(db).into_iter().map(HierarchyItem::Trait));
+            }
+            HierarchyItem::Adt(adt) => {
+                supertypes.extend(
+                    Impl::all_for_type(db, adt.ty(db))
+                        .into_iter()
+                        .filter_map(|impl_| impl_.trait_(db))
+                        .map(HierarchyItem::Trait),
+                );
+            }
+        }
+    }
+    Some(supertypes.into_iter().flat_map(|item| item.to_navs(&sema)).collect())
+}
+
+pub(crate) fn subtypes(db: &RootDatabase, position: FilePosition) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let items = hierarchy_items_at(&sema, position)?.info;
+
+    let mut subtypes = FxIndexSet::default();
+    for item in items {
+        // Types have no subtypes in the hierarchy, only traits do.
+        let HierarchyItem::Trait(trait_) = item else { continue };
+        subtypes.extend(direct_subtraits(&sema, trait_).into_iter().map(HierarchyItem::Trait));
+        // Blanket impls have no nominal self type to point to, so they are skipped.
+        subtypes.extend(
+            Impl::all_for_trait(db, trait_)
+                .into_iter()
+                .filter_map(|impl_| impl_.self_ty(db).as_adt())
+                .map(HierarchyItem::Adt),
+        );
+    }
+    Some(subtypes.into_iter().flat_map(|item| item.to_navs(&sema)).collect())
+}
+
+fn hierarchy_items_at(
+    sema: &Semantics<'_, RootDatabase>,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<RangeInfo<Vec<HierarchyItem>>> {
+    let file = sema.parse_guess_edition(file_id);
+    let token = pick_best_token(file.syntax().token_at_offset(offset), |kind| match kind {
+        IDENT | T![Self] => 1,
+        _ => 0,
+    })?;
+    let range = token.text_range();
+
+    let mut items = FxIndexSet::default();
+    for token in sema.descend_into_macros_exact(token) {
+        let Some(name_like) = token.parent().and_then(ast::NameLike::cast) else { continue };
+        let def = match name_like {
+            ast::NameLike::Name(name) => match NameClass::classify(sema, &name) {
+                Some(NameClass::Definition(def)) => def,
+                _ => continue,
+            },
+            ast::NameLike::NameRef(name_ref) => match NameRefClass::classify(sema, &name_ref) {
+                Some(NameRefClass::Definition(def, _)) => def,
+                _ => continue,
+            },
+            ast::NameLike::Lifetime(_) => continue,
+        };
+        let item = match def {
+            Definition::Trait(it) => HierarchyItem::Trait(it),
+            Definition::Adt(it) => HierarchyItem::Adt(it),
+            Definition::SelfType(impl_) => match impl_.self_ty(sema.db).as_adt() {
+                Some(it) => HierarchyItem::Adt(it),
+                None => continue,
+            },
+            _ => continue,
+        };
+        items.insert(item);
+    }
+    Some(RangeInfo { range, info: items.into_iter().collect() })
+}
+
+/// Finds the traits that name `trait_` as one of their direct supertraits.
+fn direct_subtraits(sema: &Semantics<'_, RootDatabase>, trait_: Trait) -> Vec<Trait> {
+    let mut subtraits = FxIndexSet::default();
+    for (_, references) in Definition::Trait(trait_).usages(sema).all() {
+        for FileReference { name, .. } in references {
+            let Some(name_ref) = name.as_name_ref() else { continue };
+            let Some(subtrait) = sema
+                .ancestors_with_macros(name_ref.syntax().clone())
+                .find_map(ast::Trait::cast)
+                .and_then(|it| sema.to_def(&it))
+            else {
+                continue;
+            };
+            if subtrait != trait_ && subtrait.direct_supertraits(sema.db).contains(&trait_) {
+                subtraits.insert(subtrait);
+            }
+        }
+    }
+    subtraits.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{Expect, expect};
+    use ide_db::FilePosition;
+    use itertools::Itertools;
+
+    use crate::fixture;
+
+    fn check_hierarchy(
+        #[rust_analyzer::rust_fixture] ra_fixture: &str,
+        expected_nav: Expect,
+        expected_supertypes: Expect,
+        expected_subtypes: Expect,
+    ) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+
+        let mut navs = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        expected_nav.assert_eq(&nav.debug_render());
+
+        let item_pos =
+            FilePosition { file_id: nav.file_id, offset: nav.focus_or_full_range().start() };
+        let supertypes = analysis.supertypes(item_pos).unwrap().unwrap();
+        expected_supertypes.assert_eq(&supertypes.iter().map(|it| it.debug_render()).join("\n"));
+
+        let subtypes = analysis.subtypes(item_pos).unwrap().unwrap();
+        expected_subtypes.assert_eq(&subtypes.iter().map(|it| it.debug_render()).join("\n"));
+    }
+
+    #[test]
+    fn trait_hierarchy() {
+        check_hierarchy(
+            r#"
+trait Base {}
+trait Mid$0: Base {}
+trait Leaf where Self: Mid {}
+struct S;
+impl Base for S {}
+impl Mid for S {}
+enum E {}
+impl Mid for E {}
+impl<T> Mid for [T] {}
+"#,
+            expect!["Mid Trait FileId(0) 14..32 20..23"],
+            expect!["Base Trait FileId(0) 0..13 6..10"],
+            expect![[r#"
+                Leaf Trait FileId(0) 33..62 39..43
+                E Enum FileId(0) 110..119 115..116
+                S Struct FileId(0) 63..72 70..71"#]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_on_struct() {
+        check_hierarchy(
+            r#"
+trait A {}
+trait B {}
+struct S$0;
+impl S {}
+impl A for S {}
+impl B for S {}
+impl B for &S {}
+"#,
+            expect!["S Struct FileId(0) 22..31 29..30"],
+            expect![[r#"
+                A Trait FileId(0) 0..10 6..7
+                B Trait FileId(0) 11..21 17..18"#]],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_on_self_type() {
+        check_hierarchy(
+            r#"
+trait A {}
+struct S;
+impl S {
+    fn f(_: Self$0) {}
+}
+impl A for S {}
+"#,
+            expect!["S Struct FileId(0) 11..20 18..19"],
+            expect!["A Trait FileId(0) 0..10 6..7"],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_across_files() {
+        check_hierarchy(
+            r#"
+//- /lib.rs
+mod other;
+trait Tr$0 {}
+//- /other.rs
+use crate::Tr;
+trait Sub: Tr {}
+struct S;
+impl Tr for S {}
+"#,
+            expect!["Tr Trait FileId(0) 11..22 17..19"],
+            expect![[]],
+            expect![[r#"
+                Sub Trait FileId(1) 15..31 21..24 other
+                S Struct FileId(1) 32..41 39..40 other"#]],
+        );
+    }
+
+    #[test]
+    fn no_hierarchy_for_functions() {
+        let (analysis, pos) = fixture::position(
+            r#"
+fn f$0() {}
+"#,
+        );
+        assert!(analysis.type_hierarchy(pos).unwrap().unwrap().info.is_empty());
+    }
+}
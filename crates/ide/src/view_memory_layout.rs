@@ -1,6 +1,6 @@
 use std::fmt;
 
-use hir::{DisplayTarget, Field, HirDisplay, Layout, Semantics, Type};
+use hir::{Adt, DisplayTarget, EnumTagLayout, Field, HirDisplay, Layout, Semantics, Type};
 use ide_db::{
     RootDatabase,
     defs::Definition,
@@ -19,6 +19,20 @@ pub struct MemoryLayoutNode {
     pub parent_idx: i64,
     pub children_start: i64,
     pub children_len: u64,
+    /// Number of bytes of the node that aren't covered by any of its fields or its tag.
+    pub padding: u64,
+    pub kind: MemoryLayoutNodeKind,
+    /// The tag of an enum with multiple variants, whose children are its variants.
+    pub tag: Option<EnumTagLayout>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryLayoutNodeKind {
+    /// A type, a field or a tuple element.
+    Item,
+    /// A variant of the enum of its parent, with the value the tag of the enum holds for it,
+    /// which is `None` for the variant whose field the tag is a niche of.
+    Variant { tag_value: Option<u128> },
 }
 
 pub struct RecursiveMemoryLayout {
@@ -36,11 +50,37 @@ impl fmt::Display for RecursiveMemoryLayout {
         ) -> fmt::Result {
             let mut out = "\t".repeat(depth);
             let node = &nodes[idx];
+            if let MemoryLayoutNodeKind::Variant { tag_value } = node.kind {
+                out += &match tag_value {
+                    Some(value) => format!("variant (tag value: {value}) "),
+                    None => "variant (untagged) ".to_owned(),
+                };
+            }
             out += &format!(
-                "{}: {} (size: {}, align: {}, field offset: {})\n",
+                "{}: {} (size: {}, align: {}, field offset: {}",
                 node.item_name, node.typename, node.size, node.alignment, node.offset
             );
-            write!(fmt, "{out}")?;
+            if node.padding != 0 {
+                out += &format!(", padding: {}", node.padding);
+            }
+            out += ")";
+            if let Some(tag) = &node.tag {
+                let (start, end) = tag.valid_range;
+                // A wrapping range is shown as the two ranges it's made of.
+                let valid_range = if start > end {
+                    let max = u128::MAX >> (128 - 8 * tag.size.clamp(1, 16));
+                    format!("{start}..={max} | 0..={end}")
+                } else {
+                    format!("{start}..={end}")
+                };
+                out += &format!(
+                    " [{} tag (size: {}, offset: {}, valid range: {valid_range})]",
+                    if tag.niche { "niche" } else { "direct" },
+                    tag.size,
+                    tag.offset,
+                );
+            }
+            writeln!(fmt, "{out}")?;
             if node.children_start != -1 {
                 for j in nodes[idx].children_start
                     ..(nodes[idx].children_start + nodes[idx].children_len as i64)
@@ -112,7 +152,7 @@ pub(crate) fn view_memory_layout(
         parent_idx: usize,
         display_target: DisplayTarget,
     ) {
-        let mut fields = ty
+        let fields = ty
             .fields(db)
             .into_iter()
             .map(|(f, ty)| (FieldOrTupleIdx::Field(f), ty))
@@ -124,7 +164,100 @@ pub(crate) fn view_memory_layout(
             )
             .collect::<Vec<_>>();
 
+        if let Some(Adt::Enum(enum_)) = ty.as_adt()
+            && let Some(variant_layouts) = layout.enum_variants()
+        {
+            read_enum_layout(
+                nodes,
+                db,
+                ty,
+                enum_,
+                layout,
+                variant_layouts,
+                parent_idx,
+                display_target,
+            );
+            return;
+        }
+
+        read_fields_layout(nodes, db, fields, layout, parent_idx, None, display_target);
+    }
+
+    fn read_enum_layout(
+        nodes: &mut Vec<MemoryLayoutNode>,
+        db: &RootDatabase,
+        ty: &Type<'_>,
+        enum_: hir::Enum,
+        layout: &Layout<'_>,
+        variant_layouts: Vec<Layout<'_>>,
+        parent_idx: usize,
+        display_target: DisplayTarget,
+    ) {
+        let tag = layout.enum_tag();
+        let tag_range = tag.as_ref().map(|tag| (tag.offset, tag.size));
+        let variants = enum_.variants(db);
+
+        let children_start = nodes.len();
+        nodes[parent_idx].children_start = children_start as i64;
+        nodes[parent_idx].children_len = variants.len() as u64;
+        nodes[parent_idx].tag = tag;
+
+        let typename = nodes[parent_idx].typename.clone();
+        for (variant, variant_layout) in variants.iter().zip(&variant_layouts) {
+            let name = variant.name(db).display(db, display_target.edition).to_string();
+            nodes.push(MemoryLayoutNode {
+                typename: format!("{typename}::{name}"),
+                item_name: name,
+                size: variant_layout.size(),
+                alignment: variant_layout.align(),
+                offset: 0,
+                parent_idx: parent_idx as i64,
+                children_start: -1,
+                children_len: 0,
+                padding: 0,
+                kind: MemoryLayoutNodeKind::Variant {
+                    tag_value: layout.enum_tag_value(db, *variant),
+                },
+                tag: None,
+            });
+        }
+
+        let mut occupied: Vec<_> = tag_range.into_iter().collect();
+        for (i, (variant, variant_layout)) in variants.iter().zip(&variant_layouts).enumerate() {
+            let fields = ty
+                .variant_fields(db, *variant)
+                .into_iter()
+                .map(|(f, ty)| (FieldOrTupleIdx::Field(f), ty))
+                .collect();
+            read_fields_layout(
+                nodes,
+                db,
+                fields,
+                variant_layout,
+                children_start + i,
+                tag_range,
+                display_target,
+            );
+            occupied.extend(field_ranges(nodes, children_start + i));
+        }
+        nodes[parent_idx].padding = padding(layout.size(), occupied);
+    }
+
+    /// Adds the nodes of `fields` as the children of `parent_idx`, whose bytes in `tag_range`
+    /// hold the tag of an enum.
+    fn read_fields_layout(
+        nodes: &mut Vec<MemoryLayoutNode>,
+        db: &RootDatabase,
+        mut fields: Vec<(FieldOrTupleIdx, Type<'_>)>,
+        layout: &Layout<'_>,
+        parent_idx: usize,
+        tag_range: Option<(u64, u64)>,
+        display_target: DisplayTarget,
+    ) {
         if fields.is_empty() {
+            if tag_range.is_some() {
+                nodes[parent_idx].padding = padding(layout.size(), tag_range);
+            }
             return;
         }
 
@@ -151,6 +284,9 @@ pub(crate) fn view_memory_layout(
                     parent_idx: parent_idx as i64,
                     children_start: -1,
                     children_len: 0,
+                    padding: 0,
+                    kind: MemoryLayoutNodeKind::Item,
+                    tag: None,
                 });
             } else {
                 nodes.push(MemoryLayoutNode {
@@ -164,9 +300,14 @@ pub(crate) fn view_memory_layout(
                     parent_idx: parent_idx as i64,
                     children_start: -1,
                     children_len: 0,
+                    padding: 0,
+                    kind: MemoryLayoutNodeKind::Item,
+                    tag: None,
                 });
             }
         }
+        let occupied = field_ranges(nodes, parent_idx).chain(tag_range).collect::<Vec<_>>();
+        nodes[parent_idx].padding = padding(layout.size(), occupied);
 
         for (i, (_, child_ty)) in fields.iter().enumerate() {
             if let Ok(child_layout) = child_ty.layout(db) {
@@ -199,6 +340,9 @@ pub(crate) fn view_memory_layout(
                 parent_idx: -1,
                 children_start: -1,
                 children_len: 0,
+                padding: 0,
+                kind: MemoryLayoutNodeKind::Item,
+                tag: None,
             }];
             read_layout(&mut nodes, db, &ty, &layout, 0, display_target);
 
@@ -207,6 +351,32 @@ pub(crate) fn view_memory_layout(
         .ok()
 }
 
+/// The `(offset, size)` of the children of the node at `idx`.
+fn field_ranges(nodes: &[MemoryLayoutNode], idx: usize) -> impl Iterator<Item = (u64, u64)> + '_ {
+    let node = &nodes[idx];
+    let children = match node.children_start {
+        -1 => &[][..],
+        start => &nodes[start as usize..][..node.children_len as usize],
+    };
+    children.iter().map(|child| (child.offset, child.size))
+}
+
+/// Number of bytes in `0..size` that aren't covered by any of the `(offset, size)` ranges.
+fn padding(size: u64, ranges: impl IntoIterator<Item = (u64, u64)>) -> u64 {
+    let mut ranges: Vec<_> = ranges.into_iter().collect();
+    ranges.sort_unstable();
+    let mut covered = 0;
+    let mut end = 0;
+    for (offset, len) in ranges {
+        let (start, range_end) = (offset.max(end), (offset + len).min(size));
+        if range_end > start {
+            covered += range_end - start;
+            end = range_end;
+        }
+    }
+    size - covered
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,7 +451,7 @@ static BLAH$0: bool = 0;
     #[test]
     fn view_memory_layout_tuple() {
         expect![[r#"
-            x: (f64, u8, i64) (size: 24, align: 8, field offset: 0)
+            x: (f64, u8, i64) (size: 24, align: 8, field offset: 0, padding: 7)
             	.0: f64 (size: 8, align: 8, field offset: 0)
             	.1: u8 (size: 1, align: 1, field offset: 8)
             	.2: i64 (size: 8, align: 8, field offset: 16)
@@ -302,9 +472,9 @@ fn main() {
     #[test]
     fn view_memory_layout_c_struct() {
         expect![[r#"
-            [ROOT]: Blah (size: 16, align: 4, field offset: 0)
+            [ROOT]: Blah (size: 16, align: 4, field offset: 0, padding: 3)
             	a: u32 (size: 4, align: 4, field offset: 0)
-            	b: (i32, u8) (size: 8, align: 4, field offset: 4)
+            	b: (i32, u8) (size: 8, align: 4, field offset: 4, padding: 3)
             		.0: i32 (size: 4, align: 4, field offset: 0)
             		.1: u8 (size: 1, align: 1, field offset: 4)
             	c: i8 (size: 1, align: 1, field offset: 12)
@@ -328,8 +498,8 @@ struct Blah$0 {
     #[test]
     fn view_memory_layout_struct() {
         expect![[r#"
-            [ROOT]: Blah (size: 16, align: 4, field offset: 0)
-            	b: (i32, u8) (size: 8, align: 4, field offset: 0)
+            [ROOT]: Blah (size: 16, align: 4, field offset: 0, padding: 3)
+            	b: (i32, u8) (size: 8, align: 4, field offset: 0, padding: 3)
             		.0: i32 (size: 4, align: 4, field offset: 0)
             		.1: u8 (size: 1, align: 1, field offset: 4)
             	a: u32 (size: 4, align: 4, field offset: 8)
@@ -350,6 +520,87 @@ struct Blah$0 {
         );
     }
 
+    #[test]
+    fn view_memory_layout_enum_direct_tag() {
+        expect![[r#"
+            [ROOT]: Foo (size: 8, align: 4, field offset: 0) [direct tag (size: 1, offset: 0, valid range: 200..=255 | 0..=4)]
+            	variant (tag value: 3) A: Foo::A (size: 8, align: 4, field offset: 0, padding: 2)
+            		0: u8 (size: 1, align: 1, field offset: 1)
+            		1: u32 (size: 4, align: 4, field offset: 4)
+            	variant (tag value: 4) B: Foo::B (size: 1, align: 1, field offset: 0)
+            	variant (tag value: 200) C: Foo::C (size: 4, align: 2, field offset: 0, padding: 1)
+            		x: u16 (size: 2, align: 2, field offset: 2)
+        "#]]
+        .assert_eq(
+            &make_memory_layout(
+                r#"
+#[repr(u8)]
+enum Foo$0 {
+    A(u8, u32) = 3,
+    B,
+    C { x: u16 } = 200,
+}
+"#,
+            )
+            .unwrap()
+            .to_string(),
+        );
+    }
+
+    #[test]
+    fn view_memory_layout_enum_niche() {
+        expect![[r#"
+            [ROOT]: Foo (size: 1, align: 1, field offset: 0) [niche tag (size: 1, offset: 0, valid range: 0..=4)]
+            	variant (tag value: 2) A: Foo::A (size: 0, align: 1, field offset: 0)
+            	variant (untagged) B: Foo::B (size: 1, align: 1, field offset: 0)
+            		0: bool (size: 1, align: 1, field offset: 0)
+            	variant (tag value: 4) C: Foo::C (size: 0, align: 1, field offset: 0)
+        "#]]
+        .assert_eq(
+            &make_memory_layout(
+                r#"
+enum Foo$0 {
+    A,
+    B(bool),
+    C,
+}
+"#,
+            )
+            .unwrap()
+            .to_string(),
+        );
+    }
+
+    #[test]
+    fn view_memory_layout_enum_field() {
+        expect![[r#"
+            [ROOT]: Foo (size: 24, align: 8, field offset: 0, padding: 7)
+            	a: Either<u8, u64> (size: 16, align: 8, field offset: 0, padding: 6) [direct tag (size: 1, offset: 0, valid range: 0..=1)]
+            		variant (tag value: 0) Left: Either<u8, u64>::Left (size: 2, align: 1, field offset: 0)
+            			0: u8 (size: 1, align: 1, field offset: 1)
+            		variant (tag value: 1) Right: Either<u8, u64>::Right (size: 16, align: 8, field offset: 0, padding: 7)
+            			0: u64 (size: 8, align: 8, field offset: 8)
+            	b: u8 (size: 1, align: 1, field offset: 16)
+        "#]]
+        .assert_eq(
+            &make_memory_layout(
+                r#"
+enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+struct Foo$0 {
+    a: Either<u8, u64>,
+    b: u8,
+}
+"#,
+            )
+            .unwrap()
+            .to_string(),
+        );
+    }
+
     #[test]
     fn view_memory_layout_member() {
         expect![[r#"
@@ -394,12 +394,25 @@ pub struct SourceRootConfig {
 
 impl SourceRootConfig {
     pub fn partition(&self, vfs: &vfs::Vfs) -> Vec<SourceRoot> {
+        self.partition_impl(vfs, false)
+    }
+
+    /// Like [`SourceRootConfig::partition`], but the source root of the files that belong to no
+    /// configured file set is local, as these files are edited like local files once they are
+    /// attached as ad hoc crates.
+    pub fn partition_with_local_unlinked_files(&self, vfs: &vfs::Vfs) -> Vec<SourceRoot> {
+        self.partition_impl(vfs, true)
+    }
+
+    fn partition_impl(&self, vfs: &vfs::Vfs, local_unlinked_files: bool) -> Vec<SourceRoot> {
+        let catch_all = self.fsc.catch_all_set();
         self.fsc
             .partition(vfs)
             .into_iter()
             .enumerate()
             .map(|(idx, file_set)| {
-                let is_local = self.local_filesets.contains(&(idx as u64));
+                let is_local = self.local_filesets.contains(&(idx as u64))
+                    || (local_unlinked_files && idx == catch_all);
                 if is_local {
                     SourceRoot::new_local(file_set)
                 } else {
@@ -750,7 +763,11 @@ impl ProcMacroExpander for Expander {
             current_dir,
             Some(&cb),
         ) {
-            Ok(Ok(subtree)) => Ok(subtree),
+            Ok(Ok(expansion)) => {
+                let tracked_paths = expansion.tracked_paths.into_iter().map(VfsPath::from);
+                depend_on_tracked_paths(db, call_site, tracked_paths);
+                Ok(expansion.tree)
+            }
             Ok(Err(err)) => Err(ProcMacroExpansionError::Panic(err)),
             Err(err) => Err(ProcMacroExpansionError::System(err.to_string())),
         }
@@ -761,6 +778,43 @@ impl ProcMacroExpander for Expander {
     }
 }
 
+/// Makes the running expansion depend on the files the macro tracked, so that it gets redone when
+/// one of them changes.
+///
+/// A tracked file that is not in the VFS yet (rust-analyzer only loads Rust sources by itself) is
+/// searched for in every source root, which invalidates the expansion once it gets loaded.
+fn depend_on_tracked_paths(
+    db: &dyn SourceDatabase,
+    call_site: Span,
+    paths: impl IntoIterator<Item = VfsPath>,
+) {
+    let call_site_root = db.file_source_root(call_site.anchor.file_id.file_id()).source_root_id(db);
+    let mut other_roots: Option<Vec<SourceRootId>> = None;
+    for path in paths {
+        let find_file = |source_root_id| {
+            db.source_root(source_root_id).source_root(db).file_for_path(&path).copied()
+        };
+        let file_id = find_file(call_site_root).or_else(|| {
+            other_roots
+                .get_or_insert_with(|| {
+                    ide_db::base_db::all_crates(db)
+                        .iter()
+                        .map(|krate| {
+                            db.file_source_root(krate.data(db).root_file_id).source_root_id(db)
+                        })
+                        .filter(|&id| id != call_site_root)
+                        .unique()
+                        .collect()
+                })
+                .iter()
+                .find_map(|&id| find_file(id))
+        });
+        if let Some(file_id) = file_id {
+            db.file_text(file_id).text(db);
+        }
+    }
+}
+
 fn resolve_sub_span(
     db: &dyn SourceDatabase,
     file_id: u32,
@@ -779,7 +833,13 @@ fn resolve_sub_span(
 
 #[cfg(test)]
 mod tests {
-    use ide_db::base_db::all_crates;
+    use std::{
+        any::TypeId,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use ide_db::{base_db::all_crates, prime_caches::parallel_prime_caches};
+    use intern::Symbol;
     use vfs::file_set::FileSetConfigBuilder;
 
     use super::*;
@@ -812,6 +872,101 @@ mod tests {
         assert!(n_crates > 20);
     }
 
+    #[test]
+    fn tracked_paths_redo_expansions() {
+        static EXPANSIONS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct TrackingExpander;
+        impl ProcMacroExpander for TrackingExpander {
+            fn expand(
+                &self,
+                db: &dyn SourceDatabase,
+                subtree: &tt::TopSubtree,
+                _: Option<&tt::TopSubtree>,
+                _: &Env,
+                _: Span,
+                call_site: Span,
+                _: Span,
+                _: String,
+            ) -> Result<tt::TopSubtree, ProcMacroExpansionError> {
+                EXPANSIONS.fetch_add(1, Ordering::Relaxed);
+                let tracked = VfsPath::new_virtual_path("/tracked.txt".to_owned());
+                depend_on_tracked_paths(db, call_site, [tracked]);
+                Ok(subtree.clone())
+            }
+
+            fn eq_dyn(&self, other: &dyn ProcMacroExpander) -> bool {
+                other.type_id() == TypeId::of::<Self>()
+            }
+        }
+
+        let proc_macro = ProcMacro {
+            name: Symbol::intern("tracking"),
+            kind: ProcMacroKind::Bang,
+            expander: sync::Arc::new(TrackingExpander),
+            disabled: false,
+        };
+        let fixture = test_fixture::ChangeFixture::parse_with_proc_macros(
+            r#"
+//- proc_macros: tracking
+//- /main.rs crate:main
+proc_macros::tracking!(struct S;);
+//- /tracked.txt
+tracked
+//- /untracked.txt
+untracked
+"#,
+            "",
+            vec![(
+                "#[proc_macro]\npub fn tracking(input: TokenStream) -> TokenStream { input }"
+                    .to_owned(),
+                proc_macro,
+            )],
+        );
+        let [_, tracked, untracked] = *fixture.files else { panic!("unexpected files") };
+        let mut db = RootDatabase::default();
+        db.apply_change(fixture.change);
+        let expand = |db: &RootDatabase| {
+            parallel_prime_caches(db, &all_crates(db), 1, &|_| ());
+            EXPANSIONS.load(Ordering::Relaxed)
+        };
+        let change_file = |db: &mut RootDatabase, file: span::EditionedFileId| {
+            let mut change = ChangeWithProcMacros::default();
+            change.change_file(file.file_id(), Some("changed".to_owned()));
+            db.apply_change(change);
+        };
+
+        assert_eq!(expand(&db), 1);
+        change_file(&mut db, untracked);
+        assert_eq!(expand(&db), 1);
+        change_file(&mut db, tracked);
+        assert_eq!(expand(&db), 2);
+    }
+
+    #[test]
+    fn unlinked_files_root() {
+        let mut builder = FileSetConfigBuilder::default();
+        builder.add_file_set(vec![VfsPath::new_virtual_path("/ROOT/abc".to_owned())]);
+        builder.add_file_set(vec![VfsPath::new_virtual_path("/ROOT/def".to_owned())]);
+        let fsc = builder.build();
+        let src = SourceRootConfig { fsc, local_filesets: vec![0] };
+        let mut vfs = vfs::Vfs::default();
+        for path in ["/ROOT/abc/lib.rs", "/ROOT/def/lib.rs", "/scratch.rs"] {
+            let path = VfsPath::new_virtual_path(path.to_owned());
+            vfs.set_file_contents(path, Some(Vec::new()));
+        }
+
+        let is_library = |roots: Vec<SourceRoot>| {
+            roots.iter().map(|it| (it.iter().count(), it.is_library)).collect::<Vec<_>>()
+        };
+        assert_eq!(is_library(src.partition(&vfs)), [(1, false), (1, true), (1, true)]);
+        assert_eq!(
+            is_library(src.partition_with_local_unlinked_files(&vfs)),
+            [(1, false), (1, true), (1, false)]
+        );
+    }
+
     #[test]
     fn unrelated_sources() {
         let mut builder = FileSetConfigBuilder::default();
@@ -6,11 +6,11 @@ use std::{
     sync::Arc,
 };
 
-use paths::AbsPath;
+use paths::{AbsPath, AbsPathBuf};
 use span::Span;
 
 use crate::{
-    ProcMacro, ProcMacroKind, ServerError,
+    Expansion, ProcMacro, ProcMacroKind, ServerError,
     bidirectional_protocol::msg::{
         ApiVersionCheck, BidirectionalMessage, ExpandMacro, ExpandMacroData, ExpnGlobals,
         ListMacros, Request, Response, SubRequest, SubResponse,
@@ -100,7 +100,7 @@ pub(crate) fn version_check(
 ) -> Result<u32, ServerError> {
     let request = BidirectionalMessage::Request(Request::ApiVersionCheck(ApiVersionCheck {}));
 
-    let response_payload = run_request(srv, request, callback)?;
+    let response_payload = run_request(srv, request, callback, None)?;
 
     match response_payload {
         BidirectionalMessage::Response(Response::ApiVersionCheck(version)) => Ok(version),
@@ -119,7 +119,7 @@ pub(crate) fn enable_rust_analyzer_spans(
         span_mode: SpanMode::RustAnalyzer,
     }));
 
-    let response_payload = run_request(srv, request, callback)?;
+    let response_payload = run_request(srv, request, callback, None)?;
 
     match response_payload {
         BidirectionalMessage::Response(Response::SetConfig(ServerConfig { span_mode })) => {
@@ -139,7 +139,7 @@ pub(crate) fn find_proc_macros(
         dylib_path: dylib_path.to_path_buf().into(),
     }));
 
-    let response_payload = run_request(srv, request, callback)?;
+    let response_payload = run_request(srv, request, callback, None)?;
 
     match response_payload {
         BidirectionalMessage::Response(Response::ListMacros(it)) => Ok(it),
@@ -158,7 +158,7 @@ pub(crate) fn expand(
     mixed_site: Span,
     current_dir: String,
     callback: SubCallback<'_>,
-) -> Result<Result<tt::TopSubtree, String>, crate::ServerError> {
+) -> Result<Result<Expansion, String>, crate::ServerError> {
     let version = process.version();
     let mut span_data_table = SpanDataIndexMap::default();
     let def_site = span_data_table.insert_full(def_site).0;
@@ -179,36 +179,72 @@ pub(crate) fn expand(
         },
         lib: proc_macro.dylib_path.to_path_buf().into(),
         env,
-        current_dir: Some(current_dir),
+        current_dir: Some(current_dir.clone()),
     })));
 
-    let response_payload = run_request(process, task, callback)?;
+    let response_payload = run_request(process, task, callback, Some(proc_macro))?;
 
     match response_payload {
         BidirectionalMessage::Response(Response::ExpandMacro(it)) => Ok(it
             .map(|resp| {
-                FlatTree::to_subtree_resolved(
+                let tree = FlatTree::to_subtree_resolved(
                     resp.tree,
                     version,
                     &deserialize_span_data_index_map(&resp.span_data_table),
-                )
+                );
+                let mut tracked_paths = resolve_tracked_paths(resp.tracked_paths, &current_dir);
+                tracked_paths.sort_unstable();
+                Expansion { tree, tracked_paths }
             })
             .map_err(|msg| msg.0)),
         _ => Err(ServerError { message: "unexpected response".to_owned(), io: None }),
     }
 }
 
+/// Resolves the paths a macro tracked, which may be relative to the directory it was expanded in.
+fn resolve_tracked_paths(
+    paths: impl IntoIterator<Item = Box<str>>,
+    current_dir: &str,
+) -> Vec<AbsPathBuf> {
+    let current_dir = AbsPathBuf::try_from(current_dir).ok();
+    paths
+        .into_iter()
+        .filter_map(|path| match AbsPathBuf::try_from(&*path) {
+            Ok(path) => Some(path.normalize()),
+            Err(_) => Some(current_dir.as_ref()?.absolutize(&*path)),
+        })
+        .collect()
+}
+
 fn run_request(
     srv: &ProcMacroServerProcess,
     msg: BidirectionalMessage,
     callback: SubCallback<'_>,
+    expanding: Option<&ProcMacro>,
 ) -> Result<BidirectionalMessage, ServerError> {
     if let Some(err) = srv.exited() {
         return Err(err.clone());
     }
-    srv.run_bidirectional(msg, callback)
+    srv.run_bidirectional(msg, callback, expanding)
 }
 
 pub fn reject_subrequests(req: SubRequest) -> Result<SubResponse, ServerError> {
     Err(ServerError { message: format!("{req:?} sub-request not supported here"), io: None })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracked_paths_resolve_against_current_dir() {
+        let dir = AbsPathBuf::assert_utf8(std::env::current_dir().unwrap());
+        let paths = ["data/a.txt", dir.join("b.txt").as_str(), "../c.txt"].map(Box::<str>::from);
+
+        assert_eq!(
+            resolve_tracked_paths(paths.clone(), dir.as_str()),
+            [dir.join("data/a.txt"), dir.join("b.txt"), dir.parent().unwrap().join("c.txt")]
+        );
+        assert_eq!(resolve_tracked_paths(paths, ""), [dir.join("b.txt")]);
+    }
+}
@@ -11,7 +11,7 @@ use paths::AbsPath;
 use span::Span;
 
 use crate::{
-    ProcMacro, ProcMacroKind, ServerError,
+    Expansion, ProcMacro, ProcMacroKind, ServerError,
     legacy_protocol::msg::{
         ExpandMacro, ExpandMacroData, ExpnGlobals, FlatTree, Message, Request, Response,
         ServerConfig, SpanDataIndexMap, deserialize_span_data_index_map,
@@ -37,7 +37,7 @@ impl std::fmt::Debug for SpanId {
 
 pub(crate) fn version_check(srv: &ProcMacroServerProcess) -> Result<u32, ServerError> {
     let request = Request::ApiVersionCheck {};
-    let response = send_task(srv, request)?;
+    let response = send_task(srv, request, None)?;
 
     match response {
         Response::ApiVersionCheck(version) => Ok(version),
@@ -50,7 +50,7 @@ pub(crate) fn enable_rust_analyzer_spans(
     srv: &ProcMacroServerProcess,
 ) -> Result<SpanMode, ServerError> {
     let request = Request::SetConfig(ServerConfig { span_mode: SpanMode::RustAnalyzer });
-    let response = send_task(srv, request)?;
+    let response = send_task(srv, request, None)?;
 
     match response {
         Response::SetConfig(ServerConfig { span_mode }) => Ok(span_mode),
@@ -65,7 +65,7 @@ pub(crate) fn find_proc_macros(
 ) -> Result<Result<Vec<(String, ProcMacroKind)>, String>, ServerError> {
     let request = Request::ListMacros { dylib_path: dylib_path.to_path_buf().into() };
 
-    let response = send_task(srv, request)?;
+    let response = send_task(srv, request, None)?;
 
     match response {
         Response::ListMacros(it) => Ok(it),
@@ -83,7 +83,7 @@ pub(crate) fn expand(
     call_site: Span,
     mixed_site: Span,
     current_dir: String,
-) -> Result<Result<tt::TopSubtree, String>, crate::ServerError> {
+) -> Result<Result<Expansion, String>, crate::ServerError> {
     let version = process.version();
     let mut span_data_table = SpanDataIndexMap::default();
     let def_site = span_data_table.insert_full(def_site).0;
@@ -112,7 +112,7 @@ pub(crate) fn expand(
         current_dir: Some(current_dir),
     };
 
-    let response = send_task(process, Request::ExpandMacro(Box::new(task)))?;
+    let response = send_task(process, Request::ExpandMacro(Box::new(task)), Some(proc_macro))?;
 
     match response {
         Response::ExpandMacro(it) => Ok(it
@@ -121,7 +121,7 @@ pub(crate) fn expand(
                 if proc_macro.needs_fixup_change() {
                     proc_macro.change_fixup_to_match_old_server(&mut expanded);
                 }
-                expanded
+                Expansion { tree: expanded, tracked_paths: Vec::new() }
             })
             .map_err(|msg| msg.0)),
         Response::ExpandMacroExtended(it) => Ok(it
@@ -134,7 +134,7 @@ pub(crate) fn expand(
                 if proc_macro.needs_fixup_change() {
                     proc_macro.change_fixup_to_match_old_server(&mut expanded);
                 }
-                expanded
+                Expansion { tree: expanded, tracked_paths: Vec::new() }
             })
             .map_err(|msg| msg.0)),
         _ => Err(ServerError { message: "unexpected response".to_owned(), io: None }),
@@ -142,12 +142,16 @@ pub(crate) fn expand(
 }
 
 /// Sends a request to the proc-macro server and waits for a response.
-fn send_task(srv: &ProcMacroServerProcess, req: Request) -> Result<Response, ServerError> {
+fn send_task(
+    srv: &ProcMacroServerProcess,
+    req: Request,
+    expanding: Option<&ProcMacro>,
+) -> Result<Response, ServerError> {
     if let Some(server_error) = srv.exited() {
         return Err(server_error.clone());
     }
 
-    srv.send_task_legacy::<_, _>(send_request, req)
+    srv.send_task_legacy::<_, _>(send_request, req, expanding)
 }
 
 /// Sends a request to the server and reads the response.
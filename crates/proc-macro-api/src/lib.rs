@@ -24,7 +24,11 @@ pub mod transport;
 use paths::{AbsPath, AbsPathBuf};
 use semver::Version;
 use span::{ErasedFileAstId, FIXUP_ERASED_FILE_AST_ID_MARKER, Span};
-use std::{fmt, io, sync::Arc, time::SystemTime};
+use std::{
+    fmt, io,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use crate::{
     bidirectional_protocol::SubCallback, pool::ProcMacroServerPool, process::ProcMacroServerProcess,
@@ -91,6 +95,32 @@ pub struct ProcMacroClient {
     path: AbsPathBuf,
 }
 
+/// Limits a single proc-macro expansion has to stay within. When an expansion exceeds them, the
+/// server process running it is killed and restarted, and the expansion fails.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpansionLimits {
+    /// How long an expansion may take.
+    pub time: Option<Duration>,
+    /// How many bytes of resident memory the server process may use while expanding. This is
+    /// only enforced on Linux.
+    pub memory: Option<u64>,
+}
+
+impl ExpansionLimits {
+    fn is_unlimited(&self) -> bool {
+        self.time.is_none() && self.memory.is_none()
+    }
+}
+
+/// A successful proc-macro expansion, along with the files the macro read besides its input.
+#[derive(Debug)]
+pub struct Expansion {
+    pub tree: tt::TopSubtree,
+    /// The files the macro declared to depend on through `proc_macro::tracked`, resolved against
+    /// the expansion's working directory. Always empty with the legacy protocol.
+    pub tracked_paths: Vec<AbsPathBuf>,
+}
+
 /// Represents a dynamically loaded library containing procedural macros.
 pub struct MacroDylib {
     path: AbsPathBuf,
@@ -176,7 +206,10 @@ impl ProcMacroClient {
             Box<dyn process::ProcessExit>,
             Box<dyn io::Write + Send + Sync>,
             Box<dyn io::BufRead + Send + Sync>,
-        )> + Clone,
+        )> + Clone
+        + Send
+        + Sync
+        + 'static,
         version: Option<&Version>,
         num_process: usize,
     ) -> io::Result<ProcMacroClient> {
@@ -192,6 +225,18 @@ impl ProcMacroClient {
         Ok(ProcMacroClient { pool: Arc::new(pool), path: process_path.to_owned() })
     }
 
+    /// Holds every expansion of its proc-macros to `limits`.
+    pub fn with_expansion_limits(self, limits: ExpansionLimits) -> ProcMacroClient {
+        self.set_expansion_limits(limits);
+        self
+    }
+
+    /// Replaces the limits of the expansions of its proc-macros, including the ones that were
+    /// already loaded.
+    pub fn set_expansion_limits(&self, limits: ExpansionLimits) {
+        self.pool.set_limits(limits);
+    }
+
     /// Returns the absolute path to the proc-macro server.
     pub fn server_path(&self) -> &AbsPath {
         &self.path
@@ -206,6 +251,12 @@ impl ProcMacroClient {
     pub fn exited(&self) -> Option<&ServerError> {
         self.pool.exited()
     }
+
+    /// Returns the paths the macros of this client reported as dependencies since the last
+    /// call.
+    pub fn take_tracked_paths(&self) -> Vec<AbsPathBuf> {
+        self.pool.take_tracked_paths()
+    }
 }
 
 impl ProcMacro {
@@ -249,7 +300,7 @@ impl ProcMacro {
         mixed_site: Span,
         current_dir: String,
         callback: Option<SubCallback<'_>>,
-    ) -> Result<Result<tt::TopSubtree, String>, ServerError> {
+    ) -> Result<Result<Expansion, String>, ServerError> {
         let (mut subtree, mut attr) = (subtree, attr);
         let (mut subtree_changed, mut attr_changed);
         if self.needs_fixup_change() {
@@ -264,7 +315,7 @@ impl ProcMacro {
             }
         }
 
-        self.pool.pick_process()?.expand(
+        let result = self.pool.pick_process()?.expand(
             self,
             subtree,
             attr,
@@ -274,6 +325,10 @@ impl ProcMacro {
             mixed_site,
             current_dir,
             callback,
-        )
+        );
+        if let Ok(Ok(expansion)) = &result {
+            self.pool.record_tracked_paths(&expansion.tracked_paths);
+        }
+        result
     }
 }
@@ -1,20 +1,30 @@
 //! A pool of proc-macro server processes
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use paths::AbsPathBuf;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rustc_hash::FxHashSet;
 
-use crate::{MacroDylib, ProcMacro, ServerError, process::ProcMacroServerProcess};
+use crate::{ExpansionLimits, MacroDylib, ProcMacro, ServerError, process::ProcMacroServerProcess};
 
 #[derive(Debug, Clone)]
 pub(crate) struct ProcMacroServerPool {
     workers: Arc<[ProcMacroServerProcess]>,
     version: u32,
+    /// Shared with the proc-macros loaded from this pool, so that they see the limits change.
+    limits: Arc<Mutex<ExpansionLimits>>,
+    tracked_paths: Arc<Mutex<FxHashSet<AbsPathBuf>>>,
 }
 
 impl ProcMacroServerPool {
     pub(crate) fn new(workers: Vec<ProcMacroServerProcess>) -> Self {
         let version = workers[0].version();
-        Self { workers: workers.into(), version }
+        Self {
+            workers: workers.into(),
+            version,
+            limits: Arc::default(),
+            tracked_paths: Arc::default(),
+        }
     }
 }
 
@@ -86,4 +96,23 @@ impl ProcMacroServerPool {
     pub(crate) fn version(&self) -> u32 {
         self.version
     }
+
+    pub(crate) fn limits(&self) -> ExpansionLimits {
+        *self.limits.lock().unwrap()
+    }
+
+    pub(crate) fn set_limits(&self, limits: ExpansionLimits) {
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    pub(crate) fn record_tracked_paths(&self, paths: &[AbsPathBuf]) {
+        if paths.is_empty() {
+            return;
+        }
+        self.tracked_paths.lock().unwrap().extend(paths.iter().cloned());
+    }
+
+    pub(crate) fn take_tracked_paths(&self) -> Vec<AbsPathBuf> {
+        self.tracked_paths.lock().unwrap().drain().collect()
+    }
 }
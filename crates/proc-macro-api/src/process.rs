@@ -1,14 +1,19 @@
 //! Handle process life-time and message passing for proc-macro client
 
 use std::{
+    ffi::OsString,
     fmt::Debug,
     io::{self, BufRead, BufReader, Read, Write},
+    mem,
     panic::AssertUnwindSafe,
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
     sync::{
         Arc, Mutex, OnceLock,
         atomic::{AtomicU32, Ordering},
+        mpsc,
     },
+    thread,
+    time::{Duration, Instant},
 };
 
 use paths::AbsPath;
@@ -17,7 +22,7 @@ use span::Span;
 use stdx::JodChild;
 
 use crate::{
-    ProcMacro, ProcMacroKind, ProtocolFormat, ServerError,
+    Expansion, ExpansionLimits, ProcMacro, ProcMacroKind, ProtocolFormat, ServerError,
     bidirectional_protocol::{
         self, SubCallback,
         msg::{BidirectionalMessage, SubResponse},
@@ -27,11 +32,29 @@ use crate::{
     version,
 };
 
+type SpawnFn = dyn Fn(
+        Option<ProtocolFormat>,
+    ) -> io::Result<(
+        Box<dyn ProcessExit>,
+        Box<dyn Write + Send + Sync>,
+        Box<dyn BufRead + Send + Sync>,
+    )> + Send
+    + Sync;
+
+/// How often the memory usage of the server is checked while an expansion is running.
+const MEMORY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Represents a process handling proc-macro communication.
 pub(crate) struct ProcMacroServerProcess {
     /// The state of the proc-macro server process, the protocol is currently strictly sequential
     /// hence the lock on the state.
     state: Mutex<ProcessSrvState>,
+    /// Kept apart from the rest of the state so that the watchdog can kill the process while a
+    /// request is in flight.
+    process: Arc<Mutex<Box<dyn ProcessExit>>>,
+    /// Spawns a replacement for the process after it was killed for exceeding its limits.
+    spawn: AssertUnwindSafe<Arc<SpawnFn>>,
+    format: Option<ProtocolFormat>,
     version: u32,
     protocol: Protocol,
     /// Populated when the server exits.
@@ -57,6 +80,14 @@ pub(crate) enum Protocol {
 
 pub trait ProcessExit: Send + Sync {
     fn exit_err(&mut self) -> Option<ServerError>;
+
+    /// Forcefully stops the process, used when an expansion exceeds its limits.
+    fn kill(&mut self) {}
+
+    /// The resident memory of the process in bytes, if it can be determined.
+    fn memory_usage(&mut self) -> Option<u64> {
+        None
+    }
 }
 
 impl ProcessExit for Process {
@@ -80,13 +111,113 @@ impl ProcessExit for Process {
             }
         }
     }
+
+    fn kill(&mut self) {
+        _ = self.child.kill();
+    }
+
+    #[cfg(target_os = "linux")]
+    fn memory_usage(&mut self) -> Option<u64> {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", self.child.id())).ok()?;
+        let rss = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+        let kib = rss.trim().strip_suffix("kB")?.trim().parse::<u64>().ok()?;
+        Some(kib * 1024)
+    }
 }
 
 /// Maintains the state of the proc-macro server process.
 pub(crate) struct ProcessSrvState {
-    process: Box<dyn ProcessExit>,
     stdin: Box<dyn Write + Send + Sync>,
     stdout: Box<dyn BufRead + Send + Sync>,
+    /// Spawned for the first expansion that has limits, and kept when the process is restarted.
+    watchdog: Option<Watchdog>,
+}
+
+/// A limit an expansion exceeded.
+enum LimitExceeded {
+    Time(Duration),
+    Memory(u64),
+}
+
+/// A thread that kills the server process once the expansion it is watching exceeds its limits.
+///
+/// Every worker has at most one, which lives as long as the worker and watches its expansions
+/// one after another.
+struct Watchdog {
+    /// Sends the limits of an expansion when it starts, and `None` when it finished.
+    requests: mpsc::Sender<Option<ExpansionLimits>>,
+    /// Receives the limit each finished expansion exceeded, if any.
+    replies: mpsc::Receiver<Option<LimitExceeded>>,
+}
+
+impl Watchdog {
+    fn spawn(process: Arc<Mutex<Box<dyn ProcessExit>>>) -> Watchdog {
+        let (requests, watched) = mpsc::channel();
+        let (reply, replies) = mpsc::channel();
+        thread::Builder::new()
+            .name("ProcMacroWatchdog".to_owned())
+            .spawn(move || {
+                // The channels disconnect once the worker is dropped.
+                while let Ok(request) = watched.recv() {
+                    let Some(limits) = request else { continue };
+                    let exceeded = Self::watch_expansion(&process, &watched, limits);
+                    if reply.send(exceeded).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("failed to spawn proc-macro watchdog thread");
+        Watchdog { requests, replies }
+    }
+
+    /// Runs `f` while holding the process to `limits`.
+    fn watch<R>(
+        &self,
+        limits: ExpansionLimits,
+        f: impl FnOnce() -> R,
+    ) -> (R, Option<LimitExceeded>) {
+        _ = self.requests.send(Some(limits));
+        let result = f();
+        _ = self.requests.send(None);
+        (result, self.replies.recv().ok().flatten())
+    }
+
+    fn watch_expansion(
+        process: &Mutex<Box<dyn ProcessExit>>,
+        finished: &mpsc::Receiver<Option<ExpansionLimits>>,
+        limits: ExpansionLimits,
+    ) -> Option<LimitExceeded> {
+        let start = Instant::now();
+        loop {
+            let until_deadline = limits.time.map(|it| it.saturating_sub(start.elapsed()));
+            let timeout = match (until_deadline, limits.memory) {
+                (Some(it), Some(_)) => it.min(MEMORY_POLL_INTERVAL),
+                (Some(it), None) => it,
+                (None, _) => MEMORY_POLL_INTERVAL,
+            };
+            match finished.recv_timeout(timeout) {
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Ok(_) | Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            }
+
+            let process = &mut **process.lock().unwrap();
+            let exceeded = match limits {
+                ExpansionLimits { time: Some(time), .. } if start.elapsed() >= time => {
+                    LimitExceeded::Time(time)
+                }
+                ExpansionLimits { memory: Some(memory), .. }
+                    if process.memory_usage().is_some_and(|it| it > memory) =>
+                {
+                    LimitExceeded::Memory(memory)
+                }
+                _ => continue,
+            };
+            process.kill();
+            // The reply is only expected once the expansion noticed that the process is gone.
+            _ = finished.recv();
+            return Some(exceeded);
+        }
+    }
 }
 
 impl ProcMacroServerProcess {
@@ -98,11 +229,16 @@ impl ProcMacroServerProcess {
         > + Clone,
         version: Option<&Version>,
     ) -> io::Result<ProcMacroServerProcess> {
+        let env: Vec<(OsString, Option<OsString>)> = env
+            .into_iter()
+            .map(|(k, v)| (k.as_ref().to_owned(), v.as_ref().map(|v| v.as_ref().to_owned())))
+            .collect();
+        let path = process_path.to_owned();
         Self::run(
-            |format| {
+            move |format| {
                 let mut process = Process::run(
-                    process_path,
-                    env.clone(),
+                    &path,
+                    env.iter().map(|(k, v)| (k, v)),
                     format.map(|format| format.to_string()).as_deref(),
                 )?;
                 let (stdin, stdout) = process.stdio().expect("couldn't access child stdio");
@@ -129,10 +265,13 @@ impl ProcMacroServerProcess {
             Box<dyn ProcessExit>,
             Box<dyn Write + Send + Sync>,
             Box<dyn BufRead + Send + Sync>,
-        )>,
+        )> + Send
+        + Sync
+        + 'static,
         version: Option<&Version>,
         binary_server_version: impl Fn() -> String,
     ) -> io::Result<ProcMacroServerProcess> {
+        let spawn: Arc<SpawnFn> = Arc::new(spawn);
         const VERSION: Version = Version::new(1, 93, 0);
         // we do `>` for nightly as this started working in the middle of the 1.93 nightly release, so we dont want to break on half of the nightlies
         let has_working_format_flag = version.map_or(false, |v| {
@@ -152,25 +291,7 @@ impl ProcMacroServerProcess {
 
         let mut err = None;
         for &format in formats {
-            let create_srv = || {
-                let (process, stdin, stdout) = spawn(format)?;
-
-                io::Result::Ok(ProcMacroServerProcess {
-                    state: Mutex::new(ProcessSrvState { process, stdin, stdout }),
-                    version: 0,
-                    protocol: match format {
-                        Some(ProtocolFormat::BidirectionalPostcardPrototype) => {
-                            Protocol::BidirectionalPostcardPrototype { mode: SpanMode::Id }
-                        }
-                        Some(ProtocolFormat::JsonLegacy) | None => {
-                            Protocol::LegacyJson { mode: SpanMode::Id }
-                        }
-                    },
-                    exited: OnceLock::new(),
-                    active: AtomicU32::new(0),
-                })
-            };
-            let mut srv = create_srv()?;
+            let mut srv = Self::start(spawn.clone(), format)?;
             tracing::info!("sending proc-macro server version check");
             match srv.version_check(Some(&reject_subrequests)) {
                 Ok(v) if v > version::CURRENT_API_VERSION => {
@@ -207,6 +328,47 @@ impl ProcMacroServerProcess {
         Err(err.unwrap())
     }
 
+    /// Spawns the server process without performing any handshake.
+    fn start(
+        spawn: Arc<SpawnFn>,
+        format: Option<ProtocolFormat>,
+    ) -> io::Result<ProcMacroServerProcess> {
+        let (process, stdin, stdout) = spawn(format)?;
+
+        Ok(ProcMacroServerProcess {
+            state: Mutex::new(ProcessSrvState { stdin, stdout, watchdog: None }),
+            process: Arc::new(Mutex::new(process)),
+            spawn: AssertUnwindSafe(spawn),
+            format,
+            version: 0,
+            protocol: match format {
+                Some(ProtocolFormat::BidirectionalPostcardPrototype) => {
+                    Protocol::BidirectionalPostcardPrototype { mode: SpanMode::Id }
+                }
+                Some(ProtocolFormat::JsonLegacy) | None => {
+                    Protocol::LegacyJson { mode: SpanMode::Id }
+                }
+            },
+            exited: OnceLock::new(),
+            active: AtomicU32::new(0),
+        })
+    }
+
+    /// Replaces the server process with a fresh one, configured like the current one.
+    fn restart(&self, state: &mut ProcessSrvState) -> io::Result<()> {
+        let fresh = Self::start(self.spawn.0.clone(), self.format)?;
+        if self.rust_analyzer_spans() {
+            fresh
+                .enable_rust_analyzer_spans(Some(&reject_subrequests))
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        let ProcessSrvState { stdin, stdout, .. } = fresh.state.into_inner().unwrap();
+        (state.stdin, state.stdout) = (stdin, stdout);
+        // Swapped in place, as the watchdog holds on to the process of this worker.
+        mem::swap(&mut *self.process.lock().unwrap(), &mut *fresh.process.lock().unwrap());
+        Ok(())
+    }
+
     /// Finds proc-macros in a given dynamic library.
     pub(crate) fn find_proc_macros(
         &self,
@@ -282,7 +444,7 @@ impl ProcMacroServerProcess {
         mixed_site: Span,
         current_dir: String,
         callback: Option<SubCallback<'_>>,
-    ) -> Result<Result<tt::TopSubtree, String>, ServerError> {
+    ) -> Result<Result<Expansion, String>, ServerError> {
         self.active.fetch_add(1, Ordering::AcqRel);
         let result = match self.protocol {
             Protocol::LegacyJson { .. } => legacy_protocol::expand(
@@ -323,8 +485,9 @@ impl ProcMacroServerProcess {
             &mut String,
         ) -> Result<Option<Response>, ServerError>,
         req: Request,
+        expanding: Option<&ProcMacro>,
     ) -> Result<Response, ServerError> {
-        self.with_locked_io(String::new(), |writer, reader, buf| {
+        self.with_locked_io(String::new(), expanding, |writer, reader, buf| {
             send(writer, reader, req, buf).and_then(|res| {
                 res.ok_or_else(|| {
                     let message = "proc-macro server did not respond with data".to_owned();
@@ -340,15 +503,50 @@ impl ProcMacroServerProcess {
         })
     }
 
+    /// Runs `f` with exclusive access to the server's IO. When the request expands a macro, the
+    /// expansion is held to the limits of its pool.
     fn with_locked_io<R, B>(
         &self,
         mut buf: B,
+        expanding: Option<&ProcMacro>,
         f: impl FnOnce(&mut dyn Write, &mut dyn BufRead, &mut B) -> Result<R, ServerError>,
     ) -> Result<R, ServerError> {
         let state = &mut *self.state.lock().unwrap();
-        f(&mut state.stdin, &mut state.stdout, &mut buf).map_err(|e| {
+        let limits = expanding.map_or_else(ExpansionLimits::default, |it| it.pool.limits());
+        let (result, exceeded) = if limits.is_unlimited() {
+            (f(&mut state.stdin, &mut state.stdout, &mut buf), None)
+        } else {
+            let watchdog =
+                state.watchdog.get_or_insert_with(|| Watchdog::spawn(self.process.clone()));
+            watchdog.watch(limits, || f(&mut state.stdin, &mut state.stdout, &mut buf))
+        };
+
+        if let Some(exceeded) = exceeded {
+            let name = expanding.map_or("<unknown>", |it| it.name());
+            let message = match exceeded {
+                LimitExceeded::Time(limit) => format!(
+                    "proc-macro `{name}` did not finish within {}s and was stopped",
+                    limit.as_secs_f32()
+                ),
+                LimitExceeded::Memory(limit) => format!(
+                    "proc-macro `{name}` made the proc-macro server exceed {} MiB and was stopped",
+                    limit / (1024 * 1024)
+                ),
+            };
+            tracing::warn!("{message}, restarting the proc-macro server");
+            if let Err(e) = self.restart(state) {
+                let message = format!("failed to restart proc-macro server: {e}");
+                tracing::error!("{message}");
+                self.exited.get_or_init(|| {
+                    AssertUnwindSafe(ServerError { message, io: Some(Arc::new(e)) })
+                });
+            }
+            return Err(ServerError { message, io: None });
+        }
+
+        result.map_err(|e| {
             if e.io.as_ref().map(|it| it.kind()) == Some(io::ErrorKind::BrokenPipe) {
-                match state.process.exit_err() {
+                match self.process.lock().unwrap().exit_err() {
                     None => e,
                     Some(server_error) => {
                         self.exited.get_or_init(|| AssertUnwindSafe(server_error)).0.clone()
@@ -364,8 +562,9 @@ impl ProcMacroServerProcess {
         &self,
         initial: BidirectionalMessage,
         callback: SubCallback<'_>,
+        expanding: Option<&ProcMacro>,
     ) -> Result<BidirectionalMessage, ServerError> {
-        self.with_locked_io(Vec::new(), |writer, reader, buf| {
+        self.with_locked_io(Vec::new(), expanding, |writer, reader, buf| {
             bidirectional_protocol::run_conversation(writer, reader, buf, initial, callback)
         })
     }
@@ -437,3 +636,134 @@ fn mk_child<'a>(
     }
     cmd.spawn()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use paths::AbsPathBuf;
+    use span::{
+        Edition, EditionedFileId, FileId, ROOT_ERASED_FILE_AST_ID, SpanAnchor, SyntaxContext,
+        TextRange,
+    };
+
+    use crate::{
+        MacroDylib, ProcMacroClient,
+        legacy_protocol::msg::{Message, Request, Response},
+    };
+
+    use super::*;
+
+    type Stdout = Arc<Mutex<Option<io::PipeWriter>>>;
+
+    struct FakeProcess {
+        stdout: Stdout,
+    }
+
+    impl ProcessExit for FakeProcess {
+        fn exit_err(&mut self) -> Option<ServerError> {
+            None
+        }
+
+        fn kill(&mut self) {
+            self.stdout.lock().unwrap().take();
+        }
+    }
+
+    /// A server speaking the legacy protocol that never finishes expanding `hang` and echoes the
+    /// input of every other macro.
+    fn fake_server() -> io::Result<(
+        Box<dyn ProcessExit>,
+        Box<dyn Write + Send + Sync>,
+        Box<dyn BufRead + Send + Sync>,
+    )> {
+        let (requests, stdin) = io::pipe()?;
+        let (stdout, responses) = io::pipe()?;
+        let responses: Stdout = Arc::new(Mutex::new(Some(responses)));
+        thread::spawn({
+            let responses = responses.clone();
+            move || {
+                let mut requests = BufReader::new(requests);
+                let mut buf = String::new();
+                while let Ok(Some(request)) = Request::read(&mut requests, &mut buf) {
+                    let response = match request {
+                        Request::ApiVersionCheck {} => {
+                            Response::ApiVersionCheck(version::CURRENT_API_VERSION)
+                        }
+                        Request::SetConfig(config) => Response::SetConfig(config),
+                        Request::ListMacros { .. } => Response::ListMacros(Ok(vec![
+                            ("hang".to_owned(), ProcMacroKind::Bang),
+                            ("echo".to_owned(), ProcMacroKind::Bang),
+                        ])),
+                        Request::ExpandMacro(task) if task.data.macro_name == "hang" => continue,
+                        Request::ExpandMacro(task) => {
+                            Response::ExpandMacro(Ok(task.data.macro_body))
+                        }
+                    };
+                    let Some(responses) = &mut *responses.lock().unwrap() else { return };
+                    if response.write(responses).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok((
+            Box::new(FakeProcess { stdout: responses }),
+            Box::new(stdin),
+            Box::new(BufReader::new(stdout)),
+        ))
+    }
+
+    #[test]
+    fn expansion_exceeding_time_limit_restarts_server() {
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let path = AbsPathBuf::assert_utf8(std::env::current_dir().unwrap());
+        let client = ProcMacroClient::with_io_channels(
+            &path,
+            {
+                let spawned = spawned.clone();
+                move |_| {
+                    spawned.fetch_add(1, Ordering::Relaxed);
+                    fake_server()
+                }
+            },
+            None,
+            1,
+        )
+        .unwrap();
+        let macros = client.load_dylib(MacroDylib::new(path)).unwrap();
+        let [hang, echo] = &*macros else { panic!("unexpected macros: {macros:?}") };
+        // Limits set later on still apply to the proc-macros that were loaded already.
+        client.set_expansion_limits(ExpansionLimits {
+            time: Some(Duration::from_millis(100)),
+            memory: None,
+        });
+
+        let span = Span {
+            range: TextRange::empty(0.into()),
+            anchor: SpanAnchor {
+                file_id: EditionedFileId::new(FileId::from_raw(0), Edition::CURRENT),
+                ast_id: ROOT_ERASED_FILE_AST_ID,
+            },
+            // SAFETY: Tests do not use a Database, so this won't ever be used within salsa.
+            ctx: unsafe { SyntaxContext::from_u32(0) },
+        };
+        let input = tt::TopSubtree::empty(tt::DelimSpan { open: span, close: span });
+        let expand = |proc_macro: &ProcMacro| {
+            proc_macro.expand(input.view(), None, Vec::new(), span, span, span, String::new(), None)
+        };
+
+        let err = expand(hang).unwrap_err();
+        assert_eq!(err.message, "proc-macro `hang` did not finish within 0.1s and was stopped");
+        assert_eq!(spawned.load(Ordering::Relaxed), 2);
+        assert!(client.exited().is_none());
+
+        let expanded = expand(echo).unwrap().unwrap();
+        assert_eq!(expanded.tree, input);
+
+        // The watchdog keeps watching the restarted server.
+        let err = expand(hang).unwrap_err();
+        assert_eq!(err.message, "proc-macro `hang` did not finish within 0.1s and was stopped");
+        assert_eq!(spawned.load(Ordering::Relaxed), 3);
+    }
+}
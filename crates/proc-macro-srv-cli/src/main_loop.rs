@@ -44,6 +44,17 @@ pub fn run(
     }
 }
 
+/// Set by the client to persist expansion results across server restarts.
+const EXPANSION_CACHE_DIR_ENV: &str = "RA_PROC_MACRO_EXPANSION_CACHE_DIR";
+
+fn make_srv(env: &EnvSnapshot) -> proc_macro_srv::ProcMacroSrv<'_> {
+    let srv = proc_macro_srv::ProcMacroSrv::new(env);
+    match std::env::var(EXPANSION_CACHE_DIR_ENV) {
+        Ok(dir) if !dir.is_empty() => srv.with_expansion_cache_dir(dir.into()),
+        _ => srv,
+    }
+}
+
 fn run_new(
     stdin: &mut (dyn BufRead + Send + Sync),
     stdout: &mut (dyn Write + Send + Sync),
@@ -61,7 +72,7 @@ fn run_new(
     let mut buf = Vec::default();
 
     let env_snapshot = EnvSnapshot::default();
-    let srv = proc_macro_srv::ProcMacroSrv::new(&env_snapshot);
+    let srv = make_srv(&env_snapshot);
 
     let mut span_mode = legacy::SpanMode::Id;
 
@@ -489,7 +500,7 @@ fn run_old(
     let mut write_response = |msg: legacy::Response| msg.write(stdout);
 
     let env = EnvSnapshot::default();
-    let srv = proc_macro_srv::ProcMacroSrv::new(&env);
+    let srv = make_srv(&env);
 
     let mut span_mode = legacy::SpanMode::Id;
 
@@ -18,6 +18,7 @@ use crate::{
 pub(crate) struct Expander {
     inner: ProcMacroLibrary,
     modified_time: SystemTime,
+    fingerprint: u128,
 }
 
 impl Expander {
@@ -25,12 +26,14 @@ impl Expander {
         // Some libraries for dynamic loading require canonicalized path even when it is
         // already absolute
         let lib = lib.canonicalize_utf8()?;
-        let modified_time = fs::metadata(&lib).and_then(|it| it.modified())?;
+        let metadata = fs::metadata(&lib)?;
+        let modified_time = metadata.modified()?;
+        let fingerprint = crate::expansion_cache::dylib_fingerprint(&lib, &metadata)?;
 
         let path = ensure_file_with_lock_free_access(temp_dir, &lib)?;
         let library = ProcMacroLibrary::open(path.as_ref())?;
 
-        Ok(Expander { inner: library, modified_time })
+        Ok(Expander { inner: library, modified_time, fingerprint })
     }
 
     pub(crate) fn expand<'a, S: ProcMacroSrvSpan + 'a>(
@@ -66,6 +69,10 @@ impl Expander {
     pub(crate) fn modified_time(&self) -> SystemTime {
         self.modified_time
     }
+
+    pub(crate) fn fingerprint(&self) -> u128 {
+        self.fingerprint
+    }
 }
 
 struct ProcMacroLibrary {
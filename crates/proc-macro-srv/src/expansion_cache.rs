@@ -0,0 +1,537 @@
+//! Caches the results of proc-macro expansions.
+//!
+//! An expansion is keyed by the proc-macro dylib's path, size and modification time, the macro
+//! name, the input token streams (spans included, as they flow into the output), the sites and the
+//! environment the macro runs in. Expansions whose result depends on something we can't see are never cached,
+//! that is expansions that tracked a path, talked back to the client or failed.
+//!
+//! The cache lives in memory and can optionally be backed by a directory on disk, so that results
+//! survive a restart of the server. The directory is kept below [`MAX_DISK_BYTES`] by deleting the
+//! entries that were least recently written or loaded. A macro that reads files without tracking
+//! them through `proc_macro::tracked_path` looks like any other macro to us, so its entries go stale
+//! when those files change, until they are evicted.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    hash::{DefaultHasher, Hasher},
+    io,
+    path::Path,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use intern::Symbol;
+use paths::{Utf8Path, Utf8PathBuf};
+use rustc_proc_macro::Delimiter;
+
+use crate::{
+    EnvSnapshot, ProcMacroSrvSpan, RUSTC_VERSION_STRING, TrackedEnv,
+    bridge::{DelimSpan, Group, Ident, LitKind, Literal, Punct, TokenTree},
+    token_stream::TokenStream,
+};
+
+/// Bump this whenever the encoding below changes.
+const FORMAT_VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"RAPM";
+/// Once the in-memory entries take up more than this many bytes, they are dropped wholesale.
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Once the entries on disk take up more than this many bytes, the least recently used ones are
+/// deleted.
+pub(crate) const MAX_DISK_BYTES: u64 = 512 * 1024 * 1024;
+
+pub(crate) type CacheKey = u128;
+
+pub(crate) struct ExpansionCache {
+    env_hash: u128,
+    memory: Mutex<MemoryStore>,
+    disk: Option<DiskStore>,
+}
+
+struct DiskStore {
+    dir: Utf8PathBuf,
+    max_bytes: u64,
+    /// Bytes written since the directory was last pruned.
+    written: AtomicU64,
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    entries: HashMap<CacheKey, Arc<[u8]>>,
+    size: usize,
+}
+
+/// Everything that determines the outcome of an expansion.
+pub(crate) struct ExpansionInput<'a, S> {
+    pub(crate) dylib_fingerprint: u128,
+    pub(crate) macro_name: &'a str,
+    pub(crate) macro_body: &'a TokenStream<S>,
+    pub(crate) attribute: Option<&'a TokenStream<S>>,
+    pub(crate) def_site: S,
+    pub(crate) call_site: S,
+    pub(crate) mixed_site: S,
+    pub(crate) env: &'a [(String, String)],
+    pub(crate) current_dir: Option<&'a Path>,
+}
+
+impl ExpansionCache {
+    pub(crate) fn new(env: &EnvSnapshot) -> ExpansionCache {
+        let mut vars: Vec<(&OsString, &OsString)> = env.vars.iter().collect();
+        vars.sort_unstable();
+        let mut buf = Vec::new();
+        for (name, value) in vars {
+            write_bytes(&mut buf, name.as_encoded_bytes());
+            write_bytes(&mut buf, value.as_encoded_bytes());
+        }
+        ExpansionCache { env_hash: hash128(&buf), memory: Default::default(), disk: None }
+    }
+
+    pub(crate) fn set_disk_store(&mut self, dir: Utf8PathBuf, max_bytes: u64) {
+        // Another server may have filled the directory, so start out below the limit.
+        if let Err(err) = prune(&dir, max_bytes) {
+            eprintln!("Failed to prune the proc-macro expansion cache: {err}");
+        }
+        self.disk = Some(DiskStore { dir, max_bytes, written: AtomicU64::new(0) });
+    }
+
+    pub(crate) fn key<S: ProcMacroSrvSpan>(&self, input: &ExpansionInput<'_, S>) -> CacheKey {
+        let mut buf = Vec::new();
+        write_u32(&mut buf, FORMAT_VERSION);
+        write_str(&mut buf, RUSTC_VERSION_STRING);
+        write_str(&mut buf, std::any::type_name::<S>());
+        buf.extend_from_slice(&self.env_hash.to_le_bytes());
+        buf.extend_from_slice(&input.dylib_fingerprint.to_le_bytes());
+        write_str(&mut buf, input.macro_name);
+        encode_stream(&mut buf, input.macro_body);
+        match input.attribute {
+            Some(attribute) => {
+                buf.push(1);
+                encode_stream(&mut buf, attribute);
+            }
+            None => buf.push(0),
+        }
+        input.def_site.encode_for_cache(&mut buf);
+        input.call_site.encode_for_cache(&mut buf);
+        input.mixed_site.encode_for_cache(&mut buf);
+        write_u32(&mut buf, input.env.len() as u32);
+        for (name, value) in input.env {
+            write_str(&mut buf, name);
+            write_str(&mut buf, value);
+        }
+        match input.current_dir {
+            Some(dir) => {
+                buf.push(1);
+                write_bytes(&mut buf, dir.as_os_str().as_encoded_bytes());
+            }
+            None => buf.push(0),
+        }
+        hash128(&buf)
+    }
+
+    /// Looks up the result of a previous expansion of `input`, replaying the environment variables
+    /// it tracked into `tracked_env`.
+    pub(crate) fn get<S: ProcMacroSrvSpan>(
+        &self,
+        key: CacheKey,
+        input: &ExpansionInput<'_, S>,
+        tracked_env: &mut TrackedEnv,
+    ) -> Option<TokenStream<S>> {
+        let entry = self.memory.lock().unwrap_or_else(PoisonError::into_inner).get(key);
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                let entry: Arc<[u8]> = self.read_from_disk(key)?.into();
+                self.memory
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(key, entry.clone());
+                entry
+            }
+        };
+
+        let mut contexts = S::CacheContexts::default();
+        for site in [input.def_site, input.call_site, input.mixed_site] {
+            site.collect_cache_context(&mut contexts);
+        }
+        collect_contexts(input.macro_body, &mut contexts);
+        if let Some(attribute) = input.attribute {
+            collect_contexts(attribute, &mut contexts);
+        }
+
+        let mut bytes = &*entry;
+        let result = decode_stream(&mut bytes, &contexts)?;
+        let mut env_vars = Vec::new();
+        for _ in 0..read_u32(&mut bytes)? {
+            let name = read_str(&mut bytes)?;
+            let value = match read_u8(&mut bytes)? {
+                0 => None,
+                _ => Some(read_str(&mut bytes)?),
+            };
+            env_vars.push((name, value));
+        }
+        if !bytes.is_empty() {
+            return None;
+        }
+        tracked_env
+            .env_vars
+            .extend(env_vars.into_iter().map(|(name, value)| (name.into(), value.map(Into::into))));
+        Some(result)
+    }
+
+    pub(crate) fn insert<S: ProcMacroSrvSpan>(
+        &self,
+        key: CacheKey,
+        result: &TokenStream<S>,
+        tracked_env: &TrackedEnv,
+    ) {
+        let mut buf = Vec::new();
+        encode_stream(&mut buf, result);
+        let mut env_vars: Vec<_> = tracked_env.env_vars.iter().collect();
+        env_vars.sort_unstable();
+        write_u32(&mut buf, env_vars.len() as u32);
+        for (name, value) in env_vars {
+            write_str(&mut buf, name);
+            match value {
+                Some(value) => {
+                    buf.push(1);
+                    write_str(&mut buf, value);
+                }
+                None => buf.push(0),
+            }
+        }
+
+        if let Err(err) = self.write_to_disk(key, &buf) {
+            eprintln!("Failed to persist proc-macro expansion: {err}");
+        }
+        self.memory.lock().unwrap_or_else(PoisonError::into_inner).insert(key, buf.into());
+    }
+
+    fn read_from_disk(&self, key: CacheKey) -> Option<Vec<u8>> {
+        let path = self.disk.as_ref()?.dir.join(file_name(key));
+        let contents = fs::read(&path).ok()?;
+        let mut bytes = &*contents;
+        if read_array(&mut bytes)? != *MAGIC
+            || read_u32(&mut bytes)? != FORMAT_VERSION
+            || u128::from_le_bytes(read_array(&mut bytes)?) != key
+        {
+            return None;
+        }
+        // Entries are evicted by their modification time, so mark this one as recently used.
+        _ = fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        Some(bytes.to_vec())
+    }
+
+    fn write_to_disk(&self, key: CacheKey, entry: &[u8]) -> io::Result<()> {
+        let Some(DiskStore { dir, max_bytes, written }) = &self.disk else { return Ok(()) };
+        fs::create_dir_all(dir)?;
+
+        let mut contents = Vec::with_capacity(MAGIC.len() + 4 + 16 + entry.len());
+        contents.extend_from_slice(MAGIC);
+        write_u32(&mut contents, FORMAT_VERSION);
+        contents.extend_from_slice(&key.to_le_bytes());
+        contents.extend_from_slice(entry);
+
+        // Write to a temporary file first, so that a concurrently running server never observes
+        // a partially written entry.
+        let path = dir.join(file_name(key));
+        let tmp_path = dir.join(format!("{}.{}.tmp", file_name(key), std::process::id()));
+        let len = contents.len() as u64;
+        fs::write(&tmp_path, contents)?;
+        fs::rename(&tmp_path, path).inspect_err(|_| _ = fs::remove_file(&tmp_path))?;
+
+        // Listing the directory isn't free, so only prune once a fraction of the limit was written.
+        if written.fetch_add(len, Ordering::Relaxed) + len > max_bytes / 8 {
+            written.store(0, Ordering::Relaxed);
+            prune(dir, *max_bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deletes the least recently used entries in `dir` until the remaining ones take up at most
+/// `max_bytes`.
+pub(crate) fn prune(dir: &Utf8Path, max_bytes: u64) -> io::Result<()> {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let mut entries = Vec::new();
+    let mut total = 0;
+    for entry in read_dir {
+        let entry = entry?;
+        // Leave alone anything that isn't an entry, like the temporary files of other servers.
+        let is_entry = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.len() == 32 && name.bytes().all(|b| b.is_ascii_hexdigit()));
+        let Ok(metadata) = entry.metadata() else { continue };
+        if !is_entry || !metadata.is_file() {
+            continue;
+        }
+        total += metadata.len();
+        entries.push((metadata.modified()?, metadata.len(), entry.path()));
+    }
+    if total <= max_bytes {
+        return Ok(());
+    }
+
+    entries.sort_unstable_by_key(|&(modified, ..)| modified);
+    for (_, len, path) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => total -= len,
+            // Another server may have pruned it already.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => total -= len,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+impl MemoryStore {
+    fn get(&self, key: CacheKey) -> Option<Arc<[u8]>> {
+        self.entries.get(&key).cloned()
+    }
+
+    fn insert(&mut self, key: CacheKey, entry: Arc<[u8]>) {
+        if self.size + entry.len() > MAX_MEMORY_BYTES {
+            self.entries.clear();
+            self.size = 0;
+        }
+        self.size += entry.len();
+        if let Some(prev) = self.entries.insert(key, entry) {
+            self.size -= prev.len();
+        }
+    }
+}
+
+/// Identifies a version of a proc-macro dylib without reading it, as rebuilding it changes its
+/// modification time.
+pub(crate) fn dylib_fingerprint(path: &Utf8Path, metadata: &fs::Metadata) -> io::Result<u128> {
+    let modified = metadata.modified()?.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+    let mut buf = Vec::new();
+    write_str(&mut buf, path.as_str());
+    buf.extend_from_slice(&metadata.len().to_le_bytes());
+    buf.extend_from_slice(&modified.as_nanos().to_le_bytes());
+    Ok(hash128(&buf))
+}
+
+fn file_name(key: CacheKey) -> String {
+    format!("{key:032x}")
+}
+
+fn hash128(bytes: &[u8]) -> u128 {
+    let mut lo = DefaultHasher::new();
+    let mut hi = DefaultHasher::new();
+    hi.write_u8(0xff);
+    lo.write(bytes);
+    hi.write(bytes);
+    (u128::from(hi.finish()) << 64) | u128::from(lo.finish())
+}
+
+fn encode_stream<S: ProcMacroSrvSpan>(buf: &mut Vec<u8>, stream: &TokenStream<S>) {
+    write_u32(buf, stream.len() as u32);
+    for tt in stream.iter() {
+        match tt {
+            TokenTree::Group(Group { delimiter, stream, span }) => {
+                buf.push(0);
+                buf.push(match delimiter {
+                    Delimiter::Parenthesis => 0,
+                    Delimiter::Brace => 1,
+                    Delimiter::Bracket => 2,
+                    Delimiter::None => 3,
+                });
+                span.open.encode_for_cache(buf);
+                span.close.encode_for_cache(buf);
+                span.entire.encode_for_cache(buf);
+                match stream {
+                    Some(stream) => {
+                        buf.push(1);
+                        encode_stream(buf, stream);
+                    }
+                    None => buf.push(0),
+                }
+            }
+            TokenTree::Punct(Punct { ch, joint, span }) => {
+                buf.extend_from_slice(&[1, *ch, *joint as u8]);
+                span.encode_for_cache(buf);
+            }
+            TokenTree::Ident(Ident { sym, is_raw, span }) => {
+                buf.push(2);
+                write_str(buf, sym.as_str());
+                buf.push(*is_raw as u8);
+                span.encode_for_cache(buf);
+            }
+            TokenTree::Literal(Literal { kind, symbol, suffix, span }) => {
+                buf.push(3);
+                let (tag, n) = match *kind {
+                    LitKind::Byte => (0, 0),
+                    LitKind::Char => (1, 0),
+                    LitKind::Integer => (2, 0),
+                    LitKind::Float => (3, 0),
+                    LitKind::Str => (4, 0),
+                    LitKind::StrRaw(n) => (5, n),
+                    LitKind::ByteStr => (6, 0),
+                    LitKind::ByteStrRaw(n) => (7, n),
+                    LitKind::CStr => (8, 0),
+                    LitKind::CStrRaw(n) => (9, n),
+                    LitKind::ErrWithGuar => (10, 0),
+                };
+                buf.extend_from_slice(&[tag, n]);
+                write_str(buf, symbol.as_str());
+                match suffix {
+                    Some(suffix) => {
+                        buf.push(1);
+                        write_str(buf, suffix.as_str());
+                    }
+                    None => buf.push(0),
+                }
+                span.encode_for_cache(buf);
+            }
+        }
+    }
+}
+
+fn collect_contexts<S: ProcMacroSrvSpan>(stream: &TokenStream<S>, contexts: &mut S::CacheContexts) {
+    for tt in stream.iter() {
+        match tt {
+            TokenTree::Group(Group { span, stream, .. }) => {
+                span.open.collect_cache_context(contexts);
+                span.close.collect_cache_context(contexts);
+                span.entire.collect_cache_context(contexts);
+                if let Some(stream) = stream {
+                    collect_contexts(stream, contexts);
+                }
+            }
+            TokenTree::Punct(Punct { span, .. })
+            | TokenTree::Ident(Ident { span, .. })
+            | TokenTree::Literal(Literal { span, .. }) => span.collect_cache_context(contexts),
+        }
+    }
+}
+
+fn decode_stream<S: ProcMacroSrvSpan>(
+    bytes: &mut &[u8],
+    contexts: &S::CacheContexts,
+) -> Option<TokenStream<S>> {
+    let len = read_u32(bytes)?;
+    let mut tts = Vec::with_capacity((len as usize).min(bytes.len()));
+    for _ in 0..len {
+        let tt = match read_u8(bytes)? {
+            0 => {
+                let delimiter = match read_u8(bytes)? {
+                    0 => Delimiter::Parenthesis,
+                    1 => Delimiter::Brace,
+                    2 => Delimiter::Bracket,
+                    3 => Delimiter::None,
+                    _ => return None,
+                };
+                let span = DelimSpan {
+                    open: S::decode_from_cache(bytes, contexts)?,
+                    close: S::decode_from_cache(bytes, contexts)?,
+                    entire: S::decode_from_cache(bytes, contexts)?,
+                };
+                let stream = match read_u8(bytes)? {
+                    0 => None,
+                    _ => Some(decode_stream(bytes, contexts)?),
+                };
+                TokenTree::Group(Group { delimiter, stream, span })
+            }
+            1 => {
+                let [ch, joint] = read_array(bytes)?;
+                TokenTree::Punct(Punct {
+                    ch,
+                    joint: joint != 0,
+                    span: S::decode_from_cache(bytes, contexts)?,
+                })
+            }
+            2 => {
+                let sym = Symbol::intern(read_str(bytes)?);
+                let is_raw = read_u8(bytes)? != 0;
+                TokenTree::Ident(Ident {
+                    sym,
+                    is_raw,
+                    span: S::decode_from_cache(bytes, contexts)?,
+                })
+            }
+            3 => {
+                let [tag, n] = read_array(bytes)?;
+                let kind = match tag {
+                    0 => LitKind::Byte,
+                    1 => LitKind::Char,
+                    2 => LitKind::Integer,
+                    3 => LitKind::Float,
+                    4 => LitKind::Str,
+                    5 => LitKind::StrRaw(n),
+                    6 => LitKind::ByteStr,
+                    7 => LitKind::ByteStrRaw(n),
+                    8 => LitKind::CStr,
+                    9 => LitKind::CStrRaw(n),
+                    10 => LitKind::ErrWithGuar,
+                    _ => return None,
+                };
+                let symbol = Symbol::intern(read_str(bytes)?);
+                let suffix = match read_u8(bytes)? {
+                    0 => None,
+                    _ => Some(Symbol::intern(read_str(bytes)?)),
+                };
+                TokenTree::Literal(Literal {
+                    kind,
+                    symbol,
+                    suffix,
+                    span: S::decode_from_cache(bytes, contexts)?,
+                })
+            }
+            _ => return None,
+        };
+        tts.push(tt);
+    }
+    Some(TokenStream::new(tts))
+}
+
+pub(crate) fn write_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(buf, bytes.len() as u32);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_bytes(buf, s.as_bytes());
+}
+
+pub(crate) fn read_array<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    let (head, rest) = bytes.split_first_chunk::<N>()?;
+    *bytes = rest;
+    Some(*head)
+}
+
+fn read_u8(bytes: &mut &[u8]) -> Option<u8> {
+    read_array::<1>(bytes).map(|[b]| b)
+}
+
+pub(crate) fn read_u32(bytes: &mut &[u8]) -> Option<u32> {
+    read_array(bytes).map(u32::from_le_bytes)
+}
+
+fn read_str<'a>(bytes: &mut &'a [u8]) -> Option<&'a str> {
+    let len = read_u32(bytes)? as usize;
+    if bytes.len() < len {
+        return None;
+    }
+    let (s, rest) = bytes.split_at(len);
+    *bytes = rest;
+    std::str::from_utf8(s).ok()
+}
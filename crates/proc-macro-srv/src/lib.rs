@@ -25,6 +25,7 @@ extern crate rustc_span;
 
 mod bridge;
 mod dylib;
+mod expansion_cache;
 mod server_impl;
 mod token_stream;
 
@@ -45,6 +46,8 @@ use temp_dir::TempDir;
 
 pub use crate::server_impl::token_id::SpanId;
 
+use crate::expansion_cache::{ExpansionCache, ExpansionInput};
+
 pub use rustc_proc_macro::Delimiter;
 pub use span;
 
@@ -65,6 +68,7 @@ pub struct ProcMacroSrv<'env> {
     expanders: Mutex<HashMap<Utf8PathBuf, Arc<dylib::Expander>>>,
     env: &'env EnvSnapshot,
     temp_dir: TempDir,
+    expansion_cache: ExpansionCache,
 }
 
 impl<'env> ProcMacroSrv<'env> {
@@ -73,9 +77,19 @@ impl<'env> ProcMacroSrv<'env> {
             expanders: Default::default(),
             env,
             temp_dir: TempDir::with_prefix("proc-macro-srv").unwrap(),
+            expansion_cache: ExpansionCache::new(env),
         }
     }
 
+    /// Persists expansion results in `dir`, so that they survive a restart of the server.
+    ///
+    /// Expansions of macros that read files without tracking them are persisted as well, so they
+    /// are served stale once those files change.
+    pub fn with_expansion_cache_dir(mut self, dir: Utf8PathBuf) -> Self {
+        self.expansion_cache.set_disk_store(dir, expansion_cache::MAX_DISK_BYTES);
+        self
+    }
+
     pub fn join_spans(&self, first: Span, second: Span) -> Option<Span> {
         first.join(second, |_, _| {
             // FIXME: Once we can talk back to the client, implement a "long join" request for anchors
@@ -152,11 +166,33 @@ impl ProcMacroSrv<'_> {
             reason: Some(format!("failed to load macro: {err}")),
         })?;
 
-        let prev_env = EnvChange::apply(snapped_env, env, current_dir.as_ref().map(<_>::as_ref));
+        let current_dir = current_dir.as_ref().map(<_>::as_ref);
+        let cache_input = ExpansionInput {
+            dylib_fingerprint: expander.fingerprint(),
+            macro_name,
+            macro_body: &macro_body,
+            attribute: attribute.as_ref(),
+            def_site,
+            call_site,
+            mixed_site,
+            env,
+            current_dir,
+        };
+        let cache_key = self.expansion_cache.key(&cache_input);
+        if let Some(result) = self.expansion_cache.get(cache_key, &cache_input, tracked_env) {
+            return Ok(result);
+        }
+
+        let prev_env = EnvChange::apply(snapped_env, env, current_dir);
 
+        // Results that depend on answers from the client can't be cached, so keep track of whether
+        // the macro asked anything.
+        let mut callback = callback.map(|inner| RecordingClient { inner, used: false });
         // Note, we spawn a new thread here so that thread locals allocation don't accumulate (this
         // includes the proc-macro symbol interner)
         let result = thread::scope(|s| {
+            let tracked_env = &mut *tracked_env;
+            let callback = callback.as_mut().map(|it| it as ProcMacroClientHandle<'_>);
             let thread = thread::Builder::new()
                 .stack_size(EXPANDER_STACK_SIZE)
                 .name(macro_name.to_owned())
@@ -192,6 +228,13 @@ impl ProcMacroSrv<'_> {
         });
         prev_env.rollback();
 
+        if let Ok(result) = &result
+            && tracked_env.paths.is_empty()
+            && !callback.is_some_and(|it| it.used)
+        {
+            self.expansion_cache.insert(cache_key, result, tracked_env);
+        }
+
         result
     }
 
@@ -230,6 +273,54 @@ impl ProcMacroSrv<'_> {
     }
 }
 
+/// Forwards requests to the client, remembering whether there were any.
+struct RecordingClient<'a> {
+    inner: ProcMacroClientHandle<'a>,
+    used: bool,
+}
+
+impl ProcMacroClientInterface for RecordingClient<'_> {
+    fn file(&mut self, file_id: span::FileId) -> String {
+        self.used = true;
+        self.inner.file(file_id)
+    }
+
+    fn source_text(&mut self, span: Span) -> Option<String> {
+        self.used = true;
+        self.inner.source_text(span)
+    }
+
+    fn local_file(&mut self, file_id: span::FileId) -> Option<String> {
+        self.used = true;
+        self.inner.local_file(file_id)
+    }
+
+    fn line_column(&mut self, span: Span) -> Option<(u32, u32)> {
+        self.used = true;
+        self.inner.line_column(span)
+    }
+
+    fn byte_range(&mut self, span: Span) -> Range<usize> {
+        self.used = true;
+        self.inner.byte_range(span)
+    }
+
+    fn span_source(&mut self, span: Span) -> Span {
+        self.used = true;
+        self.inner.span_source(span)
+    }
+
+    fn span_parent(&mut self, span: Span) -> Option<Span> {
+        self.used = true;
+        self.inner.span_parent(span)
+    }
+
+    fn span_join(&mut self, first: Span, second: Span) -> Option<Span> {
+        self.used = true;
+        self.inner.span_join(first, second)
+    }
+}
+
 #[derive(Default)]
 pub struct TrackedEnv {
     pub env_vars: HashMap<Box<str>, Option<Box<str>>>,
@@ -247,10 +338,22 @@ pub trait ProcMacroSrvSpan: Copy + Send + Sync {
         tracked_env: &'a mut TrackedEnv,
         callback: Option<ProcMacroClientHandle<'a>>,
     ) -> Self::Server<'a>;
+
+    /// The syntax contexts of the spans an expansion was keyed by, the only ones a cached result
+    /// may refer to.
+    type CacheContexts: Default;
+
+    /// Encodes the span for the expansion cache.
+    fn encode_for_cache(self, buf: &mut Vec<u8>);
+    fn collect_cache_context(self, contexts: &mut Self::CacheContexts);
+    /// Decodes a span encoded by [`ProcMacroSrvSpan::encode_for_cache`], rejecting ranges and
+    /// contexts that can't have come from the expansion's input.
+    fn decode_from_cache(bytes: &mut &[u8], contexts: &Self::CacheContexts) -> Option<Self>;
 }
 
 impl ProcMacroSrvSpan for SpanId {
     type Server<'a> = server_impl::token_id::SpanIdServer<'a>;
+    type CacheContexts = ();
 
     fn make_server<'a>(
         call_site: Self,
@@ -261,10 +364,22 @@ impl ProcMacroSrvSpan for SpanId {
     ) -> Self::Server<'a> {
         Self::Server { call_site, def_site, mixed_site, callback }
     }
+
+    fn encode_for_cache(self, buf: &mut Vec<u8>) {
+        expansion_cache::write_u32(buf, self.0);
+    }
+
+    fn collect_cache_context(self, (): &mut ()) {}
+
+    fn decode_from_cache(bytes: &mut &[u8], (): &()) -> Option<Self> {
+        expansion_cache::read_u32(bytes).map(SpanId)
+    }
 }
 
 impl ProcMacroSrvSpan for Span {
     type Server<'a> = server_impl::rust_analyzer_span::RaSpanServer<'a>;
+    type CacheContexts = HashMap<u32, span::SyntaxContext>;
+
     fn make_server<'a>(
         call_site: Self,
         def_site: Self,
@@ -281,6 +396,34 @@ impl ProcMacroSrvSpan for Span {
             fixup_id: FIXUP_ERASED_FILE_AST_ID_MARKER,
         }
     }
+
+    fn encode_for_cache(self, buf: &mut Vec<u8>) {
+        expansion_cache::write_u32(buf, self.range.start().into());
+        expansion_cache::write_u32(buf, self.range.end().into());
+        expansion_cache::write_u32(buf, self.anchor.file_id.as_u32());
+        expansion_cache::write_u32(buf, self.anchor.ast_id.into_raw());
+        expansion_cache::write_u32(buf, self.ctx.into_u32());
+    }
+
+    fn collect_cache_context(self, contexts: &mut Self::CacheContexts) {
+        contexts.insert(self.ctx.into_u32(), self.ctx);
+    }
+
+    fn decode_from_cache(bytes: &mut &[u8], contexts: &Self::CacheContexts) -> Option<Self> {
+        let mut read = || expansion_cache::read_u32(bytes);
+        let (start, end) = (read()?, read()?);
+        if start > end {
+            return None;
+        }
+        let file_id = span::EditionedFileId::from_raw(read()?);
+        let ast_id = span::ErasedFileAstId::from_raw(read()?);
+        let ctx = *contexts.get(&read()?)?;
+        Some(Span {
+            range: span::TextRange::new(start.into(), end.into()),
+            anchor: span::SpanAnchor { file_id, ast_id },
+            ctx,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
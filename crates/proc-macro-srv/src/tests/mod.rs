@@ -689,6 +689,42 @@ fn test_broken_input_unknowm_token() {
     assert_expand("fn_like_clone_tokens", r###"🪟"###, expect![[]], expect![[]]);
 }
 
+#[test]
+fn test_expansion_cache() {
+    assert_expand_cached(
+        "fn_like_clone_tokens",
+        r#""abc" 1.0f32 'a' b"xyz" #[attr] r#ident (x, [y])"#,
+    );
+}
+
+#[test]
+fn test_expansion_cache_rejects_foreign_spans() {
+    assert_cache_rejects_foreign_spans();
+}
+
+#[test]
+fn test_expansion_cache_evicts_least_recently_used() {
+    let dir = temp_dir::TempDir::new().unwrap();
+    let dir = paths::Utf8Path::from_path(dir.path()).unwrap().to_owned();
+    let now = std::time::SystemTime::now();
+    for (i, name) in ["0", "1", "2", "3"].into_iter().enumerate() {
+        let path = dir.join(name.repeat(32));
+        std::fs::write(&path, [0; 10]).unwrap();
+        let modified = now - std::time::Duration::from_secs(60 * (4 - i as u64));
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+    std::fs::write(dir.join("entry.tmp"), [0; 100]).unwrap();
+
+    crate::expansion_cache::prune(&dir, 25).unwrap();
+
+    let mut left: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    left.sort();
+    assert_eq!(left, ["2".repeat(32), "3".repeat(32), "entry.tmp".to_owned()]);
+}
+
 /// Tests that we find and classify all proc macros correctly.
 #[test]
 fn list_test_macros() {
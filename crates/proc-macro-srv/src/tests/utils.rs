@@ -7,8 +7,8 @@ use span::{
 use std::ops::Range;
 
 use crate::{
-    EnvSnapshot, ProcMacroClientInterface, ProcMacroSrv, SpanId, dylib, proc_macro_test_dylib_path,
-    token_stream::TokenStream,
+    EnvSnapshot, ProcMacroClientInterface, ProcMacroSrv, ProcMacroSrvSpan, SpanId, dylib,
+    expansion_cache::write_u32, proc_macro_test_dylib_path, token_stream::TokenStream,
 };
 
 fn make_ctx() -> SyntaxContext {
@@ -223,3 +223,72 @@ pub fn assert_expand_with_callback(
         .unwrap();
     expect_spanned.assert_eq(&format!("{res:?}"));
 }
+
+/// Expands `ra_fixture` with two servers sharing an on-disk expansion cache and checks that the
+/// second server hands out the result the first one stored.
+pub fn assert_expand_cached(macro_name: &str, #[rust_analyzer::rust_fixture] ra_fixture: &str) {
+    let path = proc_macro_test_dylib_path();
+    let cache_dir = temp_dir::TempDir::new().unwrap();
+    let cache_dir = paths::Utf8Path::from_path(cache_dir.path()).unwrap().to_owned();
+
+    let call_site = Span {
+        range: TextRange::new(0.into(), 100.into()),
+        anchor: SpanAnchor {
+            file_id: EditionedFileId::current_edition(FileId::from_raw(42)),
+            ast_id: ROOT_ERASED_FILE_AST_ID,
+        },
+        ctx: make_ctx(),
+    };
+    let expand = || {
+        let env = EnvSnapshot::default();
+        let srv = ProcMacroSrv::new(&env).with_expansion_cache_dir(cache_dir.clone());
+        let fixture = parse_string_spanned(call_site.anchor, call_site.ctx, ra_fixture);
+        let Ok(res) = srv.expand(
+            &path,
+            &[],
+            None::<&std::path::Path>,
+            macro_name,
+            fixture,
+            None,
+            call_site,
+            call_site,
+            call_site,
+            &mut Default::default(),
+            None,
+        ) else {
+            panic!("failed to expand {macro_name}")
+        };
+        format!("{res:?}")
+    };
+
+    let expanded = expand();
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+    assert_eq!(expand(), expanded);
+}
+
+/// Checks that a cached span only decodes if its range is well-formed and its context was part of
+/// the expansion's input.
+pub fn assert_cache_rejects_foreign_spans() {
+    let span = Span {
+        range: TextRange::new(1.into(), 4.into()),
+        anchor: SpanAnchor {
+            file_id: EditionedFileId::current_edition(FileId::from_raw(42)),
+            ast_id: ROOT_ERASED_FILE_AST_ID,
+        },
+        ctx: make_ctx(),
+    };
+    let mut contexts = Default::default();
+    span.collect_cache_context(&mut contexts);
+
+    let mut buf = Vec::new();
+    span.encode_for_cache(&mut buf);
+    assert_eq!(Span::decode_from_cache(&mut &*buf, &contexts), Some(span));
+    assert_eq!(Span::decode_from_cache(&mut &*buf, &Default::default()), None);
+
+    let mut inverted = Vec::new();
+    for value in [4, 1, span.anchor.file_id.as_u32(), span.anchor.ast_id.into_raw()] {
+        write_u32(&mut inverted, value);
+    }
+    write_u32(&mut inverted, span.ctx.into_u32());
+    assert_eq!(Span::decode_from_cache(&mut &*inverted, &contexts), None);
+}
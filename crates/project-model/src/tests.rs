@@ -240,6 +240,34 @@ fn crate_graph_dedup() {
     assert_eq!(crate_graph.iter().count(), 108);
 }
 
+#[test]
+fn ad_hoc_file_crate_graph() {
+    let mut file_map = FxHashMap::default();
+    let mut load = |path: &AbsPath| {
+        let len = file_map.len() + 1;
+        Some(*file_map.entry(path.to_path_buf()).or_insert(FileId::from_raw(len as u32)))
+    };
+
+    let workspace = load_workspace_from_metadata("hello-world-metadata.json");
+    let (mut crate_graph, mut proc_macros) =
+        workspace.to_crate_graph(&mut load, &Default::default());
+    let crate_count = crate_graph.iter().count();
+
+    let mut root = "$ROOT$scratch.rs".to_owned();
+    replace_root(&mut root, true);
+    let file = ManifestPath::try_from(AbsPathBuf::assert(Utf8PathBuf::from(root))).unwrap();
+    let ad_hoc_graph = workspace.ad_hoc_file_to_crate_graph(&file, &mut load);
+    assert_eq!(ad_hoc_graph.iter().count(), 1);
+    let ad_hoc_crate = ad_hoc_graph.iter().next().unwrap();
+    assert_eq!(ad_hoc_graph[ad_hoc_crate].basic.root_file_id, file_map[&*file]);
+    assert!(
+        ad_hoc_graph[ad_hoc_crate].cfg_options.check(&CfgAtom::Flag(sym::test).into()).unwrap()
+    );
+
+    crate_graph.extend(ad_hoc_graph, &mut proc_macros);
+    assert_eq!(crate_graph.iter().count(), crate_count + 1);
+}
+
 #[test]
 fn smoke_test_real_sysroot_cargo() {
     let file_map = &mut FxHashMap::<AbsPathBuf, FileId>::default();
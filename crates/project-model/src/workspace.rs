@@ -90,14 +90,11 @@ pub enum ProjectWorkspaceKind {
     },
     /// Project workspace was specified using a `rust-project.json` file.
     Json(ProjectJson),
-    // FIXME: The primary limitation of this approach is that the set of detached files needs to be fixed at the beginning.
-    // That's not the end user experience we should strive for.
-    // Ideally, you should be able to just open a random detached file in existing cargo projects, and get the basic features working.
-    // That needs some changes on the salsa-level though.
-    // In particular, we should split the unified CrateGraph (which currently has maximal durability) into proper crate graph, and a set of ad hoc roots (with minimal durability).
-    // Then, we need to hide the graph behind the queries such that most queries look only at the proper crate graph, and fall back to ad hoc roots only if there's no results.
-    // After this, we should be able to tweak the logic in reload.rs to add newly opened files, which don't belong to any existing crates, to the set of the detached files.
-    // //
+    // Files opened later that don't belong to any crate are not detached file workspaces, they are
+    // added as ad hoc crates on top of an existing workspace, see `ad_hoc_file_to_crate_graph`.
+    // Ad hoc crates are kept out of `base_db::all_crates`, so adding one doesn't invalidate the
+    // queries depending on the set of all crates, only the crates of their source root are looked
+    // up among them.
     /// Project with a set of disjoint files, not belonging to any particular workspace.
     /// Backed by basic sysroot crates for basic completion and highlighting.
     DetachedFile {
@@ -1042,6 +1039,7 @@ impl ProjectWorkspace {
                         sysroot,
                         cfg_overrides,
                         self.set_test,
+                        false,
                         crate_ws_data,
                     )
                 }
@@ -1051,6 +1049,33 @@ impl ProjectWorkspace {
         (crate_graph, proc_macros)
     }
 
+    /// Builds the crate graph of an ad hoc crate rooted at `file`, a file that doesn't belong to
+    /// any workspace, backed by the sysroot of this workspace.
+    ///
+    /// Unlike [`ProjectWorkspace::load_detached_file`], this doesn't query cargo or rustc, so it
+    /// can be done whenever such a file is opened.
+    pub fn ad_hoc_file_to_crate_graph(
+        &self,
+        file: &ManifestPath,
+        load: FileLoader<'_>,
+    ) -> CrateGraphBuilder {
+        let crate_ws_data = Arc::new(CrateWorkspaceData {
+            toolchain: self.toolchain.clone(),
+            target: self.target.clone(),
+        });
+        let (crate_graph, _) = detached_file_to_crate_graph(
+            self.rustc_cfg.clone(),
+            load,
+            file,
+            &self.sysroot,
+            &self.cfg_overrides,
+            self.set_test,
+            true,
+            crate_ws_data,
+        );
+        crate_graph
+    }
+
     pub fn eq_ignore_build_data(&self, other: &Self) -> bool {
         let Self {
             kind, sysroot, rustc_cfg, toolchain, target: target_layout, cfg_overrides, ..
@@ -1479,6 +1504,7 @@ fn detached_file_to_crate_graph(
     sysroot: &Sysroot,
     override_cfg: &CfgOverrides,
     set_test: bool,
+    ad_hoc: bool,
     crate_ws_data: Arc<CrateWorkspaceData>,
 ) -> (CrateGraphBuilder, ProcMacroPaths) {
     let _p = tracing::info_span!("detached_file_to_crate_graph").entered();
@@ -1526,6 +1552,10 @@ fn detached_file_to_crate_graph(
         crate_ws_data,
     );
 
+    if ad_hoc {
+        crate_graph.mark_ad_hoc(detached_file_crate);
+    }
+
     public_deps.add_to_crate_graph(&mut crate_graph, detached_file_crate);
     (crate_graph, FxHashMap::default())
 }
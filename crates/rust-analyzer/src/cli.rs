@@ -3,6 +3,7 @@
 #![allow(clippy::print_stdout, clippy::print_stderr)]
 
 mod analysis_stats;
+mod dap;
 mod diagnostics;
 pub mod flags;
 mod highlight;
@@ -21,7 +22,7 @@ mod progress_report;
 use std::io::Read;
 
 use anyhow::Result;
-use hir::{Module, Name};
+use hir::{Crate, Module, Name};
 use hir_ty::db::HirDatabase;
 use ide::{AnalysisHost, Edition};
 use itertools::Itertools;
@@ -90,3 +91,20 @@ fn full_name_of_item(db: &dyn HirDatabase, module: Module, name: Name) -> String
         .map(|it| it.display(db, Edition::LATEST).to_string())
         .join("::")
 }
+
+/// The modules of all local crates.
+fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
+    let mut worklist: Vec<_> = Crate::all(db)
+        .into_iter()
+        .filter(|x| x.origin(db).is_local())
+        .map(|krate| krate.root_module(db))
+        .collect();
+    let mut modules = Vec::new();
+
+    while let Some(module) = worklist.pop() {
+        modules.push(module);
+        worklist.extend(module.children(db));
+    }
+
+    modules
+}
@@ -11,8 +11,9 @@ use std::{
 
 use cfg::{CfgAtom, CfgDiff};
 use hir::{
-    Adt, AssocItem, Crate, DefWithBody, FindPathConfig, GenericDef, HasCrate, HasSource,
-    HirDisplay, ModuleDef, Name, Variant, crate_lang_items, db::HirDatabase,
+    Adt, AssocItem, ConstEvalError, Crate, DefWithBody, FindPathConfig, GenericDef, HasCrate,
+    HasSource, HirDisplay, MirEvalError, MirLowerError, ModuleDef, Name, Variant, crate_lang_items,
+    db::HirDatabase,
 };
 use hir_def::{
     DefWithBodyId, ExpressionStoreOwnerId, GenericDefId, SyntheticSyntax,
@@ -433,7 +434,7 @@ impl flags::AnalysisStats {
             };
             if verbosity.is_spammy() {
                 let full_name = full_name_of_item(db, a.module(db), a.name(db));
-                println!("Data layout for {full_name} failed due {e:?}");
+                eprintln!("Data layout for {full_name} failed due {e:?}");
             }
             fail += 1;
         }
@@ -465,6 +466,7 @@ impl flags::AnalysisStats {
         let mut sw = self.stop_watch();
         let mut all = 0;
         let mut fail = 0;
+        let mut report = ConstEvalReport::default();
         for &b in bodies {
             bar.set_message(move || {
                 format!("const eval: {}", full_name(db, || b.name(db), b.module(db)))
@@ -482,11 +484,20 @@ impl flags::AnalysisStats {
             if verbosity.is_spammy() {
                 let full_name =
                     full_name_of_item(db, b.module(db), b.name(db).unwrap_or(Name::missing()));
-                bar.println(format!("Const eval for {full_name} failed due {error:?}"));
+                eprintln!("Const eval for {full_name} failed due {error:?}");
+            }
+            if self.const_eval_report {
+                let full_name =
+                    full_name_of_item(db, b.module(db), b.name(db).unwrap_or(Name::missing()));
+                report.add(full_name, &error);
             }
             fail += 1;
         }
         bar.finish_and_clear();
+        if self.const_eval_report {
+            let report = report.into_json(all, fail);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
         let const_eval_time = sw.elapsed();
         eprintln!("{:<20} {}", "Const evaluation:", const_eval_time);
         eprintln!("Failed const evals: {fail} ({}%)", percentage(fail, all));
@@ -1373,6 +1384,8 @@ impl flags::AnalysisStats {
                     style_lints: false,
                     term_search_fuel: 400,
                     show_rename_conflicts: true,
+                    layout_budget_attribute: None,
+                    layout_budgets: Default::default(),
                 },
                 ide::AssistResolveStrategy::All,
                 analysis.editioned_file_id_to_vfs(file_id),
@@ -1410,6 +1423,7 @@ impl flags::AnalysisStats {
                     implicit_drop_hints: true,
                     implied_dyn_trait_hints: true,
                     lifetime_elision_hints: ide::LifetimeElisionHints::Always,
+                    lifetime_parameter_hints: true,
                     param_names_for_lifetime_elision_hints: true,
                     hide_inferred_type_hints: false,
                     hide_named_constructor_hints: false,
@@ -1420,6 +1434,7 @@ impl flags::AnalysisStats {
                     closing_brace_hints_min_lines: Some(20),
                     fields_to_resolve: InlayFieldsToResolve::empty(),
                     range_exclusive_hints: true,
+                    const_value_hints: true,
                     ra_fixture: RaFixtureConfig::default(),
                 },
                 analysis.editioned_file_id_to_vfs(file_id),
@@ -1574,6 +1589,111 @@ fn pat_syntax_range<'a>(
     }
 }
 
+/// The failed const evaluations of `--const-eval-report`, grouped by the kind of their error and
+/// by the shim they miss.
+#[derive(Default)]
+struct ConstEvalReport {
+    failures_by_kind: FxHashMap<String, Vec<String>>,
+    failures_by_shim: FxHashMap<String, Vec<String>>,
+}
+
+impl ConstEvalReport {
+    fn add(&mut self, full_name: String, error: &ConstEvalError<'_>) {
+        let (kind, missing_shim) = const_eval_failure_kind(error);
+        if let Some(shim) = missing_shim {
+            self.failures_by_shim.entry(shim).or_default().push(full_name.clone());
+        }
+        self.failures_by_kind.entry(kind).or_default().push(full_name);
+    }
+
+    /// Lists the groups from the largest to the smallest.
+    fn into_json(self, evaluated: u64, failed: u64) -> serde_json::Value {
+        let groups = |failures: FxHashMap<String, Vec<String>>, key: &str| {
+            failures
+                .into_iter()
+                .sorted_by(|(a_key, a), (b_key, b)| {
+                    b.len().cmp(&a.len()).then_with(|| a_key.cmp(b_key))
+                })
+                .map(|(name, mut items)| {
+                    items.sort();
+                    serde_json::json!({ key: name, "count": items.len(), "items": items })
+                })
+                .collect::<Vec<_>>()
+        };
+        serde_json::json!({
+            "evaluated": evaluated,
+            "failed": failed,
+            "errors": groups(self.failures_by_kind, "kind"),
+            "missingShims": groups(self.failures_by_shim, "shim"),
+        })
+    }
+}
+
+/// The variant of the error that made a const evaluation fail, looking through the errors that
+/// only wrap another one, and the intrinsic or extern function missing a shim if that's the cause.
+fn const_eval_failure_kind(error: &ConstEvalError<'_>) -> (String, Option<String>) {
+    match error {
+        ConstEvalError::MirLowerError(e) => mir_lower_failure_kind(e),
+        ConstEvalError::MirEvalError(e) => mir_eval_failure_kind(e),
+    }
+}
+
+fn mir_lower_failure_kind(error: &MirLowerError<'_>) -> (String, Option<String>) {
+    let variant = match error {
+        MirLowerError::ConstEvalError(_, e) => return const_eval_failure_kind(e),
+        MirLowerError::LayoutError(_) => "LayoutError",
+        MirLowerError::IncompleteExpr => "IncompleteExpr",
+        MirLowerError::IncompletePattern => "IncompletePattern",
+        MirLowerError::TraitFunctionDefinition(..) => "TraitFunctionDefinition",
+        MirLowerError::UnresolvedName(_) => "UnresolvedName",
+        MirLowerError::RecordLiteralWithoutPath => "RecordLiteralWithoutPath",
+        MirLowerError::UnresolvedMethod(_) => "UnresolvedMethod",
+        MirLowerError::UnresolvedField => "UnresolvedField",
+        MirLowerError::UnsizedTemporary(_) => "UnsizedTemporary",
+        MirLowerError::MissingFunctionDefinition(..) => "MissingFunctionDefinition",
+        MirLowerError::HasErrors => "HasErrors",
+        MirLowerError::TypeError(_) => "TypeError",
+        MirLowerError::NotSupported(_) => "NotSupported",
+        MirLowerError::ContinueWithoutLoop => "ContinueWithoutLoop",
+        MirLowerError::BreakWithoutLoop => "BreakWithoutLoop",
+        MirLowerError::Loop => "Loop",
+        MirLowerError::ImplementationError(_) => "ImplementationError",
+        MirLowerError::LangItemNotFound => "LangItemNotFound",
+        MirLowerError::MutatingRvalue => "MutatingRvalue",
+        MirLowerError::UnresolvedLabel => "UnresolvedLabel",
+        MirLowerError::UnresolvedUpvar(_) => "UnresolvedUpvar",
+        MirLowerError::InaccessibleLocal => "InaccessibleLocal",
+        MirLowerError::GenericArgNotProvided(..) => "GenericArgNotProvided",
+    };
+    (format!("MirLowerError::{variant}"), None)
+}
+
+fn mir_eval_failure_kind(error: &MirEvalError<'_>) -> (String, Option<String>) {
+    let variant = match error {
+        MirEvalError::InFunction(e, _) => return mir_eval_failure_kind(e),
+        MirEvalError::ConstEvalError(_, e) => return const_eval_failure_kind(e),
+        MirEvalError::MirLowerError(_, e) | MirEvalError::MirLowerErrorForClosure(_, e) => {
+            return mir_lower_failure_kind(e);
+        }
+        MirEvalError::MissingShim(name) => {
+            return ("MirEvalError::MissingShim".to_owned(), Some(name.clone()));
+        }
+        MirEvalError::LayoutError(..) => "LayoutError",
+        MirEvalError::TargetDataLayoutNotAvailable(_) => "TargetDataLayoutNotAvailable",
+        MirEvalError::UndefinedBehavior(_) => "UndefinedBehavior",
+        MirEvalError::Panic(_) => "Panic",
+        MirEvalError::TypeIsUnsized(..) => "TypeIsUnsized",
+        MirEvalError::NotSupported(_) => "NotSupported",
+        MirEvalError::InvalidConst => "InvalidConst",
+        MirEvalError::ExecutionLimitExceeded => "ExecutionLimitExceeded",
+        MirEvalError::StackOverflow => "StackOverflow",
+        MirEvalError::InvalidVTableId(_) => "InvalidVTableId",
+        MirEvalError::CoerceUnsizedError(_) => "CoerceUnsizedError",
+        MirEvalError::InternalError(_) => "InternalError",
+    };
+    (format!("MirEvalError::{variant}"), None)
+}
+
 fn shuffle<T>(rng: &mut Rand32, slice: &mut [T]) {
     for i in 0..slice.len() {
         randomize_first(rng, &mut slice[i..]);
@@ -1669,3 +1789,78 @@ impl fmt::Display for PrettyItemStats {
 //     // to make macro and non-macro code comparable.
 //     drop_whitespace(&node.to_string()).len()
 // }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    #[test]
+    fn const_eval_report_groups_failures() {
+        let mut report = ConstEvalReport::default();
+        let missing_shim =
+            |name: &str| ConstEvalError::MirEvalError(MirEvalError::MissingShim(name.to_owned()));
+        report.add("krate::B".to_owned(), &missing_shim("ctpop"));
+        report.add("krate::A".to_owned(), &missing_shim("ctpop"));
+        report.add("krate::C".to_owned(), &missing_shim("fmaf64"));
+        report.add(
+            "krate::D".to_owned(),
+            &ConstEvalError::MirLowerError(MirLowerError::ConstEvalError(
+                "E".into(),
+                Box::new(ConstEvalError::MirLowerError(MirLowerError::UnresolvedField)),
+            )),
+        );
+        report.add("krate::E".to_owned(), &ConstEvalError::MirLowerError(MirLowerError::HasErrors));
+
+        let report = serde_json::to_string_pretty(&report.into_json(10, 5)).unwrap();
+        expect![[r#"
+            {
+              "evaluated": 10,
+              "failed": 5,
+              "errors": [
+                {
+                  "kind": "MirEvalError::MissingShim",
+                  "count": 3,
+                  "items": [
+                    "krate::A",
+                    "krate::B",
+                    "krate::C"
+                  ]
+                },
+                {
+                  "kind": "MirLowerError::HasErrors",
+                  "count": 1,
+                  "items": [
+                    "krate::E"
+                  ]
+                },
+                {
+                  "kind": "MirLowerError::UnresolvedField",
+                  "count": 1,
+                  "items": [
+                    "krate::D"
+                  ]
+                }
+              ],
+              "missingShims": [
+                {
+                  "shim": "ctpop",
+                  "count": 2,
+                  "items": [
+                    "krate::A",
+                    "krate::B"
+                  ]
+                },
+                {
+                  "shim": "fmaf64",
+                  "count": 1,
+                  "items": [
+                    "krate::C"
+                  ]
+                }
+              ]
+            }"#]]
+        .assert_eq(&report);
+    }
+}
@@ -0,0 +1,820 @@
+//! A Debug Adapter Protocol server that steps through a function using the mir interpreter.
+//!
+//! The client launches a function of a project by its path with
+//! `{ "path": "/path/to/project", "function": "krate::module::function" }`, the function is then
+//! interpreted on a single thread, which is paused at breakpoints and steps and shows the locals
+//! of each frame.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    thread,
+};
+
+use crossbeam_channel::{Receiver, TryRecvError};
+use hir::{DebugFrame, Debugger, FileRange, Function, ModuleDef};
+use ide::{Edition, FileId, RootDatabase};
+use ide_db::{
+    line_index,
+    line_index::{LineCol, WideEncoding},
+};
+use load_cargo::{LoadCargoConfig, ProcMacroServerChoice, load_workspace_at};
+use project_model::{CargoConfig, RustLibSource};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use syntax::TextRange;
+use vfs::{AbsPathBuf, Vfs};
+
+use crate::cli::{Result, all_modules, flags, full_name_of_item};
+
+/// The interpreter has a single thread.
+const THREAD_ID: u64 = 1;
+
+/// How many steps are interpreted between two checks for a `pause` request.
+const POLL_INTERVAL: u32 = 1024;
+
+impl flags::Dap {
+    pub fn run(self) -> Result<()> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        thread::Builder::new().name("DapReader".to_owned()).spawn(move || {
+            let mut stdin = io::stdin().lock();
+            while let Ok(Some(message)) = read_message(&mut stdin) {
+                match serde_json::from_value::<Request>(message) {
+                    Ok(request) => {
+                        if sender.send(request).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => tracing::error!("invalid DAP message: {e}"),
+                }
+            }
+        })?;
+        Session::new(receiver, io::stdout()).serve()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    seq: i64,
+    command: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct LaunchArguments {
+    path: PathBuf,
+    function: String,
+}
+
+struct Session<W> {
+    requests: Receiver<Request>,
+    output: W,
+    seq: i64,
+    lines_start_at1: bool,
+    columns_start_at1: bool,
+    /// The ids and zero-based lines of the breakpoints the client set, by file path.
+    breakpoints: FxHashMap<String, Vec<(u64, u32)>>,
+    next_breakpoint_id: u64,
+    /// The zero-based lines the launched function can pause at, by file path, once it is
+    /// launched.
+    step_lines: Option<FxHashMap<String, (FileId, FxHashSet<u32>)>>,
+    stepper: Stepper,
+}
+
+impl<W: Write> Session<W> {
+    fn new(requests: Receiver<Request>, output: W) -> Self {
+        Session {
+            requests,
+            output,
+            seq: 0,
+            lines_start_at1: true,
+            columns_start_at1: true,
+            breakpoints: FxHashMap::default(),
+            next_breakpoint_id: 0,
+            step_lines: None,
+            stepper: Stepper::default(),
+        }
+    }
+
+    /// Answers requests until the client launches a function and finishes the configuration,
+    /// runs it and keeps answering until the client disconnects.
+    fn serve(&mut self) -> Result<()> {
+        let mut launch = None;
+        while let Ok(request) = self.requests.recv() {
+            match &*request.command {
+                "initialize" => {
+                    if let Some(it) = request.arguments["linesStartAt1"].as_bool() {
+                        self.lines_start_at1 = it;
+                    }
+                    if let Some(it) = request.arguments["columnsStartAt1"].as_bool() {
+                        self.columns_start_at1 = it;
+                    }
+                    self.respond(
+                        &request,
+                        Ok(json!({
+                            "supportsConfigurationDoneRequest": true,
+                        })),
+                    )?;
+                    self.event("initialized", json!({}))?;
+                }
+                "launch" => {
+                    match serde_json::from_value::<LaunchArguments>(request.arguments.clone()) {
+                        Ok(arguments) => {
+                            launch = Some(arguments);
+                            self.respond(&request, Ok(json!({})))?;
+                        }
+                        Err(e) => self.respond(&request, Err(format!("invalid arguments: {e}")))?,
+                    }
+                }
+                "configurationDone" => {
+                    let Some(launch) = launch.take() else {
+                        self.respond(&request, Err("no function was launched".to_owned()))?;
+                        continue;
+                    };
+                    self.launch(&request, launch)?;
+                }
+                "disconnect" => {
+                    self.respond(&request, Ok(json!({})))?;
+                    return Ok(());
+                }
+                _ => self.handle_running(&request)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the workspace of `launch` and runs its function, answering the `configurationDone`
+    /// `request` once the workspace is loaded or failed to load.
+    fn launch(&mut self, request: &Request, launch: LaunchArguments) -> Result<()> {
+        let cargo_config = CargoConfig {
+            sysroot: Some(RustLibSource::Discover),
+            all_targets: true,
+            set_test: true,
+            ..Default::default()
+        };
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: true,
+            with_proc_macro_server: ProcMacroServerChoice::Sysroot,
+            prefill_caches: false,
+            num_worker_threads: 1,
+            proc_macro_processes: 1,
+        };
+        let loaded = std::env::current_dir().map_err(Into::into).and_then(|dir| {
+            let path = AbsPathBuf::assert_utf8(dir.join(&launch.path));
+            load_workspace_at(path.as_ref(), &cargo_config, &load_cargo_config, &|_| {})
+        });
+        let (ref db, vfs, _proc_macro) = match loaded {
+            Ok(it) => it,
+            Err(e) => {
+                let message = format!("failed to load `{}`: {e}", launch.path.display());
+                self.respond(request, Err(message.clone()))?;
+                self.event("output", json!({ "category": "stderr", "output": message + "\n" }))?;
+                self.event("terminated", json!({}))?;
+                return Ok(());
+            }
+        };
+        self.respond(request, Ok(json!({})))?;
+
+        let result = hir::attach_db(db, || {
+            let function = all_modules(db)
+                .into_iter()
+                .flat_map(|module| module.declarations(db))
+                .filter_map(|def| match def {
+                    ModuleDef::Function(it) => Some(it),
+                    _ => None,
+                })
+                .find(|it| full_name_of_item(db, it.module(db), it.name(db)) == launch.function);
+            let Some(function) = function else {
+                return Err(format!("function `{}` not found", launch.function));
+            };
+            let mut step_lines = FxHashMap::<_, (_, FxHashSet<_>)>::default();
+            for range in function.debug_step_ranges(db) {
+                let file_id = range.file_id.file_id(db);
+                if let Some((path, line)) = location(db, &vfs, file_id, range.range) {
+                    step_lines
+                        .entry(path)
+                        .or_insert_with(|| (file_id, Default::default()))
+                        .1
+                        .insert(line);
+                }
+            }
+            self.set_step_lines(step_lines).map_err(|e| e.to_string())?;
+            let span_formatter = |file_id, range: TextRange| {
+                let (path, line) = location(db, &vfs, file_id, range)
+                    .unwrap_or_else(|| ("<unknown file>".to_owned(), 0));
+                format!("{path}:{}", line + 1)
+            };
+            let mut debugger =
+                DapDebugger { session: self, db, vfs: &vfs, steps: 0, error: None, function };
+            let result = function.debug(db, &mut debugger, span_formatter);
+            if let Some(e) = debugger.error {
+                return Err(e.to_string());
+            }
+            result.map_err(|e| {
+                let mut message = String::new();
+                let display_target = function.module(db).krate(db).to_display_target(db);
+                _ = e.pretty_print(&mut message, db, span_formatter, display_target);
+                message
+            })
+        });
+
+        let exit_code = match &result {
+            Ok(output) if output.trim() == "pass" => 0,
+            _ => 1,
+        };
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => e,
+        };
+        self.event("output", json!({ "category": "stdout", "output": output }))?;
+        self.event("exited", json!({ "exitCode": exit_code }))?;
+        self.event("terminated", json!({}))?;
+        Ok(())
+    }
+
+    /// Answers the requests that aren't specific to a paused execution.
+    fn handle_running(&mut self, request: &Request) -> io::Result<()> {
+        match &*request.command {
+            "setBreakpoints" => {
+                let path = request.arguments["source"]["path"].as_str().unwrap_or_default();
+                let first_line = u32::from(self.lines_start_at1);
+                let lines = request.arguments["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|it| it["line"].as_u64())
+                    .map(|line| {
+                        self.next_breakpoint_id += 1;
+                        (self.next_breakpoint_id, (line as u32).saturating_sub(first_line))
+                    })
+                    .collect();
+                self.breakpoints.insert(path.to_owned(), lines);
+                let breakpoints = self.resolve_breakpoints(path);
+                self.respond(request, Ok(json!({ "breakpoints": breakpoints })))
+            }
+            "setExceptionBreakpoints" => self.respond(request, Ok(json!({}))),
+            "threads" => self
+                .respond(request, Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }))),
+            "pause" => {
+                self.stepper.pause();
+                self.respond(request, Ok(json!({})))
+            }
+            command => self.respond(request, Err(format!("unsupported request `{command}`"))),
+        }
+    }
+
+    /// Answers requests while paused until the client resumes the execution.
+    fn handle_paused(&mut self, frames: &[StackFrame]) -> io::Result<Resume> {
+        while let Ok(request) = self.requests.recv() {
+            let resume = match &*request.command {
+                "continue" => Some(StepMode::Continue),
+                "next" => Some(StepMode::Over),
+                "stepIn" => Some(StepMode::Into),
+                "stepOut" => Some(StepMode::Out),
+                "disconnect" => {
+                    self.respond(&request, Ok(json!({})))?;
+                    return Ok(Resume::Disconnect);
+                }
+                _ => None,
+            };
+            if let Some(mode) = resume {
+                let body = if mode == StepMode::Continue {
+                    json!({ "allThreadsContinued": true })
+                } else {
+                    json!({})
+                };
+                self.respond(&request, Ok(body))?;
+                return Ok(Resume::Step(mode));
+            }
+            match &*request.command {
+                "stackTrace" => {
+                    let stack_frames: Vec<Value> = frames
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, frame)| {
+                            let mut stack_frame = json!({
+                                "id": idx + 1,
+                                "name": frame.name,
+                                "line": frame.line + u32::from(self.lines_start_at1),
+                                "column": frame.column + u32::from(self.columns_start_at1),
+                            });
+                            if let Some(path) = &frame.path {
+                                stack_frame["source"] = json!({ "path": path });
+                            }
+                            stack_frame
+                        })
+                        .collect();
+                    let total_frames = stack_frames.len();
+                    self.respond(
+                        &request,
+                        Ok(json!({ "stackFrames": stack_frames, "totalFrames": total_frames })),
+                    )?
+                }
+                "scopes" => {
+                    let frame_id = request.arguments["frameId"].as_u64().unwrap_or_default();
+                    self.respond(
+                        &request,
+                        Ok(json!({
+                            "scopes": [{
+                                "name": "Locals",
+                                "presentationHint": "locals",
+                                "variablesReference": frame_id,
+                                "expensive": false,
+                            }]
+                        })),
+                    )?
+                }
+                "variables" => {
+                    let reference =
+                        request.arguments["variablesReference"].as_u64().unwrap_or_default();
+                    let variables: Vec<Value> = (reference as usize)
+                        .checked_sub(1)
+                        .and_then(|idx| frames.get(idx))
+                        .into_iter()
+                        .flat_map(|frame| &frame.locals)
+                        .map(|local| {
+                            json!({
+                                "name": local.name,
+                                "value": local.value.as_deref().unwrap_or("<uninitialized>"),
+                                "type": local.ty,
+                                "variablesReference": 0,
+                            })
+                        })
+                        .collect();
+                    self.respond(&request, Ok(json!({ "variables": variables })))?
+                }
+                _ => self.handle_running(&request)?,
+            }
+        }
+        Ok(Resume::Disconnect)
+    }
+
+    /// Sets the lines the launched function can pause at, and tells the client which of the
+    /// breakpoints it set before can be hit.
+    fn set_step_lines(
+        &mut self,
+        step_lines: FxHashMap<String, (FileId, FxHashSet<u32>)>,
+    ) -> io::Result<()> {
+        self.step_lines = Some(step_lines);
+        let paths: Vec<String> = self.breakpoints.keys().cloned().collect();
+        for path in paths {
+            for breakpoint in self.resolve_breakpoints(&path) {
+                self.event("breakpoint", json!({ "reason": "changed", "breakpoint": breakpoint }))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the breakpoints in `path` that the launched function can hit, returning all of the
+    /// breakpoints in `path` for the client. They are all unverified until the function is
+    /// launched.
+    fn resolve_breakpoints(&mut self, path: &str) -> Vec<Value> {
+        let first_line = u32::from(self.lines_start_at1);
+        let requested = self.breakpoints.get(path).map_or(&[][..], Vec::as_slice);
+        let Some(step_lines) = &self.step_lines else {
+            return requested
+                .iter()
+                .map(
+                    |&(id, line)| json!({ "id": id, "verified": false, "line": line + first_line }),
+                )
+                .collect();
+        };
+        let step_lines = step_lines.get(path);
+        let mut hit_lines = FxHashSet::default();
+        let breakpoints = requested
+            .iter()
+            .map(|&(id, line)| {
+                let verified = step_lines.is_some_and(|(_, lines)| lines.contains(&line));
+                let mut breakpoint =
+                    json!({ "id": id, "verified": verified, "line": line + first_line });
+                if verified {
+                    hit_lines.insert(line);
+                } else {
+                    breakpoint["message"] =
+                        "the launched function doesn't execute this line".into();
+                }
+                breakpoint
+            })
+            .collect();
+        if let Some(&(file_id, _)) = step_lines {
+            self.stepper.set_breakpoints(file_id, hit_lines);
+        }
+        breakpoints
+    }
+
+    fn respond(&mut self, request: &Request, body: Result<Value, String>) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": body.is_ok(),
+        });
+        match body {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = message.into(),
+        }
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = self.seq.into();
+        write_message(&mut self.output, &message)
+    }
+}
+
+/// Implements [`Debugger`] by reporting to the client of a [`Session`].
+struct DapDebugger<'a, W> {
+    session: &'a mut Session<W>,
+    db: &'a RootDatabase,
+    vfs: &'a Vfs,
+    /// Steps since the last check for requests.
+    steps: u32,
+    /// The first error when talking to the client, after which the execution isn't paused.
+    error: Option<io::Error>,
+    function: Function,
+}
+
+impl<W: Write> DapDebugger<'_, W> {
+    fn poll_requests(&mut self) -> io::Result<()> {
+        loop {
+            match self.session.requests.try_recv() {
+                Ok(request) if request.command == "disconnect" => {
+                    self.session.respond(&request, Ok(json!({})))?;
+                    return Err(io::Error::other("the client disconnected"));
+                }
+                Ok(request) => self.session.handle_running(&request)?,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(io::Error::other("the client disconnected"));
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Debugger for DapDebugger<'_, W> {
+    fn should_pause(&mut self, depth: usize, range: Option<FileRange>) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        self.steps += 1;
+        if self.steps == POLL_INTERVAL {
+            self.steps = 0;
+            if let Err(e) = self.poll_requests() {
+                self.error = Some(e);
+                return false;
+            }
+        }
+        let Some(range) = range else { return false };
+        let file_id = range.file_id.file_id(self.db);
+        let Some(line_col) = line_index(self.db, file_id).try_line_col(range.range.start()) else {
+            return false;
+        };
+        self.session.stepper.should_pause(Location { depth, file_id, line: line_col.line })
+    }
+
+    fn paused(&mut self, frames: Vec<DebugFrame>) {
+        let frames: Vec<_> = frames
+            .into_iter()
+            .map(|frame| {
+                let function = frame.function.unwrap_or(self.function);
+                let name =
+                    full_name_of_item(self.db, function.module(self.db), function.name(self.db));
+                let (path, line, column) = match frame.range {
+                    Some(range) => {
+                        let file_id = range.file_id.file_id(self.db);
+                        let line_index = line_index(self.db, file_id);
+                        let line_col = line_index
+                            .try_line_col(range.range.start())
+                            .and_then(|it| line_index.to_wide(WideEncoding::Utf16, it))
+                            .map_or(LineCol { line: 0, col: 0 }, |it| LineCol {
+                                line: it.line,
+                                col: it.col,
+                            });
+                        (file_path(self.vfs, file_id), line_col.line, line_col.col)
+                    }
+                    None => (None, 0, 0),
+                };
+                let locals = frame
+                    .locals
+                    .into_iter()
+                    .map(|local| Variable {
+                        name: local.name.display(self.db, Edition::LATEST).to_string(),
+                        ty: local.ty,
+                        value: local.value,
+                    })
+                    .collect();
+                StackFrame { name, path, line, column, locals }
+            })
+            .collect();
+        let reason = self.session.stepper.stop_reason();
+        let resume = self
+            .session
+            .event(
+                "stopped",
+                json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+            )
+            .and_then(|()| self.session.handle_paused(&frames));
+        match resume {
+            Ok(Resume::Step(mode)) => self.session.stepper.resume(mode),
+            Ok(Resume::Disconnect) => {
+                self.error = Some(io::Error::other("the client disconnected"))
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+struct StackFrame {
+    name: String,
+    path: Option<String>,
+    /// Zero-based.
+    line: u32,
+    /// Zero-based, in UTF-16 code units.
+    column: u32,
+    locals: Vec<Variable>,
+}
+
+struct Variable {
+    name: String,
+    ty: String,
+    value: Option<String>,
+}
+
+enum Resume {
+    Step(StepMode),
+    Disconnect,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum StepMode {
+    #[default]
+    Continue,
+    Over,
+    Into,
+    Out,
+}
+
+/// A step of the interpreter, as far as the client can tell steps apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Location {
+    depth: usize,
+    file_id: FileId,
+    /// Zero-based.
+    line: u32,
+}
+
+/// Decides where to pause the execution from the breakpoints and the last command of the
+/// client.
+#[derive(Debug, Default)]
+struct Stepper {
+    /// Zero-based lines with a breakpoint, by file.
+    breakpoints: FxHashMap<FileId, FxHashSet<u32>>,
+    mode: StepMode,
+    pause_requested: bool,
+    /// Where the execution was last paused.
+    paused_at: Option<Location>,
+    /// The previous step, to pause at a breakpoint only once while staying on its line.
+    previous: Option<Location>,
+    /// Why the execution is about to be paused.
+    reason: &'static str,
+}
+
+impl Stepper {
+    fn set_breakpoints(&mut self, file_id: FileId, lines: FxHashSet<u32>) {
+        self.breakpoints.insert(file_id, lines);
+    }
+
+    fn pause(&mut self) {
+        self.pause_requested = true;
+    }
+
+    fn should_pause(&mut self, location: Location) -> bool {
+        let reason = self.reason_to_pause(&location);
+        self.previous = Some(location.clone());
+        let Some(reason) = reason else { return false };
+        self.reason = reason;
+        self.paused_at = Some(location);
+        self.pause_requested = false;
+        true
+    }
+
+    fn reason_to_pause(&self, location: &Location) -> Option<&'static str> {
+        if self.pause_requested {
+            return Some("pause");
+        }
+        if self.previous.as_ref() != Some(location)
+            && self.breakpoints.get(&location.file_id).is_some_and(|it| it.contains(&location.line))
+        {
+            return Some("breakpoint");
+        }
+        let paused_at = self.paused_at.as_ref()?;
+        let same_line = paused_at.file_id == location.file_id && paused_at.line == location.line;
+        let step = match self.mode {
+            StepMode::Continue => false,
+            StepMode::Over => {
+                location.depth < paused_at.depth
+                    || (location.depth == paused_at.depth && !same_line)
+            }
+            StepMode::Into => location.depth != paused_at.depth || !same_line,
+            StepMode::Out => location.depth < paused_at.depth,
+        };
+        step.then_some("step")
+    }
+
+    fn stop_reason(&self) -> &'static str {
+        self.reason
+    }
+
+    fn resume(&mut self, mode: StepMode) {
+        self.mode = mode;
+    }
+}
+
+fn file_path(vfs: &Vfs, file_id: FileId) -> Option<String> {
+    vfs.file_path(file_id).as_path().map(|it| it.to_string())
+}
+
+/// The path and zero-based line of the start of `range`.
+fn location(
+    db: &RootDatabase,
+    vfs: &Vfs,
+    file_id: FileId,
+    range: TextRange,
+) -> Option<(String, u32)> {
+    let line = line_index(db, file_id).try_line_col(range.start())?.line;
+    Some((file_path(vfs, file_id)?, line))
+}
+
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut size = None;
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        if input.read_line(&mut buf)? == 0 {
+            return Ok(None);
+        }
+        let header = buf.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(len) = header.strip_prefix("Content-Length: ") {
+            size = Some(len.parse::<usize>().map_err(io::Error::other)?);
+        }
+    }
+    let size = size.ok_or_else(|| io::Error::other("no Content-Length"))?;
+    let mut content = vec![0; size];
+    input.read_exact(&mut content)?;
+    serde_json::from_slice(&content).map(Some).map_err(io::Error::other)
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(depth: usize, line: u32) -> Location {
+        Location { depth, file_id: FileId::from_raw(0), line }
+    }
+
+    fn pauses(stepper: &mut Stepper, steps: &[(usize, u32)]) -> Vec<(usize, u32, &'static str)> {
+        let mut pauses = Vec::new();
+        for &(depth, line) in steps {
+            if stepper.should_pause(at(depth, line)) {
+                pauses.push((depth, line, stepper.stop_reason()));
+            }
+        }
+        pauses
+    }
+
+    #[test]
+    fn breakpoints_pause_once_per_visit_of_their_line() {
+        let mut stepper = Stepper::default();
+        stepper.set_breakpoints(FileId::from_raw(0), FxHashSet::from_iter([2]));
+        assert_eq!(
+            pauses(&mut stepper, &[(1, 1), (1, 2), (1, 2), (1, 3), (1, 2)]),
+            [(1, 2, "breakpoint"), (1, 2, "breakpoint")]
+        );
+    }
+
+    #[test]
+    fn steps_over_into_and_out_of_calls() {
+        let mut stepper = Stepper::default();
+        stepper.set_breakpoints(FileId::from_raw(0), FxHashSet::from_iter([1]));
+        assert!(stepper.should_pause(at(1, 1)));
+
+        stepper.resume(StepMode::Over);
+        assert_eq!(pauses(&mut stepper, &[(1, 1), (2, 10), (2, 11), (1, 2)]), [(1, 2, "step")]);
+
+        stepper.resume(StepMode::Into);
+        assert_eq!(pauses(&mut stepper, &[(1, 2), (2, 10)]), [(2, 10, "step")]);
+
+        stepper.resume(StepMode::Out);
+        assert_eq!(pauses(&mut stepper, &[(2, 11), (3, 20), (1, 2)]), [(1, 2, "step")]);
+
+        stepper.resume(StepMode::Continue);
+        stepper.pause();
+        assert_eq!(pauses(&mut stepper, &[(1, 3), (1, 4)]), [(1, 3, "pause")]);
+    }
+
+    #[test]
+    fn reports_workspaces_that_fail_to_load() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let requests = [
+            ("initialize", json!({})),
+            ("launch", json!({ "path": "/does/not/exist", "function": "krate::main" })),
+            ("configurationDone", json!({})),
+            ("disconnect", json!({})),
+        ];
+        for (seq, (command, arguments)) in requests.into_iter().enumerate() {
+            let request = Request { seq: seq as i64 + 1, command: command.to_owned(), arguments };
+            sender.send(request).unwrap();
+        }
+        let mut session = Session::new(receiver, Vec::new());
+        session.serve().unwrap();
+
+        let mut output = io::Cursor::new(session.output);
+        let messages: Vec<String> = std::iter::from_fn(|| read_message(&mut output).unwrap())
+            .map(|message| match message["type"].as_str().unwrap() {
+                "event" => format!("event {}", message["event"].as_str().unwrap()),
+                _ => format!(
+                    "response {} {}",
+                    message["command"].as_str().unwrap(),
+                    message["success"]
+                ),
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "response initialize true",
+                "event initialized",
+                "response launch true",
+                "response configurationDone false",
+                "event output",
+                "event terminated",
+                "response disconnect true",
+            ]
+        );
+    }
+
+    #[test]
+    fn verifies_breakpoints_once_launched() {
+        let (_sender, receiver) = crossbeam_channel::unbounded();
+        let mut session = Session::new(receiver, Vec::new());
+        let set_breakpoints = |seq, lines: &[u32]| Request {
+            seq,
+            command: "setBreakpoints".to_owned(),
+            arguments: json!({
+                "source": { "path": "/src/main.rs" },
+                "breakpoints": lines.iter().map(|line| json!({ "line": line })).collect::<Vec<_>>(),
+            }),
+        };
+        session.handle_running(&set_breakpoints(1, &[2, 3])).unwrap();
+        let step_lines = FxHashSet::from_iter([0, 1, 4]);
+        let step_lines =
+            FxHashMap::from_iter([("/src/main.rs".to_owned(), (FileId::from_raw(0), step_lines))]);
+        session.set_step_lines(step_lines).unwrap();
+        session.handle_running(&set_breakpoints(2, &[4, 5])).unwrap();
+
+        let mut output = io::Cursor::new(session.output);
+        let breakpoints: Vec<String> = std::iter::from_fn(|| read_message(&mut output).unwrap())
+            .flat_map(|message| match message["type"].as_str().unwrap() {
+                "event" => vec![message["body"]["breakpoint"].clone()],
+                _ => message["body"]["breakpoints"].as_array().unwrap().clone(),
+            })
+            .map(|it| format!("{} {} {}", it["id"], it["line"], it["verified"]))
+            .collect();
+        assert_eq!(
+            breakpoints,
+            ["1 2 false", "2 3 false", "1 2 true", "2 3 false", "3 4 false", "4 5 true"]
+        );
+        assert_eq!(session.stepper.breakpoints[&FileId::from_raw(0)], FxHashSet::from_iter([4]));
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let message = json!({ "seq": 1, "type": "request", "command": "threads" });
+        let mut buf = Vec::new();
+        write_message(&mut buf, &message).unwrap();
+        let content = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            String::from_utf8(buf.clone()).unwrap(),
+            format!("Content-Length: {}\r\n\r\n{content}", content.len())
+        );
+        let read = read_message(&mut io::Cursor::new(buf)).unwrap();
+        assert_eq!(read, Some(message));
+    }
+}
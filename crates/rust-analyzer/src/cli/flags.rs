@@ -92,6 +92,9 @@ xflags::xflags! {
             optional --skip-data-layout
             /// Skip const evaluation
             optional --skip-const-eval
+            /// Print the consts and statics that failed evaluation as JSON, grouped by the kind of
+            /// error and by the intrinsic or extern function missing a shim.
+            optional --const-eval-report
             /// Runs several IDE features after analysis, including semantics highlighting, diagnostics
             /// and annotations. This is useful for benchmarking the memory usage on a project that has
             /// been worked on for a bit in a longer running session.
@@ -109,6 +112,10 @@ xflags::xflags! {
             required path: PathBuf
         }
 
+        /// Run a Debug Adapter Protocol server on stdio that steps through functions of the
+        /// project using mir interpreter
+        cmd dap {}
+
         /// Run unit tests of the project using mir interpreter
         cmd rustc-tests {
             /// Directory with Cargo.toml.
@@ -164,6 +171,12 @@ xflags::xflags! {
         cmd ssr {
             /// A structured search replace rule (`$a.foo($b) ==>> bar($a, $b)`)
             repeated rule: SsrRule
+            /// Read rules from a file. Rules are separated by blank lines or start with a new line
+            /// containing `==>>`, and lines starting with `//` are comments.
+            repeated -f, --rules-file path: PathBuf
+            /// Print the edits as a unified `diff` or as `json` (with 0-based lines and UTF-8 columns)
+            /// instead of writing them to disk.
+            optional --output format: SsrOutputFormat
         }
 
         cmd search {
@@ -219,6 +232,7 @@ pub enum RustAnalyzerCmd {
     Highlight(Highlight),
     AnalysisStats(AnalysisStats),
     RunTests(RunTests),
+    Dap(Dap),
     RustcTests(RustcTests),
     Diagnostics(Diagnostics),
     UnresolvedReferences(UnresolvedReferences),
@@ -269,6 +283,7 @@ pub struct AnalysisStats {
     pub skip_mir_stats: bool,
     pub skip_data_layout: bool,
     pub skip_const_eval: bool,
+    pub const_eval_report: bool,
     pub run_all_ide_things: bool,
     pub run_term_search: bool,
     pub validate_term_search: bool,
@@ -279,6 +294,9 @@ pub struct RunTests {
     pub path: PathBuf,
 }
 
+#[derive(Debug)]
+pub struct Dap;
+
 #[derive(Debug)]
 pub struct RustcTests {
     pub rustc_repo: PathBuf,
@@ -318,6 +336,9 @@ pub struct PrimeCaches {
 #[derive(Debug)]
 pub struct Ssr {
     pub rule: Vec<SsrRule>,
+
+    pub rules_file: Vec<PathBuf>,
+    pub output: Option<SsrOutputFormat>,
 }
 
 #[derive(Debug)]
@@ -398,6 +419,24 @@ pub enum Severity {
     Error,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsrOutputFormat {
+    Diff,
+    Json,
+}
+
+impl FromStr for SsrOutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "diff" => Ok(Self::Diff),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown output format `{s}`")),
+        }
+    }
+}
+
 impl FromStr for Severity {
     type Err = String;
 
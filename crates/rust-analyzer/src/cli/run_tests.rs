@@ -1,7 +1,5 @@
 //! Run all tests in a project, similar to `cargo test`, but using the mir interpreter.
 
-use hir::{Crate, Module};
-use hir_ty::db::HirDatabase;
 use ide_db::{base_db::SourceDatabase, line_index};
 use profile::StopWatch;
 use project_model::{CargoConfig, RustLibSource};
@@ -9,7 +7,7 @@ use syntax::TextRange;
 
 use load_cargo::{LoadCargoConfig, ProcMacroServerChoice, load_workspace_at};
 
-use crate::cli::{Result, flags, full_name_of_item};
+use crate::cli::{Result, all_modules, flags, full_name_of_item};
 
 impl flags::RunTests {
     pub fn run(self) -> Result<()> {
@@ -75,19 +73,3 @@ impl flags::RunTests {
         Ok(())
     }
 }
-
-fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
-    let mut worklist: Vec<_> = Crate::all(db)
-        .into_iter()
-        .filter(|x| x.origin(db).is_local())
-        .map(|krate| krate.root_module(db))
-        .collect();
-    let mut modules = Vec::new();
-
-    while let Some(module) = worklist.pop() {
-        modules.push(module);
-        worklist.extend(module.children(db));
-    }
-
-    modules
-}
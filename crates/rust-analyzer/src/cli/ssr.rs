@@ -1,15 +1,36 @@
 //! Applies structured search replace rules from the command line.
 
 use anyhow::Context;
-use ide_db::{EditionedFileId, base_db::SourceDatabase};
-use ide_ssr::MatchFinder;
+use dissimilar::Chunk;
+use ide_db::{
+    EditionedFileId,
+    base_db::SourceDatabase,
+    line_index::{LineCol, LineIndex},
+    text_edit::TextEdit,
+};
+use ide_ssr::{MatchFinder, SsrError, SsrRule};
+use itertools::Itertools;
 use load_cargo::{LoadCargoConfig, ProcMacroServerChoice, load_workspace_at};
 use project_model::{CargoConfig, RustLibSource};
+use rustc_hash::FxHashMap;
+use stdx::format_to;
 
 use crate::cli::flags;
 
 impl flags::Ssr {
     pub fn run(self) -> anyhow::Result<()> {
+        let mut rules = self.rule;
+        for path in &self.rules_file {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let file_rules = parse_rules(&text)
+                .map_err(|(line, err)| anyhow::format_err!("{}:{line}: {err}", path.display()))?;
+            rules.extend(file_rules);
+        }
+        if rules.is_empty() {
+            anyhow::bail!("no rules given, pass them as arguments or with `--rules-file`");
+        }
+
         let cargo_config = CargoConfig {
             sysroot: Some(RustLibSource::Discover),
             all_targets: true,
@@ -29,23 +50,188 @@ impl flags::Ssr {
             &load_cargo_config,
             &|_| {},
         )?;
-        let mut match_finder = MatchFinder::at_first_file(db)?;
-        for rule in self.rule {
-            match_finder.add_rule(rule)?;
-        }
-        let edits = match_finder.edits();
-        for (file_id, edit) in edits {
-            if let Some(path) = vfs.file_path(file_id).as_path() {
-                let mut contents = db.file_text(file_id).text(db).to_string();
-                edit.apply(&mut contents);
-                std::fs::write(path, contents)
-                    .with_context(|| format!("failed to write {path}"))?;
+        // Compute all the edits up front, so that nothing is written if any rule fails.
+        let mut edits: Vec<_> = hir::attach_db(db, || -> anyhow::Result<_> {
+            let mut match_finder = MatchFinder::at_first_file(db)?;
+            for rule in rules {
+                match_finder.add_rule(rule)?;
+            }
+            Ok(match_finder
+                .edits()
+                .into_iter()
+                .filter_map(|(file_id, edit)| {
+                    let path = vfs.file_path(file_id).as_path()?.to_owned();
+                    Some((path, db.file_text(file_id).text(db), edit))
+                })
+                .collect())
+        })?;
+        edits.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+        match self.output {
+            None => {
+                for (path, text, edit) in edits {
+                    let mut contents = text.to_string();
+                    edit.apply(&mut contents);
+                    std::fs::write(&path, contents)
+                        .with_context(|| format!("failed to write {path}"))?;
+                }
+            }
+            Some(flags::SsrOutputFormat::Diff) => {
+                let root = std::env::current_dir()?;
+                for (path, text, edit) in edits {
+                    let path: &std::path::Path = path.as_ref();
+                    let path = path.strip_prefix(&root).unwrap_or(path);
+                    print!("{}", unified_diff(&path.display().to_string(), text, &edit));
+                }
+            }
+            Some(flags::SsrOutputFormat::Json) => {
+                let files = edits
+                    .into_iter()
+                    .map(|(path, text, edit)| {
+                        let line_index = LineIndex::new(text);
+                        let line_col = |offset| {
+                            let LineCol { line, col } = line_index.line_col(offset);
+                            serde_json::json!({ "line": line, "column": col })
+                        };
+                        let edits = edit
+                            .iter()
+                            .map(|indel| {
+                                serde_json::json!({
+                                    "start": line_col(indel.delete.start()),
+                                    "end": line_col(indel.delete.end()),
+                                    "deleted": &text[indel.delete],
+                                    "inserted": indel.insert,
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        serde_json::json!({ "path": path.to_string(), "edits": edits })
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", serde_json::to_string_pretty(&files)?);
             }
         }
         Ok(())
     }
 }
 
+/// Parses the rules of a rules file, in order. On error, returns the 1-based line the failing
+/// rule starts on.
+fn parse_rules(text: &str) -> Result<Vec<SsrRule>, (usize, SsrError)> {
+    let mut chunks: Vec<(usize, String)> = Vec::new();
+    let mut in_rule = false;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("//") {
+            continue;
+        }
+        if line.is_empty() {
+            in_rule = false;
+            continue;
+        }
+        match chunks.last_mut() {
+            Some((_, rule)) if in_rule && !(line.contains("==>>") && rule.contains("==>>")) => {
+                rule.push('\n');
+                rule.push_str(line);
+            }
+            _ => chunks.push((idx + 1, line.to_owned())),
+        }
+        in_rule = true;
+    }
+    chunks.into_iter().map(|(line, rule)| rule.parse().map_err(|err| (line, err))).collect()
+}
+
+/// Renders `edit` applied to `old` as a unified diff with three lines of context.
+fn unified_diff(path: &str, old: &str, edit: &TextEdit) -> String {
+    const CONTEXT: usize = 3;
+
+    let mut new = old.to_owned();
+    edit.apply(&mut new);
+    let lines = diff_lines(old, &new);
+
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    let (mut pos, mut old_line, mut new_line) = (0, 0, 0);
+    let mut changed = lines.iter().positions(|&(kind, _)| kind != ' ').peekable();
+    while let Some(first) = changed.next() {
+        // Changes whose context overlaps go into a single hunk.
+        let mut last = first;
+        while let Some(next) = changed.next_if(|&next| next - last <= 2 * CONTEXT + 1) {
+            last = next;
+        }
+        let start = first.saturating_sub(CONTEXT);
+        let end = (last + 1 + CONTEXT).min(lines.len());
+        for (kind, _) in &lines[pos..start] {
+            old_line += usize::from(*kind != '+');
+            new_line += usize::from(*kind != '-');
+        }
+
+        let hunk = &lines[start..end];
+        let old_len = hunk.iter().filter(|(kind, _)| *kind != '+').count();
+        let new_len = hunk.iter().filter(|(kind, _)| *kind != '-').count();
+        // Like `diff -u`, an empty range starts at the line before it.
+        let range_start = |line: usize, len: usize| if len == 0 { line } else { line + 1 };
+        format_to!(
+            out,
+            "@@ -{},{old_len} +{},{new_len} @@\n",
+            range_start(old_line, old_len),
+            range_start(new_line, new_len),
+        );
+        for (kind, line) in hunk {
+            out.push(*kind);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        old_line += old_len;
+        new_line += new_len;
+        pos = end;
+    }
+    out
+}
+
+/// Diffs `old` and `new` line by line, marking every line as kept (' '), removed ('-') or added
+/// ('+').
+///
+/// Each distinct line is interned as a single `char`, so that `dissimilar` diffs whole lines.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let mut interned = FxHashMap::default();
+    let mut intern = |text: &'a str| -> (Vec<&'a str>, String) {
+        let lines: Vec<_> = text.split_inclusive('\n').collect();
+        let encoded = lines
+            .iter()
+            .map(|&line| {
+                let next = interned.len() as u32;
+                *interned.entry(line).or_insert_with(|| {
+                    // Skip the surrogates, which aren't valid `char`s.
+                    char::from_u32(if next < 0xD800 { next } else { next + 0x800 }).unwrap()
+                })
+            })
+            .collect();
+        (lines, encoded)
+    };
+    let (old_lines, old_encoded) = intern(old);
+    let (new_lines, new_encoded) = intern(new);
+
+    let mut out = Vec::with_capacity(old_lines.len().max(new_lines.len()));
+    let (mut old_lines, mut new_lines) = (old_lines.into_iter(), new_lines.into_iter());
+    for chunk in dissimilar::diff(&old_encoded, &new_encoded) {
+        match chunk {
+            Chunk::Equal(text) => {
+                let count = text.chars().count();
+                out.extend(old_lines.by_ref().take(count).map(|line| (' ', line)));
+                new_lines.by_ref().take(count).for_each(drop);
+            }
+            Chunk::Delete(text) => {
+                out.extend(old_lines.by_ref().take(text.chars().count()).map(|line| ('-', line)))
+            }
+            Chunk::Insert(text) => {
+                out.extend(new_lines.by_ref().take(text.chars().count()).map(|line| ('+', line)))
+            }
+        }
+    }
+    out
+}
+
 impl flags::Search {
     /// Searches for `patterns`, printing debug information for any nodes whose text exactly matches
     /// `debug_snippet`. This is intended for debugging and probably isn't useful in its current
@@ -93,3 +279,95 @@ impl flags::Search {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use ide_db::text_edit::TextEdit;
+    use syntax::{TextRange, TextSize};
+
+    use super::*;
+
+    #[test]
+    fn parse_rules_file() {
+        let rules = parse_rules(
+            r#"
+// Comments and blank lines are ignored.
+foo($a) ==>> bar($a)
+
+baz($a, $b)
+    ==>> qux($a, $b)
+a::b() ==>> c::d()
+"#,
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 3);
+
+        let (line, err) =
+            parse_rules("foo() ==>> bar()\n\n// Bad rule.\nfoo($a) ==>> bar($b)\n").unwrap_err();
+        assert_eq!(line, 4);
+        assert!(err.to_string().contains("$b"), "{err}");
+    }
+
+    #[test]
+    fn unified_diff_hunks() {
+        let old = ('a'..='l').map(|c| format!("line {c}\n")).collect::<String>();
+        let line_start = |line: u32| TextSize::from((line - 1) * 7);
+        let mut builder = TextEdit::builder();
+        builder
+            .replace(TextRange::at(line_start(2) + TextSize::from(5), 1.into()), "two".to_owned());
+        builder.insert(line_start(3) + TextSize::from(6), "\nline c2".to_owned());
+        builder.delete(TextRange::new(line_start(11), line_start(12)));
+        let diff = unified_diff("src/lib.rs", &old, &builder.finish());
+        expect![[r#"
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -1,6 +1,7 @@
+             line a
+            -line b
+            -line c
+            +line two
+            +line c
+            +line c2
+             line d
+             line e
+             line f
+            @@ -8,5 +9,4 @@
+             line h
+             line i
+             line j
+            -line k
+             line l
+        "#]]
+        .assert_eq(&diff);
+    }
+
+    #[test]
+    fn unified_diff_keeps_unchanged_lines_of_a_change() {
+        // A single edit spanning several lines, which keeps some of them.
+        let old = "fn f() {\n    a();\n    b();\n    c();\n    d();\n    e();\n}";
+        let start = TextSize::from(9);
+        let edit = TextEdit::replace(
+            TextRange::new(start, TextSize::of(old)),
+            "    x();\n    b();\n    c();\n    d();\n    y();\n}\n".to_owned(),
+        );
+        let diff = unified_diff("src/lib.rs", old, &edit);
+        expect![[r#"
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -1,7 +1,7 @@
+             fn f() {
+            -    a();
+            +    x();
+                 b();
+                 c();
+                 d();
+            -    e();
+            -}
+            \ No newline at end of file
+            +    y();
+            +}
+        "#]]
+        .assert_eq(&diff);
+    }
+}
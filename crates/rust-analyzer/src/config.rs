@@ -3,7 +3,7 @@
 //! Of particular interest is the `feature_flags` hash map: while other fields
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
-use std::{env, fmt, iter, ops::Not, sync::OnceLock};
+use std::{env, fmt, iter, ops::Not, sync::OnceLock, time::Duration};
 
 use cfg::{CfgAtom, CfgDiff};
 use hir::Symbol;
@@ -11,9 +11,9 @@ use ide::{
     AnnotationConfig, AssistConfig, CallHierarchyConfig, CallableSnippets, CompletionConfig,
     CompletionFieldsToResolve, DiagnosticsConfig, GenericParameterHints, GotoDefinitionConfig,
     GotoImplementationConfig, HighlightConfig, HighlightRelatedConfig, HoverConfig, HoverDocFormat,
-    InlayFieldsToResolve, InlayHintsConfig, JoinLinesConfig, MemoryLayoutHoverConfig,
-    MemoryLayoutHoverRenderKind, RaFixtureConfig, RenameConfig, Snippet, SnippetScope,
-    SourceRootId,
+    InlayFieldsToResolve, InlayHintsConfig, JoinLinesConfig, LayoutBudget, MemoryLayoutHoverConfig,
+    MemoryLayoutHoverRenderKind, NormalizedTypeHoverConfig, RaFixtureConfig, RenameConfig, Snippet,
+    SnippetScope, SourceRootId,
 };
 use ide_db::{
     MiniCore, SnippetCap,
@@ -22,6 +22,7 @@ use ide_db::{
 };
 use itertools::{Either, Itertools};
 use paths::{Utf8Path, Utf8PathBuf};
+use proc_macro_api::ExpansionLimits;
 use project_model::{
     CargoConfig, CargoFeatures, ProjectJson, ProjectJsonData, ProjectJsonFromCommand,
     ProjectManifest, RustLibSource, TargetDirectoryConfig,
@@ -41,6 +42,7 @@ use crate::{
     flycheck::{CargoOptions, FlycheckConfig},
     lsp::capabilities::ClientCapabilities,
     lsp_ext::{WorkspaceSymbolSearchKind, WorkspaceSymbolSearchScope},
+    test_runner::TestRunner,
 };
 
 type FxIndexMap<K, V> = indexmap::IndexMap<K, V, rustc_hash::FxBuildHasher>;
@@ -92,6 +94,10 @@ config_data! {
         completion_snippets_custom: FxIndexMap<String, SnippetDef> =
             Config::completion_snippets_default(),
 
+        /// Analyze opened Rust files that don't belong to any crate as standalone crates, backed by
+        /// the sysroot of the workspace containing them, without reloading the workspace.
+        files_attachUnlinked: bool = false,
+
         /// List of files to ignore
         ///
         /// These paths (file/directories) will be ignored by rust-analyzer. They are relative to
@@ -193,6 +199,15 @@ config_data! {
         hover_memoryLayout_size: Option<MemoryLayoutHoverRenderKindDef> =
             Some(MemoryLayoutHoverRenderKindDef::Both),
 
+        /// How many levels of type aliases to expand in the type as written when showing
+        /// normalized types on hover.
+        hover_normalizedType_aliasExpansionDepth: usize = 1,
+
+        /// Show the type of type aliases, fields, constants, statics, locals and expressions and
+        /// the signature of functions with type aliases and associated types normalized on hover,
+        /// if it differs from the type as written.
+        hover_normalizedType_enable: bool = false,
+
         /// How many variants of an enum to display when hovering on. Show none if empty.
         hover_show_enumVariants: Option<usize> = Some(5),
 
@@ -226,6 +241,10 @@ config_data! {
         /// Closure notation in type and chaining inlay hints.
         inlayHints_closureStyle: ClosureStyle = ClosureStyle::ImplFn,
 
+        /// Show the evaluated values of constants, statics, associated constants at their use
+        /// sites, array lengths and enum discriminant expressions.
+        inlayHints_constValueHints_enable: bool = false,
+
         /// Show enum variant discriminant hints.
         inlayHints_discriminantHints_enable: DiscriminantHintsDef =
             DiscriminantHintsDef::Never,
@@ -273,6 +292,12 @@ config_data! {
         /// Prefer using parameter names as the name for elided lifetime hints if possible.
         inlayHints_lifetimeElisionHints_useParameterNames: bool = false,
 
+        /// Show the lifetime parameter of the callee's signature each reference argument is passed
+        /// to at call sites of functions that return borrows, and the higher-ranked lifetimes
+        /// of closures passed to `Fn(&T)`-like bounds. Elided lifetimes are named like the elision
+        /// hints would name them.
+        inlayHints_lifetimeParameterHints_enable: bool = false,
+
         /// Maximum length for inlay hints. Set to null to have an unlimited length.
         ///
         /// **Note:** This is mostly a hint, and we don't guarantee to strictly follow the limit.
@@ -391,6 +416,30 @@ config_data! {
         /// Enable support for procedural macros, implies `#rust-analyzer.cargo.buildScripts.enable#`.
         procMacro_enable: bool = true,
 
+        /// Directory to keep the results of proc-macro expansions in. Defaults to the
+        /// `rust-analyzer/proc-macros` directory in the user's cache directory.
+        procMacro_expansionCache_directory: Option<Utf8PathBuf> = None,
+
+        /// Keep the results of proc-macro expansions on disk, in the directory set by
+        /// `#rust-analyzer.procMacro.expansionCache.directory#`, so that they survive a restart
+        /// of the proc-macro server. They are keyed by the proc-macro library, its input and its
+        /// environment, and the least recently used ones are deleted once they take up 512 MiB.
+        ///
+        /// Macros that read files without tracking them through `proc_macro::tracked_path` keep
+        /// expanding to their stored results after those files change, so only enable this if no
+        /// proc-macro in the workspace does.
+        procMacro_expansionCache_enable: bool = false,
+
+        /// Maximum resident memory, in MiB, a proc-macro server process may use while expanding a
+        /// macro. When exceeded, the server is restarted and the expansion reports an error naming
+        /// the macro. Only supported on Linux. `null` or `0` disables the limit.
+        procMacro_expansionMemoryLimit: Option<usize> = None,
+
+        /// Maximum time, in seconds, a single proc-macro expansion may take. When exceeded, the
+        /// proc-macro server is restarted and the expansion reports an error naming the macro.
+        /// `null` or `0` disables the limit.
+        procMacro_expansionTimeLimit: Option<usize> = Some(60),
+
         /// Number of proc-macro server processes to spawn.
         ///
         /// Controls how many independent `proc-macro-srv` processes rust-analyzer
@@ -722,6 +771,16 @@ config_data! {
         /// usual.
         diagnostics_experimental_enable: bool = false,
 
+        /// Name of the attribute declaring the largest size and padding, in bytes, of the type it
+        /// is on, like `layout_budget` for `#[layout_budget(size = 16, padding = 0)]`. Types
+        /// exceeding it are reported by the `layout-budget-exceeded` diagnostic.
+        diagnostics_layoutBudget_attribute: Option<String> = None,
+
+        /// Map of type paths starting with their crate, like `my_crate::net::Packet`, to the largest
+        /// size and padding, in bytes, they may have, like `{ "size": 16, "padding": 0 }`. Types
+        /// exceeding it are reported by the `layout-budget-exceeded` diagnostic.
+        diagnostics_layoutBudget_types: FxHashMap<String, LayoutBudgetDef> = FxHashMap::default(),
+
         /// Map of prefixes to be substituted when parsing diagnostic file paths. This should be the
         /// reverse mapping of what is passed to `rustc` as `--remap-path-prefix`.
         diagnostics_remapPrefix: FxHashMap<String, String> = FxHashMap::default(),
@@ -1025,6 +1084,11 @@ config_data! {
         /// - `${executable_args}`: all of the above binary args bundled together
         ///   (includes `rust-analyzer.runnables.extraTestBinaryArgs`).
         runnables_test_overrideCommand: Option<Vec<String>> = None,
+        /// Test runner used by the test explorer to run tests and report their results.
+        ///
+        /// With `nextest`, doc tests still run through `cargo test`, and only the
+        /// `rust-analyzer.runnables.extraTestBinaryArgs` nextest understands are passed on.
+        runnables_test_runner: TestRunnerDef = TestRunnerDef::Libtest,
 
         /// Path to the Cargo.toml of the rust compiler workspace, for usage in rustc_private
         /// projects, or "discover" to try to automatically find it if the `rustc-dev` component
@@ -1994,6 +2058,14 @@ impl Config {
             style_lints: self.diagnostics_styleLints_enable(source_root).to_owned(),
             term_search_fuel: self.assist_termSearch_fuel(source_root).to_owned() as u64,
             show_rename_conflicts: *self.rename_showConflicts(source_root),
+            layout_budget_attribute: self.diagnostics_layoutBudget_attribute(source_root).clone(),
+            layout_budgets: self
+                .diagnostics_layoutBudget_types(source_root)
+                .iter()
+                .map(|(path, budget)| {
+                    (path.clone(), LayoutBudget { size: budget.size, padding: budget.padding })
+                })
+                .collect(),
         }
     }
 
@@ -2050,6 +2122,11 @@ impl Config {
                 padding: self.hover_memoryLayout_padding().map(mem_kind),
                 niches: self.hover_memoryLayout_niches().unwrap_or_default(),
             }),
+            normalized_type: self.hover_normalizedType_enable().then_some(
+                NormalizedTypeHoverConfig {
+                    alias_expansion_depth: *self.hover_normalizedType_aliasExpansionDepth(),
+                },
+            ),
             documentation: self.hover_documentation_enable().to_owned(),
             format: {
                 if self.caps.hover_markdown_support() {
@@ -2112,6 +2189,7 @@ impl Config {
                 LifetimeElisionDef::Never => ide::LifetimeElisionHints::Never,
                 LifetimeElisionDef::SkipTrivial => ide::LifetimeElisionHints::SkipTrivial,
             },
+            lifetime_parameter_hints: self.inlayHints_lifetimeParameterHints_enable().to_owned(),
             hide_named_constructor_hints: self
                 .inlayHints_typeHints_hideNamedConstructor()
                 .to_owned(),
@@ -2167,6 +2245,7 @@ impl Config {
             implicit_drop_hints: self.inlayHints_implicitDrops_enable().to_owned(),
             implied_dyn_trait_hints: self.inlayHints_impliedDynTraitHints_enable().to_owned(),
             range_exclusive_hints: self.inlayHints_rangeExclusiveHints_enable().to_owned(),
+            const_value_hints: self.inlayHints_constValueHints_enable().to_owned(),
             ra_fixture: self.ra_fixture(minicore),
         }
     }
@@ -2303,10 +2382,29 @@ impl Config {
             .collect()
     }
 
+    pub fn attach_unlinked_files(&self) -> bool {
+        self.files_attachUnlinked().to_owned()
+    }
+
     pub fn prefill_caches(&self) -> bool {
         self.cachePriming_enable().to_owned()
     }
 
+    /// The directory the proc-macro servers keep their expansion results in, if enabled.
+    pub fn proc_macro_expansion_cache_dir(&self) -> Option<AbsPathBuf> {
+        if !self.procMacro_expansionCache_enable() {
+            return None;
+        }
+        match self.procMacro_expansionCache_directory().clone() {
+            Some(path) => {
+                Some(AbsPathBuf::try_from(path).unwrap_or_else(|path| self.root_path.join(path)))
+            }
+            None => Some(AbsPathBuf::assert_utf8(
+                dirs::cache_dir()?.join("rust-analyzer").join("proc-macros"),
+            )),
+        }
+    }
+
     pub fn publish_diagnostics(&self, source_root: Option<SourceRootId>) -> bool {
         self.diagnostics_enable(source_root).to_owned()
     }
@@ -2583,6 +2681,13 @@ impl Config {
         }
     }
 
+    pub(crate) fn test_runner(&self, source_root: Option<SourceRootId>) -> TestRunner {
+        match self.runnables_test_runner(source_root) {
+            TestRunnerDef::Libtest => TestRunner::Libtest,
+            TestRunnerDef::Nextest => TestRunner::Nextest,
+        }
+    }
+
     pub(crate) fn flycheck(&self, source_root: Option<SourceRootId>) -> FlycheckConfig {
         match &self.check_overrideCommand(source_root) {
             Some(args) if !args.is_empty() => {
@@ -2781,6 +2886,20 @@ impl Config {
         }
     }
 
+    pub fn proc_macro_expansion_limits(&self) -> ExpansionLimits {
+        ExpansionLimits {
+            // A limit of zero would stop every expansion, so it means no limit instead.
+            time: self
+                .procMacro_expansionTimeLimit()
+                .filter(|&it| it != 0)
+                .map(|it| Duration::from_secs(it as u64)),
+            memory: self
+                .procMacro_expansionMemoryLimit()
+                .filter(|&it| it != 0)
+                .map(|it| it as u64 * 1024 * 1024),
+        }
+    }
+
     pub fn main_loop_num_threads(&self) -> usize {
         match self.numThreads() {
             Some(NumThreads::Concrete(0)) | None | Some(NumThreads::Physical) => {
@@ -3094,6 +3213,13 @@ enum CargoFeaturesDef {
     Selected(Vec<String>),
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum TestRunnerDef {
+    Libtest,
+    Nextest,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum InvocationStrategy {
@@ -3101,6 +3227,14 @@ pub(crate) enum InvocationStrategy {
     PerWorkspace,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct LayoutBudgetDef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    padding: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct CheckOnSaveTargets(#[serde(with = "single_or_array")] Vec<String>);
 
@@ -3791,6 +3925,16 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
         "FxHashMap<String, String>" => set! {
             "type": "object",
         },
+        "FxHashMap<String, LayoutBudgetDef>" => set! {
+            "type": "object",
+            "additionalProperties": {
+                "type": "object",
+                "properties": {
+                    "size": { "type": "integer", "minimum": 0 },
+                    "padding": { "type": "integer", "minimum": 0 },
+                },
+            },
+        },
         "FxHashMap<Box<str>, u16>" => set! {
             "type": "object",
         },
@@ -4023,6 +4167,14 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
                 "Render annotations above the whole item, including documentation comments and attributes."
             ],
         },
+        "TestRunnerDef" => set! {
+            "type": "string",
+            "enum": ["libtest", "nextest"],
+            "enumDescriptions": [
+                "Run tests with `cargo test`, using libtest's unstable JSON output.",
+                "Run tests with `cargo nextest run`, which needs `cargo-nextest` to be installed but also works on stable toolchains."
+            ],
+        },
         "InvocationStrategy" => set! {
             "type": "string",
             "enum": ["per_workspace", "once"],
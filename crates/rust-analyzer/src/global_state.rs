@@ -148,6 +148,7 @@ pub(crate) struct GlobalState {
     // as that handle's lifetime is the same as `GlobalState` itself.
     pub(crate) vfs_span: Option<tracing::span::EnteredSpan>,
     pub(crate) wants_to_switch: Option<Cause>,
+    pub(crate) wants_to_update_ad_hoc_crates: Option<Cause>,
 
     /// `workspaces` field stores the data we actually use, while the `OpQueue`
     /// stores the result of the last fetch.
@@ -177,6 +178,12 @@ pub(crate) struct GlobalState {
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
     pub(crate) crate_graph_file_dependencies: FxHashSet<vfs::VfsPath>,
     pub(crate) detached_files: FxHashSet<ManifestPath>,
+    /// Opened files that don't belong to any crate, analyzed as ad hoc crates on top of the
+    /// workspaces, see [`GlobalState::update_ad_hoc_crates`].
+    pub(crate) ad_hoc_files: FxHashSet<ManifestPath>,
+    /// Files proc-macros reported to read, loaded and watched on top of the workspaces' files,
+    /// see [`GlobalState::watch_proc_macro_tracked_paths`].
+    pub(crate) proc_macro_tracked_paths: FxHashSet<AbsPathBuf>,
 
     // op queues
     pub(crate) fetch_workspaces_queue: OpQueue<FetchWorkspaceRequest, FetchWorkspaceResponse>,
@@ -225,6 +232,7 @@ pub(crate) struct GlobalStateSnapshot {
     pub(crate) proc_macros_loaded: bool,
     pub(crate) flycheck: Arc<[FlycheckHandle]>,
     minicore: MiniCoreRustAnalyzerInternalOnly,
+    sender: Sender<lsp_server::Message>,
 }
 
 impl std::panic::UnwindSafe for GlobalStateSnapshot {}
@@ -316,10 +324,13 @@ impl GlobalState {
             vfs_span: None,
             vfs_done: true,
             wants_to_switch: None,
+            wants_to_update_ad_hoc_crates: None,
 
             workspaces: Arc::from(Vec::new()),
             crate_graph_file_dependencies: FxHashSet::default(),
             detached_files: FxHashSet::default(),
+            ad_hoc_files: FxHashSet::default(),
+            proc_macro_tracked_paths: FxHashSet::default(),
             fetch_workspaces_queue: OpQueue::default(),
             fetch_build_data_queue: OpQueue::default(),
             fetch_proc_macros_queue: OpQueue::default(),
@@ -440,7 +451,11 @@ impl GlobalState {
                     change.change_file(file_id, text);
                 });
                 if has_structure_changes {
-                    let roots = self.source_root_config.partition(vfs);
+                    let roots = if self.config.attach_unlinked_files() {
+                        self.source_root_config.partition_with_local_unlinked_files(vfs)
+                    } else {
+                        self.source_root_config.partition(vfs)
+                    };
                     change.set_roots(roots);
                 }
                 (change, modified_rust_files, workspace_structure_change)
@@ -584,6 +599,7 @@ impl GlobalState {
             proc_macros_loaded: !self.config.expand_proc_macros()
                 || self.fetch_proc_macros_queue.last_op_result().copied().unwrap_or(false),
             flycheck: self.flycheck.clone(),
+            sender: self.sender.clone(),
         }
     }
 
@@ -869,6 +885,22 @@ impl GlobalStateSnapshot {
         self.vfs_read().file_path(file_id).clone()
     }
 
+    /// Returns all Rust files currently known to the VFS.
+    pub(crate) fn rust_files(&self) -> Vec<FileId> {
+        self.vfs_read()
+            .iter()
+            .filter(|(_, path)| path.name_and_extension().is_some_and(|(_, ext)| ext == Some("rs")))
+            .map(|(file_id, _)| file_id)
+            .collect()
+    }
+
+    /// Sends a notification to the client directly from a request handler, e.g. to stream
+    /// partial results.
+    pub(crate) fn send_notification<N: lsp_types::Notification>(&self, params: N::Params) {
+        let not = lsp_server::Notification::new(N::METHOD.into(), params);
+        _ = self.sender.send(not.into());
+    }
+
     pub(crate) fn target_spec_for_crate(&self, crate_id: Crate) -> Option<TargetSpec> {
         let file_id = self.analysis.crate_root(crate_id).ok()?;
         self.target_spec_for_file(file_id, crate_id)
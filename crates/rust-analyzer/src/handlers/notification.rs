@@ -13,6 +13,7 @@ use lsp_types::{
     DidOpenTextDocumentParams, DidSaveTextDocumentParams, WorkDoneProgressCancelParams,
 };
 use paths::Utf8PathBuf;
+use project_model::ManifestPath;
 use triomphe::Arc;
 use vfs::{AbsPathBuf, ChangeKind, VfsPath};
 
@@ -88,7 +89,7 @@ pub(crate) fn handle_did_open_text_document(
         // contents, disk is the truth.
         if !state.source_root_config.path_is_library(&path) {
             let contents = params.text_document.text.into_bytes();
-            state.vfs.write().0.set_file_contents(path, Some(contents));
+            state.vfs.write().0.set_file_contents(path.clone(), Some(contents));
         }
         if state.config.discover_workspace_config().is_some() {
             tracing::debug!("queuing task");
@@ -96,6 +97,8 @@ pub(crate) fn handle_did_open_text_document(
                 .deferred_task_queue
                 .sender
                 .send(crate::main_loop::DeferredTask::CheckIfIndexed(params.text_document.uri));
+        } else if state.is_quiescent() {
+            state.check_if_unlinked([&path]);
         }
     }
     Ok(())
@@ -152,6 +155,11 @@ pub(crate) fn handle_did_close_text_document(
         state.semantic_tokens_cache.lock().remove(&params.text_document.uri);
 
         if let Some(path) = path.as_path() {
+            if let Ok(file) = ManifestPath::try_from(path.to_path_buf())
+                && state.ad_hoc_files.remove(&file)
+            {
+                state.wants_to_update_ad_hoc_crates = Some("closed ad hoc file".to_owned());
+            }
             state.loader.handle.invalidate(path.to_path_buf());
         }
     }
@@ -8,9 +8,9 @@ use anyhow::Context;
 use base64::{Engine, prelude::BASE64_STANDARD};
 use ide::{
     AssistKind, AssistResolveStrategy, Cancellable, CompletionFieldsToResolve,
-    CompletionItemImport, FilePosition, FileRange, FileStructureConfig, FindAllRefsConfig,
-    HoverAction, HoverGotoTypeData, InlayFieldsToResolve, Query, RangeInfo, Runnable, RunnableKind,
-    SingleResolve, SourceChange, TextEdit,
+    CompletionItemImport, DOC_TEST_ID_SUFFIX, FilePosition, FileRange, FileStructureConfig,
+    FindAllRefsConfig, HoverAction, HoverGotoTypeData, InlayFieldsToResolve, MemoryLayoutNodeKind,
+    Query, RangeInfo, Runnable, RunnableKind, SingleResolve, SourceChange, TextEdit,
 };
 use ide_db::{FxHashMap, SymbolKind};
 use itertools::Itertools;
@@ -19,10 +19,12 @@ use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
     CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
     CodeLens, CompletionItem, Contents, DocumentChange, FoldingRange, FoldingRangeParams,
-    InlayHint, InlayHintParams, Location, LocationLink, Position, PrepareRenameResult, Range,
-    RenameParams, ResourceOperationKind, SemanticTokens, SemanticTokensDeltaParams,
-    SemanticTokensDeltaResponse, SemanticTokensParams, SemanticTokensRangeParams,
-    SymbolInformation, SymbolTag, TextDocumentIdentifier, Uri, WorkspaceEdit,
+    InlayHint, InlayHintParams, InlineValueParams, Location, LocationLink, Position,
+    PrepareRenameResult, Range, RenameParams, ResourceOperationKind, SemanticTokens,
+    SemanticTokensDeltaParams, SemanticTokensDeltaResponse, SemanticTokensParams,
+    SemanticTokensRangeParams, SymbolInformation, SymbolTag, TextDocumentIdentifier,
+    TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams, Uri, WorkspaceEdit,
 };
 use paths::Utf8PathBuf;
 use project_model::{CargoWorkspace, ManifestPath, ProjectWorkspaceKind, TargetKind};
@@ -30,7 +32,7 @@ use serde_json::json;
 use stdx::{format_to, never};
 use syntax::{TextRange, TextSize};
 use triomphe::Arc;
-use vfs::{AbsPath, AbsPathBuf, FileId, VfsPath};
+use vfs::{AbsPath, AbsPathBuf, AnchoredPathBuf, FileId, VfsPath};
 
 use crate::{
     config::{
@@ -237,6 +239,7 @@ fn all_test_targets(cargo: &CargoWorkspace) -> impl Iterator<Item = TestTarget>
                     package: package.name.clone(),
                     target: target.name.clone(),
                     kind: target.kind,
+                    doc_tests: false,
                 })
             }
         })
@@ -259,35 +262,60 @@ pub(crate) fn handle_run_test(
     for ws in &*state.workspaces {
         if let ProjectWorkspaceKind::Cargo { cargo, .. } = &ws.kind {
             // need to deduplicate `include` to avoid redundant test runs
-            let tests = match params.include {
+            let mut tests = match params.include {
                 Some(ref include) => include
                     .iter()
                     .unique()
                     .filter_map(|test| {
+                        let (test, doc_tests) = match test.strip_suffix(DOC_TEST_ID_SUFFIX) {
+                            Some(item) => (item, true),
+                            None => (test.as_str(), false),
+                        };
                         let (root, remainder) = match test.split_once("::") {
-                            Some((root, remainder)) => (root.to_owned(), Some(remainder)),
-                            None => (test.clone(), None),
+                            Some((root, remainder)) => (root, Some(remainder)),
+                            None => (test, None),
                         };
-                        if let Some(target) = find_test_target(&root, cargo) {
-                            Some((target, remainder))
-                        } else {
+                        let Some(mut target) = find_test_target(root, cargo) else {
                             tracing::error!("Test target not found for: {test}");
-                            None
+                            return None;
+                        };
+                        if !doc_tests {
+                            return Some((target, remainder.map(ToOwned::to_owned)));
                         }
+                        // rustdoc names doc tests like `src/lib.rs - module::Item (line 3)`, so
+                        // this only matches the doc tests of the item itself.
+                        target.doc_tests = true;
+                        let filter = match remainder {
+                            Some(item) => format!("- {item} (line"),
+                            None => "- (line".to_owned(),
+                        };
+                        Some((target, Some(filter)))
                     })
                     .collect_vec(),
                 None => all_test_targets(cargo).map(|target| (target, None)).collect(),
             };
+            // Running all the tests of a library includes its doc tests.
+            let doc_tests = tests
+                .iter()
+                .filter(|(target, path)| {
+                    path.is_none()
+                        && !target.doc_tests
+                        && matches!(target.kind, TargetKind::Lib { .. })
+                })
+                .map(|(target, _)| (TestTarget { doc_tests: true, ..target.clone() }, None))
+                .collect_vec();
+            tests.extend(doc_tests);
 
             for (target, path) in tests {
                 let handle = CargoTestHandle::new(
-                    path,
+                    path.as_deref(),
                     state.config.cargo_test_options(None),
                     cargo.workspace_root(),
                     Some(cargo.target_directory().as_ref()),
                     target,
                     state.test_run_sender.clone(),
                     ws.toolchain.as_ref(),
+                    state.config.test_runner(None),
                 )?;
                 handles.push(handle);
             }
@@ -583,6 +611,84 @@ pub(crate) fn handle_document_diagnostics(
     ))
 }
 
+pub(crate) fn handle_workspace_diagnostics(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::WorkspaceDiagnosticParams,
+) -> anyhow::Result<lsp_types::WorkspaceDiagnosticReport> {
+    let _p = tracing::info_span!("handle_workspace_diagnostics").entered();
+    let previous_result_ids: FxHashMap<_, _> =
+        params.previous_result_ids.into_iter().map(|it| (it.uri, it.value)).collect();
+    let partial_result_token = params.partial_result_params.partial_result_token;
+
+    let mut items = Vec::new();
+    for file_id in snap.rust_files() {
+        let source_root = snap.analysis.source_root_id(file_id)?;
+        if !snap.analysis.is_local_source_root(source_root)? {
+            continue;
+        }
+        let config = snap.config.diagnostics(Some(source_root));
+        if !config.enabled {
+            continue;
+        }
+        let line_index = snap.file_line_index(file_id)?;
+        let diagnostics = snap
+            .analysis
+            .full_diagnostics(&config, AssistResolveStrategy::None, file_id)?
+            .into_iter()
+            .filter(|d| d.range.file_id == file_id)
+            .map(|d| convert_diagnostic(&line_index, d))
+            .collect::<Vec<_>>();
+
+        let uri = to_proto::url(&snap, file_id);
+        let result_id = format!(
+            "{:x}",
+            std::hash::BuildHasher::hash_one(
+                &std::hash::BuildHasherDefault::<ide_db::FxHasher>::default(),
+                &diagnostics
+            )
+        );
+        let version = snap.file_version(file_id);
+        let report = match previous_result_ids.get(&uri) {
+            Some(previous) if *previous == result_id => {
+                lsp_types::WorkspaceDocumentDiagnosticReport::WorkspaceUnchangedDocumentDiagnosticReport(
+                    lsp_types::WorkspaceUnchangedDocumentDiagnosticReport {
+                        uri,
+                        version,
+                        unchanged_document_diagnostic_report:
+                            lsp_types::UnchangedDocumentDiagnosticReport { result_id },
+                    },
+                )
+            }
+            // There is nothing to tell the client about files it has never seen diagnostics for.
+            None if diagnostics.is_empty() => continue,
+            _ => lsp_types::WorkspaceDocumentDiagnosticReport::WorkspaceFullDocumentDiagnosticReport(
+                lsp_types::WorkspaceFullDocumentDiagnosticReport {
+                    uri,
+                    version,
+                    full_document_diagnostic_report: lsp_types::FullDocumentDiagnosticReport {
+                        result_id: Some(result_id),
+                        items: diagnostics,
+                    },
+                },
+            ),
+        };
+
+        match &partial_result_token {
+            // Stream the reports file by file, the final response then has to be empty.
+            Some(token) => snap.send_notification::<lsp_types::ProgressNotification>(
+                lsp_types::ProgressParams {
+                    token: token.clone(),
+                    value: serde_json::to_value(
+                        lsp_types::WorkspaceDiagnosticReportPartialResult { items: vec![report] },
+                    )?,
+                },
+            ),
+            None => items.push(report),
+        }
+    }
+    Ok(lsp_types::WorkspaceDiagnosticReport { items })
+}
+
 pub(crate) fn handle_document_symbol(
     snap: GlobalStateSnapshot,
     params: lsp_types::DocumentSymbolParams,
@@ -835,6 +941,70 @@ pub(crate) fn handle_will_rename_files(
     }
 }
 
+pub(crate) fn handle_will_create_files(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::CreateFilesParams,
+) -> anyhow::Result<Option<lsp_types::WorkspaceEdit>> {
+    let _p = tracing::info_span!("handle_will_create_files").entered();
+
+    let rust_files = snap
+        .rust_files()
+        .into_iter()
+        .map(|file_id| (file_id, snap.file_id_to_file_path(file_id)))
+        .collect::<Vec<_>>();
+    let files = params
+        .files
+        .iter()
+        .filter_map(|lsp_types::FileCreate { uri }| {
+            let path = from_proto::vfs_path(uri).ok()?;
+            // The new file is not in the VFS yet, so anchor it at the closest existing file in
+            // one of its parent directories.
+            let (anchor, rel_path) = rust_files
+                .iter()
+                .filter_map(|(file_id, file_path)| {
+                    Some((*file_id, path.strip_prefix(&file_path.parent()?)?))
+                })
+                .min_by_key(|(_, rel_path)| rel_path.as_utf8_path().components().count())?;
+            Some(AnchoredPathBuf { anchor, path: rel_path.as_str().to_owned() })
+        })
+        .collect::<Vec<_>>();
+
+    let source_change = snap.analysis.will_create_files(&files)?;
+    if source_change.source_file_edits.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(to_proto::workspace_edit(&snap, source_change)?))
+    }
+}
+
+pub(crate) fn handle_will_delete_files(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DeleteFilesParams,
+) -> anyhow::Result<Option<lsp_types::WorkspaceEdit>> {
+    let _p = tracing::info_span!("handle_will_delete_files").entered();
+
+    let rust_files = snap.rust_files();
+    let mut files = Vec::new();
+    for lsp_types::FileDelete { uri } in &params.files {
+        let Ok(path) = from_proto::vfs_path(uri) else { continue };
+        // Deleting a folder deletes all the files in it.
+        files.extend(
+            rust_files
+                .iter()
+                .filter(|&&file_id| snap.file_id_to_file_path(file_id).starts_with(&path)),
+        );
+    }
+    files.sort_unstable();
+    files.dedup();
+
+    let source_change = snap.analysis.will_delete_files(&files)?;
+    if source_change.source_file_edits.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(to_proto::workspace_edit(&snap, source_change)?))
+    }
+}
+
 pub(crate) fn handle_goto_definition(
     snap: GlobalStateSnapshot,
     params: lsp_types::DefinitionParams,
@@ -1303,6 +1473,23 @@ pub(crate) fn handle_completion_resolve(
     Ok(resolved_completion)
 }
 
+pub(crate) fn handle_document_link(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentLinkParams,
+) -> anyhow::Result<Option<Vec<lsp_types::DocumentLink>>> {
+    let _p = tracing::info_span!("handle_document_link").entered();
+    let file_id = try_default!(from_proto::file_id(&snap, &params.text_document.uri)?);
+    let line_index = snap.file_line_index(file_id)?;
+
+    let links = snap
+        .analysis
+        .document_links(file_id)?
+        .into_iter()
+        .filter_map(|link| to_proto::document_link(&snap, &line_index, link))
+        .collect();
+    Ok(Some(links))
+}
+
 pub(crate) fn handle_folding_range(
     snap: GlobalStateSnapshot,
     params: FoldingRangeParams,
@@ -1781,6 +1968,25 @@ pub(crate) fn handle_document_highlight(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_linked_editing_range(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::LinkedEditingRangeParams,
+) -> anyhow::Result<Option<lsp_types::LinkedEditingRanges>> {
+    let _p = tracing::info_span!("handle_linked_editing_range").entered();
+    let position =
+        try_default!(from_proto::file_position(&snap, &params.text_document_position_params)?);
+    let line_index = snap.file_line_index(position.file_id)?;
+
+    let ranges = match snap.analysis.linked_editing_ranges(position)? {
+        None => return Ok(None),
+        Some(ranges) => ranges,
+    };
+    Ok(Some(lsp_types::LinkedEditingRanges {
+        ranges: ranges.into_iter().map(|range| to_proto::range(&line_index, range)).collect(),
+        word_pattern: None,
+    }))
+}
+
 pub(crate) fn handle_ssr(
     snap: GlobalStateSnapshot,
     params: lsp_ext::SsrParams,
@@ -1892,6 +2098,25 @@ pub(crate) fn handle_inlay_hints_resolve(
         .unwrap_or(original_hint))
 }
 
+pub(crate) fn handle_inline_values(
+    snap: GlobalStateSnapshot,
+    params: InlineValueParams,
+) -> anyhow::Result<Option<Vec<lsp_types::InlineValue>>> {
+    let _p = tracing::info_span!("handle_inline_values").entered();
+    let file_id = try_default!(from_proto::file_id(&snap, &params.text_document.uri)?);
+    let line_index = snap.file_line_index(file_id)?;
+    let range = from_proto::text_range(&line_index, params.range)?;
+    let stopped_at = from_proto::text_range(&line_index, params.context.stopped_location)?;
+
+    Ok(Some(
+        snap.analysis
+            .inline_values(file_id, range, stopped_at)?
+            .into_iter()
+            .map(|it| to_proto::inline_value(&line_index, it))
+            .collect(),
+    ))
+}
+
 pub(crate) fn handle_call_hierarchy_prepare(
     snap: GlobalStateSnapshot,
     params: CallHierarchyPrepareParams,
@@ -1992,6 +2217,78 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_type_hierarchy_prepare(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchyPrepareParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = tracing::info_span!("handle_type_hierarchy_prepare").entered();
+    let position =
+        try_default!(from_proto::file_position(&snap, &params.text_document_position_params)?);
+
+    let nav_info = match snap.analysis.type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let RangeInfo { range: _, info: navs } = nav_info;
+    if navs.is_empty() {
+        return Ok(None);
+    }
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_supertypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySupertypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = tracing::info_span!("handle_type_hierarchy_supertypes").entered();
+    let fpos = try_default!(type_hierarchy_item_position(&snap, params.item)?);
+
+    let navs = match snap.analysis.supertypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_subtypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySubtypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = tracing::info_span!("handle_type_hierarchy_subtypes").entered();
+    let fpos = try_default!(type_hierarchy_item_position(&snap, params.item)?);
+
+    let navs = match snap.analysis.subtypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+fn type_hierarchy_item_position(
+    snap: &GlobalStateSnapshot,
+    item: TypeHierarchyItem,
+) -> anyhow::Result<Option<FilePosition>> {
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = try_default!(from_proto::file_range(snap, &doc, item.selection_range)?);
+    Ok(Some(FilePosition { file_id: frange.file_id, offset: frange.range.start() }))
+}
+
 pub(crate) fn handle_semantic_tokens_full(
     snap: GlobalStateSnapshot,
     params: SemanticTokensParams,
@@ -2201,6 +2498,21 @@ pub(crate) fn handle_view_recursive_memory_layout(
                 parent_idx: n.parent_idx,
                 children_start: n.children_start,
                 children_len: n.children_len,
+                padding: n.padding,
+                is_variant: matches!(n.kind, MemoryLayoutNodeKind::Variant { .. }),
+                tag_value: match n.kind {
+                    MemoryLayoutNodeKind::Variant { tag_value } => {
+                        tag_value.map(|it| it.to_string())
+                    }
+                    MemoryLayoutNodeKind::Item => None,
+                },
+                tag: n.tag.as_ref().map(|tag| lsp_ext::MemoryLayoutTag {
+                    offset: tag.offset,
+                    size: tag.size,
+                    valid_range_start: tag.valid_range.0.to_string(),
+                    valid_range_end: tag.valid_range.1.to_string(),
+                    niche: tag.niche,
+                }),
             })
             .collect(),
     }))
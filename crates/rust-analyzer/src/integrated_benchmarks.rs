@@ -282,6 +282,8 @@ fn integrated_diagnostics_benchmark() {
         prefer_absolute: false,
         term_search_fuel: 400,
         show_rename_conflicts: true,
+        layout_budget_attribute: None,
+        layout_budgets: Default::default(),
     };
     host.analysis()
         .full_diagnostics(&diagnostics_config, ide::AssistResolveStrategy::None, file_id)
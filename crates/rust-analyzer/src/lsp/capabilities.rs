@@ -4,15 +4,16 @@ use ide_db::{FxHashSet, line_index::WideEncoding};
 use lsp_types::{
     CallHierarchyProvider, ChangeNotifications, CodeActionKind, CodeActionOptions,
     CodeActionProvider, CodeLensOptions, CompletionOptions, DeclarationProvider,
-    DefinitionProvider, DocumentFormattingProvider, DocumentHighlightProvider,
+    DefinitionProvider, DocumentFormattingProvider, DocumentHighlightProvider, DocumentLinkOptions,
     DocumentOnTypeFormattingOptions, DocumentRangeFormattingProvider, DocumentSymbolProvider,
     FileOperationFilter, FileOperationOptions, FileOperationPattern, FileOperationPatternKind,
     FileOperationRegistrationOptions, FoldingRangeProvider, Full, HoverProvider,
-    ImplementationProvider, InlayHintOptions, InlayHintProvider, PositionEncodingKind,
-    ReferencesProvider, RenameOptions, RenameProvider, SaveOptions, SelectionRangeProvider,
-    SemanticTokensLegend, SemanticTokensOptions, ServerCapabilities, ServerCompletionItemOptions,
-    SignatureHelpOptions, TextDocumentSync, TextDocumentSyncKind, TextDocumentSyncOptions,
-    TypeDefinitionProvider, WorkDoneProgressOptions, WorkspaceFoldersServerCapabilities,
+    ImplementationProvider, InlayHintOptions, InlayHintProvider, InlineValueProvider,
+    LinkedEditingRangeProvider, PositionEncodingKind, ReferencesProvider, RenameOptions,
+    RenameProvider, SaveOptions, SelectionRangeProvider, SemanticTokensLegend,
+    SemanticTokensOptions, ServerCapabilities, ServerCompletionItemOptions, SignatureHelpOptions,
+    TextDocumentSync, TextDocumentSyncKind, TextDocumentSyncOptions, TypeDefinitionProvider,
+    TypeHierarchyProvider, WorkDoneProgressOptions, WorkspaceFoldersServerCapabilities,
     WorkspaceOptions, WorkspaceSymbolProvider,
 };
 use serde_json::json;
@@ -23,6 +24,28 @@ use crate::{
     lsp::{ext, semantic_tokens},
 };
 
+fn rust_file_filter() -> FileOperationFilter {
+    FileOperationFilter {
+        scheme: Some(String::from("file")),
+        pattern: FileOperationPattern {
+            glob: String::from("**/*.rs"),
+            matches: Some(FileOperationPatternKind::File),
+            options: None,
+        },
+    }
+}
+
+fn folder_filter() -> FileOperationFilter {
+    FileOperationFilter {
+        scheme: Some(String::from("file")),
+        pattern: FileOperationPattern {
+            glob: String::from("**"),
+            matches: Some(FileOperationPatternKind::Folder),
+            options: None,
+        },
+    }
+}
+
 pub fn server_capabilities(config: &Config) -> ServerCapabilities {
     ServerCapabilities {
         position_encoding: match config.caps().negotiated_encoding() {
@@ -98,7 +121,10 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             prepare_provider: Some(true),
             work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
         })),
-        document_link_provider: None,
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: None,
+            work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+        }),
         color_provider: None,
         folding_range_provider: Some(FoldingRangeProvider::Bool(true)),
         declaration_provider: Some(DeclarationProvider::Bool(true)),
@@ -110,30 +136,17 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             }),
             file_operations: Some(FileOperationOptions {
                 did_create: None,
-                will_create: None,
+                will_create: Some(FileOperationRegistrationOptions {
+                    filters: vec![rust_file_filter()],
+                }),
                 did_rename: None,
                 will_rename: Some(FileOperationRegistrationOptions {
-                    filters: vec![
-                        FileOperationFilter {
-                            scheme: Some(String::from("file")),
-                            pattern: FileOperationPattern {
-                                glob: String::from("**/*.rs"),
-                                matches: Some(FileOperationPatternKind::File),
-                                options: None,
-                            },
-                        },
-                        FileOperationFilter {
-                            scheme: Some(String::from("file")),
-                            pattern: FileOperationPattern {
-                                glob: String::from("**"),
-                                matches: Some(FileOperationPatternKind::Folder),
-                                options: None,
-                            },
-                        },
-                    ],
+                    filters: vec![rust_file_filter(), folder_filter()],
                 }),
                 did_delete: None,
-                will_delete: None,
+                will_delete: Some(FileOperationRegistrationOptions {
+                    filters: vec![rust_file_filter(), folder_filter()],
+                }),
             }),
             text_document_content: None,
         }),
@@ -158,8 +171,8 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             .into(),
         ),
         moniker_provider: None,
-        linked_editing_range_provider: None,
-        inline_value_provider: None,
+        linked_editing_range_provider: Some(LinkedEditingRangeProvider::Bool(true)),
+        inline_value_provider: Some(InlineValueProvider::Bool(true)),
         inlay_hint_provider: Some(InlayHintProvider::InlayHintOptions(InlayHintOptions {
             work_done_progress_options: Default::default(),
             resolve_provider: Some(config.caps().inlay_hints_resolve_provider()),
@@ -168,8 +181,7 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             lsp_types::DiagnosticOptions {
                 identifier: Some("rust-analyzer".to_owned()),
                 inter_file_dependencies: true,
-                // FIXME
-                workspace_diagnostics: false,
+                workspace_diagnostics: true,
                 work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
             },
         )),
@@ -190,7 +202,7 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             "ssr": true,
             "workspaceSymbolScopeKindFiltering": true,
         })),
-        type_hierarchy_provider: None,
+        type_hierarchy_provider: Some(TypeHierarchyProvider::Bool(true)),
     }
 }
 
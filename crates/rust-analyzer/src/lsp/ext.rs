@@ -294,8 +294,17 @@ impl Notification for AbortRunTestNotification {
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase", tag = "tag")]
 pub enum TestState {
-    Passed,
-    Failed { message: String },
+    Passed {
+        /// The duration of the test in milliseconds, if known.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration: Option<f64>,
+    },
+    Failed {
+        message: String,
+        /// The duration of the test in milliseconds, if known.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration: Option<f64>,
+    },
     Skipped,
     Started,
     Enqueued,
@@ -367,6 +376,24 @@ pub struct MemoryLayoutNode {
     pub parent_idx: i64,
     pub children_start: i64,
     pub children_len: u64,
+    pub padding: u64,
+    pub is_variant: bool,
+    /// A decimal string, as the tag value may not fit in a JSON number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<MemoryLayoutTag>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryLayoutTag {
+    pub offset: u64,
+    pub size: u64,
+    /// Decimal strings, as the bounds may not fit in a JSON number.
+    pub valid_range_start: String,
+    pub valid_range_end: String,
+    pub niche: bool,
 }
 
 pub enum CancelFlycheckNotification {}
@@ -9,12 +9,12 @@ use std::{
 use base64::{Engine, prelude::BASE64_STANDARD};
 use ide::{
     Annotation, AnnotationKind, Assist, AssistKind, Cancellable, CompletionFieldsToResolve,
-    CompletionItem, CompletionItemKind, CompletionRelevance, Documentation, FileId, FileRange,
-    FileSystemEdit, Fold, FoldKind, Highlight, HlMod, HlOperator, HlPunct, HlRange, HlTag, Indel,
-    InlayFieldsToResolve, InlayHint, InlayHintLabel, InlayHintLabelPart, InlayKind, LazyProperty,
-    Markup, NavigationTarget, ReferenceCategory, RenameError, Runnable, Severity, SignatureHelp,
-    SnippetEdit, SourceChange, StructureNodeKind, SymbolKind, TextEdit, TextRange, TextSize,
-    UpdateTest,
+    CompletionItem, CompletionItemKind, CompletionRelevance, DocumentLink, DocumentLinkTarget,
+    Documentation, FileId, FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HlMod, HlOperator,
+    HlPunct, HlRange, HlTag, Indel, InlayFieldsToResolve, InlayHint, InlayHintLabel,
+    InlayHintLabelPart, InlayKind, InlineValue, LazyProperty, Markup, NavigationTarget,
+    ReferenceCategory, RenameError, Runnable, Severity, SignatureHelp, SnippetEdit, SourceChange,
+    StructureNodeKind, SymbolKind, TextEdit, TextRange, TextSize, UpdateTest,
 };
 use ide_db::{
     FxHasher, MiniCore, assists, rust_doc::format_docs, source_change::ChangeAnnotationId,
@@ -581,6 +581,43 @@ pub(crate) fn signature_help(
     }
 }
 
+pub(crate) fn document_link(
+    snap: &GlobalStateSnapshot,
+    line_index: &LineIndex,
+    link: DocumentLink,
+) -> Option<lsp_types::DocumentLink> {
+    let target = match link.target {
+        DocumentLinkTarget::File(file_id) => url(snap, file_id),
+        DocumentLinkTarget::Url(it) => it.parse().ok()?,
+    };
+    Some(lsp_types::DocumentLink {
+        range: range(line_index, link.range),
+        target: Some(target),
+        tooltip: None,
+        data: None,
+    })
+}
+
+pub(crate) fn inline_value(line_index: &LineIndex, value: InlineValue) -> lsp_types::InlineValue {
+    match value {
+        InlineValue::VariableLookup { range, name } => {
+            lsp_types::InlineValue::VariableLookup(lsp_types::InlineValueVariableLookup {
+                range: self::range(line_index, range),
+                variable_name: Some(name),
+                case_sensitive_lookup: true,
+            })
+        }
+        InlineValue::EvaluatableExpression { range, expression } => {
+            lsp_types::InlineValue::EvaluatableExpression(
+                lsp_types::InlineValueEvaluatableExpression {
+                    range: self::range(line_index, range),
+                    expression: Some(expression),
+                },
+            )
+        }
+    }
+}
+
 pub(crate) fn inlay_hint(
     snap: &GlobalStateSnapshot,
     fields_to_resolve: &InlayFieldsToResolve,
@@ -1586,6 +1623,26 @@ pub(crate) fn call_hierarchy_item(
     })
 }
 
+pub(crate) fn type_hierarchy_item(
+    snap: &GlobalStateSnapshot,
+    target: NavigationTarget,
+) -> Cancellable<lsp_types::TypeHierarchyItem> {
+    let name = target.name.to_string();
+    let detail = target.description.clone();
+    let kind = target.kind.map(symbol_kind).unwrap_or(lsp_types::SymbolKind::Struct);
+    let (uri, range, selection_range) = location_info(snap, target)?;
+    Ok(lsp_types::TypeHierarchyItem {
+        name,
+        kind,
+        tags: None,
+        detail,
+        uri,
+        range,
+        selection_range,
+        data: None,
+    })
+}
+
 pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
     match kind {
         AssistKind::Generate => lsp_types::CodeActionKind::Empty,
@@ -1932,16 +1989,15 @@ pub(crate) fn test_item(
                     project_model::TargetKind::Bin
                     | project_model::TargetKind::Lib { .. }
                     | project_model::TargetKind::Example
+                    | project_model::TargetKind::Bench
                     | project_model::TargetKind::BuildScript
                     | project_model::TargetKind::Other => lsp_ext::TestItemKind::Package,
                     project_model::TargetKind::Test => lsp_ext::TestItemKind::Test,
-                    // benches are not tests needed to be shown in the test explorer
-                    project_model::TargetKind::Bench => return None,
                 },
                 None => lsp_ext::TestItemKind::Package,
             },
             ide::TestItemKind::Module => lsp_ext::TestItemKind::Module,
-            ide::TestItemKind::Function => lsp_ext::TestItemKind::Test,
+            ide::TestItemKind::Function | ide::TestItemKind::DocTest => lsp_ext::TestItemKind::Test,
         },
         can_resolve_children: matches!(
             test_item.kind,
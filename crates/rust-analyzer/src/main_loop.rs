@@ -12,6 +12,7 @@ use crossbeam_channel::{Receiver, never, select};
 use ide_db::base_db::{SourceDatabase, VfsPath};
 use lsp_server::{Connection, Notification, Request};
 use lsp_types::{Notification as _, TextDocumentIdentifier};
+use project_model::ManifestPath;
 use stdx::thread::ThreadIntent;
 use tracing::{Level, error, span};
 use vfs::{AbsPathBuf, FileId, loader::LoadingProgress};
@@ -101,6 +102,7 @@ impl fmt::Display for Event {
 pub(crate) enum DeferredTask {
     CheckIfIndexed(lsp_types::Uri),
     CheckProcMacroSources(Vec<FileId>),
+    CheckIfUnlinked(Vec<FileId>),
 }
 
 #[derive(Debug)]
@@ -117,6 +119,7 @@ pub(crate) enum Task {
     Diagnostics(DiagnosticsTaskKind),
     DiscoverTest(lsp_ext::DiscoverTestResults),
     PrimeCaches(PrimeCachesProgress),
+    AttachUnlinkedFiles(Vec<ManifestPath>),
     FetchWorkspace(ProjectWorkspaceProgress),
     FetchBuildData(BuildDataProgress),
     LoadProcMacros(ProcMacroProgress),
@@ -506,7 +509,21 @@ impl GlobalState {
                         (None, None) => None,
                     };
                 }
-                (self.process_changes(), self.mem_docs.take_changes())
+                let (state_changed, changes_cancellation_time) = self.process_changes();
+                // The cause is kept until the in-flight fetches settle, a workspace switch
+                // might not recreate the crate graph.
+                let ad_hoc_crates_changed = if self.is_quiescent()
+                    && let Some(cause) = self.wants_to_update_ad_hoc_crates.take()
+                {
+                    self.update_ad_hoc_crates(cause);
+                    true
+                } else {
+                    false
+                };
+                (
+                    (state_changed || ad_hoc_crates_changed, changes_cancellation_time),
+                    self.mem_docs.take_changes(),
+                )
             } else {
                 ((false, None), false)
             };
@@ -518,8 +535,13 @@ impl GlobalState {
 
         let mut gc_elapsed = None;
         if self.is_quiescent() {
+            if self.vfs_done {
+                self.watch_proc_macro_tracked_paths();
+            }
             let became_quiescent = !was_quiescent;
             if became_quiescent {
+                // Files opened while loading may not belong to the loaded workspaces.
+                self.check_if_unlinked(self.mem_docs.iter());
                 // delay initial cache priming until proc macros are loaded, or we will load up a bunch of garbage into salsa
                 let proc_macros_loaded = self.config.prefill_caches()
                     && (!self.config.expand_proc_macros()
@@ -875,6 +897,15 @@ impl GlobalState {
                 }
                 PrimeCachesProgress::End { .. } => prime_caches_progress.push(progress),
             },
+            Task::AttachUnlinkedFiles(files) => {
+                // Files closed while they were checked are left alone.
+                let files = files.into_iter().filter(|file| {
+                    self.mem_docs.contains(&VfsPath::from(file.to_path_buf()))
+                        && !self.detached_files.contains(file)
+                });
+                self.ad_hoc_files.extend(files);
+                self.wants_to_update_ad_hoc_crates = Some("attached unlinked files".to_owned());
+            }
             Task::FetchWorkspace(progress) => {
                 let (state, msg) = match progress {
                     ProjectWorkspaceProgress::Begin => (Progress::Begin, None),
@@ -1106,6 +1137,25 @@ impl GlobalState {
                     }
                 });
             }
+            DeferredTask::CheckIfUnlinked(file_ids) => {
+                let snap = self.snapshot();
+                self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, move |sender| {
+                    let _p = tracing::info_span!("GlobalState::check_if_unlinked").entered();
+                    let unlinked = file_ids
+                        .into_iter()
+                        .filter(|&file_id| {
+                            snap.analysis.crates_for(file_id).is_ok_and(|crates| crates.is_empty())
+                        })
+                        .filter_map(|file_id| {
+                            let path = snap.file_id_to_file_path(file_id).as_path()?.to_path_buf();
+                            ManifestPath::try_from(path).ok()
+                        })
+                        .collect::<Vec<_>>();
+                    if !unlinked.is_empty() {
+                        sender.send(Task::AttachUnlinkedFiles(unlinked)).unwrap();
+                    }
+                });
+            }
             DeferredTask::CheckProcMacroSources(modified_rust_files) => {
                 let analysis = AssertUnwindSafe(self.snapshot().analysis);
                 self.task_pool.handle.spawn_with_sender(stdx::thread::ThreadIntent::Worker, {
@@ -1177,15 +1227,19 @@ impl GlobalState {
     fn handle_cargo_test_msg(&mut self, message: CargoTestMessage) {
         match message.output {
             CargoTestOutput::Test { name, state } => {
+                let millis = |secs: Option<f64>| secs.map(|secs| secs * 1000.0);
                 let state = match state {
                     TestState::Started => lsp_ext::TestState::Started,
                     TestState::Ignored => lsp_ext::TestState::Skipped,
-                    TestState::Ok => lsp_ext::TestState::Passed,
-                    TestState::Failed { stdout } => lsp_ext::TestState::Failed { message: stdout },
+                    TestState::Ok { exec_time } => {
+                        lsp_ext::TestState::Passed { duration: millis(exec_time) }
+                    }
+                    TestState::Failed { stdout, exec_time } => {
+                        lsp_ext::TestState::Failed { message: stdout, duration: millis(exec_time) }
+                    }
                 };
 
-                // The notification requires the namespace form (with underscores) of the target
-                let test_id = format!("{}::{name}", message.target.target.replace('-', "_"));
+                let test_id = message.target.test_id(&name);
 
                 self.send_notification::<lsp_ext::ChangeTestStateNotification>(
                     lsp_ext::ChangeTestStateParams { test_id, state },
@@ -1388,25 +1442,40 @@ impl GlobalState {
                     retrigger_request: true
                 }).ok(),
             })
+            .on_with_vfs_default::<lsp_types::WorkspaceDiagnosticRequest>(handlers::handle_workspace_diagnostics, lsp_types::WorkspaceDiagnosticReport::default, || lsp_server::ResponseError {
+                code: lsp_server::ErrorCode::ServerCancelled as i32,
+                message: "server cancelled the request".to_owned(),
+                data: serde_json::to_value(lsp_types::DiagnosticServerCancellationData {
+                    retrigger_request: true
+                }).ok(),
+            })
             .on::<RETRY, lsp_types::DocumentSymbolRequest>(handlers::handle_document_symbol)
             .on::<RETRY, lsp_types::FoldingRangeRequest>(handlers::handle_folding_range)
+            .on::<NO_RETRY, lsp_types::DocumentLinkRequest>(handlers::handle_document_link)
             .on::<NO_RETRY, lsp_types::SignatureHelpRequest>(handlers::handle_signature_help)
             .on::<RETRY, lsp_types::WillRenameFilesRequest>(handlers::handle_will_rename_files)
+            .on::<RETRY, lsp_types::WillCreateFilesRequest>(handlers::handle_will_create_files)
+            .on::<RETRY, lsp_types::WillDeleteFilesRequest>(handlers::handle_will_delete_files)
             .on::<NO_RETRY, lsp_types::DefinitionRequest>(handlers::handle_goto_definition)
             .on::<NO_RETRY, lsp_types::DeclarationRequest>(handlers::handle_goto_declaration)
             .on::<NO_RETRY, lsp_types::ImplementationRequest>(handlers::handle_goto_implementation)
             .on::<NO_RETRY, lsp_types::TypeDefinitionRequest>(handlers::handle_goto_type_definition)
             .on::<NO_RETRY, lsp_types::InlayHintRequest>(handlers::handle_inlay_hints)
             .on_identity::<NO_RETRY, lsp_types::InlayHintResolveRequest, _>(handlers::handle_inlay_hints_resolve)
+            .on::<NO_RETRY, lsp_types::InlineValueRequest>(handlers::handle_inline_values)
             .on::<NO_RETRY, lsp_types::CodeLensRequest>(handlers::handle_code_lens)
             .on_identity::<NO_RETRY, lsp_types::CodeLensResolveRequest, _>(handlers::handle_code_lens_resolve)
             .on::<NO_RETRY, lsp_types::PrepareRenameRequest>(handlers::handle_prepare_rename)
             .on::<NO_RETRY, lsp_types::RenameRequest>(handlers::handle_rename)
             .on::<NO_RETRY, lsp_types::ReferencesRequest>(handlers::handle_references)
             .on::<NO_RETRY, lsp_types::DocumentHighlightRequest>(handlers::handle_document_highlight)
+            .on::<NO_RETRY, lsp_types::LinkedEditingRangeRequest>(handlers::handle_linked_editing_range)
             .on::<NO_RETRY, lsp_types::CallHierarchyPrepareRequest>(handlers::handle_call_hierarchy_prepare)
             .on::<NO_RETRY, lsp_types::CallHierarchyIncomingCallsRequest>(handlers::handle_call_hierarchy_incoming)
             .on::<NO_RETRY, lsp_types::CallHierarchyOutgoingCallsRequest>(handlers::handle_call_hierarchy_outgoing)
+            .on::<NO_RETRY, lsp_types::TypeHierarchyPrepareRequest>(handlers::handle_type_hierarchy_prepare)
+            .on::<NO_RETRY, lsp_types::TypeHierarchySupertypesRequest>(handlers::handle_type_hierarchy_supertypes)
+            .on::<NO_RETRY, lsp_types::TypeHierarchySubtypesRequest>(handlers::handle_type_hierarchy_subtypes)
             // All other request handlers (lsp extension)
             .on::<RETRY, lsp_ext::FetchDependencyListRequest>(handlers::fetch_dependency_list)
             .on::<RETRY, lsp_ext::AnalyzerStatusRequest>(handlers::handle_analyzer_status)
@@ -21,7 +21,7 @@ use ide_db::{
     base_db::{CrateGraphBuilder, ProcMacroLoadingError, ProcMacroPaths},
 };
 use itertools::Itertools;
-use load_cargo::{ProjectFolders, load_proc_macro};
+use load_cargo::{ProjectFolders, SourceRootConfig, load_proc_macro};
 use lsp_types::FileSystemWatcher;
 use paths::Utf8Path;
 use proc_macro_api::ProcMacroClient;
@@ -39,7 +39,7 @@ use crate::{
         FetchBuildDataResponse, FetchWorkspaceRequest, FetchWorkspaceResponse, GlobalState,
     },
     lsp_ext,
-    main_loop::{DiscoverProjectParam, Task},
+    main_loop::{DeferredTask, DiscoverProjectParam, Task},
     op_queue::Cause,
 };
 use tracing::{debug, info};
@@ -109,6 +109,13 @@ impl GlobalState {
             self.reload_flycheck();
         }
 
+        let limits = self.config.proc_macro_expansion_limits();
+        if limits != old_config.proc_macro_expansion_limits() {
+            for client in self.proc_macro_clients.iter().flatten().flatten() {
+                client.set_expansion_limits(limits);
+            }
+        }
+
         hir::db::set_expand_proc_attr_macros(
             self.analysis_host.raw_database_mut(),
             self.config.expand_proc_attr_macros(),
@@ -652,13 +659,6 @@ impl GlobalState {
             );
         }
 
-        let files_config = self.config.files();
-        let project_folders = ProjectFolders::new(
-            &self.workspaces,
-            &files_config.exclude,
-            Config::user_config_dir_path().as_deref(),
-        );
-
         if (self.proc_macro_clients.len() < self.workspaces.len() || !same_workspaces)
             && self.config.expand_proc_macros()
         {
@@ -680,7 +680,7 @@ impl GlobalState {
                     },
                 };
 
-                let env: FxHashMap<_, _> = match &ws.kind {
+                let mut env: FxHashMap<_, _> = match &ws.kind {
                     ProjectWorkspaceKind::Cargo { cargo, .. }
                     | ProjectWorkspaceKind::DetachedFile { cargo: Some((cargo, ..)), .. } => cargo
                         .env()
@@ -702,6 +702,12 @@ impl GlobalState {
 
                     _ => Default::default(),
                 };
+                if let Some(dir) = self.config.proc_macro_expansion_cache_dir() {
+                    env.insert(
+                        "RA_PROC_MACRO_EXPANSION_CACHE_DIR".to_owned(),
+                        Some(dir.to_string()),
+                    );
+                }
 
                 let key = (path, ws.toolchain.clone(), env);
                 if let Some((_, client)) = clients.iter().find(|(k, _)| *k == key) {
@@ -711,9 +717,11 @@ impl GlobalState {
                 let (path, toolchain, env) = &key;
                 info!("Spawning proc-macro server at {path}");
                 let num_process = self.config.proc_macro_num_processes();
+                let limits = self.config.proc_macro_expansion_limits();
 
                 Some(match ProcMacroClient::spawn(path, env, toolchain.as_ref(), num_process) {
                     Ok(client) => {
+                        let client = client.with_expansion_limits(limits);
                         clients.push((key.clone(), client.clone()));
                         Ok(client)
                     }
@@ -729,6 +737,32 @@ impl GlobalState {
             }))
         }
 
+        self.source_root_config = self.set_vfs_loader_config();
+        self.local_roots_parent_map = Arc::new(self.source_root_config.source_root_parent_map());
+
+        info!(?cause, "recreating the crate graph");
+        let cancellation_time = self.recreate_crate_graph(cause, switching_from_empty_workspace);
+
+        info!("did switch workspaces");
+        cancellation_time
+    }
+
+    /// Points the VFS loader at the files of the workspaces and the files proc-macros reported to
+    /// read, returning how the loaded files are partitioned into source roots.
+    fn set_vfs_loader_config(&mut self) -> SourceRootConfig {
+        let files_config = self.config.files();
+        let mut project_folders = ProjectFolders::new(
+            &self.workspaces,
+            &files_config.exclude,
+            Config::user_config_dir_path().as_deref(),
+        );
+        if !self.proc_macro_tracked_paths.is_empty() {
+            project_folders.watch.push(project_folders.load.len());
+            project_folders.load.push(vfs::loader::Entry::Files(
+                self.proc_macro_tracked_paths.iter().cloned().collect(),
+            ));
+        }
+
         let watch = match files_config.watcher {
             FilesWatcher::Client => vec![],
             FilesWatcher::Server => project_folders.watch,
@@ -739,14 +773,55 @@ impl GlobalState {
             watch,
             version: self.vfs_config_version,
         });
-        self.source_root_config = project_folders.source_root_config;
-        self.local_roots_parent_map = Arc::new(self.source_root_config.source_root_parent_map());
+        project_folders.source_root_config
+    }
 
-        info!(?cause, "recreating the crate graph");
-        let cancellation_time = self.recreate_crate_graph(cause, switching_from_empty_workspace);
+    /// Loads and watches the files proc-macros reported to read since the last call, so that
+    /// changing them redoes the expansions that read them.
+    pub(crate) fn watch_proc_macro_tracked_paths(&mut self) {
+        let new_paths = self
+            .proc_macro_clients
+            .iter()
+            .flatten()
+            .filter_map(|client| client.as_ref().ok())
+            .flat_map(ProcMacroClient::take_tracked_paths)
+            .filter(|path| self.proc_macro_tracked_paths.insert(path.clone()))
+            .collect::<Vec<_>>();
+        if new_paths.is_empty() {
+            return;
+        }
+        info!(tracked_paths = self.proc_macro_tracked_paths.len(), "Watching proc-macro inputs");
 
-        info!("did switch workspaces");
-        cancellation_time
+        match self.config.files().watcher {
+            FilesWatcher::Client => {
+                for path in new_paths {
+                    self.loader.handle.invalidate(path);
+                }
+                let watchers = self
+                    .proc_macro_tracked_paths
+                    .iter()
+                    .sorted()
+                    .map(|path| lsp_types::FileSystemWatcher {
+                        glob_pattern: lsp_types::GlobPattern::Pattern(path.to_string()),
+                        kind: None,
+                    })
+                    .collect();
+                let registration_options =
+                    lsp_types::DidChangeWatchedFilesRegistrationOptions { watchers };
+                let registration = lsp_types::Registration {
+                    id: "proc-macro-tracked-paths".to_owned(),
+                    method: "workspace/didChangeWatchedFiles".to_owned(),
+                    register_options: Some(serde_json::to_value(registration_options).unwrap()),
+                };
+                self.send_request::<lsp_types::RegistrationRequest>(
+                    lsp_types::RegistrationParams { registrations: vec![registration] },
+                    |_, _| (),
+                );
+            }
+            // Only the new paths are added to the watcher, reloading the whole configuration
+            // would rescan every root. They are part of the configuration from its next change on.
+            FilesWatcher::Server => self.loader.handle.watch_files(new_paths),
+        }
     }
 
     fn recreate_crate_graph(&mut self, cause: String, initial_build: bool) -> Option<Duration> {
@@ -760,9 +835,6 @@ impl GlobalState {
             None,
         );
 
-        // crate graph construction relies on these paths, record them so when one of them gets
-        // deleted or created we trigger a reconstruction of the crate graph
-        self.crate_graph_file_dependencies.clear();
         self.detached_files = self
             .workspaces
             .iter()
@@ -771,23 +843,9 @@ impl GlobalState {
                 _ => None,
             })
             .collect();
+        self.ad_hoc_files.retain(|file| !self.detached_files.contains(file));
 
-        self.incomplete_crate_graph = false;
-        let (crate_graph, proc_macro_paths) = {
-            // Create crate graph from all the workspaces
-            let vfs = &self.vfs.read().0;
-            let load = |path: &AbsPath| {
-                let vfs_path = vfs::VfsPath::from(path.to_path_buf());
-                self.crate_graph_file_dependencies.insert(vfs_path.clone());
-                let file_id = vfs.file_id(&vfs_path);
-                self.incomplete_crate_graph |= file_id.is_none();
-                file_id.and_then(|(file_id, excluded)| {
-                    (excluded == vfs::FileExcluded::No).then_some(file_id)
-                })
-            };
-
-            ws_to_crate_graph(&self.workspaces, self.config.extra_env(None), load)
-        };
+        let (crate_graph, proc_macro_paths) = self.build_crate_graph();
         let mut change = ChangeWithProcMacros::default();
         if initial_build || !self.config.expand_proc_macros() {
             if self.config.expand_proc_macros() {
@@ -824,6 +882,84 @@ impl GlobalState {
         cancellation_time
     }
 
+    /// Checks whether the opened Rust files at `paths` belong to any crate in the background,
+    /// attaching the ones that don't as ad hoc crates.
+    pub(crate) fn check_if_unlinked<'a>(&self, paths: impl IntoIterator<Item = &'a vfs::VfsPath>) {
+        if !self.config.attach_unlinked_files() || self.workspaces.is_empty() {
+            return;
+        }
+        let vfs = &self.vfs.read().0;
+        let file_ids = paths
+            .into_iter()
+            .filter(|path| path.name_and_extension().is_some_and(|(_, ext)| ext == Some("rs")))
+            .filter(|path| !self.source_root_config.path_is_library(path))
+            .filter(|path| {
+                let file =
+                    path.as_path().and_then(|it| ManifestPath::try_from(it.to_path_buf()).ok());
+                file.is_some_and(|file| {
+                    !self.detached_files.contains(&file) && !self.ad_hoc_files.contains(&file)
+                })
+            })
+            .filter_map(|path| match vfs.file_id(path)? {
+                (file_id, vfs::FileExcluded::No) => Some(file_id),
+                (_, vfs::FileExcluded::Yes) => None,
+            })
+            .collect::<Vec<_>>();
+        if !file_ids.is_empty() {
+            _ = self.deferred_task_queue.sender.send(DeferredTask::CheckIfUnlinked(file_ids));
+        }
+    }
+
+    /// Rebuilds the crate graph after the set of ad hoc files changed.
+    ///
+    /// Unlike [`GlobalState::recreate_crate_graph`], this keeps the loaded proc macros, as the
+    /// crates of the workspaces don't change and ad hoc crates don't have any.
+    pub(crate) fn update_ad_hoc_crates(&mut self, cause: String) {
+        info!(?cause, ad_hoc_files = self.ad_hoc_files.len(), "Updating ad hoc crates");
+        let (crate_graph, _) = self.build_crate_graph();
+        let mut change = ChangeWithProcMacros::default();
+        change.set_crate_graph(crate_graph);
+        self.analysis_host.apply_change(change);
+    }
+
+    /// Creates the crate graph of the workspaces and the ad hoc files.
+    fn build_crate_graph(&mut self) -> (CrateGraphBuilder, Vec<ProcMacroPaths>) {
+        // crate graph construction relies on these paths, record them so when one of them gets
+        // deleted or created we trigger a reconstruction of the crate graph
+        self.crate_graph_file_dependencies.clear();
+        self.incomplete_crate_graph = false;
+
+        let vfs = &self.vfs.read().0;
+        let mut load = |path: &AbsPath| {
+            let vfs_path = vfs::VfsPath::from(path.to_path_buf());
+            self.crate_graph_file_dependencies.insert(vfs_path.clone());
+            let file_id = vfs.file_id(&vfs_path);
+            self.incomplete_crate_graph |= file_id.is_none();
+            file_id.and_then(|(file_id, excluded)| {
+                (excluded == vfs::FileExcluded::No).then_some(file_id)
+            })
+        };
+
+        // Create crate graph from all the workspaces
+        let (mut crate_graph, proc_macro_paths) =
+            ws_to_crate_graph(&self.workspaces, self.config.extra_env(None), &mut load);
+        for file in self.ad_hoc_files.iter().sorted() {
+            // Back the file by the innermost workspace containing it, or else the first one.
+            let Some(ws) = self
+                .workspaces
+                .iter()
+                .filter(|ws| file.starts_with(ws.workspace_root()))
+                .max_by_key(|ws| ws.workspace_root().as_str().len())
+                .or_else(|| self.workspaces.first())
+            else {
+                break;
+            };
+            let ad_hoc_graph = ws.ad_hoc_file_to_crate_graph(file, &mut load);
+            crate_graph.extend(ad_hoc_graph, &mut ProcMacroPaths::default());
+        }
+        (crate_graph, proc_macro_paths)
+    }
+
     pub(crate) fn finish_loading_crate_graph(&mut self) -> Option<Duration> {
         let (_, cancellation_time) = self.process_changes();
         self.reload_flycheck();
@@ -1,7 +1,10 @@
-//! This module provides the functionality needed to run `cargo test` in a background
-//! thread and report the result of each test in a channel.
+//! This module provides the functionality needed to run `cargo test` (or `cargo nextest run`) in a
+//! background thread and report the result of each test in a channel.
+
+use std::process::Command;
 
 use crossbeam_channel::Sender;
+use ide::DOC_TEST_ID_SUFFIX;
 use paths::{AbsPath, Utf8Path};
 use project_model::TargetKind;
 use serde::Deserialize as _;
@@ -13,16 +16,39 @@ use crate::{
     flycheck::CargoOptions,
 };
 
+/// The backend used to run tests and stream their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TestRunner {
+    /// `cargo test` with libtest's unstable JSON output, enabled through `RUSTC_BOOTSTRAP`.
+    Libtest,
+    /// `cargo nextest run` with nextest's libtest-compatible JSON output.
+    Nextest,
+}
+
+impl TestRunner {
+    /// Returns the runner used for `target`, which is libtest for doc tests as nextest can't run
+    /// them.
+    fn for_target(self, target: &TestTarget) -> TestRunner {
+        if target.doc_tests { TestRunner::Libtest } else { self }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "event", rename_all = "camelCase")]
 pub(crate) enum TestState {
     Started,
-    Ok,
+    Ok {
+        /// The duration of the test in seconds, if the runner reports it.
+        #[serde(default)]
+        exec_time: Option<f64>,
+    },
     Ignored,
     Failed {
         // the stdout field is not always present depending on cargo test flags
         #[serde(skip_serializing_if = "String::is_empty", default)]
         stdout: String,
+        #[serde(default)]
+        exec_time: Option<f64>,
     },
 }
 
@@ -49,11 +75,12 @@ pub(crate) enum CargoTestOutput {
 
 pub(crate) struct CargoTestOutputParser {
     pub target: TestTarget,
+    pub runner: TestRunner,
 }
 
 impl CargoTestOutputParser {
-    pub(crate) fn new(test_target: &TestTarget) -> Self {
-        Self { target: test_target.clone() }
+    pub(crate) fn new(test_target: &TestTarget, runner: TestRunner) -> Self {
+        Self { target: test_target.clone(), runner }
     }
 }
 
@@ -62,14 +89,19 @@ impl JsonLinesParser<CargoTestMessage> for CargoTestOutputParser {
         let mut deserializer = serde_json::Deserializer::from_str(line);
         deserializer.disable_recursion_limit();
 
-        Some(CargoTestMessage {
-            target: self.target.clone(),
-            output: if let Ok(message) = CargoTestOutput::deserialize(&mut deserializer) {
-                message
-            } else {
-                CargoTestOutput::Custom { text: line.to_owned() }
-            },
-        })
+        let output = match CargoTestOutput::deserialize(&mut deserializer) {
+            // nextest prefixes test names with the binary id, e.g. `my-crate::bin/my-bin$tests::foo`
+            Ok(CargoTestOutput::Test { name, state }) if self.runner == TestRunner::Nextest => {
+                let name = match name.split_once('$') {
+                    Some((_binary_id, name)) => name.to_owned(),
+                    None => name,
+                };
+                CargoTestOutput::Test { name, state }
+            }
+            Ok(message) => message,
+            Err(_) => CargoTestOutput::Custom { text: line.to_owned() },
+        };
+        Some(CargoTestMessage { target: self.target.clone(), output })
     }
 
     fn from_stderr_line(&self, line: &str, _error: &mut String) -> Option<CargoTestMessage> {
@@ -91,13 +123,41 @@ pub(crate) struct CargoTestHandle {
 
 // Example of a cargo test command:
 //
-// cargo test --package my-package --bin my_bin --no-fail-fast -- module::func -Z unstable-options --format=json
+// cargo test --package my-package --bin my_bin --no-fail-fast -- module::func -Z unstable-options --format=json --report-time
+//
+// and of a cargo nextest command:
+//
+// cargo nextest run --package my-package --bin my_bin --no-fail-fast --message-format=libtest-json-plus --no-capture -- module::func
+//
+// Doc tests always run through `cargo test --doc`, as nextest doesn't support them.
 
 #[derive(Debug, Clone)]
 pub(crate) struct TestTarget {
     pub package: String,
     pub target: String,
     pub kind: TargetKind,
+    /// Whether to run the doc tests of the (library) target instead of its unit tests.
+    pub doc_tests: bool,
+}
+
+impl TestTarget {
+    /// Returns the test explorer id of the test called `name` in this target.
+    pub(crate) fn test_id(&self, name: &str) -> String {
+        // The test explorer uses the namespace form (with underscores) of the target
+        let target = self.target.replace('-', "_");
+        if !self.doc_tests {
+            return format!("{target}::{name}");
+        }
+        // rustdoc names doc tests like `src/lib.rs - module::Item (line 3)`, and the crate docs
+        // like `src/lib.rs - (line 1)`
+        let item = name.split_once(" - ").map_or(name, |(_, it)| it);
+        let item = item.find("(line ").map_or(item, |idx| item[..idx].trim_end());
+        if item.is_empty() {
+            format!("{target}{DOC_TEST_ID_SUFFIX}")
+        } else {
+            format!("{target}::{item}{DOC_TEST_ID_SUFFIX}")
+        }
+    }
 }
 
 impl CargoTestHandle {
@@ -109,53 +169,251 @@ impl CargoTestHandle {
         test_target: TestTarget,
         sender: Sender<CargoTestMessage>,
         toolchain_version: Option<&semver::Version>,
+        runner: TestRunner,
     ) -> anyhow::Result<Self> {
-        let mut cmd = toolchain::command(Tool::Cargo.path(), root, &options.extra_env);
-        cmd.env("RUSTC_BOOTSTRAP", "1");
-        cmd.arg("--color=always");
-        cmd.arg(&options.subcommand); // test, usually
-
-        cmd.arg("--package");
-        cmd.arg(&test_target.package);
-
-        if let TargetKind::Lib { .. } = test_target.kind {
-            // no name required with lib because there can only be one lib target per package
-            cmd.arg("--lib");
-        } else if let Some(cargo_target) = test_target.kind.as_cargo_target() {
-            cmd.arg(format!("--{cargo_target}"));
-            cmd.arg(&test_target.target);
-        } else {
-            tracing::warn!("Running test for unknown cargo target {:?}", test_target.kind);
-        }
-
-        // --no-fail-fast is needed to ensure that all requested tests will run
-        cmd.arg("--no-fail-fast");
-        cmd.arg("--manifest-path");
-        cmd.arg(root.join("Cargo.toml"));
-        options.apply_on_command(
-            &mut cmd,
+        let runner = runner.for_target(&test_target);
+        let cmd = test_command(
+            path,
+            &options,
+            root,
             ws_target_dir,
-            Some(&test_target.package),
+            &test_target,
             toolchain_version,
+            runner,
         );
-        cmd.arg("--");
-        if let Some(path) = path {
-            cmd.arg(path);
-        }
-        cmd.args(["-Z", "unstable-options"]);
-        cmd.arg("--format=json");
-
-        for extra_arg in options.extra_test_bin_args {
-            cmd.arg(extra_arg);
-        }
-
         Ok(Self {
             _handle: CommandHandle::spawn(
                 cmd,
-                CargoTestOutputParser::new(&test_target),
+                CargoTestOutputParser::new(&test_target, runner),
                 sender,
                 None,
             )?,
         })
     }
 }
+
+fn test_command(
+    path: Option<&str>,
+    options: &CargoOptions,
+    root: &AbsPath,
+    ws_target_dir: Option<&Utf8Path>,
+    test_target: &TestTarget,
+    toolchain_version: Option<&semver::Version>,
+    runner: TestRunner,
+) -> Command {
+    let mut cmd = toolchain::command(Tool::Cargo.path(), root, &options.extra_env);
+    cmd.arg("--color=always");
+    match runner {
+        TestRunner::Libtest => {
+            cmd.env("RUSTC_BOOTSTRAP", "1");
+            cmd.arg(&options.subcommand); // test, usually
+        }
+        TestRunner::Nextest => {
+            // libtest-json output is still experimental in nextest, but works on stable
+            cmd.env("NEXTEST_EXPERIMENTAL_LIBTEST_JSON", "1");
+            cmd.arg("nextest");
+            // `cargo test` is `cargo nextest run`, other subcommands are nextest's own
+            cmd.arg(match options.subcommand.as_str() {
+                "test" => "run",
+                subcommand => subcommand,
+            });
+        }
+    }
+
+    cmd.arg("--package");
+    cmd.arg(&test_target.package);
+
+    if test_target.doc_tests {
+        cmd.arg("--doc");
+    } else if let TargetKind::Lib { .. } = test_target.kind {
+        // no name required with lib because there can only be one lib target per package
+        cmd.arg("--lib");
+    } else if let Some(cargo_target) = test_target.kind.as_cargo_target() {
+        cmd.arg(format!("--{cargo_target}"));
+        cmd.arg(&test_target.target);
+    } else {
+        tracing::warn!("Running test for unknown cargo target {:?}", test_target.kind);
+    }
+
+    // --no-fail-fast is needed to ensure that all requested tests will run
+    cmd.arg("--no-fail-fast");
+    cmd.arg("--manifest-path");
+    cmd.arg(root.join("Cargo.toml"));
+    options.apply_on_command(
+        &mut cmd,
+        ws_target_dir,
+        Some(&test_target.package),
+        toolchain_version,
+    );
+    match runner {
+        TestRunner::Libtest => {
+            cmd.arg("--");
+            if let Some(path) = path {
+                cmd.arg(path);
+            }
+            cmd.args(["-Z", "unstable-options"]);
+            cmd.arg("--format=json");
+            cmd.arg("--report-time");
+            cmd.args(&options.extra_test_bin_args);
+        }
+        TestRunner::Nextest => {
+            cmd.arg("--message-format=libtest-json-plus");
+            let (nextest_args, filter_args) = nextest_args(&options.extra_test_bin_args);
+            cmd.args(nextest_args);
+            cmd.arg("--");
+            if let Some(path) = path {
+                cmd.arg(path);
+            }
+            cmd.args(filter_args);
+        }
+    }
+    cmd
+}
+
+/// Translates the libtest arguments of `runnables.extraTestBinaryArgs` into nextest options and
+/// the filter arguments nextest accepts after `--`, dropping the ones nextest doesn't support.
+fn nextest_args(bin_args: &[String]) -> (Vec<&str>, Vec<&str>) {
+    let (mut options, mut filters) = (Vec::new(), Vec::new());
+    let mut args = bin_args.iter().map(String::as_str);
+    while let Some(arg) = args.next() {
+        match arg {
+            "--nocapture" => options.push("--no-capture"),
+            "--test-threads" => options.extend(["--test-threads"].into_iter().chain(args.next())),
+            "--exact" | "--ignored" | "--include-ignored" => filters.push(arg),
+            "--skip" => filters.extend(["--skip"].into_iter().chain(args.next())),
+            _ if arg.starts_with("--test-threads=") => options.push(arg),
+            _ if !arg.starts_with('-') => filters.push(arg),
+            _ => tracing::warn!("Ignoring test binary argument `{arg}` not supported by nextest"),
+        }
+    }
+    (options, filters)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use project_model::TargetDirectoryConfig;
+
+    use super::*;
+
+    fn parse(runner: TestRunner, line: &str) -> CargoTestOutput {
+        let target = TestTarget {
+            package: "my-package".to_owned(),
+            target: "my_bin".to_owned(),
+            kind: TargetKind::Bin,
+            doc_tests: false,
+        };
+        CargoTestOutputParser::new(&target, runner)
+            .from_line(line, &mut String::new())
+            .unwrap()
+            .output
+    }
+
+    #[test]
+    fn parse_libtest_json() {
+        let output = parse(
+            TestRunner::Libtest,
+            r#"{ "type": "test", "name": "tests::foo", "event": "ok", "exec_time": 0.25 }"#,
+        );
+        assert!(matches!(
+            output,
+            CargoTestOutput::Test { name, state: TestState::Ok { exec_time: Some(0.25) } }
+                if name == "tests::foo"
+        ));
+    }
+
+    #[test]
+    fn parse_nextest_libtest_json() {
+        let output = parse(
+            TestRunner::Nextest,
+            r#"{"type":"test","event":"failed","name":"my-package::bin/my_bin$tests::foo","exec_time":1.5,"stdout":"panicked"}"#,
+        );
+        assert!(matches!(
+            output,
+            CargoTestOutput::Test {
+                name,
+                state: TestState::Failed { stdout, exec_time: Some(1.5) },
+            } if name == "tests::foo" && stdout == "panicked"
+        ));
+
+        let output = parse(TestRunner::Nextest, "   Compiling my-package v0.1.0");
+        assert!(matches!(output, CargoTestOutput::Custom { .. }));
+    }
+
+    fn command_line(target: TestTarget, runner: TestRunner) -> String {
+        let options = CargoOptions {
+            subcommand: "test".to_owned(),
+            target_tuples: vec![],
+            all_targets: false,
+            set_test: true,
+            no_default_features: false,
+            all_features: false,
+            features: vec![],
+            extra_args: vec![],
+            extra_test_bin_args: [
+                "--nocapture",
+                "--test-threads",
+                "2",
+                "--exact",
+                "--format=terse",
+            ]
+            .map(ToOwned::to_owned)
+            .to_vec(),
+            extra_env: Default::default(),
+            config_path: None,
+            target_dir_config: TargetDirectoryConfig::default(),
+        };
+        let root = AbsPath::assert(Utf8Path::new(if cfg!(windows) { "C:\\ws" } else { "/ws" }));
+        let runner = runner.for_target(&target);
+        let cmd = test_command(Some("tests::foo"), &options, root, None, &target, None, runner);
+        let args = cmd.get_args().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>();
+        // Leave out the platform dependent manifest path
+        args.join(" ").replace(&root.join("Cargo.toml").to_string(), "$MANIFEST")
+    }
+
+    #[test]
+    fn nextest_command_line() {
+        let target = TestTarget {
+            package: "my-package".to_owned(),
+            target: "my_bin".to_owned(),
+            kind: TargetKind::Bin,
+            doc_tests: false,
+        };
+        let nextest = command_line(target.clone(), TestRunner::Nextest);
+        expect![[r#"
+            --color=always nextest run --package my-package --bin my_bin --no-fail-fast --manifest-path $MANIFEST --message-format=libtest-json-plus --no-capture --test-threads 2 -- tests::foo --exact"#]]
+        .assert_eq(&nextest);
+        let libtest = command_line(target.clone(), TestRunner::Libtest);
+        expect![[r#"
+            --color=always test --package my-package --bin my_bin --no-fail-fast --manifest-path $MANIFEST -- tests::foo -Z unstable-options --format=json --report-time --nocapture --test-threads 2 --exact --format=terse"#]]
+        .assert_eq(&libtest);
+
+        let lib = TestTarget {
+            kind: TargetKind::Lib { is_proc_macro: false },
+            doc_tests: true,
+            ..target
+        };
+        let doc_tests = command_line(lib, TestRunner::Nextest);
+        expect![[r#"
+            --color=always test --package my-package --doc --no-fail-fast --manifest-path $MANIFEST -- tests::foo -Z unstable-options --format=json --report-time --nocapture --test-threads 2 --exact --format=terse"#]]
+        .assert_eq(&doc_tests);
+    }
+
+    #[test]
+    fn doc_test_ids() {
+        let target = TestTarget {
+            package: "my-package".to_owned(),
+            target: "my-lib".to_owned(),
+            kind: TargetKind::Lib { is_proc_macro: false },
+            doc_tests: true,
+        };
+        assert_eq!(target.test_id("src/lib.rs - (line 1)"), "my_lib (doctest)");
+        assert_eq!(
+            target.test_id("src/foo.rs - foo::Bar::new (line 12)"),
+            "my_lib::foo::Bar::new (doctest)"
+        );
+        let target = TestTarget { doc_tests: false, ..target };
+        assert_eq!(target.test_id("foo::tests::it_works"), "my_lib::foo::tests::it_works");
+    }
+}
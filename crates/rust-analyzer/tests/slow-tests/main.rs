@@ -25,15 +25,20 @@ use std::{path::PathBuf, time::Instant};
 use ide_db::FxHashMap;
 use lsp_types::{
     CodeActionContext, CodeActionParams, CodeActionRequest, CompletionParams, CompletionRequest,
-    DidOpenTextDocumentNotification, DidOpenTextDocumentParams, DocumentFormattingParams,
-    DocumentFormattingRequest, DocumentRangeFormattingParams, DocumentRangeFormattingRequest,
-    FileRename, FormattingOptions, HoverParams, HoverRequest, InlayHint, InlayHintParams,
-    InlayHintRequest, InlayHintResolveRequest, Label, LanguageKind, PartialResultParams, Position,
+    CreateFilesParams, DeleteFilesParams, DidCloseTextDocumentNotification,
+    DidCloseTextDocumentParams, DidOpenTextDocumentNotification, DidOpenTextDocumentParams,
+    DocumentFormattingParams, DocumentFormattingRequest, DocumentRangeFormattingParams,
+    DocumentRangeFormattingRequest, FileCreate, FileDelete, FileRename, FormattingOptions,
+    HoverParams, HoverRequest, InlayHint, InlayHintParams, InlayHintRequest,
+    InlayHintResolveRequest, Label, LanguageKind, PartialResultParams, Position, PreviousResultId,
     Range, RenameFilesParams, TextDocumentItem, TextDocumentPositionParams, TypeDefinitionParams,
-    TypeDefinitionRequest, Uri, WillRenameFilesRequest, WorkDoneProgressParams,
-    WorkspaceSymbolRequest,
+    TypeDefinitionRequest, Uri, WillCreateFilesRequest, WillDeleteFilesRequest,
+    WillRenameFilesRequest, WorkDoneProgressParams, WorkspaceDiagnosticParams,
+    WorkspaceDiagnosticRequest, WorkspaceSymbolRequest,
+};
+use rust_analyzer::lsp::ext::{
+    OnEnterRequest, ReloadWorkspaceRequest, RunnablesParams, RunnablesRequest,
 };
-use rust_analyzer::lsp::ext::{OnEnterRequest, RunnablesParams, RunnablesRequest};
 use serde_json::json;
 use stdx::format_to_acc;
 
@@ -736,6 +741,185 @@ fn main() {
     );
 }
 
+#[test]
+fn test_workspace_diagnostics() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = project(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+mod bar;
+mod missing;
+
+//- /src/bar.rs
+pub fn f() {}
+"#,
+    )
+    .wait_until_workspace_is_loaded();
+
+    let uri = server.doc_id("src/lib.rs").uri;
+    let report = server.send_request::<WorkspaceDiagnosticRequest>(WorkspaceDiagnosticParams {
+        identifier: None,
+        previous_result_ids: vec![],
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    });
+    let items = report["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{report:#}");
+    assert_eq!(items[0]["uri"], uri.as_str());
+    assert_eq!(items[0]["kind"], "full");
+    assert_eq!(items[0]["items"][0]["code"], "E0583");
+    let result_id = items[0]["resultId"].as_str().unwrap().to_owned();
+
+    let report = server.send_request::<WorkspaceDiagnosticRequest>(WorkspaceDiagnosticParams {
+        identifier: None,
+        previous_result_ids: vec![PreviousResultId { uri, value: result_id.clone() }],
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    });
+    let items = report["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{report:#}");
+    assert_eq!(items[0]["kind"], "unchanged");
+    assert_eq!(items[0]["resultId"], result_id.as_str());
+}
+
+#[test]
+fn attaches_opened_unlinked_file() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+pub fn foo() {}
+
+//- /scratch.rs
+fn main() {
+    let greeting = String::new();
+}
+"#,
+    )
+    .with_config(serde_json::json!({
+        "cargo": { "sysroot": "discover" },
+        "files": { "attachUnlinked": true },
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    server.notification::<DidOpenTextDocumentNotification>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: server.doc_id("scratch.rs").uri,
+            language_id: LanguageKind::Rust,
+            version: 0,
+            text: "fn main() {\n    let greeting = String::new();\n}\n".to_owned(),
+        },
+    });
+
+    // The file is attached in the background, so poll until it is analyzed.
+    let start = Instant::now();
+    loop {
+        let res = server.send_request::<HoverRequest>(HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                server.doc_id("scratch.rs"),
+                Position::new(1, 10),
+            ),
+            work_done_progress_params: Default::default(),
+        });
+        if res.to_string().contains("String") {
+            break;
+        }
+        assert!(start.elapsed().as_secs() < 30, "scratch.rs was not attached: {res}");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn detaches_unlinked_file_closed_during_fetch() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+pub fn foo() {}
+
+//- /scratch.rs
+fn main() {
+    let greeting = String::new();
+}
+"#,
+    )
+    .with_config(serde_json::json!({
+        "cargo": { "sysroot": "discover" },
+        "files": { "attachUnlinked": true },
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let hover = || {
+        server.send_request::<HoverRequest>(HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                server.doc_id("scratch.rs"),
+                Position::new(1, 10),
+            ),
+            work_done_progress_params: Default::default(),
+        })
+    };
+
+    server.notification::<DidOpenTextDocumentNotification>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: server.doc_id("scratch.rs").uri,
+            language_id: LanguageKind::Rust,
+            version: 0,
+            text: "fn main() {\n    let greeting = String::new();\n}\n".to_owned(),
+        },
+    });
+    let start = Instant::now();
+    loop {
+        let res = hover();
+        if res.to_string().contains("String") {
+            break;
+        }
+        assert!(start.elapsed().as_secs() < 30, "scratch.rs was not attached: {res}");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    // Close the file while the reloaded workspace is still being fetched.
+    server.send_request::<ReloadWorkspaceRequest>(());
+    server.notification::<DidCloseTextDocumentNotification>(DidCloseTextDocumentParams {
+        text_document: server.doc_id("scratch.rs"),
+    });
+
+    let start = Instant::now();
+    loop {
+        let res = hover();
+        if !res.to_string().contains("String") {
+            break;
+        }
+        assert!(start.elapsed().as_secs() < 30, "scratch.rs was not detached: {res}");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
+
 #[test]
 fn test_missing_module_code_action() {
     if skip_slow_tests() {
@@ -1260,6 +1444,77 @@ pub fn foo(_input: TokenStream) -> TokenStream {
     .assert_eq(value);
 }
 
+#[test]
+fn test_will_create_and_delete_files() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let tmp_dir = TestDir::new();
+    let tmp_dir_path = tmp_dir.path().to_owned();
+    let base_path = PathBuf::from(format!("file://{}", tmp_dir_path.as_str()));
+    let uri = |path: &str| Uri::parse(base_path.join(path).to_str().unwrap()).unwrap();
+
+    let code = r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+mod foo;
+fn main() {}
+
+//- /src/foo/mod.rs
+mod bar;
+
+//- /src/foo/bar.rs
+
+"#;
+    let server =
+        Project::with_fixture(code).tmp_dir(tmp_dir).server().wait_until_workspace_is_loaded();
+
+    server.request::<WillCreateFilesRequest>(
+        CreateFilesParams { files: vec![FileCreate { uri: uri("src/foo/baz.rs") }] },
+        json!({
+          "documentChanges": [
+            {
+              "textDocument": { "uri": uri("src/foo/mod.rs"), "version": null },
+              "edits": [
+                {
+                  "range": {
+                    "start": { "line": 0, "character": 8 },
+                    "end": { "line": 0, "character": 8 }
+                  },
+                  "newText": "\nmod baz;"
+                }
+              ]
+            }
+          ]
+        }),
+    );
+
+    server.request::<WillDeleteFilesRequest>(
+        DeleteFilesParams { files: vec![FileDelete { uri: uri("src/foo") }] },
+        json!({
+          "documentChanges": [
+            {
+              "textDocument": { "uri": uri("src/lib.rs"), "version": null },
+              "edits": [
+                {
+                  "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 1, "character": 0 }
+                  },
+                  "newText": ""
+                }
+              ]
+            }
+          ]
+        }),
+    );
+}
+
 #[test]
 fn test_will_rename_files_same_level() {
     if skip_slow_tests() {
@@ -5,7 +5,12 @@
 #[cfg(feature = "in-rust-tree")]
 extern crate rustc_driver as _;
 
-use std::{any::TypeId, mem, str::FromStr, sync};
+use std::{
+    any::{Any, TypeId},
+    mem,
+    str::FromStr,
+    sync,
+};
 
 use base_db::target::TargetData;
 use base_db::{
@@ -709,9 +714,45 @@ pub fn generate_suffixed_type(_attr: TokenStream, input: TokenStream) -> TokenSt
                 disabled: false,
             },
         ),
+        (
+            r#"
+#[proc_macro_attribute]
+pub fn async_test(_attr: TokenStream, input: TokenStream) -> TokenStream {
+    input
+}
+"#
+            .into(),
+            ProcMacro {
+                name: Symbol::intern("async_test"),
+                kind: ProcMacroKind::Attr,
+                expander: sync::Arc::new(AsyncTestProcMacroExpander),
+                disabled: false,
+            },
+        ),
     ])
 }
 
+/// A function-like proc-macro called `name` whose expansions fail with `message`, the way the
+/// proc-macro server reports failures. Pass it to [`ChangeFixture::parse_with_proc_macros`].
+pub fn failing_proc_macro(name: &str, message: &str) -> (String, ProcMacro) {
+    (
+        format!(
+            r#"
+#[proc_macro]
+pub fn {name}(input: TokenStream) -> TokenStream {{
+    input
+}}
+"#
+        ),
+        ProcMacro {
+            name: Symbol::intern(name),
+            kind: ProcMacroKind::Bang,
+            expander: sync::Arc::new(FailingProcMacroExpander(message.to_owned())),
+            disabled: false,
+        },
+    )
+}
+
 fn filter_test_proc_macros(
     proc_macro_names: &[String],
     proc_macro_defs: Vec<(String, ProcMacro)>,
@@ -899,6 +940,55 @@ impl ProcMacroExpander for Issue18089ProcMacroExpander {
     }
 }
 
+// Marks the item as a test, like `#[tokio::test]` does.
+#[derive(Debug)]
+struct AsyncTestProcMacroExpander;
+impl ProcMacroExpander for AsyncTestProcMacroExpander {
+    fn expand(
+        &self,
+        _: &dyn SourceDatabase,
+        item: &TopSubtree,
+        _: Option<&TopSubtree>,
+        _: &Env,
+        _: Span,
+        call_site: Span,
+        _: Span,
+        _: String,
+    ) -> Result<TopSubtree, ProcMacroExpansionError> {
+        Ok(quote! { call_site =>
+            #[::core::prelude::v1::test]
+            #item
+        })
+    }
+
+    fn eq_dyn(&self, other: &dyn ProcMacroExpander) -> bool {
+        other.type_id() == TypeId::of::<Self>()
+    }
+}
+
+// Fails with a system error carrying the message
+#[derive(Debug)]
+struct FailingProcMacroExpander(String);
+impl ProcMacroExpander for FailingProcMacroExpander {
+    fn expand(
+        &self,
+        _: &dyn SourceDatabase,
+        _: &TopSubtree,
+        _: Option<&TopSubtree>,
+        _: &Env,
+        _: Span,
+        _: Span,
+        _: Span,
+        _: String,
+    ) -> Result<TopSubtree, ProcMacroExpansionError> {
+        Err(ProcMacroExpansionError::System(self.0.clone()))
+    }
+
+    fn eq_dyn(&self, other: &dyn ProcMacroExpander) -> bool {
+        (other as &dyn Any).downcast_ref::<Self>().is_some_and(|other| other.0 == self.0)
+    }
+}
+
 // Pastes the attribute input as its output
 #[derive(Debug)]
 struct AttributeInputReplaceProcMacroExpander;
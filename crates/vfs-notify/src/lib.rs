@@ -32,6 +32,7 @@ pub struct NotifyHandle {
 enum Message {
     Config(loader::Config),
     Invalidate(AbsPathBuf),
+    WatchFiles(Vec<AbsPathBuf>),
 }
 
 impl loader::Handle for NotifyHandle {
@@ -52,6 +53,10 @@ impl loader::Handle for NotifyHandle {
         self.sender.send(Message::Invalidate(path)).unwrap();
     }
 
+    fn watch_files(&mut self, files: Vec<AbsPathBuf>) {
+        self.sender.send(Message::WatchFiles(files)).unwrap();
+    }
+
     fn load_sync(&mut self, path: &AbsPath) -> Option<Vec<u8>> {
         read(path)
     }
@@ -104,17 +109,7 @@ impl NotifyActor {
                     Message::Config(config) => {
                         self.watcher = None;
                         if !config.watch.is_empty() {
-                            let (watcher_sender, watcher_receiver) = unbounded();
-                            let watcher = log_notify_error(RecommendedWatcher::new(
-                                move |event| {
-                                    // we don't care about the error. If sending fails that usually
-                                    // means we were dropped, so unwrapping will just add to the
-                                    // panic noise.
-                                    _ = watcher_sender.send(event);
-                                },
-                                Config::default(),
-                            ));
-                            self.watcher = watcher.map(|it| (it, watcher_receiver));
+                            self.start_watcher();
                         }
 
                         let config_version = config.version;
@@ -195,6 +190,20 @@ impl NotifyActor {
                         let files = vec![(path, contents)];
                         self.send(loader::Message::Changed { files });
                     }
+                    Message::WatchFiles(files) => {
+                        if self.watcher.is_none() {
+                            self.start_watcher();
+                        }
+                        let files = Self::load_entry(
+                            |f| self.watch(f),
+                            loader::Entry::Files(files),
+                            true,
+                            |_| {},
+                        );
+                        self.watched_file_entries
+                            .extend(files.iter().map(|(path, _)| path.clone()));
+                        self.send(loader::Message::Loaded { files });
+                    }
                 },
                 Event::NotifyEvent(event) => {
                     if let Some(event) = log_notify_error(event)
@@ -349,6 +358,20 @@ impl NotifyActor {
         }
     }
 
+    fn start_watcher(&mut self) {
+        let (watcher_sender, watcher_receiver) = unbounded();
+        let watcher = log_notify_error(RecommendedWatcher::new(
+            move |event| {
+                // we don't care about the error. If sending fails that usually
+                // means we were dropped, so unwrapping will just add to the
+                // panic noise.
+                _ = watcher_sender.send(event);
+            },
+            Config::default(),
+        ));
+        self.watcher = watcher.map(|it| (it, watcher_receiver));
+    }
+
     fn watch(&mut self, path: &Path) {
         if let Some((watcher, _)) = &mut self.watcher {
             log_notify_error(watcher.watch(path, RecursiveMode::Recursive));
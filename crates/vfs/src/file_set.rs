@@ -133,7 +133,13 @@ impl FileSetConfig {
     /// else).
     pub fn classify_path(&self, path: &VfsPath) -> Option<usize> {
         let idx = self.classify(path, &mut Vec::new());
-        (idx != self.len() - 1).then_some(idx)
+        (idx != self.catch_all_set()).then_some(idx)
+    }
+
+    /// Returns the index of the catch-all set, holding the paths that belong to none of the
+    /// configured sets.
+    pub fn catch_all_set(&self) -> usize {
+        self.len() - 1
     }
 
     /// Returns the set index for the given `path`.
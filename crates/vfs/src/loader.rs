@@ -87,6 +87,10 @@ pub trait Handle: fmt::Debug {
     /// The file's content at `path` has been modified, and should be reloaded.
     fn invalidate(&mut self, path: AbsPathBuf);
 
+    /// Load `files` and watch them on top of the current configuration, without reloading the
+    /// files it covers. The next [`Handle::set_config`] stops watching them.
+    fn watch_files(&mut self, files: Vec<AbsPathBuf>);
+
     /// Load the content of the given file, returning [`None`] if it does not
     /// exists.
     fn load_sync(&mut self, path: &AbsPath) -> Option<Vec<u8>>;
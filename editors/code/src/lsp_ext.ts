@@ -107,8 +107,8 @@ export type DiscoverTestResults = {
     scopeFile: lc.TextDocumentIdentifier[] | undefined;
 };
 export type TestState =
-    | { tag: "failed"; message: string }
-    | { tag: "passed" }
+    | { tag: "failed"; message: string; duration?: number }
+    | { tag: "passed"; duration?: number }
     | { tag: "started" }
     | { tag: "enqueued" }
     | { tag: "skipped" };
@@ -323,6 +323,17 @@ export type RecursiveMemoryLayoutNode = {
     parentIdx: number;
     childrenStart: number;
     childrenLen: number;
+    padding: number;
+    isVariant: boolean;
+    tagValue?: string;
+    tag?: MemoryLayoutTag;
+};
+export type MemoryLayoutTag = {
+    offset: number;
+    size: number;
+    validRangeStart: string;
+    validRangeEnd: string;
+    niche: boolean;
 };
 export type RecursiveMemoryLayout = {
     nodes: RecursiveMemoryLayoutNode[];
@@ -184,9 +184,13 @@ export const prepareTestExplorer = (
         client.onNotification(ra.changeTestState, (results) => {
             const test = idToTestMap.get(results.testId)!;
             if (results.state.tag === "failed") {
-                currentTestRun!.failed(test, new vscode.TestMessage(results.state.message));
+                currentTestRun!.failed(
+                    test,
+                    new vscode.TestMessage(results.state.message),
+                    results.state.duration,
+                );
             } else if (results.state.tag === "passed") {
-                currentTestRun!.passed(test);
+                currentTestRun!.passed(test, results.state.duration);
             } else if (results.state.tag === "started") {
                 currentTestRun!.started(test);
             } else if (results.state.tag === "skipped") {